@@ -43,6 +43,12 @@ pub enum CoreError {
     #[error("{0}")]
     EmptyContent(&'static str),
 
+    #[error("generator error {0}")]
+    Generator(String),
+
+    #[error("{0} is not supported")]
+    NotSupported(String),
+
     #[error(transparent)]
     Polars(#[from] polars::error::PolarsError),
 
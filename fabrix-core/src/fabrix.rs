@@ -44,8 +44,11 @@
 //! 1. popup_rows
 //! 1. slice
 //! 1. take_cols
+//! 1. iter_batches
+//! 1. chunks
+//! 1. split_by
+//! 1. estimated_size
 
-use itertools::Itertools;
 use polars::datatypes::IdxCa;
 use polars::prelude::{BooleanChunked, DataFrame, Field, NewChunkedArray};
 use ref_cast::RefCast;
@@ -108,7 +111,7 @@ impl IntoIndexTag for usize {
             Some(field) => Ok(IndexTag {
                 loc: self,
                 name: field.name().to_owned(),
-                data_type: field.data_type().into(),
+                data_type: field.data_type().try_into()?,
             }),
             None => Err(lnm_err(fields.len(), self)),
         }
@@ -121,7 +124,7 @@ impl IntoIndexTag for &str {
             Some(loc) => Ok(IndexTag {
                 loc,
                 name: self.to_string(),
-                data_type: fields[loc].data_type().into(),
+                data_type: fields[loc].data_type().try_into()?,
             }),
             None => Err(nnf_err(self)),
         }
@@ -134,7 +137,7 @@ impl IntoIndexTag for String {
             Some(loc) => Ok(IndexTag {
                 loc,
                 name: self,
-                data_type: fields[loc].data_type().into(),
+                data_type: fields[loc].data_type().try_into()?,
             }),
             None => Err(nnf_err(&self)),
         }
@@ -167,9 +170,12 @@ pub struct Fabrix {
 }
 
 impl Fabrix {
-    /// DataFrame constructor
+    /// DataFrame constructor, fails if any column's dtype has no corresponding `ValueType`
     pub fn new(data: DataFrame, index_tag: impl IntoIndexTag) -> CoreResult<Self> {
         let fields = data.fields();
+        for f in fields.iter() {
+            ValueType::try_from(f.data_type())?;
+        }
         let index_tag = match index_tag.into_index_tag(&fields) {
             Ok(it) => Ok(Some(it)),
             Err(e) => match e {
@@ -303,8 +309,8 @@ impl Fabrix {
     }
 
     /// dataframe dtypes
-    pub fn dtypes(&self) -> Vec<&ValueType> {
-        self.data().dtypes().iter().map(|t| t.into()).collect_vec()
+    pub fn dtypes(&self) -> CoreResult<Vec<&ValueType>> {
+        self.data().dtypes().iter().map(|t| t.try_into()).collect()
     }
 
     /// index check null.
@@ -325,12 +331,12 @@ impl Fabrix {
     }
 
     /// get DataFrame fields info
-    pub fn fields(&self) -> Vec<FieldInfo> {
+    pub fn fields(&self) -> CoreResult<Vec<FieldInfo>> {
         self.data()
             .fields()
             .iter()
-            .map(FieldInfo::from)
-            .collect::<Vec<_>>()
+            .map(FieldInfo::try_from)
+            .collect::<CoreResult<Vec<_>>>()
     }
 
     /// get index field info
@@ -409,6 +415,47 @@ impl Fabrix {
         })
     }
 
+    /// iterate over the DataFrame by slices of `n` rows, the last slice may be shorter
+    pub fn iter_batches(&self, n: usize) -> CoreResult<IntoIteratorBatch<'_>> {
+        if n == 0 {
+            return Err(CoreError::InvalidLength);
+        }
+
+        Ok(IntoIteratorBatch {
+            fabrix: self,
+            batch_size: n,
+            offset: 0,
+        })
+    }
+
+    /// split the DataFrame into slices of `n` rows, the last slice may be shorter
+    pub fn chunks(&self, n: usize) -> CoreResult<Vec<Fabrix>> {
+        Ok(self.iter_batches(n)?.collect())
+    }
+
+    /// split the DataFrame into groups by the distinct values of a column,
+    /// groups are kept in the order of their first appearance
+    pub fn split_by(&self, col: &str) -> CoreResult<Vec<Fabrix>> {
+        self.get_column(col)?;
+
+        let res = self
+            .data()
+            .partition_by_stable([col])?
+            .into_iter()
+            .map(|data| Fabrix {
+                data,
+                index_tag: self.index_tag().cloned(),
+            })
+            .collect();
+
+        Ok(res)
+    }
+
+    /// estimated memory footprint of the DataFrame in bytes
+    pub fn estimated_size(&self) -> usize {
+        self.data().estimated_size()
+    }
+
     /// rechunk: aggregate all chunks to a contiguous array of memory
     pub fn rechunk(&mut self) {
         self.data.rechunk();
@@ -443,6 +490,17 @@ impl Fabrix {
         Ok(self)
     }
 
+    /// cast a column to another type, self mutation
+    pub fn cast(&mut self, name: &str, dtype: &ValueType) -> CoreResult<&mut Self> {
+        let series = self.get_column(name)?.cast(dtype)?;
+        self.data.replace(name, series.0)?;
+        if let Some(it) = self.index_tag.as_mut().filter(|it| it.name == name) {
+            it.data_type = dtype.clone();
+        }
+
+        Ok(self)
+    }
+
     /// horizontal stack, return cloned data
     pub fn hconcat(&self, columns: Vec<Series>) -> CoreResult<Fabrix> {
         let raw_columns = columns.into_iter().map(|v| v.0).collect::<Vec<_>>();
@@ -657,16 +715,16 @@ impl FabrixDataFrame {
         self.0.height()
     }
 
-    pub fn dtypes(&self) -> Vec<&ValueType> {
-        self.0.dtypes().iter().map(|t| t.into()).collect_vec()
+    pub fn dtypes(&self) -> CoreResult<Vec<&ValueType>> {
+        self.0.dtypes().iter().map(|t| t.try_into()).collect()
     }
 
-    pub fn fields(&self) -> Vec<FieldInfo> {
+    pub fn fields(&self) -> CoreResult<Vec<FieldInfo>> {
         self.0
             .fields()
             .iter()
-            .map(FieldInfo::from)
-            .collect::<Vec<_>>()
+            .map(FieldInfo::try_from)
+            .collect::<CoreResult<Vec<_>>>()
     }
 
     pub fn data(&self) -> &DataFrame {
@@ -740,6 +798,34 @@ impl<'a> IntoIterator for FabrixDataFrameIterToColumn<'a> {
     }
 }
 
+// ================================================================================================
+// IntoIteratorBatch for Fabrix
+// ================================================================================================
+
+/// IntoIteratorBatch
+///
+/// yields `Fabrix` slices sharing the same index tag
+pub struct IntoIteratorBatch<'a> {
+    fabrix: &'a Fabrix,
+    batch_size: usize,
+    offset: usize,
+}
+
+impl<'a> Iterator for IntoIteratorBatch<'a> {
+    type Item = Fabrix;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.fabrix.height() {
+            return None;
+        }
+
+        let res = self.fabrix.slice(self.offset as i64, self.batch_size);
+        self.offset += self.batch_size;
+
+        Some(res)
+    }
+}
+
 // ================================================================================================
 // PartialEq
 // ================================================================================================
@@ -784,7 +870,7 @@ mod test_fabrix_dataframe {
         let df = df.unwrap();
 
         assert_eq!(
-            df.dtypes(),
+            df.dtypes().unwrap(),
             vec![&ValueType::String, &ValueType::I32, &ValueType::I32]
         );
 
@@ -806,7 +892,7 @@ mod test_fabrix_dataframe {
 
         println!("{:?}", df.fields());
         assert_eq!(
-            df.fields(),
+            df.fields().unwrap(),
             vec![
                 FieldInfo::new("names", ValueType::String),
                 FieldInfo::new("ord", ValueType::I32),
@@ -869,6 +955,42 @@ mod test_fabrix_dataframe {
         assert!(res.is_ok(), "vconcat_mut should work");
     }
 
+    #[test]
+    fn fx_batches_success() {
+        let df = fx![
+            "ord";
+            "names" => ["Jacob", "Sam", "James", "Jason", "Mia"],
+            "ord" => [1,2,3,4,5],
+            "val" => [Some(10), None, Some(8), Some(10), None]
+        ]
+        .unwrap();
+
+        let chunks = df.chunks(2).unwrap();
+        assert_eq!(
+            chunks.iter().map(|c| c.height()).collect::<Vec<_>>(),
+            vec![2, 2, 1]
+        );
+        assert!(chunks.iter().all(|c| c.index_tag() == df.index_tag()));
+        assert_eq!(df.iter_batches(5).unwrap().count(), 1);
+        assert!(matches!(
+            df.iter_batches(0),
+            Err(crate::CoreError::InvalidLength)
+        ));
+
+        let groups = df.split_by("val").unwrap();
+        assert_eq!(
+            groups.iter().map(|c| c.height()).collect::<Vec<_>>(),
+            vec![2, 2, 1]
+        );
+        assert_eq!(
+            groups[0].get_column("ord").unwrap(),
+            &series!("ord" => [1, 4])
+        );
+        assert!(df.split_by("foo").is_err());
+
+        assert!(df.estimated_size() > 0);
+    }
+
     #[test]
     fn fx_has_null_success() {
         let fx = fx![
@@ -890,5 +1012,16 @@ mod test_fabrix_dataframe {
         )
     }
 
+    #[test]
+    fn fx_new_unsupported_dtype_fail() {
+        use polars::prelude::{DataFrame, NamedFrom, Series};
+
+        let list = Series::new("list", [Series::new("", [1, 2]), Series::new("", [3])]);
+        let df = DataFrame::new(vec![Series::new("ord", [1, 2]), list]).unwrap();
+
+        let fx = crate::Fabrix::new(df, None::<usize>);
+        assert!(matches!(fx, Err(crate::CoreError::TypeMismatch("List"))));
+    }
+
     // TODO: test the rest of the methods
 }
@@ -0,0 +1,613 @@
+//! Fabrix generator
+//!
+//! Synthetic data generator driven by `Schema`.
+//!
+//! Each column is generated by a `GeneratorRule`, columns without a rule fall back to a default
+//! rule according to their `ValueType`. Columns can also have a null ratio or be unique keys, and
+//! a seed makes the generated data reproducible.
+
+use std::collections::{HashMap, HashSet};
+
+use polars::prelude::{DataType, Series as PolarsSeries};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_distr::{Distribution, Exp, Normal, WeightedIndex};
+use rust_decimal::prelude::ToPrimitive;
+
+use super::{nnf_err, tms_err};
+use crate::{
+    date, datetime, value, Bytes, CoreError, CoreResult, Decimal, Fabrix, FieldInfo, Schema,
+    Series, Uuid, Value, ValueType,
+};
+
+/// consecutive failed attempts before giving up on generating a unique value
+const MAX_UNIQUE_ATTEMPTS: usize = 1000;
+/// upper bound of repetition for `*` & `+` in patterns
+const MAX_PATTERN_REPEAT: usize = 8;
+/// nanoseconds of a day
+const NANO_DAY: i64 = 86_400_000_000_000;
+
+/// rules for generating a column's values
+#[derive(Debug, Clone)]
+pub enum GeneratorRule {
+    /// uniform values between `min` and `max`, both are inclusive except for floats.
+    /// Works with numeric, temporal & decimal types (decimals are generated with scale 2).
+    Range(Value, Value),
+    /// normal distribution with the given mean & standard deviation, numeric types only
+    Normal(f64, f64),
+    /// exponential distribution with the given rate, numeric types only
+    Exponential(f64),
+    /// one of the given values, picked uniformly
+    Enum(Vec<Value>),
+    /// one of the given values, picked by weights
+    WeightedEnum(Vec<(Value, f64)>),
+    /// regex-like pattern for string & bytes types, supporting literals, `.`, `\d`, `\w`, `\s`,
+    /// classes like `[A-Za-z_]` and quantifiers `?`, `*`, `+`, `{n}` & `{n,m}`
+    Pattern(String),
+    /// incrementing sequence from `start` by `step`, numeric & temporal types only
+    Sequence(i64, i64),
+}
+
+/// default rule of a column without a given rule, `None` for uuid & null columns, which are
+/// generated as random uuids & nulls respectively
+fn default_rule(dtype: &ValueType) -> Option<GeneratorRule> {
+    let res = match dtype {
+        ValueType::Bool => GeneratorRule::Enum(vec![value!(true), value!(false)]),
+        ValueType::U8 | ValueType::I8 => GeneratorRule::Range(value!(0i64), value!(100i64)),
+        ValueType::U16
+        | ValueType::U32
+        | ValueType::U64
+        | ValueType::I16
+        | ValueType::I32
+        | ValueType::I64
+        | ValueType::Decimal => GeneratorRule::Range(value!(0i64), value!(10_000i64)),
+        ValueType::F32 | ValueType::F64 => GeneratorRule::Range(value!(0f64), value!(1f64)),
+        ValueType::Date => {
+            GeneratorRule::Range(value!(date!(2000, 1, 1)), value!(date!(2030, 12, 31)))
+        }
+        ValueType::Time => GeneratorRule::Range(Value::Time(0), Value::Time(NANO_DAY - 1)),
+        ValueType::DateTime => GeneratorRule::Range(
+            value!(datetime!(2000, 1, 1, 0, 0, 0)),
+            value!(datetime!(2030, 12, 31, 23, 59, 59)),
+        ),
+        ValueType::Categorical(Some(categories)) => {
+            GeneratorRule::Enum(categories.iter().map(|c| value!(c.as_str())).collect())
+        }
+        ValueType::Categorical(None) => GeneratorRule::Pattern(String::from("[A-E]")),
+        ValueType::String | ValueType::Bytes => GeneratorRule::Pattern(String::from("[a-z]{8}")),
+        ValueType::Uuid | ValueType::Null => return None,
+    };
+
+    Some(res)
+}
+
+#[derive(Debug, Clone, Default)]
+struct ColumnRule {
+    rule: Option<GeneratorRule>,
+    null_ratio: f64,
+    unique: bool,
+}
+
+/// Generator
+///
+/// generates a `Fabrix` of N rows from a `Schema` and per-column rules
+#[derive(Debug, Clone)]
+pub struct Generator {
+    fields: Vec<FieldInfo>,
+    rules: HashMap<String, ColumnRule>,
+    seed: Option<u64>,
+}
+
+impl Generator {
+    pub fn new(schema: &Schema) -> CoreResult<Self> {
+        let fields = schema.iter().collect::<CoreResult<Vec<_>>>()?;
+        Ok(Self::from_field_infos(fields))
+    }
+
+    /// keeps the category lists of `ValueType::Categorical`, which `Schema` drops
+    pub fn from_field_infos(fields: Vec<FieldInfo>) -> Self {
+        Self {
+            fields,
+            rules: HashMap::new(),
+            seed: None,
+        }
+    }
+
+    pub fn with_rule(&mut self, column: &str, rule: GeneratorRule) -> &mut Self {
+        self.rules.entry(column.to_owned()).or_default().rule = Some(rule);
+        self
+    }
+
+    /// ratio of nulls in a column, between 0 and 1
+    pub fn with_null_ratio(&mut self, column: &str, ratio: f64) -> &mut Self {
+        self.rules.entry(column.to_owned()).or_default().null_ratio = ratio;
+        self
+    }
+
+    /// unique key column, which never contains nulls
+    pub fn with_unique(&mut self, column: &str) -> &mut Self {
+        self.rules.entry(column.to_owned()).or_default().unique = true;
+        self
+    }
+
+    pub fn with_seed(&mut self, seed: u64) -> &mut Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// generate a `Fabrix` of `n` rows
+    pub fn generate(&self, n: usize) -> CoreResult<Fabrix> {
+        if let Some(name) = self
+            .rules
+            .keys()
+            .find(|k| !self.fields.iter().any(|f| f.name() == k.as_str()))
+        {
+            return Err(nnf_err(name));
+        }
+
+        if n == 0 {
+            return Fabrix::new_empty_no_index(self.fields.clone());
+        }
+
+        let mut rng = match self.seed {
+            Some(s) => StdRng::seed_from_u64(s),
+            None => StdRng::from_entropy(),
+        };
+
+        let series = self
+            .fields
+            .iter()
+            .map(|f| {
+                let cr = self.rules.get(f.name()).cloned().unwrap_or_default();
+                generate_series(&mut rng, f, &cr, n)
+            })
+            .collect::<CoreResult<Vec<_>>>()?;
+
+        Fabrix::from_series_no_index(series)
+    }
+}
+
+fn generate_series(
+    rng: &mut StdRng,
+    field: &FieldInfo,
+    column_rule: &ColumnRule,
+    n: usize,
+) -> CoreResult<Series> {
+    let dtype = field.dtype();
+    let sampler = Sampler::new(column_rule.rule.clone(), dtype)?;
+
+    if !(0.0..=1.0).contains(&column_rule.null_ratio) {
+        return Err(CoreError::Generator(format!(
+            "null ratio {} of column {} is not between 0 and 1",
+            column_rule.null_ratio,
+            field.name()
+        )));
+    }
+
+    let mut values = Vec::with_capacity(n);
+    let mut seen = HashSet::new();
+
+    for i in 0..n {
+        if !column_rule.unique && rng.gen_bool(column_rule.null_ratio) {
+            values.push(Value::Null);
+            continue;
+        }
+
+        let mut attempts = 0;
+        let v = loop {
+            let v = sampler.sample(rng, i);
+            if !column_rule.unique || seen.insert(format!("{:?}", v)) {
+                break v;
+            }
+            attempts += 1;
+            if attempts >= MAX_UNIQUE_ATTEMPTS {
+                return Err(CoreError::Generator(format!(
+                    "cannot generate {} unique values for column {}",
+                    n,
+                    field.name()
+                )));
+            }
+        };
+        values.push(v);
+    }
+
+    let series = if values.iter().all(Value::is_null) {
+        let dtype = DataType::from(dtype);
+        Series(PolarsSeries::full_null(field.name(), n, &dtype))
+    } else {
+        Series::from_values(values, field.name(), true)?
+    };
+
+    match dtype {
+        ValueType::Categorical(_) => series.cast(dtype),
+        _ => Ok(series),
+    }
+}
+
+// ================================================================================================
+// Sampler
+// ================================================================================================
+
+/// a rule checked against its column type
+enum Sampler {
+    Null,
+    Uuid,
+    Int(i64, i64, ValueType),
+    Float(f64, f64, ValueType),
+    Decimal(i64, i64),
+    Normal(Normal<f64>, ValueType),
+    Exponential(Exp<f64>, ValueType),
+    Enum(Vec<Value>),
+    WeightedEnum(Vec<Value>, WeightedIndex<f64>),
+    Pattern(Vec<PatternPiece>, bool),
+    Sequence(i64, i64, ValueType),
+}
+
+impl Sampler {
+    fn new(rule: Option<GeneratorRule>, dtype: &ValueType) -> CoreResult<Self> {
+        let rule = match rule.or_else(|| default_rule(dtype)) {
+            Some(r) => r,
+            None if *dtype == ValueType::Uuid => return Ok(Sampler::Uuid),
+            None => return Ok(Sampler::Null),
+        };
+
+        let res = match (rule, dtype) {
+            (GeneratorRule::Range(min, max), ValueType::Decimal) => {
+                let (min, max) = (range_bound(&min)?, range_bound(&max)?);
+                check_range(min, max)?;
+                Sampler::Decimal((min * 100.0) as i64, (max * 100.0) as i64)
+            }
+            (GeneratorRule::Range(min, max), ValueType::F32 | ValueType::F64) => {
+                let (min, max) = (range_bound(&min)?, range_bound(&max)?);
+                check_range(min, max)?;
+                Sampler::Float(min, max, dtype.clone())
+            }
+            (GeneratorRule::Range(min, max), t) if is_integer_like(t) => {
+                let (min, max) = (range_bound(&min)?, range_bound(&max)?);
+                check_range(min, max)?;
+                Sampler::Int(min as i64, max as i64, dtype.clone())
+            }
+            (GeneratorRule::Range(_, _), _) => return Err(tms_err("range rule")),
+            (GeneratorRule::Normal(mean, std_dev), t) if is_numeric(t) => {
+                let d =
+                    Normal::new(mean, std_dev).map_err(|e| CoreError::Generator(e.to_string()))?;
+                Sampler::Normal(d, dtype.clone())
+            }
+            (GeneratorRule::Normal(_, _), _) => return Err(tms_err("normal rule")),
+            (GeneratorRule::Exponential(rate), t) if is_numeric(t) => {
+                let d = Exp::new(rate).map_err(|e| CoreError::Generator(e.to_string()))?;
+                Sampler::Exponential(d, dtype.clone())
+            }
+            (GeneratorRule::Exponential(_), _) => return Err(tms_err("exponential rule")),
+            (GeneratorRule::Enum(values), _) => {
+                if values.is_empty() {
+                    return Err(CoreError::EmptyContent("enum rule"));
+                }
+                Sampler::Enum(values.into_iter().map(|v| v.force_cast(dtype)).collect())
+            }
+            (GeneratorRule::WeightedEnum(pairs), _) => {
+                let (values, weights): (Vec<_>, Vec<_>) = pairs.into_iter().unzip();
+                let wi =
+                    WeightedIndex::new(weights).map_err(|e| CoreError::Generator(e.to_string()))?;
+                let values = values.into_iter().map(|v| v.force_cast(dtype)).collect();
+                Sampler::WeightedEnum(values, wi)
+            }
+            (GeneratorRule::Pattern(p), ValueType::String | ValueType::Categorical(_)) => {
+                Sampler::Pattern(parse_pattern(&p)?, false)
+            }
+            (GeneratorRule::Pattern(p), ValueType::Bytes) => {
+                Sampler::Pattern(parse_pattern(&p)?, true)
+            }
+            (GeneratorRule::Pattern(_), _) => return Err(tms_err("pattern rule")),
+            (GeneratorRule::Sequence(start, step), t) if is_integer_like(t) => {
+                Sampler::Sequence(start, step, dtype.clone())
+            }
+            (GeneratorRule::Sequence(_, _), _) => return Err(tms_err("sequence rule")),
+        };
+
+        Ok(res)
+    }
+
+    /// sample the `i`th value of a column
+    fn sample(&self, rng: &mut StdRng, i: usize) -> Value {
+        match self {
+            Sampler::Null => Value::Null,
+            Sampler::Uuid => {
+                let u = uuid::Builder::from_random_bytes(rng.gen()).into_uuid();
+                Value::Uuid(Uuid::new(u))
+            }
+            Sampler::Int(min, max, t) => Value::I64(rng.gen_range(*min..=*max)).force_cast(t),
+            Sampler::Float(min, max, t) => {
+                let v = if min == max {
+                    *min
+                } else {
+                    rng.gen_range(*min..*max)
+                };
+                Value::F64(v).force_cast(t)
+            }
+            Sampler::Decimal(min, max) => {
+                Value::Decimal(Decimal::new(rng.gen_range(*min..=*max), 2))
+            }
+            Sampler::Normal(d, t) => sample_numeric(d.sample(rng), t),
+            Sampler::Exponential(d, t) => sample_numeric(d.sample(rng), t),
+            Sampler::Enum(values) => values.choose(rng).cloned().unwrap_or(Value::Null),
+            Sampler::WeightedEnum(values, wi) => values[wi.sample(rng)].clone(),
+            Sampler::Pattern(pieces, is_bytes) => {
+                let s = sample_pattern(rng, pieces);
+                if *is_bytes {
+                    Value::Bytes(Bytes::from(s))
+                } else {
+                    Value::String(s)
+                }
+            }
+            Sampler::Sequence(start, step, t) => Value::I64(start + step * i as i64).force_cast(t),
+        }
+    }
+}
+
+fn is_integer_like(dtype: &ValueType) -> bool {
+    matches!(
+        dtype,
+        ValueType::U8
+            | ValueType::U16
+            | ValueType::U32
+            | ValueType::U64
+            | ValueType::I8
+            | ValueType::I16
+            | ValueType::I32
+            | ValueType::I64
+            | ValueType::Date
+            | ValueType::Time
+            | ValueType::DateTime
+    )
+}
+
+fn is_numeric(dtype: &ValueType) -> bool {
+    matches!(dtype, ValueType::F32 | ValueType::F64) || is_integer_like(dtype)
+}
+
+/// integers are rounded, so that e.g. a normal distribution centers around its mean
+fn sample_numeric(v: f64, dtype: &ValueType) -> Value {
+    match dtype {
+        ValueType::F32 | ValueType::F64 => Value::F64(v).force_cast(dtype),
+        _ => Value::I64(v.round() as i64).force_cast(dtype),
+    }
+}
+
+fn range_bound(value: &Value) -> CoreResult<f64> {
+    match value.clone().force_cast(&ValueType::F64) {
+        Value::F64(v) => Ok(v),
+        Value::Decimal(d) => {
+            d.0.to_f64()
+                .ok_or_else(|| CoreError::new_parse_error(d, "f64"))
+        }
+        _ => Err(tms_err("range bound")),
+    }
+}
+
+fn check_range(min: f64, max: f64) -> CoreResult<()> {
+    if min > max {
+        return Err(CoreError::Generator(format!(
+            "range min {} is greater than max {}",
+            min, max
+        )));
+    }
+    Ok(())
+}
+
+// ================================================================================================
+// Pattern
+// ================================================================================================
+
+#[derive(Debug, Clone)]
+struct PatternPiece {
+    chars: Vec<char>,
+    min: usize,
+    max: usize,
+}
+
+fn char_range(from: char, to: char) -> Vec<char> {
+    (from..=to).collect()
+}
+
+fn word_chars() -> Vec<char> {
+    let mut res = char_range('a', 'z');
+    res.extend(char_range('A', 'Z'));
+    res.extend(char_range('0', '9'));
+    res.push('_');
+    res
+}
+
+fn escaped_chars(c: char) -> Vec<char> {
+    match c {
+        'd' => char_range('0', '9'),
+        'w' => word_chars(),
+        's' => vec![' '],
+        c => vec![c],
+    }
+}
+
+fn pattern_err(pattern: &str) -> CoreError {
+    CoreError::Generator(format!("invalid pattern {}", pattern))
+}
+
+fn parse_pattern(pattern: &str) -> CoreResult<Vec<PatternPiece>> {
+    let mut res = Vec::new();
+    let mut chars = pattern.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let set = match c {
+            '\\' => escaped_chars(chars.next().ok_or_else(|| pattern_err(pattern))?),
+            '.' => {
+                let mut s = word_chars();
+                s.pop();
+                s
+            }
+            '[' => {
+                let mut set = Vec::new();
+                loop {
+                    let c = match chars.next() {
+                        Some(']') => break,
+                        Some('\\') => chars.next(),
+                        c => c,
+                    }
+                    .ok_or_else(|| pattern_err(pattern))?;
+
+                    if chars.peek() == Some(&'-') {
+                        chars.next();
+                        match chars.next() {
+                            Some(']') | None => return Err(pattern_err(pattern)),
+                            Some(to) if to >= c => set.extend(char_range(c, to)),
+                            Some(_) => return Err(pattern_err(pattern)),
+                        }
+                    } else {
+                        set.extend(escaped_chars(c));
+                    }
+                }
+                if set.is_empty() {
+                    return Err(pattern_err(pattern));
+                }
+                set
+            }
+            '?' | '*' | '+' | '{' | ']' | '}' => return Err(pattern_err(pattern)),
+            c => vec![c],
+        };
+
+        let (min, max) = match chars.peek() {
+            Some('?') => (0, 1),
+            Some('*') => (0, MAX_PATTERN_REPEAT),
+            Some('+') => (1, MAX_PATTERN_REPEAT),
+            Some('{') => {
+                chars.next();
+                let mut quantifier = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => quantifier.push(c),
+                        None => return Err(pattern_err(pattern)),
+                    }
+                }
+                let mut bounds = quantifier.splitn(2, ',').map(|b| b.trim().parse::<usize>());
+                let min = bounds.next().and_then(Result::ok);
+                let max = bounds.next().map(|b| b.ok()).unwrap_or(min);
+                match (min, max) {
+                    (Some(min), Some(max)) if min <= max => (min, max),
+                    _ => return Err(pattern_err(pattern)),
+                }
+            }
+            _ => (1, 1),
+        };
+        if matches!(chars.peek(), Some('?' | '*' | '+')) {
+            chars.next();
+        }
+
+        res.push(PatternPiece {
+            chars: set,
+            min,
+            max,
+        });
+    }
+
+    Ok(res)
+}
+
+fn sample_pattern(rng: &mut StdRng, pieces: &[PatternPiece]) -> String {
+    let mut res = String::new();
+    for p in pieces {
+        for _ in 0..rng.gen_range(p.min..=p.max) {
+            res.push(p.chars[rng.gen_range(0..p.chars.len())]);
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod test_generator {
+    use super::*;
+
+    fn schema() -> Vec<FieldInfo> {
+        vec![
+            FieldInfo::new("id", ValueType::U64),
+            FieldInfo::new("name", ValueType::String),
+            FieldInfo::new("score", ValueType::F64),
+            FieldInfo::new("birth", ValueType::Date),
+            FieldInfo::new(
+                "gender",
+                ValueType::Categorical(Some(vec!["M".to_owned(), "F".to_owned()])),
+            ),
+            FieldInfo::new("uid", ValueType::Uuid),
+        ]
+    }
+
+    #[test]
+    fn generate_success() {
+        let mut generator = Generator::from_field_infos(schema());
+        generator
+            .with_seed(42)
+            .with_rule("id", GeneratorRule::Sequence(1, 1))
+            .with_rule(
+                "name",
+                GeneratorRule::Pattern(String::from(r"[A-Z][a-z]{2,6}")),
+            )
+            .with_rule("score", GeneratorRule::Normal(60.0, 10.0))
+            .with_null_ratio("birth", 0.5)
+            .with_unique("uid");
+
+        let fx = generator.generate(100).unwrap();
+
+        assert_eq!(fx.shape(), (100, 6));
+        assert_eq!(
+            fx.get_column("id").unwrap().get(99).unwrap(),
+            value!(100u64)
+        );
+        assert!(matches!(
+            fx.get_column("gender").unwrap().dtype(),
+            Ok(ValueType::Categorical(_))
+        ));
+        assert_eq!(
+            fx.get_column("birth").unwrap().dtype().unwrap(),
+            &ValueType::Date
+        );
+        assert!(fx.has_null()[3]);
+
+        for v in fx.get_column("name").unwrap().try_iter().unwrap() {
+            match v {
+                Value::String(s) => {
+                    assert!((3..=7).contains(&s.len()));
+                    assert!(s.chars().next().unwrap().is_ascii_uppercase());
+                }
+                v => panic!("unexpected value {:?}", v),
+            }
+        }
+
+        // same seed, same data
+        assert!(fx == generator.generate(100).unwrap());
+    }
+
+    #[test]
+    fn generate_fail() {
+        let mut generator = Generator::from_field_infos(schema());
+        generator.with_rule("foo", GeneratorRule::Sequence(1, 1));
+        assert!(matches!(
+            generator.generate(10),
+            Err(CoreError::NameNotFound(_))
+        ));
+
+        let mut generator = Generator::from_field_infos(schema());
+        generator.with_rule("name", GeneratorRule::Normal(0.0, 1.0));
+        assert!(matches!(
+            generator.generate(10),
+            Err(CoreError::TypeMismatch(_))
+        ));
+
+        let mut generator = Generator::from_field_infos(schema());
+        generator
+            .with_rule("name", GeneratorRule::Pattern(String::from("[AB]")))
+            .with_unique("name");
+        assert!(matches!(
+            generator.generate(10),
+            Err(CoreError::Generator(_))
+        ));
+    }
+}
@@ -7,17 +7,21 @@
 pub mod error;
 pub mod fabrix;
 pub mod fmt;
+pub mod generator;
 pub(crate) mod macros;
 pub mod namedrow;
+pub mod observe;
 pub mod row;
 pub mod schema;
 pub mod series;
+pub mod testing;
 pub mod util;
 pub mod value;
 
 pub use error::*;
 pub use fabrix::*;
 pub use namedrow::*;
+pub use observe::*;
 pub use row::*;
 pub use schema::*;
 pub use series::*;
@@ -577,19 +577,19 @@ pub(crate) use se_series_iterator;
 /// ```rust
 /// let mut map = serializer.serialize_map(Some(3))?;
 /// map.serialize_entry("name", self.name())?;
-/// map.serialize_entry("datatype", self.dtype())?;
-/// map.serialize_entry("values", &self.iter())?
+/// map.serialize_entry("datatype", dtype)?;
+/// map.serialize_entry("values", &self.try_iter().map_err(ser::Error::custom)?)?
 /// map.end()
 /// ```
 macro_rules! se_series {
-    ($sz:expr, $self:expr) => {{
+    ($sz:expr, $self:expr, $dtype:expr) => {{
         let mut map = $sz.serialize_map(Some(3))?;
         // name:
         map.serialize_entry("name", $self.name())?;
         // datatype:
-        map.serialize_entry("datatype", $self.dtype())?;
+        map.serialize_entry("datatype", $dtype)?;
         // values:
-        map.serialize_entry("values", &$self.iter())?;
+        map.serialize_entry("values", &$self.try_iter().map_err(ser::Error::custom)?)?;
 
         map.end()
     }};
@@ -645,6 +645,40 @@ macro_rules! rows {
     }};
 }
 
+/// assert two `Fabrix` are equal, printing the differing cells on failure
+/// Supporting:
+/// 1. default `EqOptions`
+/// 1. given `EqOptions`
+#[macro_export]
+macro_rules! assert_fabrix_eq {
+    ($left:expr, $right:expr $(,)*) => {
+        $crate::assert_fabrix_eq!($left, $right, $crate::testing::EqOptions::default())
+    };
+    ($left:expr, $right:expr, $options:expr $(,)*) => {{
+        let diffs = $crate::testing::fabrix_differences(&$left, &$right, &$options);
+        if !diffs.is_empty() {
+            panic!("{}", $crate::testing::format_differences("Fabrix", &diffs));
+        }
+    }};
+}
+
+/// assert two `Series` are equal, printing the differing cells on failure
+/// Supporting:
+/// 1. default `EqOptions`
+/// 1. given `EqOptions`
+#[macro_export]
+macro_rules! assert_series_eq {
+    ($left:expr, $right:expr $(,)*) => {
+        $crate::assert_series_eq!($left, $right, $crate::testing::EqOptions::default())
+    };
+    ($left:expr, $right:expr, $options:expr $(,)*) => {{
+        let diffs = $crate::testing::series_differences(&$left, &$right, &$options);
+        if !diffs.is_empty() {
+            panic!("{}", $crate::testing::format_differences("Series", &diffs));
+        }
+    }};
+}
+
 #[cfg(test)]
 mod test_macros {
 
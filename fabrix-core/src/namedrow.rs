@@ -177,8 +177,8 @@ impl Fabrix {
         let (data, index) = (
             self.data
                 .iter()
-                .map(|s| (s.name().to_owned(), Value::from(s.get(idx))))
-                .collect::<Vec<_>>(),
+                .map(|s| Ok((s.name().to_owned(), Value::try_from(s.get(idx))?)))
+                .collect::<CoreResult<Vec<_>>>()?,
             self.index_tag().map(|it| it.loc),
         );
 
@@ -198,8 +198,13 @@ impl Fabrix {
         }
     }
 
-    pub fn iter_named_rows(&self) -> IntoIteratorNamedRow {
-        FabrixIterToNamedRow(self).into_iter()
+    /// iterate through the named rows, failing if a column's dtype has no `ValueType`
+    pub fn iter_named_rows(&self) -> CoreResult<IntoIteratorNamedRow> {
+        IntoIteratorNamedRow::new(
+            self.index_tag().map(IndexTag::loc),
+            self.iter_column(),
+            self.height(),
+        )
     }
 }
 
@@ -207,15 +212,30 @@ impl Fabrix {
 // IntoIteratorNamedRow for Fabrix & FabrixDataFrame
 // ================================================================================================
 
-/// FabrixIterToNamedRow
-pub struct FabrixIterToNamedRow<'a>(&'a Fabrix);
-
 pub struct IntoIteratorNamedRow<'a> {
     index: Option<usize>,
     data_iters: Vec<(&'a str, SeriesIterator<'a>)>,
     stepper: Stepper,
 }
 
+impl<'a> IntoIteratorNamedRow<'a> {
+    fn new<I>(index: Option<usize>, columns: I, height: usize) -> CoreResult<Self>
+    where
+        I: IntoIterator<Item = &'a Series>,
+    {
+        let data_iters = columns
+            .into_iter()
+            .map(|s| Ok((s.name(), s.try_iter()?)))
+            .collect::<CoreResult<Vec<_>>>()?;
+
+        Ok(IntoIteratorNamedRow {
+            index,
+            data_iters,
+            stepper: Stepper::new(height),
+        })
+    }
+}
+
 impl<'a> Iterator for IntoIteratorNamedRow<'a> {
     type Item = NamedRow;
 
@@ -235,46 +255,8 @@ impl<'a> Iterator for IntoIteratorNamedRow<'a> {
     }
 }
 
-impl<'a> IntoIterator for FabrixIterToNamedRow<'a> {
-    type Item = NamedRow;
-    type IntoIter = IntoIteratorNamedRow<'a>;
-
-    fn into_iter(self) -> Self::IntoIter {
-        let mut data_iters = Vec::with_capacity(self.0.width());
-        for s in self.0.iter_column() {
-            data_iters.push((s.name(), s.into_iter()));
-        }
-
-        IntoIteratorNamedRow {
-            index: self.0.index_tag().map(IndexTag::loc),
-            data_iters,
-            stepper: Stepper::new(self.0.height()),
-        }
-    }
-}
-
-pub struct FabrixDataFrameIterToNamedRow<'a>(&'a FabrixDataFrame);
-
-impl<'a> IntoIterator for FabrixDataFrameIterToNamedRow<'a> {
-    type Item = NamedRow;
-    type IntoIter = IntoIteratorNamedRow<'a>;
-
-    fn into_iter(self) -> Self::IntoIter {
-        let mut data_iters = Vec::with_capacity(self.0.width());
-        for s in self.0.iter_column() {
-            data_iters.push((s.name(), s.into_iter()));
-        }
-
-        IntoIteratorNamedRow {
-            index: None,
-            data_iters,
-            stepper: Stepper::new(self.0.height()),
-        }
-    }
-}
-
 impl FabrixDataFrame {
-    pub fn iter_named_rows(&self) -> IntoIteratorNamedRow {
-        FabrixDataFrameIterToNamedRow(self).into_iter()
+    pub fn iter_named_rows(&self) -> CoreResult<IntoIteratorNamedRow> {
+        IntoIteratorNamedRow::new(None, self.iter_column(), self.height())
     }
 }
@@ -0,0 +1,353 @@
+//! Observe
+//!
+//! Progress reporting of reading, transforming & writing:
+//! - Stage & ProgressEvent: what happened, reported by dispatchers & executors
+//! - Observer: receives events, closures `Fn(&ProgressEvent)` are observers as well
+//! - StageSpan: times a stage and reports its batches, its end or its failure
+//! - Metrics: in-memory counters, which can be exported as Prometheus text
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::Fabrix;
+
+// ================================================================================================
+// Stage & ProgressEvent
+// ================================================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stage {
+    Read,
+    Transform,
+    Write,
+}
+
+impl Stage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Read => "read",
+            Stage::Transform => "transform",
+            Stage::Write => "write",
+        }
+    }
+}
+
+impl Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// `source` is the source type of a reader or writer, e.g. `csv`, `sqlite`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressEvent<'a> {
+    /// a batch of rows has been processed, `bytes` is an estimated size (0 if unknown)
+    Batch {
+        stage: Stage,
+        source: &'a str,
+        rows: usize,
+        bytes: usize,
+    },
+    /// a stage has finished successfully
+    Finished {
+        stage: Stage,
+        source: &'a str,
+        elapsed: Duration,
+    },
+    /// a stage has failed
+    Failed {
+        stage: Stage,
+        source: &'a str,
+        error: &'a str,
+        elapsed: Duration,
+    },
+}
+
+impl<'a> ProgressEvent<'a> {
+    pub fn stage(&self) -> Stage {
+        match self {
+            ProgressEvent::Batch { stage, .. }
+            | ProgressEvent::Finished { stage, .. }
+            | ProgressEvent::Failed { stage, .. } => *stage,
+        }
+    }
+
+    pub fn source(&self) -> &'a str {
+        match self {
+            ProgressEvent::Batch { source, .. }
+            | ProgressEvent::Finished { source, .. }
+            | ProgressEvent::Failed { source, .. } => source,
+        }
+    }
+}
+
+// ================================================================================================
+// Observer & StageSpan
+// ================================================================================================
+
+pub trait Observer: Send + Sync {
+    fn on_event(&self, event: &ProgressEvent<'_>);
+}
+
+impl<F> Observer for F
+where
+    F: Fn(&ProgressEvent<'_>) + Send + Sync,
+{
+    fn on_event(&self, event: &ProgressEvent<'_>) {
+        self(event)
+    }
+}
+
+pub type SharedObserver = Arc<dyn Observer>;
+
+/// a running stage, nothing is reported without an observer
+pub struct StageSpan {
+    observer: Option<SharedObserver>,
+    stage: Stage,
+    source: String,
+    start: Instant,
+}
+
+impl StageSpan {
+    pub fn start(observer: Option<&SharedObserver>, stage: Stage, source: &str) -> Self {
+        Self {
+            observer: observer.cloned(),
+            stage,
+            source: source.to_owned(),
+            start: Instant::now(),
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    pub fn batch(&self, rows: usize, bytes: usize) {
+        if let Some(o) = self.observer.as_ref() {
+            o.on_event(&ProgressEvent::Batch {
+                stage: self.stage,
+                source: &self.source,
+                rows,
+                bytes,
+            });
+        }
+    }
+
+    /// report the end of the stage, by its result
+    pub fn finish<T, E: Display>(self, result: Result<T, E>) -> Result<T, E> {
+        if let Some(o) = self.observer.as_ref() {
+            let elapsed = self.start.elapsed();
+            match &result {
+                Ok(_) => o.on_event(&ProgressEvent::Finished {
+                    stage: self.stage,
+                    source: &self.source,
+                    elapsed,
+                }),
+                Err(e) => o.on_event(&ProgressEvent::Failed {
+                    stage: self.stage,
+                    source: &self.source,
+                    error: &e.to_string(),
+                    elapsed,
+                }),
+            }
+        }
+
+        result
+    }
+
+    /// report `rows` & `bytes` as a batch if succeeded, then the end of the stage
+    pub fn finish_rows<T, E: Display>(
+        self,
+        result: Result<T, E>,
+        rows: usize,
+        bytes: usize,
+    ) -> Result<T, E> {
+        if result.is_ok() {
+            self.batch(rows, bytes);
+        }
+        self.finish(result)
+    }
+
+    /// report the rows & estimated size of a resulting fabrix as a batch, then the end of the stage
+    pub fn finish_fabrix<E: Display>(self, result: Result<Fabrix, E>) -> Result<Fabrix, E> {
+        if let Ok(fx) = &result {
+            self.batch(fx.height(), fx.estimated_size());
+        }
+        self.finish(result)
+    }
+}
+
+// ================================================================================================
+// Metrics
+// ================================================================================================
+
+/// counters of a stage & source
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct StageMetrics {
+    pub rows: u64,
+    pub bytes: u64,
+    pub batches: u64,
+    /// finished & failed runs
+    pub runs: u64,
+    pub errors: u64,
+    /// total duration of runs
+    pub seconds: f64,
+}
+
+/// an observer keeping in-memory counters, keyed by (stage, source)
+#[derive(Debug, Default)]
+pub struct Metrics {
+    counters: Mutex<BTreeMap<(Stage, String), StageMetrics>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, stage: Stage, source: &str) -> StageMetrics {
+        self.counters
+            .lock()
+            .unwrap()
+            .get(&(stage, source.to_owned()))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<(Stage, String), StageMetrics> {
+        self.counters.lock().unwrap().clone()
+    }
+
+    pub fn reset(&self) {
+        self.counters.lock().unwrap().clear();
+    }
+
+    /// Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        type Getter = fn(&StageMetrics) -> String;
+        let families: [(&str, &str, Getter); 6] = [
+            ("fabrix_rows_total", "Rows processed.", |m| {
+                m.rows.to_string()
+            }),
+            ("fabrix_bytes_total", "Estimated bytes processed.", |m| {
+                m.bytes.to_string()
+            }),
+            ("fabrix_batches_total", "Batches processed.", |m| {
+                m.batches.to_string()
+            }),
+            ("fabrix_runs_total", "Finished or failed runs.", |m| {
+                m.runs.to_string()
+            }),
+            ("fabrix_errors_total", "Failed runs.", |m| {
+                m.errors.to_string()
+            }),
+            ("fabrix_duration_seconds_total", "Duration of runs.", |m| {
+                m.seconds.to_string()
+            }),
+        ];
+
+        let counters = self.counters.lock().unwrap();
+        let mut res = String::new();
+        for (name, help, getter) in families {
+            let _ = writeln!(res, "# HELP {name} {help}");
+            let _ = writeln!(res, "# TYPE {name} counter");
+            for ((stage, source), m) in counters.iter() {
+                let _ = writeln!(
+                    res,
+                    "{name}{{stage=\"{stage}\",source=\"{}\"}} {}",
+                    escape_label(source),
+                    getter(m)
+                );
+            }
+        }
+
+        res
+    }
+}
+
+fn escape_label(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+impl Observer for Metrics {
+    fn on_event(&self, event: &ProgressEvent<'_>) {
+        let mut counters = self.counters.lock().unwrap();
+        let m = counters
+            .entry((event.stage(), event.source().to_owned()))
+            .or_default();
+
+        match event {
+            ProgressEvent::Batch { rows, bytes, .. } => {
+                m.rows += *rows as u64;
+                m.bytes += *bytes as u64;
+                m.batches += 1;
+            }
+            ProgressEvent::Finished { elapsed, .. } => {
+                m.runs += 1;
+                m.seconds += elapsed.as_secs_f64();
+            }
+            ProgressEvent::Failed { elapsed, .. } => {
+                m.runs += 1;
+                m.errors += 1;
+                m.seconds += elapsed.as_secs_f64();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_observe {
+    use super::*;
+
+    #[test]
+    fn test_metrics() {
+        let metrics = Arc::new(Metrics::new());
+        let observer: SharedObserver = metrics.clone();
+
+        let span = StageSpan::start(Some(&observer), Stage::Read, "csv");
+        span.batch(10, 100);
+        span.batch(5, 50);
+        span.finish(Ok::<_, String>(())).unwrap();
+
+        let span = StageSpan::start(Some(&observer), Stage::Write, "sqlite");
+        assert!(span.finish(Err::<(), _>("locked")).is_err());
+
+        let read = metrics.get(Stage::Read, "csv");
+        assert_eq!((read.rows, read.bytes, read.batches), (15, 150, 2));
+        assert_eq!((read.runs, read.errors), (1, 0));
+        let write = metrics.get(Stage::Write, "sqlite");
+        assert_eq!((write.runs, write.errors), (1, 1));
+
+        let text = metrics.to_prometheus();
+        assert!(text.contains("# TYPE fabrix_rows_total counter"));
+        assert!(text.contains("fabrix_rows_total{stage=\"read\",source=\"csv\"} 15"));
+        assert!(text.contains("fabrix_errors_total{stage=\"write\",source=\"sqlite\"} 1"));
+
+        metrics.reset();
+        assert!(metrics.snapshot().is_empty());
+    }
+
+    #[test]
+    fn test_fn_observer() {
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let errors_cloned = errors.clone();
+        let observer: SharedObserver = Arc::new(move |e: &ProgressEvent<'_>| {
+            if let ProgressEvent::Failed { error, .. } = e {
+                errors_cloned.lock().unwrap().push(error.to_string());
+            }
+        });
+
+        let span = StageSpan::start(Some(&observer), Stage::Transform, "cast");
+        let _ = span.finish(Err::<(), _>("invalid type"));
+        assert_eq!(*errors.lock().unwrap(), vec!["invalid type".to_owned()]);
+
+        // no observer
+        let span = StageSpan::start(None, Stage::Read, "csv");
+        span.batch(1, 0);
+        assert!(span.finish(Ok::<_, String>(1)).is_ok());
+    }
+}
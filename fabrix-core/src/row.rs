@@ -261,8 +261,8 @@ impl Fabrix {
         let (data, index) = (
             self.data
                 .iter()
-                .map(|s| Value::from(s.get(idx)))
-                .collect::<Vec<_>>(),
+                .map(|s| Value::try_from(s.get(idx)))
+                .collect::<CoreResult<Vec<_>>>()?,
             self.index_tag().map(|it| it.loc),
         );
 
@@ -344,9 +344,13 @@ impl Fabrix {
         }
     }
 
-    /// iterate through the rows of the dataframe, same as `into_iter()`
-    pub fn iter_rows(&self) -> IntoIteratorRow {
-        FabrixIterToRow(self).into_iter()
+    /// iterate through the rows of the dataframe, failing if a column's dtype has no `ValueType`
+    pub fn iter_rows(&self) -> CoreResult<IntoIteratorRow> {
+        IntoIteratorRow::new(
+            self.index_tag().map(IndexTag::loc),
+            self.iter_column(),
+            self.height(),
+        )
     }
 }
 
@@ -354,15 +358,30 @@ impl Fabrix {
 // IntoIteratorRow for Fabrix & FabrixDataFrame
 // ================================================================================================
 
-/// FabrixIterToRow
-pub struct FabrixIterToRow<'a>(&'a Fabrix);
-
 pub struct IntoIteratorRow<'a> {
     index: Option<usize>,
     data_iters: Vec<SeriesIterator<'a>>,
     stepper: Stepper,
 }
 
+impl<'a> IntoIteratorRow<'a> {
+    fn new<I>(index: Option<usize>, columns: I, height: usize) -> CoreResult<Self>
+    where
+        I: IntoIterator<Item = &'a Series>,
+    {
+        let data_iters = columns
+            .into_iter()
+            .map(Series::try_iter)
+            .collect::<CoreResult<Vec<_>>>()?;
+
+        Ok(IntoIteratorRow {
+            index,
+            data_iters,
+            stepper: Stepper::new(height),
+        })
+    }
+}
+
 impl<'a> Iterator for IntoIteratorRow<'a> {
     type Item = Row;
 
@@ -382,48 +401,9 @@ impl<'a> Iterator for IntoIteratorRow<'a> {
     }
 }
 
-impl<'a> IntoIterator for FabrixIterToRow<'a> {
-    type Item = Row;
-    type IntoIter = IntoIteratorRow<'a>;
-
-    fn into_iter(self) -> Self::IntoIter {
-        let mut data_iters = Vec::with_capacity(self.0.width());
-        for s in self.0.iter_column() {
-            data_iters.push(s.into_iter());
-        }
-
-        IntoIteratorRow {
-            index: self.0.index_tag().map(IndexTag::loc),
-            data_iters,
-            stepper: Stepper::new(self.0.height()),
-        }
-    }
-}
-
-/// FabrixDataFrameIterToRow
-pub struct FabrixDataFrameIterToRow<'a>(&'a FabrixDataFrame);
-
-impl<'a> IntoIterator for FabrixDataFrameIterToRow<'a> {
-    type Item = Row;
-    type IntoIter = IntoIteratorRow<'a>;
-
-    fn into_iter(self) -> Self::IntoIter {
-        let mut data_iters = Vec::with_capacity(self.0.width());
-        for s in self.0.iter_column() {
-            data_iters.push(s.into_iter());
-        }
-
-        IntoIteratorRow {
-            index: None,
-            data_iters,
-            stepper: Stepper::new(self.0.height()),
-        }
-    }
-}
-
 impl FabrixDataFrame {
-    pub fn iter_rows(&self) -> IntoIteratorRow {
-        FabrixDataFrameIterToRow(self).into_iter()
+    pub fn iter_rows(&self) -> CoreResult<IntoIteratorRow> {
+        IntoIteratorRow::new(None, self.iter_column(), self.height())
     }
 }
 
@@ -551,7 +531,7 @@ mod test_row {
         ]
         .unwrap();
 
-        let mut iter = fx.iter_rows();
+        let mut iter = fx.iter_rows().unwrap();
 
         let r1 = iter.next();
         assert!(r1.is_some());
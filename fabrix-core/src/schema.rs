@@ -5,7 +5,7 @@
 use polars::datatypes::Field as PolarsField;
 use polars::prelude::Schema as PolarsSchema;
 
-use crate::ValueType;
+use crate::{CoreError, CoreResult, ValueType};
 
 /// field info: column name, column type & has null
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
@@ -38,21 +38,25 @@ impl From<FieldInfo> for PolarsField {
     }
 }
 
-impl From<PolarsField> for FieldInfo {
-    fn from(f: PolarsField) -> Self {
-        FieldInfo {
+impl TryFrom<PolarsField> for FieldInfo {
+    type Error = CoreError;
+
+    fn try_from(f: PolarsField) -> Result<Self, Self::Error> {
+        Ok(FieldInfo {
             name: f.name().to_owned(),
-            dtype: f.data_type().into(),
-        }
+            dtype: f.data_type().try_into()?,
+        })
     }
 }
 
-impl From<&PolarsField> for FieldInfo {
-    fn from(f: &PolarsField) -> Self {
-        FieldInfo {
+impl TryFrom<&PolarsField> for FieldInfo {
+    type Error = CoreError;
+
+    fn try_from(f: &PolarsField) -> Result<Self, Self::Error> {
+        Ok(FieldInfo {
             name: f.name().to_owned(),
-            dtype: f.data_type().into(),
-        }
+            dtype: f.data_type().try_into()?,
+        })
     }
 }
 
@@ -110,17 +114,24 @@ impl Schema {
         self.0.merge(other.0);
     }
 
-    pub fn get(&self, index: usize) -> Option<FieldInfo> {
-        self.0.get_index(index).map(|(name, dtype)| FieldInfo {
-            name: name.to_owned(),
-            dtype: dtype.clone().into(),
-        })
-    }
-
-    pub fn iter(&self) -> impl Iterator<Item = FieldInfo> + '_ {
-        self.0.iter().map(|(name, dtype)| FieldInfo {
-            name: name.to_owned(),
-            dtype: dtype.clone().into(),
+    pub fn get(&self, index: usize) -> CoreResult<Option<FieldInfo>> {
+        self.0
+            .get_index(index)
+            .map(|(name, dtype)| {
+                Ok(FieldInfo {
+                    name: name.to_owned(),
+                    dtype: dtype.try_into()?,
+                })
+            })
+            .transpose()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = CoreResult<FieldInfo>> + '_ {
+        self.0.iter().map(|(name, dtype)| {
+            Ok(FieldInfo {
+                name: name.to_owned(),
+                dtype: dtype.try_into()?,
+            })
         })
     }
 }
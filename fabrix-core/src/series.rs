@@ -43,11 +43,11 @@ use std::str::FromStr;
 use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
 use itertools::Itertools;
 use polars::prelude::{
-    BooleanChunked, BooleanType, DateChunked, DatetimeChunked, Float32Chunked, Float32Type,
-    Float64Chunked, Float64Type, Int16Chunked, Int16Type, Int32Chunked, Int32Type, Int64Chunked,
-    Int64Type, Int8Chunked, Int8Type, NamedFromOwned, ObjectChunked, TakeRandom, TakeRandomUtf8,
-    TimeChunked, UInt16Chunked, UInt16Type, UInt32Chunked, UInt32Type, UInt64Chunked, UInt64Type,
-    UInt8Chunked, UInt8Type, Utf8Chunked, Utf8Type,
+    BooleanChunked, BooleanType, CategoricalChunked, DateChunked, DatetimeChunked, Float32Chunked,
+    Float32Type, Float64Chunked, Float64Type, Int16Chunked, Int16Type, Int32Chunked, Int32Type,
+    Int64Chunked, Int64Type, Int8Chunked, Int8Type, NamedFromOwned, ObjectChunked, TakeRandom,
+    TakeRandomUtf8, TimeChunked, UInt16Chunked, UInt16Type, UInt32Chunked, UInt32Type,
+    UInt64Chunked, UInt64Type, UInt8Chunked, UInt8Type, Utf8Chunked, Utf8Type,
 };
 use polars::prelude::{
     DataType, Field, IntoSeries, NamedFrom, NewChunkedArray, Series as PolarsSeries, TimeUnit,
@@ -55,16 +55,16 @@ use polars::prelude::{
 use ref_cast::RefCast;
 use serde::de::{MapAccess, Visitor};
 use serde::ser::{SerializeMap, SerializeSeq};
-use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
 
 use super::{
     chunked_array_from_values, cis_err, impl_named_from_owned, impl_named_from_ref, oob_err,
-    s_fn_next, sc_fn_next, sfv, si, tms_err, FieldInfo, ObjectTypeBytes, ObjectTypeDecimal,
-    ObjectTypeUuid, Stepper, BYTES, DAYS19700101, DECIMAL, IDX, NANO10E9, UUID,
+    s_fn_next, sc_fn_next, sfv, si, tms_err, vnf_err, FieldInfo, ObjectTypeBytes,
+    ObjectTypeDecimal, ObjectTypeUuid, Stepper, BYTES, DAYS19700101, DECIMAL, IDX, NANO10E9, UUID,
 };
 use crate::{
-    de_series_values, se_series, se_series_iterator, series, value, Bytes, CoreResult, Decimal,
-    Uuid, Value, Value2ChronoHelper, ValueType,
+    de_series_values, se_series, se_series_iterator, series, value, Bytes, CoreError, CoreResult,
+    Decimal, Uuid, Value, Value2ChronoHelper, ValueType,
 };
 
 // ================================================================================================
@@ -351,16 +351,46 @@ impl Series {
     }
 
     /// show Series type
-    pub fn dtype(&self) -> &ValueType {
-        self.data().dtype().into()
+    pub fn dtype(&self) -> CoreResult<&ValueType> {
+        self.data().dtype().try_into()
     }
 
     /// get series field
-    pub fn field(&self) -> FieldInfo {
+    pub fn field(&self) -> CoreResult<FieldInfo> {
         let name = self.name();
-        let dtype = self.dtype();
+        // owned conversion, which keeps the category list of a categorical
+        let dtype = ValueType::try_from(self.data().dtype())?;
+
+        Ok((name, &dtype).into())
+    }
+
+    /// cast to another type. When casting into a `Categorical` with a fixed category list,
+    /// values out of the list are rejected, and the list is kept by the result's dtype.
+    pub fn cast(&self, dtype: &ValueType) -> CoreResult<Series> {
+        if let ValueType::Categorical(Some(categories)) = dtype {
+            let s = self.data().cast(&DataType::Utf8)?;
+            let unknown = s
+                .utf8()?
+                .into_iter()
+                .flatten()
+                .find(|v| !categories.iter().any(|c| c == v));
+            if let Some(v) = unknown {
+                return Err(vnf_err(&value!(v)));
+            }
+
+            // polars maps categories in order of appearance: prefixing the values with the
+            // category list makes the mapping hold every category, even those without a value
+            let mut prefixed = Utf8Chunked::from_slice(self.name(), categories).into_series();
+            prefixed.append(&s)?;
+            let c = prefixed
+                .rechunk()
+                .cast(&DataType::Categorical(None))?
+                .slice(categories.len() as i64, self.len());
+
+            return Ok(Series(c));
+        }
 
-        (name, dtype).into()
+        Ok(Series(self.data().cast(&dtype.into())?))
     }
 
     /// check whether the series is empty
@@ -390,7 +420,7 @@ impl Series {
         if idx >= len {
             Err(oob_err(idx, len))
         } else {
-            Ok(value!(self.data().get(idx)))
+            Value::try_from(self.data().get(idx))
         }
     }
 
@@ -405,26 +435,29 @@ impl Series {
         self.data().slice(offset, length).into()
     }
 
-    /// check Series whether contains a value (`self.into_iter` is not zero copy)
+    /// check Series whether contains a value (`self.try_iter` is not zero copy), a series
+    /// without a `ValueType` contains no value
     pub fn contains(&self, val: &Value) -> bool {
-        self.iter().contains(val)
+        self.try_iter().map_or(false, |mut i| i.contains(val))
     }
 
-    /// find idx by a Value (`self.into_iter` is not zero copy)
+    /// find idx by a Value (`self.try_iter` is not zero copy)
     pub fn find_index(&self, val: &Value) -> Option<usize> {
-        self.iter().position(|ref e| e == val)
+        self.try_iter().ok()?.position(|ref e| e == val)
     }
 
-    /// find idx vector by a Series (`self.into_iter` is not zero copy)
+    /// find idx vector by a Series (`self.try_iter` is not zero copy)
     pub fn find_indices(&self, series: &Series) -> Vec<usize> {
-        self.iter()
-            .enumerate()
-            .fold(Vec::new(), |mut accum, (idx, e)| {
-                if series.contains(&e) {
-                    accum.push(idx);
-                }
-                accum
-            })
+        let iter = match self.try_iter() {
+            Ok(iter) => iter,
+            Err(_) => return Vec::new(),
+        };
+        iter.enumerate().fold(Vec::new(), |mut accum, (idx, e)| {
+            if series.contains(&e) {
+                accum.push(idx);
+            }
+            accum
+        })
     }
 
     /// split into two series
@@ -549,8 +582,37 @@ impl Series {
         unimplemented!()
     }
 
-    pub fn iter(&self) -> SeriesIterator {
-        self.into_iter()
+    /// iterate over values, failing if the dtype of the series has no `ValueType`, e.g. `List`
+    /// or `Duration`
+    pub fn try_iter(&self) -> CoreResult<SeriesIterator> {
+        let dtype = self
+            .dtype()
+            .map_err(|_| CoreError::NotSupported(format!("iterating {}", self.0.dtype())))?;
+
+        let iter = match dtype {
+            ValueType::Bool => si!(self.0.bool(), Bool),
+            ValueType::U8 => si!(self.0.u8(), U8),
+            ValueType::U16 => si!(self.0.u16(), U16),
+            ValueType::U32 => si!(self.0.u32(), U32),
+            ValueType::U64 => si!(self.0.u64(), U64),
+            ValueType::I8 => si!(self.0.i8(), I8),
+            ValueType::I16 => si!(self.0.i16(), I16),
+            ValueType::I32 => si!(self.0.i32(), I32),
+            ValueType::I64 => si!(self.0.i64(), I64),
+            ValueType::F32 => si!(self.0.f32(), F32),
+            ValueType::F64 => si!(self.0.f64(), F64),
+            ValueType::String => si!(self.0.utf8(), String),
+            ValueType::Date => si!(self.0.date(), Date),
+            ValueType::Time => si!(self.0.time(), Time),
+            ValueType::DateTime => si!(self.0.datetime(), DateTime),
+            ValueType::Decimal => si!(self.0.as_any(), Decimal, Decimal),
+            ValueType::Uuid => si!(self.0.as_any(), Uuid, Uuid),
+            ValueType::Bytes => si!(self.0.as_any(), Bytes, Bytes),
+            ValueType::Categorical(_) => si!(self.0.categorical(), Categorical),
+            ValueType::Null => SeriesIterator::Null(Stepper::new(self.len())),
+        };
+
+        Ok(iter)
     }
 }
 
@@ -601,7 +663,10 @@ fn from_values(values: Vec<Value>, name: &str, nullable: bool) -> CoreResult<Ser
     match opt_dtype {
         Some(v) => match ValueType::from(v) {
             ValueType::Bool => sfv!(nullable; name, values; bool, BooleanType),
-            ValueType::String => sfv!(nullable; name, values; String, Utf8Type),
+            // `Value` has no categorical variant, this arm is never reached
+            ValueType::String | ValueType::Categorical(_) => {
+                sfv!(nullable; name, values; String, Utf8Type)
+            }
             ValueType::U8 => sfv!(nullable; name, values; u8, UInt8Type),
             ValueType::U16 => sfv!(nullable; name, values; u16, UInt16Type),
             ValueType::U32 => sfv!(nullable; name, values; u32, UInt32Type),
@@ -614,25 +679,25 @@ fn from_values(values: Vec<Value>, name: &str, nullable: bool) -> CoreResult<Ser
             ValueType::F64 => sfv!(nullable; name, values; f64, Float64Type),
             ValueType::Date => {
                 let ca = if nullable {
-                    chunked_array_from_values!(name, values; i32, Int32Type)
-                } else {
                     chunked_array_from_values!(name, values; Option<i32>, Int32Type)
+                } else {
+                    chunked_array_from_values!(name, values; i32, Int32Type)
                 };
                 Ok(Series(ca.into_date().into_series()))
             }
             ValueType::Time => {
                 let ca = if nullable {
-                    chunked_array_from_values!(name, values; i64, Int64Type)
-                } else {
                     chunked_array_from_values!(name, values; Option<i64>, Int64Type)
+                } else {
+                    chunked_array_from_values!(name, values; i64, Int64Type)
                 };
                 Ok(Series(ca.into_time().into_series()))
             }
             ValueType::DateTime => {
                 let ca = if nullable {
-                    chunked_array_from_values!(name, values; i64, Int64Type)
-                } else {
                     chunked_array_from_values!(name, values; Option<i64>, Int64Type)
+                } else {
+                    chunked_array_from_values!(name, values; i64, Int64Type)
                 };
                 let s = ca.into_datetime(TimeUnit::Nanoseconds, None).into_series();
                 Ok(Series(s))
@@ -679,8 +744,15 @@ fn empty_series_from_field(field: &Field, nullable: bool) -> CoreResult<Series>
         DataType::Object(DECIMAL) => sfv!(nullable; field.name(); Decimal, ObjectTypeDecimal),
         DataType::Object(UUID) => sfv!(nullable; field.name(); Uuid, ObjectTypeUuid),
         DataType::Object(BYTES) => sfv!(nullable; field.name(); Bytes, ObjectTypeBytes),
+        DataType::Categorical(_) => {
+            let s = Utf8Chunked::from_slice(field.name(), &[] as &[&str]).into_series();
+            Ok(Series(s.cast(&DataType::Categorical(None))?))
+        }
         DataType::Null => sfv!(nullable; field.name(); u64, UInt64Type),
-        _ => unimplemented!(),
+        dtype => Err(CoreError::NotSupported(format!(
+            "an empty series of {}",
+            dtype
+        ))),
     }
 }
 
@@ -715,39 +787,9 @@ impl AsRef<Series> for PolarsSeries {
 }
 
 // ================================================================================================
-// IntoIterator impls
+// SeriesIterator
 // ================================================================================================
 
-impl<'a> IntoIterator for &'a Series {
-    type Item = Value;
-    type IntoIter = SeriesIterator<'a>;
-
-    fn into_iter(self) -> Self::IntoIter {
-        match self.dtype() {
-            ValueType::Bool => si!(self.0.bool(), Bool),
-            ValueType::U8 => si!(self.0.u8(), U8),
-            ValueType::U16 => si!(self.0.u16(), U16),
-            ValueType::U32 => si!(self.0.u32(), U32),
-            ValueType::U64 => si!(self.0.u64(), U64),
-            ValueType::I8 => si!(self.0.i8(), I8),
-            ValueType::I16 => si!(self.0.i16(), I16),
-            ValueType::I32 => si!(self.0.i32(), I32),
-            ValueType::I64 => si!(self.0.i64(), I64),
-            ValueType::F32 => si!(self.0.f32(), F32),
-            ValueType::F64 => si!(self.0.f64(), F64),
-            ValueType::String => si!(self.0.utf8(), String),
-            ValueType::Date => si!(self.0.date(), Date),
-            ValueType::Time => si!(self.0.time(), Time),
-            ValueType::DateTime => si!(self.0.datetime(), DateTime),
-            ValueType::Decimal => si!(self.0.as_any(), Decimal, Decimal),
-            ValueType::Uuid => si!(self.0.as_any(), Uuid, Uuid),
-            ValueType::Bytes => si!(self.0.as_any(), Bytes, Bytes),
-            // temporary ignore the rest of DataType variants
-            _ => unimplemented!(),
-        }
-    }
-}
-
 pub enum SeriesIterator<'a> {
     Id(&'a UInt64Chunked, Stepper),
     Bool(&'a BooleanChunked, Stepper),
@@ -768,6 +810,8 @@ pub enum SeriesIterator<'a> {
     Decimal(&'a ObjectChunked<Decimal>, Stepper),
     Uuid(&'a ObjectChunked<Uuid>, Stepper),
     Bytes(&'a ObjectChunked<Bytes>, Stepper),
+    Categorical(&'a CategoricalChunked, Stepper),
+    Null(Stepper),
 }
 
 impl<'a> Iterator for SeriesIterator<'a> {
@@ -794,6 +838,24 @@ impl<'a> Iterator for SeriesIterator<'a> {
             SeriesIterator::Decimal(arr, s) => sc_fn_next!(arr, s),
             SeriesIterator::Uuid(arr, s) => sc_fn_next!(arr, s),
             SeriesIterator::Bytes(arr, s) => sc_fn_next!(arr, s),
+            SeriesIterator::Categorical(arr, s) => {
+                if s.exhausted() {
+                    None
+                } else {
+                    let rev_map = arr.get_rev_map();
+                    let res = value!(arr.logical().get(s.step).map(|i| rev_map.get(i)));
+                    s.forward();
+                    Some(res)
+                }
+            }
+            SeriesIterator::Null(s) => {
+                if s.exhausted() {
+                    None
+                } else {
+                    s.forward();
+                    Some(Value::Null)
+                }
+            }
         }
     }
 }
@@ -827,6 +889,12 @@ impl<'a> Serialize for SeriesIterator<'a> {
             SeriesIterator::Decimal(arr, s) => se_series_iterator!(serializer, arr, s),
             SeriesIterator::Uuid(arr, s) => se_series_iterator!(serializer, arr, s),
             SeriesIterator::Bytes(arr, s) => se_series_iterator!(serializer, arr, s),
+            SeriesIterator::Categorical(arr, s) => {
+                se_series_iterator!(serializer, arr.iter_str(), s)
+            }
+            SeriesIterator::Null(s) => {
+                se_series_iterator!(serializer, (0..s.len).map(|_| Value::Null), s)
+            }
         }
     }
 }
@@ -836,26 +904,28 @@ impl Serialize for Series {
     where
         S: Serializer,
     {
-        match self.dtype() {
-            ValueType::Bool => se_series!(serializer, self),
-            ValueType::U8 => se_series!(serializer, self),
-            ValueType::U16 => se_series!(serializer, self),
-            ValueType::U32 => se_series!(serializer, self),
-            ValueType::U64 => se_series!(serializer, self),
-            ValueType::I8 => se_series!(serializer, self),
-            ValueType::I16 => se_series!(serializer, self),
-            ValueType::I32 => se_series!(serializer, self),
-            ValueType::I64 => se_series!(serializer, self),
-            ValueType::F32 => se_series!(serializer, self),
-            ValueType::F64 => se_series!(serializer, self),
-            ValueType::Date => se_series!(serializer, self),
-            ValueType::Time => se_series!(serializer, self),
-            ValueType::DateTime => se_series!(serializer, self),
-            ValueType::String => se_series!(serializer, self),
-            ValueType::Decimal => se_series!(serializer, self),
-            ValueType::Uuid => se_series!(serializer, self),
-            ValueType::Bytes => se_series!(serializer, self),
-            ValueType::Null => se_series!(serializer, self),
+        let dtype = self.dtype().map_err(ser::Error::custom)?;
+        match dtype {
+            ValueType::Bool => se_series!(serializer, self, dtype),
+            ValueType::U8 => se_series!(serializer, self, dtype),
+            ValueType::U16 => se_series!(serializer, self, dtype),
+            ValueType::U32 => se_series!(serializer, self, dtype),
+            ValueType::U64 => se_series!(serializer, self, dtype),
+            ValueType::I8 => se_series!(serializer, self, dtype),
+            ValueType::I16 => se_series!(serializer, self, dtype),
+            ValueType::I32 => se_series!(serializer, self, dtype),
+            ValueType::I64 => se_series!(serializer, self, dtype),
+            ValueType::F32 => se_series!(serializer, self, dtype),
+            ValueType::F64 => se_series!(serializer, self, dtype),
+            ValueType::Date => se_series!(serializer, self, dtype),
+            ValueType::Time => se_series!(serializer, self, dtype),
+            ValueType::DateTime => se_series!(serializer, self, dtype),
+            ValueType::String => se_series!(serializer, self, dtype),
+            ValueType::Decimal => se_series!(serializer, self, dtype),
+            ValueType::Uuid => se_series!(serializer, self, dtype),
+            ValueType::Bytes => se_series!(serializer, self, dtype),
+            ValueType::Categorical(_) => se_series!(serializer, self, dtype),
+            ValueType::Null => se_series!(serializer, self, dtype),
         }
     }
 }
@@ -977,7 +1047,16 @@ impl<'de> Deserialize<'de> for Series {
                             .collect::<Vec<_>>();
                         Ok(Series::new(&name, values))
                     }
-                    _ => unimplemented!("not implemented"),
+                    ValueType::Categorical(_) => {
+                        let values: Vec<Option<String>> = map.next_value()?;
+                        Series::new(&name, values)
+                            .cast(&dtype)
+                            .map_err(de::Error::custom)
+                    }
+                    dtype => Err(de::Error::custom(CoreError::NotSupported(format!(
+                        "deserializing a series of {}",
+                        dtype
+                    )))),
                 }
             }
         }
@@ -990,44 +1069,29 @@ impl<'de> Deserialize<'de> for Series {
 // PartialEq
 // ================================================================================================
 
+/// compare two series of the same dtype value by value
+fn values_eq(l: &Series, r: &Series) -> bool {
+    if l.len() != r.len() || l.name() != r.name() {
+        return false;
+    }
+    match (l.try_iter(), r.try_iter()) {
+        (Ok(li), Ok(ri)) => li.zip(ri).all(|(l, r)| l == r),
+        _ => false,
+    }
+}
+
 impl PartialEq for Series {
     fn eq(&self, other: &Self) -> bool {
-        match (&self.dtype(), other.dtype()) {
-            (ValueType::Decimal, ValueType::Decimal) => {
-                if self.len() != other.len() || self.name() != other.name() {
-                    return false;
-                }
-                for (l, r) in self.iter().zip(other.iter()) {
-                    if l != r {
-                        return false;
-                    }
-                }
-                true
-            }
-            (ValueType::Uuid, ValueType::Uuid) => {
-                if self.len() != other.len() || self.name() != other.name() {
-                    return false;
-                }
-                for (l, r) in self.iter().zip(other.iter()) {
-                    if l != r {
-                        return false;
-                    }
-                }
-                true
-            }
-            (ValueType::Bytes, ValueType::Bytes) => {
-                if self.len() != other.len() || self.name() != other.name() {
-                    return false;
-                }
-                for (l, r) in self.iter().zip(other.iter()) {
-                    if l != r {
-                        return false;
-                    }
-                }
-                true
+        match (self.dtype().ok(), other.dtype().ok()) {
+            (Some(ValueType::Decimal), Some(ValueType::Decimal))
+            | (Some(ValueType::Uuid), Some(ValueType::Uuid))
+            | (Some(ValueType::Bytes), Some(ValueType::Bytes)) => values_eq(self, other),
+            // categoricals from different sources cannot be compared by polars
+            (Some(ValueType::Categorical(_)), Some(ValueType::Categorical(_))) => {
+                values_eq(self, other)
             }
             (s, o) => {
-                if *s == o {
+                if s == o {
                     self.0 == other.0
                 } else {
                     false
@@ -1041,7 +1105,31 @@ impl PartialEq for Series {
 mod test_fabrix_series {
 
     use super::*;
-    use crate::{bytes, date, datetime, decimal, series, time, uuid, value};
+    use crate::{bytes, date, datetime, decimal, series, time, uuid, value, CoreError};
+
+    #[test]
+    fn series_from_nullable_temporal_values_success() {
+        // nullable date/time/datetime series keep their nulls
+        let values = vec![Value::Date(19000), Value::Null, Value::Date(19001)];
+        let s = Series::from_values(values, "date", true).unwrap();
+        assert_eq!(s.dtype().unwrap(), &ValueType::Date);
+        assert_eq!(s.get(1).unwrap(), Value::Null);
+
+        let values = vec![Value::Null, Value::Time(1_000), Value::Null];
+        let s = Series::from_values(values, "time", true).unwrap();
+        assert_eq!(s.dtype().unwrap(), &ValueType::Time);
+        assert_eq!(s.get(1).unwrap(), Value::Time(1_000));
+        assert_eq!(s.get(2).unwrap(), Value::Null);
+
+        let values = vec![Value::DateTime(1_000), Value::Null];
+        let s = Series::from_values(values, "datetime", true).unwrap();
+        assert_eq!(s.dtype().unwrap(), &ValueType::DateTime);
+        assert_eq!(s.get(1).unwrap(), Value::Null);
+
+        let values = vec![Value::DateTime(1_000), Value::DateTime(2_000)];
+        let s = Series::from_values(values, "datetime", false).unwrap();
+        assert!(!s.has_null());
+    }
 
     #[test]
     fn series_from_ref_success() {
@@ -1049,13 +1137,41 @@ mod test_fabrix_series {
         println!("{:?}", s);
     }
 
+    #[test]
+    fn series_cast_categorical_success() {
+        let s = series!("gender" => ["M", "F", "M"]);
+
+        let categories = vec!["M".to_owned(), "F".to_owned()];
+        let c = s.cast(&ValueType::Categorical(Some(categories))).unwrap();
+        assert!(matches!(c.dtype(), Ok(ValueType::Categorical(_))));
+        assert_eq!(c.get(1).unwrap(), value!("F"));
+
+        let values = c.try_iter().unwrap().collect::<Vec<_>>();
+        assert_eq!(values, vec![value!("M"), value!("F"), value!("M")]);
+
+        let categories = vec!["M".to_owned()];
+        let c = s.cast(&ValueType::Categorical(Some(categories)));
+        assert!(matches!(c, Err(CoreError::ValueNotFound(_))));
+
+        // categories without any value are kept
+        let categories = vec!["M".to_owned(), "F".to_owned(), "X".to_owned()];
+        let c = s
+            .cast(&ValueType::Categorical(Some(categories.clone())))
+            .unwrap();
+        assert_eq!(c.len(), 3);
+        assert_eq!(
+            c.field().unwrap().dtype(),
+            &ValueType::Categorical(Some(categories))
+        );
+    }
+
     #[test]
     fn series_creation_success() {
         let s = Series::from_integer_default_name(10u32);
         assert!(s.is_ok());
 
         let s = s.unwrap();
-        assert_eq!(s.dtype(), &ValueType::U32);
+        assert_eq!(s.dtype().unwrap(), &ValueType::U32);
         assert_eq!(s.get(9).unwrap(), value!(9u32));
         assert_eq!(s.take(&[0, 3, 9]).unwrap().len(), 3);
 
@@ -1063,7 +1179,7 @@ mod test_fabrix_series {
         assert!(s.is_ok());
 
         let s = s.unwrap();
-        assert_eq!(s.dtype(), &ValueType::U8);
+        assert_eq!(s.dtype().unwrap(), &ValueType::U8);
         assert!(s.get(100).is_err(), "get value by exceeded index is error");
         assert_eq!(s.take(&[0, 4]).unwrap().len(), 2);
 
@@ -1078,7 +1194,7 @@ mod test_fabrix_series {
         assert!(s.is_ok());
 
         let s = s.unwrap();
-        assert_eq!(s.dtype(), &ValueType::String);
+        assert_eq!(s.dtype().unwrap(), &ValueType::String);
 
         // `Series::from_values` & `Series::from_values_default_name`
         // if nullable is false, it will be in a strict mode, any type who mismatched will
@@ -1120,7 +1236,7 @@ mod test_fabrix_series {
         ]);
 
         println!("date:");
-        for i in s.into_iter() {
+        for i in s.try_iter().unwrap() {
             println!("{:?}", i);
 
             if let Value::Date(v) = i {
@@ -1142,7 +1258,7 @@ mod test_fabrix_series {
         ]);
 
         println!("time:");
-        for i in s.into_iter() {
+        for i in s.try_iter().unwrap() {
             println!("{:?}", i);
 
             if let Value::Time(v) = i {
@@ -1164,7 +1280,7 @@ mod test_fabrix_series {
         ]);
 
         println!("datetime:");
-        for i in s.into_iter() {
+        for i in s.try_iter().unwrap() {
             println!("{:?}", i);
 
             if let Value::DateTime(v) = i {
@@ -1177,7 +1293,7 @@ mod test_fabrix_series {
         // ==========================
 
         let s = series!("dollars" => ["Jacob", "Sam", "James", "April", "Julia", "Jack", "Henry"]);
-        let mut iter = s.into_iter();
+        let mut iter = s.try_iter().unwrap();
 
         assert_eq!(iter.next().unwrap(), value!("Jacob"));
         assert_eq!(iter.next().unwrap(), value!("Sam"));
@@ -1189,6 +1305,29 @@ mod test_fabrix_series {
         assert!(iter.next().is_none());
     }
 
+    #[test]
+    fn test_series_iteration_unsupported() {
+        let ps = PolarsSeries::new("d", &[1i64, 2])
+            .cast(&DataType::Duration(TimeUnit::Milliseconds))
+            .unwrap();
+        let s = Series::from(ps);
+        assert!(matches!(s.try_iter(), Err(CoreError::NotSupported(_))));
+        assert!(!s.contains(&value!(1i64)));
+        assert!(serde_json::to_string(&s).is_err());
+
+        let field = Field::new("d", DataType::Duration(TimeUnit::Milliseconds));
+        assert!(matches!(
+            Series::empty_series_from_field(&field, true),
+            Err(CoreError::NotSupported(_))
+        ));
+
+        let s = Series::from(PolarsSeries::new("u", &[1u32, 2]));
+        assert_eq!(
+            s.try_iter().unwrap().collect::<Vec<_>>(),
+            vec![value!(1u32), value!(2u32)]
+        );
+    }
+
     #[test]
     fn series_se_and_de() {
         let s = series!([
@@ -1287,10 +1426,10 @@ mod test_fabrix_series {
                 date!(2019, 1, 4),
             ],
         );
-        assert_eq!(s.dtype(), &ValueType::Date);
+        assert_eq!(s.dtype().unwrap(), &ValueType::Date);
 
         let s = Series::new("num", &[1u8, 3, 5, 7, 9]);
-        assert_eq!(s.dtype(), &ValueType::U8);
+        assert_eq!(s.dtype().unwrap(), &ValueType::U8);
     }
 
     #[test]
@@ -1416,7 +1555,7 @@ mod test_fabrix_series {
 
         println!("{:?}", s2);
 
-        let mut iter = s2.into_iter();
+        let mut iter = s2.try_iter().unwrap();
 
         assert_eq!(iter.next().unwrap(), value!("Jacob!"));
         assert_eq!(iter.next().unwrap(), value!("Sam!"));
@@ -0,0 +1,378 @@
+//! Fabrix testing
+//!
+//! Assertion helpers for comparing `Fabrix` and `Series` with readable diffs.
+//!
+//! Unlike `PartialEq`, the comparison collects every difference it finds, so that a failed
+//! assertion reports which cells differ. Used by `assert_fabrix_eq!` & `assert_series_eq!`.
+
+use std::fmt::Display;
+
+use crate::{Fabrix, IndexTag, Series, Value, ValueType};
+
+/// maximum number of differences printed by a failed assertion
+const MAX_REPORTED_DIFFERENCES: usize = 20;
+
+/// options for comparing `Fabrix` and `Series`
+#[derive(Debug, Clone)]
+pub struct EqOptions {
+    /// absolute tolerance for comparing floats, `None` means exact comparison
+    pub float_tolerance: Option<f64>,
+    /// match columns by name instead of by position
+    pub ignore_column_order: bool,
+    /// treat numbers of the same kind but different width (e.g. `I32` & `I64`) as equal
+    pub ignore_dtype_width: bool,
+    /// compare the index tag of both `Fabrix`
+    pub check_index_tag: bool,
+}
+
+impl Default for EqOptions {
+    fn default() -> Self {
+        Self {
+            float_tolerance: None,
+            ignore_column_order: false,
+            ignore_dtype_width: false,
+            check_index_tag: true,
+        }
+    }
+}
+
+impl EqOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_float_tolerance(&mut self, tolerance: f64) -> &mut Self {
+        self.float_tolerance = Some(tolerance);
+        self
+    }
+
+    pub fn with_ignore_column_order(&mut self, ignore: bool) -> &mut Self {
+        self.ignore_column_order = ignore;
+        self
+    }
+
+    pub fn with_ignore_dtype_width(&mut self, ignore: bool) -> &mut Self {
+        self.ignore_dtype_width = ignore;
+        self
+    }
+
+    pub fn with_check_index_tag(&mut self, check: bool) -> &mut Self {
+        self.check_index_tag = check;
+        self
+    }
+}
+
+/// a single difference found between two `Fabrix` or `Series`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Difference {
+    IndexTag(Option<IndexTag>, Option<IndexTag>),
+    Shape((usize, usize), (usize, usize)),
+    ColumnNames(Vec<String>, Vec<String>),
+    MissingColumn(String),
+    ExtraColumn(String),
+    Name(String, String),
+    Dtype(String, Option<ValueType>, Option<ValueType>),
+    Length(String, usize, usize),
+    Cell(String, usize, Value, Value),
+    UnsupportedDtype(String, String),
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Difference::IndexTag(l, r) => write!(f, "index tag: {:?} != {:?}", l, r),
+            Difference::Shape(l, r) => write!(f, "shape: {:?} != {:?}", l, r),
+            Difference::ColumnNames(l, r) => write!(f, "column names: {:?} != {:?}", l, r),
+            Difference::MissingColumn(c) => write!(f, "column {:?} missing on the left", c),
+            Difference::ExtraColumn(c) => write!(f, "column {:?} missing on the right", c),
+            Difference::Name(l, r) => write!(f, "name: {:?} != {:?}", l, r),
+            Difference::Dtype(c, l, r) => write!(f, "column {:?} dtype: {:?} != {:?}", c, l, r),
+            Difference::Length(c, l, r) => write!(f, "column {:?} length: {} != {}", c, l, r),
+            Difference::Cell(c, i, l, r) => write!(f, "column {:?} row {}: {} != {}", c, i, l, r),
+            Difference::UnsupportedDtype(c, t) => {
+                write!(f, "column {:?} dtype {} cannot be compared", c, t)
+            }
+        }
+    }
+}
+
+/// collect the differences between two `Series`, names included
+pub fn series_differences(left: &Series, right: &Series, options: &EqOptions) -> Vec<Difference> {
+    let mut res = Vec::new();
+
+    if left.name() != right.name() {
+        res.push(Difference::Name(
+            left.name().to_owned(),
+            right.name().to_owned(),
+        ));
+    }
+
+    res.extend(values_differences(left, right, options));
+
+    res
+}
+
+/// collect the differences between two `Fabrix`
+pub fn fabrix_differences(left: &Fabrix, right: &Fabrix, options: &EqOptions) -> Vec<Difference> {
+    let mut res = Vec::new();
+
+    if options.check_index_tag && left.index_tag() != right.index_tag() {
+        res.push(Difference::IndexTag(
+            left.index_tag().cloned(),
+            right.index_tag().cloned(),
+        ));
+    }
+
+    if left.shape() != right.shape() {
+        res.push(Difference::Shape(left.shape(), right.shape()));
+    }
+
+    let left_names = left.get_column_names();
+    let right_names = right.get_column_names();
+
+    let pairs = if options.ignore_column_order {
+        for name in right_names.iter().filter(|n| !left_names.contains(n)) {
+            res.push(Difference::MissingColumn(name.to_string()));
+        }
+        for name in left_names.iter().filter(|n| !right_names.contains(n)) {
+            res.push(Difference::ExtraColumn(name.to_string()));
+        }
+
+        left.iter_column()
+            .filter_map(|l| right.get_column(l.name()).ok().map(|r| (l, r)))
+            .collect::<Vec<_>>()
+    } else {
+        if left_names != right_names {
+            res.push(Difference::ColumnNames(
+                left_names.iter().map(|n| n.to_string()).collect(),
+                right_names.iter().map(|n| n.to_string()).collect(),
+            ));
+        }
+
+        left.iter_column().zip(right.iter_column()).collect()
+    };
+
+    for (l, r) in pairs {
+        res.extend(values_differences(l, r, options));
+    }
+
+    res
+}
+
+/// render the differences as a failure message
+pub fn format_differences(title: &str, differences: &[Difference]) -> String {
+    let mut res = format!(
+        "{} are not equal, {} difference(s):",
+        title,
+        differences.len()
+    );
+
+    for d in differences.iter().take(MAX_REPORTED_DIFFERENCES) {
+        res.push_str(&format!("\n  {}", d));
+    }
+
+    if differences.len() > MAX_REPORTED_DIFFERENCES {
+        res.push_str(&format!(
+            "\n  ... and {} more",
+            differences.len() - MAX_REPORTED_DIFFERENCES
+        ));
+    }
+
+    res
+}
+
+/// compare dtypes & values of two `Series`, ignoring their names
+fn values_differences(left: &Series, right: &Series, options: &EqOptions) -> Vec<Difference> {
+    let name = left.name().to_owned();
+    let mut res = Vec::new();
+
+    let left_dtype = left.dtype().ok().cloned();
+    let right_dtype = right.dtype().ok().cloned();
+    let dtype_eq = match (&left_dtype, &right_dtype) {
+        (Some(l), Some(r)) if options.ignore_dtype_width => widen_dtype(l) == widen_dtype(r),
+        (l, r) => l == r,
+    };
+    if !dtype_eq {
+        res.push(Difference::Dtype(name, left_dtype, right_dtype));
+        return res;
+    }
+
+    if left.len() != right.len() {
+        res.push(Difference::Length(name, left.len(), right.len()));
+        return res;
+    }
+
+    // values of a dtype without a `ValueType` cannot be compared, which is a difference itself
+    let (left_iter, right_iter) = match (left.try_iter(), right.try_iter()) {
+        (Ok(l), Ok(r)) => (l, r),
+        (Err(_), _) => {
+            res.push(Difference::UnsupportedDtype(
+                name,
+                left.data().dtype().to_string(),
+            ));
+            return res;
+        }
+        (_, Err(_)) => {
+            res.push(Difference::UnsupportedDtype(
+                name,
+                right.data().dtype().to_string(),
+            ));
+            return res;
+        }
+    };
+
+    for (idx, (l, r)) in left_iter.zip(right_iter).enumerate() {
+        if !value_eq(&l, &r, options) {
+            res.push(Difference::Cell(name.clone(), idx, l, r));
+        }
+    }
+
+    res
+}
+
+fn value_eq(left: &Value, right: &Value, options: &EqOptions) -> bool {
+    let (left, right) = if options.ignore_dtype_width {
+        (widen_value(left), widen_value(right))
+    } else {
+        (left.clone(), right.clone())
+    };
+
+    match (&left, &right, options.float_tolerance) {
+        (Value::F32(l), Value::F32(r), tol) => float_eq(*l as f64, *r as f64, tol),
+        (Value::F64(l), Value::F64(r), tol) => float_eq(*l, *r, tol),
+        _ => left == right,
+    }
+}
+
+fn float_eq(left: f64, right: f64, tolerance: Option<f64>) -> bool {
+    if left.is_nan() && right.is_nan() {
+        return true;
+    }
+    match tolerance {
+        Some(tol) => (left - right).abs() <= tol,
+        None => left == right,
+    }
+}
+
+fn widen_dtype(dtype: &ValueType) -> ValueType {
+    match dtype {
+        ValueType::U8 | ValueType::U16 | ValueType::U32 | ValueType::U64 => ValueType::U64,
+        ValueType::I8 | ValueType::I16 | ValueType::I32 | ValueType::I64 => ValueType::I64,
+        ValueType::F32 | ValueType::F64 => ValueType::F64,
+        t => t.clone(),
+    }
+}
+
+fn widen_value(value: &Value) -> Value {
+    match value {
+        Value::U8(v) => Value::U64(*v as u64),
+        Value::U16(v) => Value::U64(*v as u64),
+        Value::U32(v) => Value::U64(*v as u64),
+        Value::I8(v) => Value::I64(*v as i64),
+        Value::I16(v) => Value::I64(*v as i64),
+        Value::I32(v) => Value::I64(*v as i64),
+        Value::F32(v) => Value::F64(*v as f64),
+        v => v.clone(),
+    }
+}
+
+#[cfg(test)]
+mod test_fabrix_testing {
+    use super::*;
+    use crate::{fx, series};
+
+    #[test]
+    fn series_differences_success() {
+        let s1 = series!("val" => [1.0, 2.0, 3.0]);
+        let s2 = series!("val" => [1.0, 2.001, 4.0]);
+
+        let mut options = EqOptions::new();
+        options.with_float_tolerance(0.01);
+
+        let diffs = series_differences(&s1, &s2, &options);
+        assert_eq!(
+            diffs,
+            vec![Difference::Cell(
+                "val".to_owned(),
+                2,
+                Value::F64(3.0),
+                Value::F64(4.0)
+            )]
+        );
+    }
+
+    #[test]
+    fn series_differences_unsupported_dtype() {
+        let ps = polars::prelude::Series::new("d", &[1i64, 2])
+            .cast(&polars::prelude::DataType::Duration(
+                polars::prelude::TimeUnit::Milliseconds,
+            ))
+            .unwrap();
+        let s1 = Series::from(ps.clone());
+        let s2 = Series::from(ps);
+
+        let diffs = series_differences(&s1, &s2, &EqOptions::default());
+        assert!(matches!(
+            diffs.as_slice(),
+            [Difference::UnsupportedDtype(c, _)] if c == "d"
+        ));
+    }
+
+    #[test]
+    fn fabrix_differences_success() {
+        let df1 = fx![
+            "ord";
+            "ord" => [1, 2, 3],
+            "names" => ["Jacob", "Sam", "James"],
+        ]
+        .unwrap();
+        let df2 = fx![
+            "ord";
+            "names" => ["Jacob", "Sam", "Jason"],
+            "ord" => [1i64, 2, 3],
+        ]
+        .unwrap();
+
+        let mut options = EqOptions::new();
+        options
+            .with_ignore_column_order(true)
+            .with_ignore_dtype_width(true)
+            .with_check_index_tag(false);
+
+        let diffs = fabrix_differences(&df1, &df2, &options);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(
+            diffs[0].to_string(),
+            r#"column "names" row 2: James != Jason"#
+        );
+
+        let diffs = fabrix_differences(&df1, &df2, &EqOptions::default());
+        assert!(diffs
+            .iter()
+            .any(|d| matches!(d, Difference::ColumnNames(_, _))));
+    }
+
+    #[test]
+    fn assert_macros_success() {
+        let df1 = fx!["ord" => [1, 2, 3], "val" => [0.1, 0.2, 0.3]].unwrap();
+        let df2 = fx!["ord" => [1, 2, 3], "val" => [0.1, 0.2, 0.30001]].unwrap();
+
+        let mut options = EqOptions::new();
+        options.with_float_tolerance(1e-3);
+
+        crate::assert_fabrix_eq!(df1, df2, options);
+        crate::assert_series_eq!(
+            df1.get_column("ord").unwrap(),
+            df2.get_column("ord").unwrap()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = r#"column "val" row 2: 0.3 != 0.30001"#)]
+    fn assert_macros_fail() {
+        let df1 = fx!["ord" => [1, 2, 3], "val" => [0.1, 0.2, 0.3]].unwrap();
+        let df2 = fx!["ord" => [1, 2, 3], "val" => [0.1, 0.2, 0.30001]].unwrap();
+
+        crate::assert_fabrix_eq!(df1, df2);
+    }
+}
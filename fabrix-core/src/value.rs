@@ -10,10 +10,12 @@ use std::any::Any;
 use std::borrow::Cow;
 use std::fmt::{Debug, Display};
 use std::str::FromStr;
+use std::sync::Arc;
 
 use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
 use polars::chunked_array::object::PolarsObjectSafe;
-use polars::prelude::{AnyValue, DataType, Field, ObjectType, PolarsObject, TimeUnit};
+use polars::export::arrow::array::Utf8Array;
+use polars::prelude::{AnyValue, DataType, Field, ObjectType, PolarsObject, RevMapping, TimeUnit};
 use serde::{Deserialize, Serialize};
 
 use crate::{CoreError, CoreResult};
@@ -246,6 +248,9 @@ pub enum ValueType {
     Decimal,
     Uuid,
     Bytes,
+    /// Low-cardinality strings, with an optional fixed category list.
+    /// `None` means the categories are not fixed.
+    Categorical(Option<Vec<String>>),
     Null,
 }
 
@@ -259,6 +264,7 @@ impl std::fmt::Display for ValueType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ValueType::Null => write!(f, "null"),
+            ValueType::Categorical(_) => write!(f, "Categorical"),
             _ => write!(f, "{:?}", self),
         }
     }
@@ -401,14 +407,25 @@ impl From<&ValueType> for DataType {
             ValueType::Decimal => DataType::Object(DECIMAL),
             ValueType::Uuid => DataType::Object(UUID),
             ValueType::Bytes => DataType::Object(BYTES),
+            ValueType::Categorical(categories) => categorical_dtype(categories.as_deref()),
             ValueType::Null => DataType::Null,
         }
     }
 }
 
-impl From<&DataType> for &ValueType {
-    fn from(v: &DataType) -> Self {
-        match v {
+/// polars categorical type, whose mapping holds the fixed category list if any
+fn categorical_dtype(categories: Option<&[String]>) -> DataType {
+    let rev_map = categories.map(|c| Arc::new(RevMapping::Local(Utf8Array::<i64>::from_slice(c))));
+    DataType::Categorical(rev_map)
+}
+
+/// Borrowed conversion, which cannot hold a category list: a categorical is always
+/// `Categorical(None)`. Use the owned `ValueType::try_from` to keep the list.
+impl TryFrom<&DataType> for &ValueType {
+    type Error = CoreError;
+
+    fn try_from(v: &DataType) -> Result<Self, Self::Error> {
+        let vt = match v {
             DataType::Boolean => &ValueType::Bool,
             DataType::UInt8 => &ValueType::U8,
             DataType::UInt16 => &ValueType::U16,
@@ -427,9 +444,12 @@ impl From<&DataType> for &ValueType {
             DataType::Object(DECIMAL) => &ValueType::Decimal,
             DataType::Object(UUID) => &ValueType::Uuid,
             DataType::Object(BYTES) => &ValueType::Bytes,
+            DataType::Categorical(_) => &ValueType::Categorical(None),
             DataType::Null => &ValueType::Null,
-            _ => unimplemented!(),
-        }
+            _ => return Err(dtype_mismatch(v)),
+        };
+
+        Ok(vt)
     }
 }
 
@@ -439,33 +459,6 @@ impl From<ValueType> for DataType {
     }
 }
 
-impl AsRef<ValueType> for DataType {
-    fn as_ref(&self) -> &ValueType {
-        match &self {
-            DataType::Boolean => &ValueType::Bool,
-            DataType::UInt8 => &ValueType::U8,
-            DataType::UInt16 => &ValueType::U16,
-            DataType::UInt32 => &ValueType::U32,
-            DataType::UInt64 => &ValueType::U32,
-            DataType::Int8 => &ValueType::I8,
-            DataType::Int16 => &ValueType::I16,
-            DataType::Int32 => &ValueType::I32,
-            DataType::Int64 => &ValueType::I64,
-            DataType::Float32 => &ValueType::F32,
-            DataType::Float64 => &ValueType::F64,
-            DataType::Date => &ValueType::Date,
-            DataType::Time => &ValueType::Time,
-            DataType::Datetime(TimeUnit::Nanoseconds, None) => &ValueType::DateTime,
-            DataType::Utf8 => &ValueType::String,
-            DataType::Object(DECIMAL) => &ValueType::Decimal,
-            DataType::Object(UUID) => &ValueType::Uuid,
-            DataType::Object(BYTES) => &ValueType::Bytes,
-            DataType::Null => &ValueType::Null,
-            _ => unimplemented!(),
-        }
-    }
-}
-
 impl AsRef<DataType> for ValueType {
     fn as_ref(&self) -> &DataType {
         match &self {
@@ -487,14 +480,17 @@ impl AsRef<DataType> for ValueType {
             ValueType::Decimal => &DataType::Object(DECIMAL),
             ValueType::Uuid => &DataType::Object(UUID),
             ValueType::Bytes => &DataType::Object(BYTES),
+            ValueType::Categorical(_) => &DataType::Categorical(None),
             ValueType::Null => &DataType::Null,
         }
     }
 }
 
-impl From<&DataType> for ValueType {
-    fn from(v: &DataType) -> Self {
-        match v {
+impl TryFrom<&DataType> for ValueType {
+    type Error = CoreError;
+
+    fn try_from(v: &DataType) -> Result<Self, Self::Error> {
+        let vt = match v {
             DataType::Boolean => ValueType::Bool,
             DataType::UInt8 => ValueType::U8,
             DataType::UInt16 => ValueType::U16,
@@ -513,16 +509,46 @@ impl From<&DataType> for ValueType {
             DataType::Object(DECIMAL) => ValueType::Decimal,
             DataType::Object(UUID) => ValueType::Uuid,
             DataType::Object(BYTES) => ValueType::Bytes,
+            DataType::Categorical(rev_map) => {
+                ValueType::Categorical(rev_map.as_ref().map(|r| rev_map_categories(r)))
+            }
             DataType::Null => ValueType::Null,
-            _ => unimplemented!(),
+            _ => return Err(dtype_mismatch(v)),
+        };
+
+        Ok(vt)
+    }
+}
+
+/// categories held by a polars categorical mapping
+fn rev_map_categories(rev_map: &RevMapping) -> Vec<String> {
+    match rev_map {
+        RevMapping::Global(_, cats, _) | RevMapping::Local(cats) => {
+            cats.values_iter().map(String::from).collect()
         }
     }
 }
 
+/// Polars `DataType`s which have no corresponding `ValueType`, i.e. List, Struct, Duration and
+/// Datetime with a timezone or a non-nanosecond unit.
+fn dtype_mismatch(dtype: &DataType) -> CoreError {
+    let name = match dtype {
+        DataType::Datetime(_, Some(_)) => "Datetime with timezone",
+        DataType::Datetime(_, None) => "Datetime with non-nanosecond unit",
+        DataType::Duration(_) => "Duration",
+        DataType::List(_) => "List",
+        DataType::Object(_) => "Object",
+        DataType::Struct(_) => "Struct",
+        _ => "Unknown",
+    };
+    CoreError::TypeMismatch(name)
+}
+
 // ================================================================================================
 // Wrappers for the Polars DataType enum
 // ================================================================================================
 
+#[derive(Clone)]
 pub struct ValueTypes {
     inner: Vec<DataType>,
 }
@@ -573,9 +599,11 @@ impl From<ValueTypes> for Vec<DataType> {
 // Conversions of Polars DataType & ValueType
 // ================================================================================================
 
-impl From<DataType> for ValueType {
-    fn from(v: DataType) -> Self {
-        ValueType::from(&v)
+impl TryFrom<DataType> for ValueType {
+    type Error = CoreError;
+
+    fn try_from(v: DataType) -> Result<Self, Self::Error> {
+        ValueType::try_from(&v)
     }
 }
 
@@ -632,6 +660,7 @@ impl From<&ValueType> for Field {
             ValueType::Decimal => Field::new("", DataType::Object(DECIMAL)),
             ValueType::Uuid => Field::new("", DataType::Object(UUID)),
             ValueType::Bytes => Field::new("", DataType::Object(BYTES)),
+            ValueType::Categorical(c) => Field::new("", categorical_dtype(c.as_deref())),
             ValueType::Null => Field::new("", DataType::Null),
         }
     }
@@ -682,12 +711,15 @@ impl From<&dyn PolarsObjectSafe> for Value {
 
 /// Type conversion: polars' AnyValue -> Value. Beware performance, it usually used in getting values
 /// from a Series, or getting a row from a DataFrame through iterating Vec<Series> it owned.
-impl<'a> From<AnyValue<'a>> for Value {
-    fn from(av: AnyValue<'a>) -> Self {
-        match av {
+impl<'a> TryFrom<AnyValue<'a>> for Value {
+    type Error = CoreError;
+
+    fn try_from(av: AnyValue<'a>) -> Result<Self, Self::Error> {
+        let v = match av {
             AnyValue::Null => Value::Null,
             AnyValue::Boolean(v) => Value::Bool(v),
             AnyValue::Utf8(v) => Value::String(v.to_owned()),
+            AnyValue::Utf8Owned(v) => Value::String(v),
             AnyValue::UInt8(v) => Value::U8(v),
             AnyValue::UInt16(v) => Value::U16(v),
             AnyValue::UInt32(v) => Value::U32(v),
@@ -702,8 +734,21 @@ impl<'a> From<AnyValue<'a>> for Value {
             AnyValue::Time(v) => Value::Time(v),
             AnyValue::Datetime(v, TimeUnit::Nanoseconds, None) => Value::DateTime(v),
             AnyValue::Object(v) => v.into(),
-            _ => unimplemented!(),
-        }
+            AnyValue::Datetime(_, _, Some(_)) => {
+                return Err(CoreError::TypeMismatch("Datetime with timezone"))
+            }
+            AnyValue::Datetime(_, _, None) => {
+                return Err(CoreError::TypeMismatch("Datetime with non-nanosecond unit"))
+            }
+            AnyValue::Duration(_, _) => return Err(CoreError::TypeMismatch("Duration")),
+            AnyValue::Categorical(idx, rev_map) => Value::String(rev_map.get(idx).to_owned()),
+            AnyValue::List(_) => return Err(CoreError::TypeMismatch("List")),
+            AnyValue::Struct(_, _) | AnyValue::StructOwned(_) => {
+                return Err(CoreError::TypeMismatch("Struct"))
+            }
+        };
+
+        Ok(v)
     }
 }
 
@@ -1096,4 +1141,32 @@ mod test_value {
         println!("{:?}", polars_time);
         println!("{:?}", polars_datetime);
     }
+
+    #[test]
+    fn unsupported_dtype_conversion_fail() {
+        use polars::prelude::{AnyValue, TimeUnit};
+
+        let list = DataType::List(Box::new(DataType::Int32));
+        assert!(matches!(
+            ValueType::try_from(&list),
+            Err(CoreError::TypeMismatch("List"))
+        ));
+
+        let dt_tz = DataType::Datetime(TimeUnit::Nanoseconds, Some("UTC".to_owned()));
+        assert!(matches!(
+            <&ValueType>::try_from(&dt_tz),
+            Err(CoreError::TypeMismatch("Datetime with timezone"))
+        ));
+
+        let duration = AnyValue::Duration(1, TimeUnit::Milliseconds);
+        assert!(matches!(
+            Value::try_from(duration),
+            Err(CoreError::TypeMismatch("Duration"))
+        ));
+
+        assert_eq!(
+            ValueType::try_from(DataType::UInt64).unwrap(),
+            ValueType::U64
+        );
+    }
 }
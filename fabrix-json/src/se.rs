@@ -2,7 +2,7 @@
 
 use fabrix_core::polars::prelude::DataFrame;
 use fabrix_core::ValueType;
-use serde::ser::SerializeMap;
+use serde::ser::{self, SerializeMap};
 use serde::{ser::SerializeSeq, Serializer};
 
 pub(crate) fn dataframe_column_wise_serialize<S>(df: &DataFrame, s: S) -> Result<S::Ok, S::Error>
@@ -23,10 +23,13 @@ where
 {
     let fx = df.as_ref();
     let mut m = s.serialize_map(Some(2))?;
-    let types = fx.dtypes();
+    let types = fx.dtypes().map_err(ser::Error::custom)?;
     m.serialize_entry("types", &types)?;
 
-    let values = fx.iter_named_rows().collect::<Vec<_>>();
+    let values = fx
+        .iter_named_rows()
+        .map_err(ser::Error::custom)?
+        .collect::<Vec<_>>();
     m.serialize_entry("values", &values)?;
 
     m.end()
@@ -41,6 +44,7 @@ where
 
     let (names, types) = fx
         .fields()
+        .map_err(ser::Error::custom)?
         .into_iter()
         .map(|f| (f.name, f.dtype))
         .unzip::<String, ValueType, Vec<_>, Vec<_>>();
@@ -48,7 +52,10 @@ where
     m.serialize_entry("names", &names)?;
     m.serialize_entry("types", &types)?;
 
-    let values = fx.iter_rows().collect::<Vec<_>>();
+    let values = fx
+        .iter_rows()
+        .map_err(ser::Error::custom)?
+        .collect::<Vec<_>>();
     m.serialize_entry("values", &values)?;
 
     m.end()
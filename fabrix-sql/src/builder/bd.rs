@@ -101,6 +101,7 @@ fn from_data_type_to_null_svalue(dtype: &ValueType) -> SValue {
         ValueType::F32 => SValue::Float(None),
         ValueType::F64 => SValue::Double(None),
         ValueType::String => SValue::String(None),
+        ValueType::Categorical(_) => SValue::String(None),
         ValueType::Date => SValue::ChronoDate(None),
         ValueType::Time => SValue::ChronoTime(None),
         ValueType::DateTime => SValue::ChronoDateTime(None),
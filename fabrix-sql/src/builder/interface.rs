@@ -31,6 +31,8 @@ pub trait DdlMutation {
         if_not_exists: Option<bool>,
     ) -> String;
 
+    fn create_enum_types(&self, table_name: &str, columns: &[FieldInfo]) -> Vec<String>;
+
     fn alter_table(&self, alter: &sql_adt::AlterTable) -> String;
 
     fn drop_table(&self, table_name: &str) -> String;
@@ -54,6 +56,8 @@ pub trait DmlQuery {
     fn select_existing_ids(&self, table_name: &str, index: &Series) -> SqlResult<String>;
 
     fn select(&self, select: &sql_adt::Select) -> String;
+
+    fn count_rows(&self, table_name: &str) -> String;
 }
 
 // DML Mutation
@@ -1,7 +1,8 @@
 //! Sql Builder: ddl mutation
 
 use fabrix_core::{FieldInfo, ValueType};
-use sea_query::{ColumnDef, ForeignKey, ForeignKeyAction, Index, Table};
+use sea_query::extension::postgres::Type;
+use sea_query::{ColumnDef, ForeignKey, ForeignKeyAction, Index, PostgresQueryBuilder, Table};
 
 use super::{alias, sql_adt, statement};
 use crate::{DdlMutation, SqlBuilder};
@@ -27,12 +28,51 @@ impl DdlMutation for SqlBuilder {
         }
 
         columns.iter().for_each(|c| {
-            statement.col(&mut gen_col(c));
+            statement.col(&mut gen_col(table_name, c));
         });
 
         statement!(self, statement)
     }
 
+    /// Postgres enums are standalone types, which should exist before the table. Each categorical
+    /// column with fixed categories yields a statement creating its type unless it already exists,
+    /// followed by a statement adding each category missing from an existing type. An existing
+    /// type is never dropped, since other tables may depend on it.
+    ///
+    /// A value added to an existing enum type cannot be used before it is committed, so these
+    /// statements should not run in the transaction inserting the data.
+    fn create_enum_types(&self, table_name: &str, columns: &[FieldInfo]) -> Vec<String> {
+        match self {
+            SqlBuilder::Postgres => columns
+                .iter()
+                .filter_map(|c| match c.dtype() {
+                    ValueType::Categorical(Some(categories)) => {
+                        let name = enum_type_name(table_name, c.name());
+                        let create = Type::create()
+                            .as_enum(alias!(&name))
+                            .values(categories.iter().map(|v| alias!(v)))
+                            .to_owned();
+                        let create = format!(
+                            "DO $$ BEGIN {}; EXCEPTION WHEN duplicate_object THEN NULL; END $$",
+                            create.to_string(PostgresQueryBuilder)
+                        );
+                        let add_values = categories.iter().map(move |v| {
+                            format!(
+                                "ALTER TYPE {} ADD VALUE IF NOT EXISTS {}",
+                                pg_ident(&name),
+                                pg_literal(v)
+                            )
+                        });
+                        Some(std::iter::once(create).chain(add_values))
+                    }
+                    _ => None,
+                })
+                .flatten()
+                .collect(),
+            _ => vec![],
+        }
+    }
+
     fn alter_table(&self, alter: &sql_adt::AlterTable) -> String {
         let mut statement = Table::alter();
         match alter {
@@ -45,7 +85,7 @@ impl DdlMutation for SqlBuilder {
                 statement.table(alias!(table));
                 //
                 let field = FieldInfo::new(column, dtype.clone());
-                statement.add_column(&mut gen_col(&field));
+                statement.add_column(&mut gen_col(table, &field));
             }
             sql_adt::AlterTable::Delete { table, column } => {
                 statement.table(alias!(table));
@@ -60,7 +100,7 @@ impl DdlMutation for SqlBuilder {
             } => {
                 statement.table(alias!(table));
                 let field = FieldInfo::new(column, dtype.clone());
-                statement.modify_column(&mut gen_col(&field));
+                statement.modify_column(&mut gen_col(table, &field));
             }
         };
 
@@ -158,8 +198,23 @@ fn gen_primary_col(index_option: &sql_adt::IndexOption) -> ColumnDef {
 
 // TODO: is_nullable
 
+/// enum type name of a categorical column, only used by Postgres
+fn enum_type_name(table_name: &str, column_name: &str) -> String {
+    format!("{table_name}_{column_name}")
+}
+
+/// a quoted Postgres identifier, case-sensitive
+fn pg_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// a quoted Postgres string literal
+fn pg_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
 /// generate column by `DataframeColumn`
-fn gen_col(field: &FieldInfo) -> ColumnDef {
+fn gen_col(table_name: &str, field: &FieldInfo) -> ColumnDef {
     let mut c = ColumnDef::new(alias!(field.name()));
     match field.dtype() {
         ValueType::Bool => c.boolean(),
@@ -179,6 +234,12 @@ fn gen_col(field: &FieldInfo) -> ColumnDef {
         ValueType::DateTime => c.date_time(),
         ValueType::Decimal => c.decimal(),
         ValueType::Uuid => c.uuid(),
+        // quoted as its `CREATE TYPE`, so that a mixed-case name refers to the same type
+        ValueType::Categorical(Some(categories)) => c.enumeration(
+            pg_ident(&enum_type_name(table_name, field.name())),
+            categories,
+        ),
+        ValueType::Categorical(None) => c.string(),
         _ => unimplemented!(),
     };
 
@@ -254,6 +315,65 @@ mod test_mutation_ddl {
         );
     }
 
+    #[test]
+    fn test_create_table_with_categorical() {
+        let categories = vec!["M".to_string(), "F".to_string()];
+        let columns = [
+            FieldInfo::new("name", ValueType::String),
+            FieldInfo::new("gender", ValueType::Categorical(Some(categories))),
+            FieldInfo::new("status", ValueType::Categorical(None)),
+        ];
+
+        let create_table = SqlBuilder::Mysql.create_table("test", &columns, None, None);
+
+        assert_eq!(
+            create_table,
+            r#"CREATE TABLE `test` ( `name` varchar(255), `gender` ENUM('M', 'F'), `status` varchar(255) )"#
+        );
+
+        let enum_types = SqlBuilder::Postgres.create_enum_types("test", &columns);
+        let create_table = SqlBuilder::Postgres.create_table("test", &columns, None, None);
+
+        assert_eq!(
+            enum_types,
+            vec![
+                r#"DO $$ BEGIN CREATE TYPE "test_gender" AS ENUM ('M', 'F'); EXCEPTION WHEN duplicate_object THEN NULL; END $$"#,
+                r#"ALTER TYPE "test_gender" ADD VALUE IF NOT EXISTS 'M'"#,
+                r#"ALTER TYPE "test_gender" ADD VALUE IF NOT EXISTS 'F'"#,
+            ]
+        );
+        assert_eq!(
+            create_table,
+            r#"CREATE TABLE "test" ( "name" varchar, "gender" "test_gender", "status" varchar )"#
+        );
+
+        assert!(SqlBuilder::Sqlite
+            .create_enum_types("test", &columns)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_enum_types_mixed_case() {
+        let categories = vec!["New".to_string(), "Won't fix".to_string()];
+        let columns = [FieldInfo::new(
+            "Status",
+            ValueType::Categorical(Some(categories)),
+        )];
+
+        let enum_types = SqlBuilder::Postgres.create_enum_types("Tickets", &columns);
+        let create_table = SqlBuilder::Postgres.create_table("Tickets", &columns, None, None);
+
+        assert!(enum_types[0].contains(r#"CREATE TYPE "Tickets_Status" AS ENUM"#));
+        assert_eq!(
+            enum_types[2],
+            r#"ALTER TYPE "Tickets_Status" ADD VALUE IF NOT EXISTS 'Won''t fix'"#
+        );
+        assert_eq!(
+            create_table,
+            r#"CREATE TABLE "Tickets" ( "Status" "Tickets_Status" )"#
+        );
+    }
+
     #[test]
     fn test_delete_table() {
         let delete_table = SqlBuilder::Sqlite.drop_table("test");
@@ -14,15 +14,14 @@ impl DmlMutation for SqlBuilder {
         // given a table name, insert into it
         statement.into_table(alias!(table_name));
 
-        let columns = fx
-            .fields()
+        let column_info = fx.fields()?;
+        let columns = column_info
             .iter()
             .map(|c| alias!(c.name()))
             .collect::<Vec<_>>();
         statement.columns(columns);
-        let column_info = fx.fields();
 
-        for row in fx.iter_rows() {
+        for row in fx.iter_rows()? {
             let record = row
                 .data
                 .into_iter()
@@ -44,11 +43,11 @@ impl DmlMutation for SqlBuilder {
     fn update(&self, table_name: &str, fx: Fabrix) -> SqlResult<String> {
         match fx.index_tag() {
             Some(it) => {
-                let column_info = fx.fields();
+                let column_info = fx.fields()?;
                 let column_name = it.name().to_owned();
                 let column_loc = it.loc();
                 let mut res = String::new();
-                for row in fx.iter_rows() {
+                for row in fx.iter_rows()? {
                     let mut statement = Query::update();
                     statement.table(alias!(table_name));
 
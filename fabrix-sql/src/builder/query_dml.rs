@@ -1,7 +1,7 @@
 //! Sql Builder: dml query
 
 use fabrix_core::Series;
-use sea_query::{Expr, Order, Query};
+use sea_query::{Expr, Func, Order, Query};
 
 use super::{
     alias, column_builder, filter_builder, join_builder, sql_adt, statement,
@@ -13,7 +13,7 @@ impl DmlQuery for SqlBuilder {
     /// given a list of ids, check existed ids (used for `upsert` method). Make sure index contains only not-null values
     fn select_existing_ids(&self, table_name: &str, index: &Series) -> SqlResult<String> {
         let mut statement = Query::select();
-        let (index_name, index_dtype) = (index.name(), index.dtype());
+        let (index_name, index_dtype) = (index.name(), index.dtype()?);
         let ids = index
             .into_iter()
             .map(|i| try_from_value_to_svalue(i, index_dtype, false))
@@ -27,6 +27,16 @@ impl DmlQuery for SqlBuilder {
         Ok(statement!(self, statement))
     }
 
+    /// count rows of an existing table
+    fn count_rows(&self, table_name: &str) -> String {
+        let mut statement = Query::select();
+        statement
+            .expr(Func::count(Expr::asterisk()))
+            .from(alias!(table_name));
+
+        statement!(self, statement)
+    }
+
     /// select from an existing table
     fn select(&self, select: &sql_adt::Select) -> String {
         let mut statement = Query::select();
@@ -102,6 +112,12 @@ mod test_query_dml {
         assert!(sql.is_ok(), "select_ids should not fail");
     }
 
+    #[test]
+    fn count_rows() {
+        let sql = SqlBuilder::Postgres.count_rows("dev");
+        assert_eq!(sql, r#"SELECT COUNT(*) FROM "dev""#);
+    }
+
     #[test]
     fn simple_select() {
         let filter = xpr!([
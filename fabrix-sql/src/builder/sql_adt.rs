@@ -899,6 +899,55 @@ pub enum SaveStrategy {
     Upsert,
 }
 
+// ================================================================================================
+// SavePlan
+// ================================================================================================
+
+/// A dry run of saving data into a table, see `SqlEngine::plan`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SavePlan {
+    pub table: String,
+    pub strategy: SaveStrategy,
+    pub table_exists: bool,
+    /// schema of the existing table, empty if the table does not exist
+    pub existing_schema: Vec<TableSchema>,
+    /// rows of the existing table, dropped by `Replace`
+    pub existing_rows: usize,
+    /// schema of the table after saving
+    pub target_schema: Vec<TableSchema>,
+    /// statements in the order they would be executed
+    pub statements: Vec<String>,
+    pub rows_to_insert: usize,
+    pub rows_to_update: usize,
+    /// columns of the data that do not fit the existing table
+    pub incompatibilities: Vec<String>,
+    /// the reason why saving would fail, e.g. `FailIfExists` on an existing table
+    pub failure: Option<String>,
+}
+
+impl SavePlan {
+    pub fn new(table: &str, strategy: &SaveStrategy) -> Self {
+        SavePlan {
+            table: table.to_owned(),
+            strategy: strategy.clone(),
+            table_exists: false,
+            existing_schema: vec![],
+            existing_rows: 0,
+            target_schema: vec![],
+            statements: vec![],
+            rows_to_insert: 0,
+            rows_to_update: 0,
+            incompatibilities: vec![],
+            failure: None,
+        }
+    }
+
+    /// whether saving is expected to succeed
+    pub fn is_valid(&self) -> bool {
+        self.failure.is_none() && self.incompatibilities.is_empty()
+    }
+}
+
 // ================================================================================================
 // IndexType & IndexOption
 // ================================================================================================
@@ -958,7 +1007,7 @@ impl IndexOption {
     }
 
     pub fn try_from_series(series: &Series) -> SqlResult<Self> {
-        let dtype = series.dtype();
+        let dtype = series.dtype()?;
         let index_type = match dtype {
             ValueType::U8 => Ok(IndexType::Int),
             ValueType::U16 => Ok(IndexType::Int),
@@ -8,12 +8,14 @@
 use std::{any::Any, str::FromStr};
 
 use async_trait::async_trait;
-use fabrix_core::{D1Value, Fabrix, Series, Value, ValueType};
+use fabrix_core::{
+    D1Value, Fabrix, FieldInfo, Series, SharedObserver, Stage, StageSpan, Value, ValueType,
+};
 
 use super::{
     conn_e_err, conn_n_err,
     loader::{DatabaseType, LoaderTransaction},
-    types::string_try_into_value_type,
+    types::{string_try_into_value_type, value_type_try_into_marker},
     FabrixDatabaseLoader, SqlConnInfo,
 };
 use crate::{
@@ -74,6 +76,9 @@ pub trait SqlHelper: Send + Sync {
     /// get existing ids, supposing that the primary key is a single column, and the value is a string
     async fn get_existing_ids(&self, table_name: &str, ids: &Series) -> SqlResult<D1Value>;
 
+    /// get the number of rows of a table
+    async fn get_row_count(&self, table_name: &str) -> SqlResult<usize>;
+
     /// get all indexes from a table
     // async fn get_indexes(&self, table_name: &str) -> SqlResult<Vec<String>>;
 
@@ -124,9 +129,21 @@ pub trait SqlEngine: SqlMeta + SqlHelper + Send + Sync {
         strategy: &sql_adt::SaveStrategy,
     ) -> SqlResult<usize>;
 
+    /// dry run of `save`: statements, target schema and rows to be affected, nothing is mutated
+    async fn plan(
+        &self,
+        table_name: &str,
+        data: &Fabrix,
+        strategy: &sql_adt::SaveStrategy,
+    ) -> SqlResult<sql_adt::SavePlan>;
+
     /// delete data from an existing table.
     async fn delete(&self, delete: &sql_adt::Delete) -> SqlResult<u64>;
 
+    /// delete the rows of `delete` then insert `data` in one transaction, e.g. to replace the
+    /// rows of a key. The table is created from `data` if not exists
+    async fn replace_rows(&self, delete: &sql_adt::Delete, data: Fabrix) -> SqlResult<u64>;
+
     /// get data from db. If the table has primary key, DataFrame's index will be the primary key
     async fn select(&self, select: &sql_adt::Select) -> SqlResult<Fabrix>;
 
@@ -142,6 +159,7 @@ where
     driver: SqlBuilder,
     conn_str: String,
     pool: Option<T>,
+    observer: Option<SharedObserver>,
 }
 
 impl<T> SqlExecutor<T>
@@ -154,12 +172,231 @@ where
             driver: conn_info.driver.clone(),
             conn_str: conn_info.to_string(),
             pool: None,
+            observer: None,
         }
     }
 
     pub fn get_conn_str(&self) -> &str {
         &self.conn_str
     }
+
+    /// report rows read by `select` & written by `save` to an observer, e.g. `Metrics`
+    pub fn with_observer(&mut self, observer: SharedObserver) -> &mut Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// create the enum types of a new table (Postgres only) out of any transaction, since a value
+    /// added to an existing enum type cannot be used before it is committed
+    async fn create_enum_types(&self, table_name: &str, data: &Fabrix) -> SqlResult<()> {
+        let (fields, _) = table_fields(data)?;
+        for enum_str in self.driver.create_enum_types(table_name, &fields) {
+            self.pool.as_ref().unwrap().execute(&enum_str).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn save_data(
+        &self,
+        table_name: &str,
+        data: Fabrix,
+        strategy: &sql_adt::SaveStrategy,
+    ) -> SqlResult<usize> {
+        conn_n_err!(self.pool);
+
+        match strategy {
+            sql_adt::SaveStrategy::FailIfExists => {
+                if self.get_table_exists(table_name).await {
+                    return Err(SqlError::SourceAlreadyExists("table"));
+                }
+                self.create_enum_types(table_name, &data).await?;
+
+                // start a transaction
+                let txn = self.pool.as_ref().unwrap().begin_transaction().await?;
+
+                let res = txn_create_and_insert(&self.driver, txn, table_name, data, false).await?;
+
+                Ok(res as usize)
+            }
+            sql_adt::SaveStrategy::Replace => {
+                self.create_enum_types(table_name, &data).await?;
+
+                // start a transaction
+                let mut txn = self.pool.as_ref().unwrap().begin_transaction().await?;
+
+                if self.get_table_exists(table_name).await {
+                    let del_str = self.driver.drop_table(table_name);
+                    txn.execute(&del_str).await?;
+                }
+
+                let res = txn_create_and_insert(&self.driver, txn, table_name, data, true).await?;
+
+                Ok(res as usize)
+            }
+            sql_adt::SaveStrategy::Append => {
+                // insert to an existing table and ignore primary key
+                // this action is supposed that primary key can be auto generated
+                let que = self.driver.insert(table_name, data)?;
+                let res = self.pool.as_ref().unwrap().execute(&que).await?;
+
+                Ok(res.rows_affected as usize)
+            }
+            sql_adt::SaveStrategy::Upsert => {
+                if let Some(s) = data.index() {
+                    // get existing ids from selected table
+                    let existing_ids = self.get_existing_ids(table_name, s).await?;
+
+                    let existing_ids = Series::from_values_default_name(existing_ids, false)?;
+
+                    // declare a df for inserting
+                    let mut df_to_insert = data;
+                    // popup a df for updating
+                    let df_to_update = df_to_insert.popup_rows(&existing_ids)?;
+
+                    let r1 = self.insert(table_name, df_to_insert).await?;
+                    let r2 = self.update(table_name, df_to_update).await?;
+
+                    Ok((r1 + r2) as usize)
+                } else {
+                    let r1 = self.insert(table_name, data).await?;
+                    Ok(r1 as usize)
+                }
+            }
+        }
+    }
+
+    async fn plan_data(
+        &self,
+        table_name: &str,
+        data: &Fabrix,
+        strategy: &sql_adt::SaveStrategy,
+    ) -> SqlResult<sql_adt::SavePlan> {
+        conn_n_err!(self.pool);
+
+        let mut plan = sql_adt::SavePlan::new(table_name, strategy);
+        plan.table_exists = self.get_table_exists(table_name).await;
+        if plan.table_exists {
+            plan.existing_schema = self.get_table_schema(table_name).await?;
+            plan.existing_rows = self.get_row_count(table_name).await?;
+        }
+
+        match strategy {
+            sql_adt::SaveStrategy::FailIfExists | sql_adt::SaveStrategy::Replace => {
+                let replace = strategy == &sql_adt::SaveStrategy::Replace;
+                if plan.table_exists {
+                    if !replace {
+                        plan.failure = Some(SqlError::SourceAlreadyExists("table").to_string());
+                        return Ok(plan);
+                    }
+                    plan.statements.push(self.driver.drop_table(table_name));
+                }
+
+                let (fields, index_option) = table_fields(data)?;
+                plan.statements
+                    .extend(self.driver.create_enum_types(table_name, &fields));
+                plan.statements.push(self.driver.create_table(
+                    table_name,
+                    &fields,
+                    index_option.as_ref(),
+                    Some(replace),
+                ));
+                plan.statements
+                    .push(self.driver.insert(table_name, data.clone())?);
+
+                // the index is the primary key, and comes first
+                plan.target_schema = data
+                    .index_field()
+                    .map(|f| sql_adt::TableSchema {
+                        name: f.name,
+                        dtype: f.dtype,
+                        is_nullable: false,
+                    })
+                    .into_iter()
+                    .chain(fields.into_iter().map(sql_adt::TableSchema::from))
+                    .collect();
+                plan.rows_to_insert = data.height();
+            }
+            sql_adt::SaveStrategy::Append | sql_adt::SaveStrategy::Upsert => {
+                if !plan.table_exists {
+                    plan.failure = Some(SqlError::SourceNotFound("table").to_string());
+                    return Ok(plan);
+                }
+
+                let primary_key = self.get_primary_key(table_name).await.ok();
+                plan.incompatibilities = schema_incompatibilities(
+                    &self.driver,
+                    &plan.existing_schema,
+                    primary_key.as_deref(),
+                    data,
+                )?;
+                plan.target_schema = plan.existing_schema.clone();
+
+                let (df_to_insert, df_to_update) = match (strategy, data.index()) {
+                    (sql_adt::SaveStrategy::Upsert, Some(s)) => {
+                        let existing_ids = self.get_existing_ids(table_name, s).await?;
+                        let existing_ids = Series::from_values_default_name(existing_ids, false)?;
+
+                        let mut df_to_insert = data.clone();
+                        let df_to_update = df_to_insert.popup_rows(&existing_ids)?;
+                        (df_to_insert, Some(df_to_update))
+                    }
+                    _ => (data.clone(), None),
+                };
+
+                plan.rows_to_insert = df_to_insert.height();
+                if plan.rows_to_insert > 0 {
+                    plan.statements
+                        .push(self.driver.insert(table_name, df_to_insert)?);
+                }
+                if let Some(df) = df_to_update.filter(|df| df.height() > 0) {
+                    plan.rows_to_update = df.height();
+                    plan.statements.extend(
+                        self.driver
+                            .update(table_name, df)?
+                            .split(";\n")
+                            .filter(|s| !s.is_empty())
+                            .map(String::from),
+                    );
+                }
+            }
+        }
+
+        Ok(plan)
+    }
+
+    async fn select_data(&self, select: &sql_adt::Select) -> SqlResult<Fabrix> {
+        conn_n_err!(self.pool);
+
+        let mut df = None::<Fabrix>;
+
+        // if `select.include_primary_key` is true, try to get the primary key
+        // if primary key does not exist, ignore it
+        if let Some(true) = select.include_primary_key {
+            if let Ok(pk) = self.get_primary_key(&select.table).await {
+                let mut new_select = select.clone();
+                add_primary_key_to_select(&pk, &mut new_select);
+                let que = self.driver.select(&new_select);
+                let res = self.pool.as_ref().unwrap().fetch_all_to_rows(&que).await?;
+                let mut res = Fabrix::from_rows(res)?;
+                res.set_index_tag(0)?;
+                df = Some(res);
+            }
+        };
+
+        let mut df = match df {
+            Some(d) => d,
+            None => {
+                let que = self.driver.select(select);
+                let res = self.pool.as_ref().unwrap().fetch_all(&que).await?;
+                Fabrix::from_row_values(res, None, false)?
+            }
+        };
+
+        df.set_column_names(&select.columns_name())?;
+
+        Ok(df)
+    }
 }
 
 impl<T> FromStr for SqlExecutor<T>
@@ -179,6 +416,7 @@ where
             driver,
             conn_str: s.to_string(),
             pool: None,
+            observer: None,
         })
     }
 }
@@ -388,7 +626,7 @@ where
     async fn get_existing_ids(&self, table_name: &str, ids: &Series) -> SqlResult<D1Value> {
         conn_n_err!(self.pool);
         let que = self.driver.select_existing_ids(table_name, ids)?;
-        let schema = vec![ids.dtype().to_owned()];
+        let schema = vec![ids.dtype()?.to_owned()];
         let res = self
             .pool
             .as_ref()
@@ -402,6 +640,25 @@ where
         Ok(res)
     }
 
+    async fn get_row_count(&self, table_name: &str) -> SqlResult<usize> {
+        conn_n_err!(self.pool);
+        let que = self.driver.count_rows(table_name);
+        let res = self
+            .pool
+            .as_ref()
+            .unwrap()
+            .fetch_all_with_schema(&que, &[ValueType::I64])
+            .await?
+            .into_iter()
+            .flatten()
+            .next();
+
+        match res {
+            Some(Value::I64(v)) => Ok(v as usize),
+            _ => Err(SqlError::InvalidType("row count is not an integer")),
+        }
+    }
+
     async fn drop_table(&self, table_name: &str) -> SqlResult<()> {
         conn_n_err!(self.pool);
         let que = self.driver.drop_table(table_name);
@@ -479,64 +736,23 @@ where
         data: Fabrix,
         strategy: &sql_adt::SaveStrategy,
     ) -> SqlResult<usize> {
-        conn_n_err!(self.pool);
-
-        match strategy {
-            sql_adt::SaveStrategy::FailIfExists => {
-                if self.get_table_exists(table_name).await {
-                    return Err(SqlError::SourceAlreadyExists("table"));
-                }
-
-                // start a transaction
-                let txn = self.pool.as_ref().unwrap().begin_transaction().await?;
-
-                let res = txn_create_and_insert(&self.driver, txn, table_name, data, false).await?;
-
-                Ok(res as usize)
-            }
-            sql_adt::SaveStrategy::Replace => {
-                // start a transaction
-                let mut txn = self.pool.as_ref().unwrap().begin_transaction().await?;
-
-                if self.get_table_exists(table_name).await {
-                    let del_str = self.driver.drop_table(table_name);
-                    txn.execute(&del_str).await?;
-                }
-
-                let res = txn_create_and_insert(&self.driver, txn, table_name, data, true).await?;
-
-                Ok(res as usize)
-            }
-            sql_adt::SaveStrategy::Append => {
-                // insert to an existing table and ignore primary key
-                // this action is supposed that primary key can be auto generated
-                let que = self.driver.insert(table_name, data)?;
-                let res = self.pool.as_ref().unwrap().execute(&que).await?;
-
-                Ok(res.rows_affected as usize)
-            }
-            sql_adt::SaveStrategy::Upsert => {
-                if let Some(s) = data.index() {
-                    // get existing ids from selected table
-                    let existing_ids = self.get_existing_ids(table_name, s).await?;
-
-                    let existing_ids = Series::from_values_default_name(existing_ids, false)?;
-
-                    // declare a df for inserting
-                    let mut df_to_insert = data;
-                    // popup a df for updating
-                    let df_to_update = df_to_insert.popup_rows(&existing_ids)?;
-
-                    let r1 = self.insert(table_name, df_to_insert).await?;
-                    let r2 = self.update(table_name, df_to_update).await?;
+        let span = StageSpan::start(
+            self.observer.as_ref(),
+            Stage::Write,
+            &self.driver.to_string(),
+        );
+        let (rows, bytes) = (data.height(), data.estimated_size());
+        let res = self.save_data(table_name, data, strategy).await;
+        span.finish_rows(res, rows, bytes)
+    }
 
-                    Ok((r1 + r2) as usize)
-                } else {
-                    let r1 = self.insert(table_name, data).await?;
-                    Ok(r1 as usize)
-                }
-            }
-        }
+    async fn plan(
+        &self,
+        table_name: &str,
+        data: &Fabrix,
+        strategy: &sql_adt::SaveStrategy,
+    ) -> SqlResult<sql_adt::SavePlan> {
+        self.plan_data(table_name, data, strategy).await
     }
 
     async fn delete(&self, delete: &sql_adt::Delete) -> SqlResult<u64> {
@@ -547,37 +763,34 @@ where
         Ok(res.rows_affected)
     }
 
-    async fn select(&self, select: &sql_adt::Select) -> SqlResult<Fabrix> {
+    async fn replace_rows(&self, delete: &sql_adt::Delete, data: Fabrix) -> SqlResult<u64> {
         conn_n_err!(self.pool);
+        let table_exists = self.get_table_exists(&delete.table).await;
+        self.create_enum_types(&delete.table, &data).await?;
 
-        let mut df = None::<Fabrix>;
+        // start a transaction
+        let mut txn = self.pool.as_ref().unwrap().begin_transaction().await?;
 
-        // if `select.include_primary_key` is true, try to get the primary key
-        // if primary key does not exist, ignore it
-        if let Some(true) = select.include_primary_key {
-            if let Ok(pk) = self.get_primary_key(&select.table).await {
-                let mut new_select = select.clone();
-                add_primary_key_to_select(&pk, &mut new_select);
-                let que = self.driver.select(&new_select);
-                let res = self.pool.as_ref().unwrap().fetch_all_to_rows(&que).await?;
-                let mut res = Fabrix::from_rows(res)?;
-                res.set_index_tag(0)?;
-                df = Some(res);
+        if table_exists {
+            let del_str = self.driver.delete(delete);
+            if let Err(e) = txn.execute(&del_str).await {
+                txn.rollback().await?;
+                return Err(e);
             }
-        };
+        }
 
-        let mut df = match df {
-            Some(d) => d,
-            None => {
-                let que = self.driver.select(select);
-                let res = self.pool.as_ref().unwrap().fetch_all(&que).await?;
-                Fabrix::from_row_values(res, None, false)?
-            }
-        };
+        let res = txn_create_and_insert(&self.driver, txn, &delete.table, data, true).await?;
 
-        df.set_column_names(&select.columns_name())?;
+        Ok(res as u64)
+    }
 
-        Ok(df)
+    async fn select(&self, select: &sql_adt::Select) -> SqlResult<Fabrix> {
+        let span = StageSpan::start(
+            self.observer.as_ref(),
+            Stage::Read,
+            &self.driver.to_string(),
+        );
+        span.finish_fabrix(self.select_data(select).await)
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -598,15 +811,8 @@ fn try_value_into_string(value: &Value) -> SqlResult<String> {
     }
 }
 
-/// create table
-async fn txn_create_and_insert<'a>(
-    driver: &SqlBuilder,
-    mut txn: LoaderTransaction<'a>,
-    table_name: &str,
-    data: Fabrix,
-    if_not_exists: bool,
-) -> SqlResult<usize> {
-    // create table string
+/// fields of a new table & its primary key, which is the index of the data
+fn table_fields(data: &Fabrix) -> SqlResult<(Vec<FieldInfo>, Option<sql_adt::IndexOption>)> {
     let index_option = data
         .index_field()
         .map(sql_adt::IndexOption::try_from)
@@ -614,12 +820,76 @@ async fn txn_create_and_insert<'a>(
     // if index_option is not None, data.fields should remove the index field
     let fields = match data.index_tag() {
         Some(it) => {
-            let mut fields = data.fields();
+            let mut fields = data.fields()?;
             fields.remove(it.loc());
             fields
         }
-        None => data.fields(),
+        None => data.fields()?,
     };
+
+    Ok((fields, index_option))
+}
+
+/// columns of the data that cannot be saved into an existing table: unknown columns,
+/// mismatched types, nulls in not-nullable columns & missing not-nullable columns
+fn schema_incompatibilities(
+    driver: &SqlBuilder,
+    schema: &[sql_adt::TableSchema],
+    primary_key: Option<&str>,
+    data: &Fabrix,
+) -> SqlResult<Vec<String>> {
+    let fields = data.fields()?;
+    let markers = value_type_try_into_marker(
+        driver,
+        &fields.iter().map(|f| f.dtype.clone()).collect::<Vec<_>>(),
+    );
+    let mut res = vec![];
+
+    for (field, marker) in fields.iter().zip(markers) {
+        let ts = match schema.iter().find(|ts| ts.name == field.name) {
+            Some(ts) => ts,
+            None => {
+                res.push(format!("column `{}` does not exist", field.name));
+                continue;
+            }
+        };
+        // types unknown to the driver are not compared
+        if let Some(dtype) = marker.map(|m| m.to_dtype()) {
+            if ts.dtype != ValueType::Null && ts.dtype != dtype {
+                res.push(format!(
+                    "column `{}` is {:?}, but {:?} is expected",
+                    field.name, field.dtype, ts.dtype
+                ));
+            }
+        }
+        if !ts.is_nullable && data.get_column(&field.name)?.has_null() {
+            res.push(format!(
+                "column `{}` is not nullable, but has nulls",
+                field.name
+            ));
+        }
+    }
+
+    for ts in schema.iter() {
+        let missing = !fields.iter().any(|f| f.name == ts.name);
+        if missing && !ts.is_nullable && primary_key != Some(ts.name.as_str()) {
+            res.push(format!("column `{}` is not nullable, but missing", ts.name));
+        }
+    }
+
+    Ok(res)
+}
+
+/// create table, its enum types should have been created by `SqlExecutor::create_enum_types`
+async fn txn_create_and_insert<'a>(
+    driver: &SqlBuilder,
+    mut txn: LoaderTransaction<'a>,
+    table_name: &str,
+    data: Fabrix,
+    if_not_exists: bool,
+) -> SqlResult<usize> {
+    // create table string
+    let (fields, index_option) = table_fields(&data)?;
     let create_str = driver.create_table(
         table_name,
         &fields,
@@ -717,6 +987,99 @@ mod test_executor {
         assert_eq!(res.unwrap(), 5);
     }
 
+    #[tokio::test]
+    async fn save_and_select_with_observer_success() {
+        use fabrix_core::Metrics;
+        use std::sync::Arc;
+
+        let metrics = Arc::new(Metrics::new());
+        let mut exc = SqlExecutor::<DatabaseSqlite>::from_str(CONN3).unwrap();
+        exc.with_observer(metrics.clone());
+        exc.connect().await.expect("connection is ok");
+
+        let df = fx![
+            "id" => [1, 2, 3],
+            "name" => ["a", "b", "c"],
+        ]
+        .unwrap();
+        let res = exc
+            .save("dev_observed", df, &sql_adt::SaveStrategy::Replace)
+            .await;
+        assert_eq!(res.unwrap(), 3);
+
+        let select = sql_adt::Select::new("dev_observed").columns(&["id", "name"]);
+        assert_eq!(exc.select(&select).await.unwrap().height(), 3);
+        assert!(exc.select(&sql_adt::Select::new("unknown")).await.is_err());
+
+        let write = metrics.get(Stage::Write, "sqlite");
+        assert_eq!((write.rows, write.runs), (3, 1));
+        let read = metrics.get(Stage::Read, "sqlite");
+        assert_eq!((read.rows, read.runs, read.errors), (3, 2, 1));
+    }
+
+    #[tokio::test]
+    async fn plan_save_success() {
+        let mut exc = SqlExecutor::<DatabaseSqlite>::from_str(CONN3).unwrap();
+        exc.connect().await.expect("connection is ok");
+
+        let table = "dev_plan";
+        let df = fx![
+            "id";
+            "id" => [1, 2, 3],
+            "name" => ["a", "b", "c"],
+        ]
+        .unwrap();
+        exc.save(table, df, &sql_adt::SaveStrategy::Replace)
+            .await
+            .unwrap();
+
+        // nothing is dropped by planning a replace
+        let df = fx![
+            "id";
+            "id" => [3, 4],
+            "name" => ["x", "y"],
+        ]
+        .unwrap();
+        let plan = exc
+            .plan(table, &df, &sql_adt::SaveStrategy::Replace)
+            .await
+            .unwrap();
+        assert!(plan.is_valid());
+        assert!(plan.table_exists);
+        assert_eq!(plan.existing_rows, 3);
+        assert_eq!(plan.statements.len(), 3);
+        assert!(plan.statements[0].starts_with("DROP TABLE"));
+        assert_eq!(plan.target_schema[0].name, "id");
+        assert!(exc.get_table_exists(table).await);
+
+        let plan = exc
+            .plan(table, &df, &sql_adt::SaveStrategy::FailIfExists)
+            .await
+            .unwrap();
+        assert!(plan.failure.is_some());
+
+        let plan = exc
+            .plan(table, &df, &sql_adt::SaveStrategy::Upsert)
+            .await
+            .unwrap();
+        assert!(plan.is_valid());
+        assert_eq!((plan.rows_to_insert, plan.rows_to_update), (1, 1));
+        assert!(plan.statements[1].starts_with("UPDATE"));
+
+        let df = fx![
+            "id" => [5],
+            "name" => ["z"],
+            "age" => [7],
+        ]
+        .unwrap();
+        let plan = exc
+            .plan(table, &df, &sql_adt::SaveStrategy::Append)
+            .await
+            .unwrap();
+        assert_eq!(plan.incompatibilities, vec!["column `age` does not exist"]);
+        assert_eq!(exc.get_row_count(table).await.unwrap(), 3);
+    }
+
     #[tokio::test]
     async fn save_quotes_into_sqlite_success() {
         let mut exc = SqlExecutor::<DatabaseSqlite>::from_str(CONN3).unwrap();
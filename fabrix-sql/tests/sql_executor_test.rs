@@ -320,7 +320,7 @@ async fn transaction_success() {
         .await
         .expect("begin transaction is ok");
 
-    let columns = df.fields();
+    let columns = df.fields().unwrap();
     let que = SqlBuilder::Postgres.create_table("test_transaction", &columns, None, None);
 
     txn.execute(&que).await.unwrap();
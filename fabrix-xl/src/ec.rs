@@ -6,14 +6,19 @@ use std::fmt::Display;
 use std::fs::File;
 use std::io::{Cursor, Read, Seek};
 use std::marker::PhantomData;
+use std::path::PathBuf;
 
 use async_trait::async_trait;
-use fabrix_core::D2;
+use fabrix_core::{SharedObserver, Stage, StageSpan, D2};
 use futures::future::BoxFuture;
+use url::Url;
 
 use super::{RowIter, XlCell, XlSource, XlWorkbook};
 use crate::{XlError, XlResult};
 
+/// source type reported to observers
+pub const XL_SOURCE_TYPE: &str = "xl";
+
 /// A convert function pointer used for converting `D2<UnitOut>` into type parameter `FinalOut`.
 pub type ConvertFP<IN, OUT> = fn(IN) -> XlResult<OUT>;
 
@@ -91,6 +96,21 @@ pub trait XlConsumer<CORE> {
     }
 }
 
+/// local path of a `file://` uri; remote uris are fetched by the caller, e.g. by fabrix's
+/// `UriResolver`, into `XlSource::File` or `XlSource::Buff`
+fn uri_path(uri: &str) -> XlResult<PathBuf> {
+    let url =
+        Url::parse(uri).map_err(|e| XlError::Unexpected(format!("invalid uri {uri}: {e}")))?;
+    if url.scheme() != "file" {
+        return Err(XlError::Unexpected(format!(
+            "uri {uri} is not a local file, fetch it into a file or a buffer first"
+        )));
+    }
+
+    url.to_file_path()
+        .map_err(|_| XlError::Unexpected(format!("invalid file uri {uri}")))
+}
+
 impl TryFrom<XlSource> for XlWorkbook<File> {
     type Error = XlError;
 
@@ -98,6 +118,7 @@ impl TryFrom<XlSource> for XlWorkbook<File> {
         match value {
             XlSource::File(file) => Ok(XlWorkbook::new(file)?),
             XlSource::Path(path) => Ok(XlWorkbook::new(File::open(path)?)?),
+            XlSource::Uri(uri) => Ok(XlWorkbook::new(File::open(uri_path(&uri)?)?)?),
             _ => Err(XlError::UnsupportedSource),
         }
     }
@@ -109,6 +130,9 @@ impl TryFrom<XlSource> for XlWorkbook<Cursor<Vec<u8>>> {
     fn try_from(value: XlSource) -> Result<Self, Self::Error> {
         match value {
             XlSource::Buff(bytes) => Ok(XlWorkbook::new(bytes)?),
+            XlSource::Uri(uri) => Ok(XlWorkbook::new(Cursor::new(std::fs::read(uri_path(
+                &uri,
+            )?)?))?),
             _ => Err(XlError::UnsupportedSource),
         }
     }
@@ -261,11 +285,58 @@ where
     READER: Read + Seek,
 {
     workbook: Option<XlWorkbook<READER>>,
+    observer: Option<SharedObserver>,
     consumer: PhantomData<CONSUMER>,
     core: PhantomData<CORE>,
     xl_form: PhantomData<READER>,
 }
 
+/// Run a batch loop over a sheet within a `Stage::Read` span
+///
+/// Shared by the `consume` methods of `XlExecutor`: each batch is reported to the observer before
+/// `$body` converts & consumes it, and the span finishes with the loop's result.
+macro_rules! consume_sheet {
+    ($self:ident, $batch_size:expr, $sheet_name:expr, |$d:ident| $body:block) => {{
+        let span = StageSpan::start($self.observer.as_ref(), Stage::Read, XL_SOURCE_TYPE);
+        let res = (|| {
+            let iter = gen_worksheet_iter::<CONSUMER, CORE, READER>(
+                &mut $self.workbook,
+                $batch_size,
+                $sheet_name,
+            )?;
+
+            for $d in iter {
+                span.batch($d.len(), 0);
+                $body
+            }
+
+            Ok(())
+        })();
+
+        span.finish(res)
+    }};
+    (async $self:ident, $batch_size:expr, $sheet_name:expr, |$d:ident| $body:block) => {{
+        let span = StageSpan::start($self.observer.as_ref(), Stage::Read, XL_SOURCE_TYPE);
+        let res = async {
+            let iter = gen_worksheet_iter::<CONSUMER, CORE, READER>(
+                &mut $self.workbook,
+                $batch_size,
+                $sheet_name,
+            )?;
+
+            for $d in iter {
+                span.batch($d.len(), 0);
+                $body
+            }
+
+            Ok(())
+        }
+        .await;
+
+        span.finish(res)
+    }};
+}
+
 impl<CONSUMER, CORE, READER> XlExecutor<CONSUMER, CORE, READER>
 where
     CONSUMER: XlConsumer<CORE> + Send,
@@ -275,6 +346,7 @@ where
     pub fn new() -> Self {
         Self {
             workbook: None,
+            observer: None,
             consumer: PhantomData,
             core: PhantomData,
             xl_form: PhantomData,
@@ -285,12 +357,19 @@ where
     pub fn new_with_source(source: XlWorkbook<READER>) -> Self {
         Self {
             workbook: Some(source),
+            observer: None,
             consumer: PhantomData,
             core: PhantomData,
             xl_form: PhantomData,
         }
     }
 
+    /// report rows read by the `consume` methods, batch by batch
+    pub fn with_observer(&mut self, observer: SharedObserver) -> &mut Self {
+        self.observer = Some(observer);
+        self
+    }
+
     /// replace or set a new workbook
     pub fn add_source(&mut self, source: XlWorkbook<READER>) -> XlResult<()> {
         self.workbook = Some(source);
@@ -314,18 +393,10 @@ where
         convert_fn: ConvertFP<D2<CONSUMER::UnitOut>, CONSUMER::FinalOut>,
         consume_fn: SyncConsumeFP<CONSUMER::FinalOut>,
     ) -> XlResult<()> {
-        let iter = gen_worksheet_iter::<CONSUMER, CORE, READER>(
-            &mut self.workbook,
-            batch_size,
-            sheet_name,
-        )?;
-
-        for d in iter {
+        consume_sheet!(self, batch_size, sheet_name, |d| {
             let cd = convert_fn(d)?;
             CONSUMER::consume(cd, consume_fn)?;
-        }
-
-        Ok(())
+        })
     }
 
     /// consume a sheet synchronously
@@ -336,18 +407,10 @@ where
         convert_fn: impl Fn(D2<CONSUMER::UnitOut>) -> XlResult<CONSUMER::FinalOut>,
         consume_fn: SyncConsumeFP<CONSUMER::FinalOut>,
     ) -> XlResult<()> {
-        let iter = gen_worksheet_iter::<CONSUMER, CORE, READER>(
-            &mut self.workbook,
-            batch_size,
-            sheet_name,
-        )?;
-
-        for d in iter {
+        consume_sheet!(self, batch_size, sheet_name, |d| {
             let cd = convert_fn(d)?;
             CONSUMER::consume(cd, consume_fn)?;
-        }
-
-        Ok(())
+        })
     }
 
     /// consume a sheet synchronously
@@ -358,18 +421,10 @@ where
         mut convert_fn: impl FnMut(D2<CONSUMER::UnitOut>) -> XlResult<CONSUMER::FinalOut>,
         mut consume_fn: impl FnMut(CONSUMER::FinalOut) -> XlResult<()>,
     ) -> XlResult<()> {
-        let iter = gen_worksheet_iter::<CONSUMER, CORE, READER>(
-            &mut self.workbook,
-            batch_size,
-            sheet_name,
-        )?;
-
-        for d in iter {
+        consume_sheet!(self, batch_size, sheet_name, |d| {
             let cd = convert_fn(d)?;
             CONSUMER::consume_mut(cd, &mut consume_fn)?;
-        }
-
-        Ok(())
+        })
     }
 
     /// consume a sheet asynchronously
@@ -380,18 +435,10 @@ where
         convert_fn: ConvertFP<D2<CONSUMER::UnitOut>, CONSUMER::FinalOut>,
         consume_fn: AsyncConsumeFP<'a, CONSUMER::FinalOut>,
     ) -> XlResult<()> {
-        let iter = gen_worksheet_iter::<CONSUMER, CORE, READER>(
-            &mut self.workbook,
-            batch_size,
-            sheet_name,
-        )?;
-
-        for d in iter {
+        consume_sheet!(async self, batch_size, sheet_name, |d| {
             let cd = convert_fn(d)?;
             CONSUMER::consume_async(cd, consume_fn).await?
-        }
-
-        Ok(())
+        })
     }
 
     /// consume a sheet asynchronously
@@ -402,18 +449,10 @@ where
         convert_fn: ConvertFP<D2<CONSUMER::UnitOut>, CONSUMER::FinalOut>,
         consume_fn: AsyncConsumeFP<'a, CONSUMER::FinalOut>,
     ) -> XlResult<()> {
-        let iter = gen_worksheet_iter::<CONSUMER, CORE, READER>(
-            &mut self.workbook,
-            batch_size,
-            sheet_name,
-        )?;
-
-        for d in iter {
+        consume_sheet!(async self, batch_size, sheet_name, |d| {
             let cd = convert_fn(d)?;
             CONSUMER::consume_async(cd, consume_fn).await?
-        }
-
-        Ok(())
+        })
     }
 
     /// consume a sheet asynchronously with mutable convert & consume functions
@@ -427,20 +466,12 @@ where
     where
         CONSUMER::FinalOut: 'a,
     {
-        let iter = gen_worksheet_iter::<CONSUMER, CORE, READER>(
-            &mut self.workbook,
-            batch_size,
-            sheet_name,
-        )?;
-
         let csm = &mut consume_fn;
 
-        for d in iter {
+        consume_sheet!(async self, batch_size, sheet_name, |d| {
             let cd = convert_fn(d)?;
             CONSUMER::consume_async_mut(cd, &mut *csm).await?
-        }
-
-        Ok(())
+        })
     }
 }
 
@@ -528,6 +559,24 @@ mod test_xl_executor {
         }
     }
 
+    #[test]
+    fn test_uri_source() {
+        let path = std::fs::canonicalize(XL_PATH).unwrap();
+        let uri = Url::from_file_path(&path).unwrap().to_string();
+
+        let source: XlWorkbook<File> = XlSource::Uri(uri.clone()).try_into().unwrap();
+        let mut xle = XlExecutor::<TestExec, (), File>::new_with_source(source);
+        assert!(xle.iter_sheet(None, SHEET_NAME).is_ok());
+
+        let source: XlWorkbook<Cursor<Vec<u8>>> = XlSource::Uri(uri).try_into().unwrap();
+        let mut xle = XlExecutor::<TestExec, (), Cursor<Vec<u8>>>::new_with_source(source);
+        assert!(xle.iter_sheet(None, SHEET_NAME).is_ok());
+
+        let remote: XlResult<XlWorkbook<File>> =
+            XlSource::Uri("https://localhost/test.xlsx".to_owned()).try_into();
+        assert!(remote.is_err());
+    }
+
     fn convert_fn(data: D2<String>) -> XlResult<Fo> {
         Ok(Fo(data))
     }
@@ -579,6 +628,28 @@ mod test_xl_executor {
         assert!(foo.is_ok(), "consuming source should not fail");
     }
 
+    #[test]
+    fn test_exec_consume_with_observer() {
+        use fabrix_core::Metrics;
+
+        let source: XlWorkbook<File> = XlSource::Path(XL_PATH.to_owned()).try_into().unwrap();
+        let mut xle = XlExecutor::<TestExec, (), File>::new_with_source(source);
+        let metrics = Arc::new(Metrics::new());
+        xle.with_observer(metrics.clone());
+
+        let foo = xle.consume(Some(20), SHEET_NAME, convert_fn, |_| Ok(()));
+        assert!(foo.is_ok(), "consuming source should not fail");
+
+        let read = metrics.get(Stage::Read, XL_SOURCE_TYPE);
+        assert!(read.rows > 20 && read.batches > 1);
+        assert_eq!((read.runs, read.errors), (1, 0));
+
+        // unknown sheet
+        let foo = xle.consume(Some(20), "unknown", convert_fn, consume_fn);
+        assert!(foo.is_err());
+        assert_eq!(metrics.get(Stage::Read, XL_SOURCE_TYPE).errors, 1);
+    }
+
     // consume synchronously
     #[tokio::test]
     async fn test_exec_async_consume() {
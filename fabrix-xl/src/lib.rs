@@ -14,7 +14,7 @@ mod util;
 pub mod wb;
 pub(crate) mod ws;
 
-pub use ec::{AsyncConsumeFP, ConvertFP, SyncConsumeFP, XlConsumer, XlExecutor};
+pub use ec::{AsyncConsumeFP, ConvertFP, SyncConsumeFP, XlConsumer, XlExecutor, XL_SOURCE_TYPE};
 pub use error::*;
 pub use wb::XlWorkbook;
 pub(crate) use ws::SheetReader;
@@ -0,0 +1,392 @@
+//! Checkpoint
+//!
+//! Records the last committed batch of a pipeline run, so that an interrupted run can be resumed:
+//! - Checkpoint: run id, offset (number of committed batches), committed rows & the last batch key
+//! - CheckpointStore: where checkpoints are kept, e.g. `FileCheckpointStore` & `SqlCheckpointStore`
+//!
+//! A batch is identified by its idempotency key `{run_id}:{offset}`, which stays the same across
+//! reruns of the same run id.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::FabrixResult;
+
+// ================================================================================================
+// Checkpoint & CheckpointStore
+// ================================================================================================
+
+/// progress of a pipeline run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub run_id: String,
+    /// number of committed batches, i.e. the offset of the next batch
+    pub offset: usize,
+    /// number of committed rows
+    pub rows: usize,
+    /// idempotency key of the last committed batch
+    pub key: Option<String>,
+}
+
+impl Checkpoint {
+    /// a fresh run, nothing committed
+    pub fn new(run_id: &str) -> Self {
+        Self {
+            run_id: run_id.to_owned(),
+            offset: 0,
+            rows: 0,
+            key: None,
+        }
+    }
+
+    /// commit the batch at `offset`, which has `rows` rows
+    pub fn commit(&mut self, offset: usize, rows: usize) -> &mut Self {
+        self.offset = offset + 1;
+        self.rows += rows;
+        self.key = Some(batch_key(&self.run_id, offset));
+        self
+    }
+
+    /// whether the batch at `offset` has been committed
+    pub fn is_committed(&self, offset: usize) -> bool {
+        offset < self.offset
+    }
+}
+
+/// idempotency key of a batch
+pub fn batch_key(run_id: &str, offset: usize) -> String {
+    format!("{run_id}:{offset}")
+}
+
+/// persistence of checkpoints, keyed by run id
+#[async_trait]
+pub trait CheckpointStore: Send + Sync {
+    async fn load(&self, run_id: &str) -> FabrixResult<Option<Checkpoint>>;
+
+    async fn save(&self, checkpoint: &Checkpoint) -> FabrixResult<()>;
+
+    /// remove a checkpoint, normally after a run has finished
+    async fn clear(&self, run_id: &str) -> FabrixResult<()>;
+}
+
+// ================================================================================================
+// FileCheckpointStore
+// ================================================================================================
+
+/// checkpoints saved as `{dir}/{run_id}.json`, the run id escaped by `file_stem`
+#[derive(Debug, Clone)]
+pub struct FileCheckpointStore {
+    dir: PathBuf,
+}
+
+impl FileCheckpointStore {
+    /// the directory is created if not exists
+    pub fn new<P: Into<PathBuf>>(dir: P) -> FabrixResult<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, run_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", file_stem(run_id)))
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for FileCheckpointStore {
+    async fn load(&self, run_id: &str) -> FabrixResult<Option<Checkpoint>> {
+        read_json(&self.path(run_id))
+    }
+
+    async fn save(&self, checkpoint: &Checkpoint) -> FabrixResult<()> {
+        write_json(&self.path(&checkpoint.run_id), checkpoint)
+    }
+
+    async fn clear(&self, run_id: &str) -> FabrixResult<()> {
+        remove_file(&self.path(run_id))
+    }
+}
+
+/// a key as a file name: bytes other than ascii alphanumerics, `-` & `_` are escaped as `%XX`, so
+/// that a key cannot point out of its directory (e.g. `../x`) and distinct keys never share a file
+pub(crate) fn file_stem(key: &str) -> String {
+    key.bytes()
+        .map(
+            |b| match b.is_ascii_alphanumeric() || b == b'-' || b == b'_' {
+                true => (b as char).to_string(),
+                false => format!("%{b:02X}"),
+            },
+        )
+        .collect()
+}
+
+/// `None` if the file does not exist
+pub(crate) fn read_json<T: DeserializeOwned>(path: &Path) -> FabrixResult<Option<T>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    Ok(Some(serde_json::from_slice(&fs::read(path)?)?))
+}
+
+/// write to a temporary file then rename, so that the file is never half-written
+pub(crate) fn write_json<T: Serialize>(path: &Path, value: &T) -> FabrixResult<()> {
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_vec(value)?)?;
+    fs::rename(tmp, path)?;
+
+    Ok(())
+}
+
+pub(crate) fn remove_file(path: &Path) -> FabrixResult<()> {
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+
+    Ok(())
+}
+
+// ================================================================================================
+// SqlCheckpointStore
+// ================================================================================================
+
+#[cfg(feature = "sql")]
+pub use sql_store::*;
+
+#[cfg(feature = "sql")]
+pub(crate) use sql_store::SqlJsonTable;
+
+#[cfg(feature = "sql")]
+mod sql_store {
+    use std::str::FromStr;
+
+    use async_trait::async_trait;
+    use fabrix_core::{CoreError, Value};
+    use fabrix_sql::{
+        sql_adt, xpr, DatabaseType, SqlEngine, SqlError, SqlExecutor, SqlHelper, SqlMeta,
+    };
+    use polars::prelude::{DataFrame, NamedFrom, Series as PolarsSeries};
+    use serde::{de::DeserializeOwned, Serialize};
+
+    use super::{Checkpoint, CheckpointStore};
+    use crate::{Fabrix, FabrixError, FabrixResult};
+
+    pub const DEFAULT_CHECKPOINT_TABLE: &str = "_fabrix_checkpoint";
+
+    /// a table of string key columns and a json column, shared by the sql stores. Rows are
+    /// replaced by their first key in one transaction, and the table is created on the first save
+    pub(crate) struct SqlJsonTable<T: DatabaseType> {
+        executor: SqlExecutor<T>,
+        table: String,
+        column: &'static str,
+    }
+
+    impl<T: DatabaseType> SqlJsonTable<T> {
+        pub(crate) async fn new(
+            conn: &str,
+            table: &str,
+            column: &'static str,
+        ) -> FabrixResult<Self> {
+            let mut executor = SqlExecutor::from_str(conn)?;
+            executor.connect().await?;
+            Ok(Self {
+                executor,
+                table: table.to_owned(),
+                column,
+            })
+        }
+
+        pub(crate) fn with_table(&mut self, table: &str) -> &mut Self {
+            self.table = table.to_owned();
+            self
+        }
+
+        pub(crate) fn table(&self) -> &str {
+            &self.table
+        }
+
+        pub(crate) fn key_filter(key: &str, value: &str) -> sql_adt::Expressions {
+            xpr!([xpr!(key, "=", value)])
+        }
+
+        /// the json values of the rows matching `filter`, nothing if the table does not exist
+        pub(crate) async fn select<R: DeserializeOwned>(
+            &self,
+            filter: Option<&sql_adt::Expressions>,
+        ) -> FabrixResult<Vec<R>> {
+            if !self.executor.get_table_exists(&self.table).await {
+                return Ok(Vec::new());
+            }
+
+            let mut select = sql_adt::Select::new(&self.table).columns(&[self.column]);
+            if let Some(filter) = filter {
+                select = select.filter(filter);
+            }
+            let fx = match self.executor.select(&select).await {
+                Ok(fx) => fx,
+                Err(SqlError::Core(CoreError::EmptyContent(_))) => return Ok(Vec::new()),
+                Err(e) => return Err(e.into()),
+            };
+
+            fx.get_column(self.column)?
+                .try_iter()?
+                .map(|v| match v {
+                    Value::String(s) => Ok(serde_json::from_str(&s)?),
+                    v => Err(FabrixError::new_uncategorized(format!(
+                        "invalid {}: {v}",
+                        self.column
+                    ))),
+                })
+                .collect()
+        }
+
+        /// replace the rows of the first key by one row of `keys` & `value`
+        pub(crate) async fn replace<R: Serialize + Sync>(
+            &self,
+            keys: &[(&str, &str)],
+            value: &R,
+        ) -> FabrixResult<()> {
+            let (key, key_value) = keys.first().ok_or(FabrixError::NotSet("key columns"))?;
+
+            let mut columns = keys
+                .iter()
+                .map(|(k, v)| PolarsSeries::new(k, [*v]))
+                .collect::<Vec<_>>();
+            columns.push(PolarsSeries::new(
+                self.column,
+                [serde_json::to_string(value)?.as_str()],
+            ));
+            let data = Fabrix::new_no_index(DataFrame::new(columns)?);
+
+            let mut delete = sql_adt::Delete::new(self.table.clone());
+            delete.filter(&Self::key_filter(key, key_value));
+            self.executor.replace_rows(&delete, data).await?;
+
+            Ok(())
+        }
+
+        /// delete the rows of a key, if the table exists
+        pub(crate) async fn delete(&self, key: &str, value: &str) -> FabrixResult<()> {
+            if self.executor.get_table_exists(&self.table).await {
+                let mut delete = sql_adt::Delete::new(self.table.clone());
+                delete.filter(&Self::key_filter(key, value));
+                self.executor.delete(&delete).await?;
+            }
+
+            Ok(())
+        }
+    }
+
+    /// checkpoints saved in a table of (`run_id`, `checkpoint`), the latter is a json string.
+    /// The table can live in the target database, e.g. a SQLite file next to the loaded data.
+    pub struct SqlCheckpointStore<T: DatabaseType> {
+        table: SqlJsonTable<T>,
+    }
+
+    impl<T: DatabaseType> SqlCheckpointStore<T> {
+        pub async fn new(conn: &str) -> FabrixResult<Self> {
+            let table = SqlJsonTable::new(conn, DEFAULT_CHECKPOINT_TABLE, "checkpoint").await?;
+            Ok(Self { table })
+        }
+
+        pub fn with_table(&mut self, table: &str) -> &mut Self {
+            self.table.with_table(table);
+            self
+        }
+
+        pub fn table(&self) -> &str {
+            self.table.table()
+        }
+    }
+
+    #[async_trait]
+    impl<T: DatabaseType> CheckpointStore for SqlCheckpointStore<T> {
+        async fn load(&self, run_id: &str) -> FabrixResult<Option<Checkpoint>> {
+            let filter = SqlJsonTable::<T>::key_filter("run_id", run_id);
+            Ok(self.table.select(Some(&filter)).await?.into_iter().next())
+        }
+
+        async fn save(&self, checkpoint: &Checkpoint) -> FabrixResult<()> {
+            // one row per run: replace the previous checkpoint of the run atomically
+            self.table
+                .replace(&[("run_id", &checkpoint.run_id)], checkpoint)
+                .await
+        }
+
+        async fn clear(&self, run_id: &str) -> FabrixResult<()> {
+            self.table.delete("run_id", run_id).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod checkpoint_tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint() {
+        let mut cp = Checkpoint::new("r1");
+        assert!(!cp.is_committed(0));
+
+        cp.commit(0, 10).commit(1, 5);
+        assert_eq!(cp.offset, 2);
+        assert_eq!(cp.rows, 15);
+        assert_eq!(cp.key.as_deref(), Some("r1:1"));
+        assert!(cp.is_committed(1));
+        assert!(!cp.is_committed(2));
+    }
+
+    #[tokio::test]
+    async fn test_file_store() {
+        let store = FileCheckpointStore::new("../cache/checkpoint").unwrap();
+        store.clear("file_run").await.unwrap();
+        assert_eq!(store.load("file_run").await.unwrap(), None);
+
+        let mut cp = Checkpoint::new("file_run");
+        cp.commit(0, 3);
+        store.save(&cp).await.unwrap();
+        assert_eq!(store.load("file_run").await.unwrap(), Some(cp.clone()));
+
+        cp.commit(1, 3);
+        store.save(&cp).await.unwrap();
+        assert_eq!(store.load("file_run").await.unwrap().unwrap().offset, 2);
+
+        store.clear("file_run").await.unwrap();
+        assert_eq!(store.load("file_run").await.unwrap(), None);
+
+        // run ids never escape the directory
+        assert_eq!(file_stem("../etc/x.y"), "%2E%2E%2Fetc%2Fx%2Ey");
+        let cp = Checkpoint::new("../file_run");
+        store.save(&cp).await.unwrap();
+        assert!(store.path("../file_run").starts_with("../cache/checkpoint"));
+        assert_eq!(store.load("../file_run").await.unwrap(), Some(cp));
+        store.clear("../file_run").await.unwrap();
+    }
+
+    #[cfg(feature = "sql")]
+    #[tokio::test]
+    async fn test_sql_store() {
+        use fabrix_sql::DatabaseSqlite;
+
+        let store = SqlCheckpointStore::<DatabaseSqlite>::new("sqlite://dev.sqlite")
+            .await
+            .unwrap();
+        store.clear("sql_run").await.unwrap();
+        assert_eq!(store.load("sql_run").await.unwrap(), None);
+
+        let mut cp = Checkpoint::new("sql_run");
+        cp.commit(0, 3);
+        store.save(&cp).await.unwrap();
+        cp.commit(1, 4);
+        store.save(&cp).await.unwrap();
+
+        let loaded = store.load("sql_run").await.unwrap().unwrap();
+        assert_eq!(loaded, cp);
+
+        store.clear("sql_run").await.unwrap();
+        assert_eq!(store.load("sql_run").await.unwrap(), None);
+    }
+}
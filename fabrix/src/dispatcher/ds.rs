@@ -5,12 +5,28 @@
 //! - Writer: write the data in memory to one destination
 //!
 //! Additionally, read & write options are traits that should be implemented
+//!
+//! A writer implementing `PlanSource` can be dry-run by `Dispatcher::plan`, which reports what a
+//! write would do without writing anything
+//!
+//! Between reading & writing, the data can be transformed in place, e.g. by a `ColumnMapping`
+//!
+//! With an observer, rows & estimated bytes, duration and errors of each read & write are reported.
+//! Since the whole source is read at once, a read is reported as a single batch; use a
+//! `StreamDispatcher` for progress batch by batch
+//!
+//! With an `Audit`, a record of each run is appended to an audit store after the write, or after
+//! a failed read (see `AuditRun`)
 
 use std::marker::PhantomData;
+use std::time::Instant;
 
 use async_trait::async_trait;
 
-use crate::{Fabrix, FabrixError, FabrixResult};
+use crate::{
+    Audit, AuditRecord, AuditRun, Endpoint, Fabrix, FabrixError, FabrixResult, SharedObserver,
+    Stage, StageSpan, Transform,
+};
 
 // ================================================================================================
 // Read & Write Options
@@ -38,6 +54,11 @@ where
         R::source_type()
     }
 
+    /// where the data is read from, for audit records; by default only the source type
+    fn endpoint(&self, _options: &R) -> Endpoint {
+        Endpoint::new(self.source_type())
+    }
+
     async fn async_read<'o>(&mut self, options: &'o R) -> FabrixResult<Fabrix>
     where
         'o: 'a;
@@ -56,6 +77,11 @@ where
         W::source_type()
     }
 
+    /// where the data is written to, for audit records; by default only the source type
+    fn endpoint(&self, _options: &W) -> Endpoint {
+        Endpoint::new(self.source_type())
+    }
+
     async fn async_write<'o>(&mut self, fabrix: Fabrix, options: &'o W) -> FabrixResult<()>
     where
         'o: 'a;
@@ -63,6 +89,30 @@ where
     fn sync_write<'o>(&mut self, fabrix: Fabrix, options: &'o W) -> FabrixResult<()>
     where
         'o: 'a;
+
+    /// whether each write with the options appends its rows to the previous ones, and a failed
+    /// write appends none of them, e.g. a sql writer with `SaveStrategy::Append` inserting a batch
+    /// by one statement. Only then parts of a failed batch can be written again (see
+    /// `ErrorHandler`), other writers would replace the target on each part, or duplicate the rows
+    /// written before the failure
+    fn atomic_appends(&self, _options: &W) -> bool {
+        false
+    }
+}
+
+/// a dry run of `IntoSource`
+#[async_trait]
+pub trait PlanSource<'a, W>: IntoSource<'a, W>
+where
+    W: WriteOptions,
+{
+    /// what a write would do, e.g. `SavePlan` of a sql writer
+    type Plan: Send;
+
+    /// report what `async_write` would do with the data, nothing is written
+    async fn plan<'o>(&mut self, fabrix: &Fabrix, options: &'o W) -> FabrixResult<Self::Plan>
+    where
+        'o: 'a;
 }
 
 // ================================================================================================
@@ -82,6 +132,10 @@ where
     write_options: PhantomData<WO>,
     lifetime: PhantomData<&'a ()>,
     fabrix: Option<Fabrix>,
+    observer: Option<SharedObserver>,
+    audit: Option<Audit>,
+    /// the audited run, from a read to a write
+    run: AuditRun,
 }
 
 impl<'a, R, W, RO, WO> Dispatcher<'a, R, W, RO, WO>
@@ -99,9 +153,29 @@ where
             write_options: PhantomData,
             lifetime: PhantomData,
             fabrix: None,
+            observer: None,
+            audit: None,
+            run: AuditRun::default(),
         }
     }
 
+    /// report progress of reading & writing to an observer, e.g. `Metrics`
+    pub fn with_observer(&mut self, observer: SharedObserver) -> &mut Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// append an audit record of each run to a store
+    pub fn with_audit(&mut self, audit: Audit) -> &mut Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// the record of the audited run, once finished
+    pub fn audit_record(&self) -> Option<&AuditRecord> {
+        self.run.finished()
+    }
+
     pub fn fabrix_ref(&self) -> Option<&Fabrix> {
         self.fabrix.as_ref()
     }
@@ -150,28 +224,133 @@ where
         &mut self.writer
     }
 
+    /// a run starts by a read, the destination is known by the write
+    fn start_run(&mut self, source: Endpoint) {
+        let destination = Endpoint::new(self.writer_type());
+        self.run = AuditRun::start(self.audit.as_ref(), source, destination);
+    }
+
+    /// a write without a read through the dispatcher, e.g. of `fabrix_put`, starts its own run
+    fn start_write_run(&mut self) {
+        if !self.run.is_running() {
+            self.start_run(Endpoint::new(self.reader_type()));
+        }
+    }
+
     pub fn sync_read(&mut self, options: &'a RO) -> FabrixResult<()> {
-        self.fabrix = Some(self.reader.sync_read(options)?);
-        Ok(())
+        self.start_run(self.reader.endpoint(options));
+        let started = Instant::now();
+        let span = StageSpan::start(self.observer.as_ref(), Stage::Read, self.reader_type());
+        let res = span
+            .finish_fabrix(self.reader.sync_read(options))
+            .and_then(|fx| self.run.read(&fx, started).map(|_| fx));
+        match res {
+            Ok(fx) => {
+                self.fabrix = Some(fx);
+                Ok(())
+            }
+            Err(e) => {
+                let res: FabrixResult<()> = Err(e);
+                let appended = self.run.sync_finish(&res);
+                res.and(appended)
+            }
+        }
     }
 
     pub async fn async_read(&mut self, options: &'a RO) -> FabrixResult<()> {
-        self.fabrix = Some(self.reader.async_read(options).await?);
+        self.start_run(self.reader.endpoint(options));
+        let started = Instant::now();
+        let span = StageSpan::start(self.observer.as_ref(), Stage::Read, self.reader_type());
+        let res = span
+            .finish_fabrix(self.reader.async_read(options).await)
+            .and_then(|fx| self.run.read(&fx, started).map(|_| fx));
+        match res {
+            Ok(fx) => {
+                self.fabrix = Some(fx);
+                Ok(())
+            }
+            Err(e) => {
+                let res: FabrixResult<()> = Err(e);
+                let appended = self.run.async_finish(&res).await;
+                res.and(appended)
+            }
+        }
+    }
+
+    /// transform the data read, before writing
+    pub fn sync_transform(&mut self, transform: &dyn Transform) -> FabrixResult<()> {
+        let fx = self
+            .fabrix
+            .take()
+            .ok_or(FabrixError::EmptyContent("fabrix data"))?;
+        let span = StageSpan::start(self.observer.as_ref(), Stage::Transform, transform.name());
+        self.fabrix = Some(span.finish_fabrix(transform.sync_transform(fx))?);
+        Ok(())
+    }
+
+    /// transform the data read, before writing, asynchronously
+    pub async fn async_transform(&mut self, transform: &dyn Transform) -> FabrixResult<()> {
+        let fx = self
+            .fabrix
+            .take()
+            .ok_or(FabrixError::EmptyContent("fabrix data"))?;
+        let span = StageSpan::start(self.observer.as_ref(), Stage::Transform, transform.name());
+        self.fabrix = Some(span.finish_fabrix(transform.async_transform(fx).await)?);
         Ok(())
     }
 
     pub fn sync_write(&mut self, options: &'a WO) -> FabrixResult<()> {
-        match self.fabrix.take() {
-            Some(fx) => self.writer.sync_write(fx, options),
-            None => Err(FabrixError::EmptyContent("fabrix data")),
+        let fx = self
+            .fabrix
+            .take()
+            .ok_or(FabrixError::EmptyContent("fabrix data"))?;
+        self.start_write_run();
+        self.run.with_destination(self.writer.endpoint(options));
+        let started = Instant::now();
+        let span = StageSpan::start(self.observer.as_ref(), Stage::Write, self.writer_type());
+        let (rows, bytes) = (fx.height(), fx.estimated_size());
+        let res = span.finish_rows(self.writer.sync_write(fx, options), rows, bytes);
+        if res.is_ok() {
+            self.run.written(rows, started);
         }
+        let appended = self.run.sync_finish(&res);
+        res.and(appended)
     }
 
     pub async fn async_write(&mut self, options: &'a WO) -> FabrixResult<()> {
-        match self.fabrix.take() {
-            Some(fx) => self.writer.async_write(fx, options).await,
-            None => Err(FabrixError::EmptyContent("fabrix data")),
+        let fx = self
+            .fabrix
+            .take()
+            .ok_or(FabrixError::EmptyContent("fabrix data"))?;
+        self.start_write_run();
+        self.run.with_destination(self.writer.endpoint(options));
+        let started = Instant::now();
+        let span = StageSpan::start(self.observer.as_ref(), Stage::Write, self.writer_type());
+        let (rows, bytes) = (fx.height(), fx.estimated_size());
+        let res = self.writer.async_write(fx, options).await;
+        let res = span.finish_rows(res, rows, bytes);
+        if res.is_ok() {
+            self.run.written(rows, started);
         }
+        let appended = self.run.async_finish(&res).await;
+        res.and(appended)
+    }
+}
+
+impl<'a, R, W, RO, WO> Dispatcher<'a, R, W, RO, WO>
+where
+    R: FromSource<'a, RO>,
+    W: PlanSource<'a, WO>,
+    RO: ReadOptions,
+    WO: WriteOptions,
+{
+    /// dry run of writing, the data read is kept for a following write
+    pub async fn plan(&mut self, options: &'a WO) -> FabrixResult<W::Plan> {
+        let fx = self
+            .fabrix
+            .as_ref()
+            .ok_or(FabrixError::EmptyContent("fabrix data"))?;
+        self.writer.plan(fx, options).await
     }
 }
 
@@ -179,11 +358,12 @@ where
 #[cfg(all(feature = "csv", feature = "json", feature = "parquet"))]
 mod dispatcher_tests {
     use std::fs::File;
+    use std::sync::Arc;
 
     use super::*;
     use crate::{
         CsvReadOptions, CsvReader, CsvWriteOptions, CsvWriter, JsonWriteOptions, JsonWriter,
-        ParquetReadOptions, ParquetReader, ParquetWriteOptions, ParquetWriter,
+        Metrics, ParquetReadOptions, ParquetReader, ParquetWriteOptions, ParquetWriter,
     };
 
     const CSV_READ: &str = "../mock/test.csv";
@@ -249,6 +429,22 @@ mod dispatcher_tests {
         }
     }
 
+    #[async_trait]
+    impl<'a> PlanSource<'a, EmptyOption> for EmptyWrite {
+        type Plan = usize;
+
+        async fn plan<'o>(
+            &mut self,
+            fabrix: &Fabrix,
+            _options: &'o EmptyOption,
+        ) -> FabrixResult<usize>
+        where
+            'o: 'a,
+        {
+            Ok(fabrix.height())
+        }
+    }
+
     #[test]
     fn test_empty_dispatcher() {
         let mut dispatcher = Dispatcher::new(EmptyRead, EmptyWrite);
@@ -322,8 +518,89 @@ mod dispatcher_tests {
 
         let wo = JsonWriteOptions {
             is_json: Some(true),
+            ..Default::default()
         };
         let res = dispatcher.sync_write(&wo);
         assert!(res.is_ok(), "sync_write parquet option is always true");
     }
+
+    #[tokio::test]
+    async fn csv_read_plan() {
+        let reader = CsvReader::new(File::open(CSV_READ).unwrap());
+        let ro = CsvReadOptions::default();
+        let mut dispatcher = Dispatcher::new(reader, EmptyWrite);
+        assert!(dispatcher.plan(&EmptyOption).await.is_err());
+
+        dispatcher.sync_read(&ro).unwrap();
+        assert_eq!(dispatcher.plan(&EmptyOption).await.unwrap(), 100);
+        assert!(dispatcher.has_data());
+    }
+
+    #[test]
+    fn csv_read_with_observer() {
+        let metrics = Arc::new(Metrics::new());
+        let reader = CsvReader::new(File::open(CSV_READ).unwrap());
+
+        let ro = CsvReadOptions::default();
+        let mut dispatcher = Dispatcher::new(reader, EmptyWrite);
+        dispatcher.with_observer(metrics.clone());
+
+        dispatcher.sync_read(&ro).unwrap();
+        dispatcher.sync_write(&EmptyOption).unwrap();
+
+        let read = metrics.get(Stage::Read, "csv");
+        assert_eq!((read.rows, read.batches, read.runs), (100, 1, 1));
+        assert!(read.bytes > 0);
+        let write = metrics.get(Stage::Write, "empty");
+        assert_eq!((write.rows, write.runs, write.errors), (100, 1, 0));
+    }
+
+    #[tokio::test]
+    async fn csv_read_with_audit() {
+        use crate::{AuditStore, Endpoint, FileAuditStore, RunOutcome};
+
+        let path = "../cache/audit/csv_read_with_audit.jsonl";
+        let _ = std::fs::remove_file(path);
+        let store = FileAuditStore::new(path).unwrap();
+
+        let mut audit = crate::Audit::new(Arc::new(store.clone()));
+        audit
+            .with_source(Endpoint::file("csv", CSV_READ))
+            .with_tag("audit test");
+
+        let reader = CsvReader::new(File::open(CSV_READ).unwrap());
+        let ro = CsvReadOptions::default();
+        let mut dispatcher = Dispatcher::new(reader, EmptyWrite);
+        dispatcher.with_audit(audit);
+
+        dispatcher.async_read(&ro).await.unwrap();
+        assert!(dispatcher.audit_record().is_none());
+        dispatcher.async_write(&EmptyOption).await.unwrap();
+
+        let record = dispatcher.audit_record().unwrap();
+        assert_eq!(record.outcome, RunOutcome::Success);
+        assert_eq!((record.rows_read, record.rows_written), (100, 100));
+        assert_eq!(record.tag.as_deref(), Some("audit test"));
+        assert!(record.schema_hash.is_some());
+
+        let history = store
+            .query(crate::AuditQuery::new().with_source_location(CSV_READ))
+            .await
+            .unwrap();
+        assert_eq!(history, vec![record.clone()]);
+
+        // sync runs are audited as well
+        let reader = CsvReader::new(File::open(CSV_READ).unwrap());
+        let mut dispatcher = Dispatcher::new(reader, EmptyWrite);
+        dispatcher.with_audit(crate::Audit::new(Arc::new(store.clone())));
+        dispatcher.sync_read(&ro).unwrap();
+        dispatcher.sync_write(&EmptyOption).unwrap();
+        let record = dispatcher.audit_record().unwrap();
+        assert_eq!((record.rows_read, record.rows_written), (100, 100));
+        assert_eq!(record.destination, Endpoint::new("empty"));
+        assert_eq!(
+            store.query(&crate::AuditQuery::new()).await.unwrap().len(),
+            2
+        );
+    }
 }
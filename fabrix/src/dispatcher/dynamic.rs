@@ -0,0 +1,361 @@
+//! Dynamic Dispatcher
+//!
+//! Type-erased readers & writers, so that sources can be chosen at runtime:
+//! - DynReader & DynWriter: object-safe readers & writers, which own their options
+//! - SourceRegistry: build `DynReader` & `DynWriter` from a `source_type` and an options map
+//!
+//! `Box<dyn DynReader>` & `Box<dyn DynWriter>` implement `FromSource` & `IntoSource` (with
+//! the empty `DynReadOptions` & `DynWriteOptions`), hence they work with all the dispatchers.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde_json::Value as JsonValue;
+
+use crate::{
+    Endpoint, Fabrix, FabrixError, FabrixResult, FromSource, IntoSource, ReadOptions, WriteOptions,
+};
+
+// ================================================================================================
+// DynReader & DynWriter
+// ================================================================================================
+
+/// an object-safe reader, which owns its read options
+#[async_trait]
+pub trait DynReader: Send {
+    fn source_type(&self) -> &str;
+
+    /// where the data is read from, for audit records; by default only the source type
+    fn endpoint(&self) -> Endpoint {
+        Endpoint::new(self.source_type())
+    }
+
+    fn sync_read(&mut self) -> FabrixResult<Fabrix>;
+
+    async fn async_read(&mut self) -> FabrixResult<Fabrix>;
+}
+
+/// an object-safe writer, which owns its write options
+#[async_trait]
+pub trait DynWriter: Send {
+    fn source_type(&self) -> &str;
+
+    /// where the data is written to, for audit records; by default only the source type
+    fn endpoint(&self) -> Endpoint {
+        Endpoint::new(self.source_type())
+    }
+
+    fn sync_write(&mut self, fabrix: Fabrix) -> FabrixResult<()>;
+
+    async fn async_write(&mut self, fabrix: Fabrix) -> FabrixResult<()>;
+}
+
+// ================================================================================================
+// Dyn read & write options, FromSource & IntoSource impl
+// ================================================================================================
+
+/// options of `Box<dyn DynReader>`, which are held by the reader itself
+#[derive(Default)]
+pub struct DynReadOptions;
+
+impl ReadOptions for DynReadOptions {
+    fn source_type() -> &'static str {
+        "dyn"
+    }
+}
+
+/// options of `Box<dyn DynWriter>`, which are held by the writer itself
+#[derive(Default)]
+pub struct DynWriteOptions;
+
+impl WriteOptions for DynWriteOptions {
+    fn source_type() -> &'static str {
+        "dyn"
+    }
+}
+
+#[async_trait]
+impl<'a> FromSource<'a, DynReadOptions> for Box<dyn DynReader> {
+    fn source_type(&self) -> &str {
+        self.as_ref().source_type()
+    }
+
+    fn endpoint(&self, _options: &DynReadOptions) -> Endpoint {
+        self.as_ref().endpoint()
+    }
+
+    async fn async_read<'o>(&mut self, _options: &'o DynReadOptions) -> FabrixResult<Fabrix>
+    where
+        'o: 'a,
+    {
+        self.as_mut().async_read().await
+    }
+
+    fn sync_read<'o>(&mut self, _options: &'o DynReadOptions) -> FabrixResult<Fabrix>
+    where
+        'o: 'a,
+    {
+        self.as_mut().sync_read()
+    }
+}
+
+#[async_trait]
+impl<'a> IntoSource<'a, DynWriteOptions> for Box<dyn DynWriter> {
+    fn source_type(&self) -> &str {
+        self.as_ref().source_type()
+    }
+
+    fn endpoint(&self, _options: &DynWriteOptions) -> Endpoint {
+        self.as_ref().endpoint()
+    }
+
+    async fn async_write<'o>(
+        &mut self,
+        fabrix: Fabrix,
+        _options: &'o DynWriteOptions,
+    ) -> FabrixResult<()>
+    where
+        'o: 'a,
+    {
+        self.as_mut().async_write(fabrix).await
+    }
+
+    fn sync_write<'o>(&mut self, fabrix: Fabrix, _options: &'o DynWriteOptions) -> FabrixResult<()>
+    where
+        'o: 'a,
+    {
+        self.as_mut().sync_write(fabrix)
+    }
+}
+
+/// a single-byte option (delimiter, quoting char, etc.) given as a char
+pub(crate) fn ascii_byte(name: &str, c: char) -> FabrixResult<u8> {
+    u8::try_from(c)
+        .ok()
+        .filter(u8::is_ascii)
+        .ok_or_else(|| FabrixError::InvalidArgument(format!("{name} `{c}` is not an ascii char")))
+}
+
+// ================================================================================================
+// SourceRegistry
+// ================================================================================================
+
+pub type DynReaderBuilder =
+    Box<dyn Fn(JsonValue) -> FabrixResult<Box<dyn DynReader>> + Send + Sync>;
+
+pub type DynWriterBuilder =
+    Box<dyn Fn(JsonValue) -> FabrixResult<Box<dyn DynWriter>> + Send + Sync>;
+
+/// builders of `DynReader` & `DynWriter`, keyed by `source_type`
+#[derive(Default)]
+pub struct SourceRegistry {
+    readers: HashMap<String, DynReaderBuilder>,
+    writers: HashMap<String, DynWriterBuilder>,
+}
+
+impl SourceRegistry {
+    /// an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// a registry of all the sources enabled by features:
+    /// `csv`, `parquet`, `json`, `xl` (reader only), `sql` & `mongo`
+    pub fn builtin() -> Self {
+        #[allow(unused_mut)]
+        let mut registry = Self::new();
+
+        #[cfg(feature = "csv")]
+        registry
+            .with_reader::<crate::CsvReaderSpec>("csv")
+            .with_writer::<crate::CsvWriterSpec>("csv");
+        #[cfg(feature = "parquet")]
+        registry
+            .with_reader::<crate::ParquetReaderSpec>("parquet")
+            .with_writer::<crate::ParquetWriterSpec>("parquet");
+        #[cfg(feature = "json")]
+        registry
+            .with_reader::<crate::JsonReaderSpec>("json")
+            .with_writer::<crate::JsonWriterSpec>("json");
+        #[cfg(feature = "xl")]
+        registry.with_reader::<crate::XlReaderSpec>("xl");
+        #[cfg(feature = "sql")]
+        registry
+            .with_reader::<crate::SqlReaderSpec>("sql")
+            .with_writer::<crate::SqlWriterSpec>("sql");
+        #[cfg(feature = "mongo")]
+        registry
+            .with_reader::<crate::MongoReaderSpec>("mongo")
+            .with_writer::<crate::MongoWriterSpec>("mongo");
+
+        registry
+    }
+
+    /// register a reader, which is deserialized from the options map
+    pub fn with_reader<T>(&mut self, source_type: &str) -> &mut Self
+    where
+        T: DynReader + DeserializeOwned + 'static,
+    {
+        self.with_reader_builder(source_type, |options| {
+            Ok(Box::new(serde_json::from_value::<T>(options)?))
+        })
+    }
+
+    /// register a writer, which is deserialized from the options map
+    pub fn with_writer<T>(&mut self, source_type: &str) -> &mut Self
+    where
+        T: DynWriter + DeserializeOwned + 'static,
+    {
+        self.with_writer_builder(source_type, |options| {
+            Ok(Box::new(serde_json::from_value::<T>(options)?))
+        })
+    }
+
+    /// register a custom reader builder, replacing the existing one of the same `source_type`
+    pub fn with_reader_builder<F>(&mut self, source_type: &str, builder: F) -> &mut Self
+    where
+        F: Fn(JsonValue) -> FabrixResult<Box<dyn DynReader>> + Send + Sync + 'static,
+    {
+        self.readers
+            .insert(source_type.to_owned(), Box::new(builder));
+        self
+    }
+
+    /// register a custom writer builder, replacing the existing one of the same `source_type`
+    pub fn with_writer_builder<F>(&mut self, source_type: &str, builder: F) -> &mut Self
+    where
+        F: Fn(JsonValue) -> FabrixResult<Box<dyn DynWriter>> + Send + Sync + 'static,
+    {
+        self.writers
+            .insert(source_type.to_owned(), Box::new(builder));
+        self
+    }
+
+    pub fn has_reader(&self, source_type: &str) -> bool {
+        self.readers.contains_key(source_type)
+    }
+
+    pub fn has_writer(&self, source_type: &str) -> bool {
+        self.writers.contains_key(source_type)
+    }
+
+    /// registered reader types, sorted
+    pub fn reader_types(&self) -> Vec<&str> {
+        let mut res = self.readers.keys().map(String::as_str).collect::<Vec<_>>();
+        res.sort_unstable();
+        res
+    }
+
+    /// registered writer types, sorted
+    pub fn writer_types(&self) -> Vec<&str> {
+        let mut res = self.writers.keys().map(String::as_str).collect::<Vec<_>>();
+        res.sort_unstable();
+        res
+    }
+
+    /// build a reader from its `source_type` & options map
+    pub fn reader(
+        &self,
+        source_type: &str,
+        options: JsonValue,
+    ) -> FabrixResult<Box<dyn DynReader>> {
+        let builder = self
+            .readers
+            .get(source_type)
+            .ok_or_else(|| FabrixError::NotFound(format!("reader `{source_type}`")))?;
+
+        builder(options)
+    }
+
+    /// build a writer from its `source_type` & options map
+    pub fn writer(
+        &self,
+        source_type: &str,
+        options: JsonValue,
+    ) -> FabrixResult<Box<dyn DynWriter>> {
+        let builder = self
+            .writers
+            .get(source_type)
+            .ok_or_else(|| FabrixError::NotFound(format!("writer `{source_type}`")))?;
+
+        builder(options)
+    }
+}
+
+#[cfg(test)]
+mod dynamic_dispatcher_tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Dispatcher, FanOutDispatcher};
+
+    const CSV_FILE_PATH: &str = "../mock/test.csv";
+    const PARQUET_FILE_PATH: &str = "../cache/write_dyn.parquet";
+    const JSON_FILE_PATH: &str = "../cache/write_dyn.json";
+
+    #[test]
+    fn test_registry() {
+        let registry = SourceRegistry::builtin();
+        assert!(registry.has_reader("csv"));
+        assert!(registry.has_writer("parquet"));
+        assert!(!registry.has_writer("xl"));
+
+        let reader = registry.reader("csv", json!({ "path": CSV_FILE_PATH }));
+        assert_eq!(reader.unwrap().source_type(), "csv");
+
+        // unregistered source type
+        assert!(registry.reader("csv2", json!({})).is_err());
+        // missing path
+        assert!(registry
+            .writer("csv", json!({ "has_header": true }))
+            .is_err());
+        // invalid delimiter
+        let mut reader = registry
+            .reader("csv", json!({ "path": CSV_FILE_PATH, "delimiter": "，" }))
+            .unwrap();
+        assert!(reader.sync_read(&DynReadOptions).is_err());
+    }
+
+    #[test]
+    fn test_dyn_dispatcher() {
+        let registry = SourceRegistry::builtin();
+        let reader = registry
+            .reader("csv", json!({ "path": CSV_FILE_PATH, "num_rows": 10 }))
+            .unwrap();
+        let writer = registry
+            .writer("parquet", json!({ "path": PARQUET_FILE_PATH }))
+            .unwrap();
+
+        let mut dispatcher = Dispatcher::new(reader, writer);
+        assert_eq!(dispatcher.reader_type(), "csv");
+        assert_eq!(dispatcher.writer_type(), "parquet");
+
+        dispatcher.sync_read(&DynReadOptions).unwrap();
+        assert_eq!(dispatcher.fabrix_ref().unwrap().height(), 10);
+        dispatcher.sync_write(&DynWriteOptions).unwrap();
+
+        let mut reader = registry
+            .reader("parquet", json!({ "path": PARQUET_FILE_PATH }))
+            .unwrap();
+        assert_eq!(reader.sync_read(&DynReadOptions).unwrap().height(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_dyn_fan_out() {
+        let registry = SourceRegistry::builtin();
+        let reader = registry
+            .reader("csv", json!({ "path": CSV_FILE_PATH }))
+            .unwrap();
+        let writer = registry
+            .writer("json", json!({ "path": JSON_FILE_PATH }))
+            .unwrap();
+
+        let mut dispatcher = FanOutDispatcher::new(reader);
+        dispatcher.with_writer("json", writer, &DynWriteOptions);
+
+        let report = dispatcher.async_dispatch(&DynReadOptions).await.unwrap();
+        assert!(report.is_success());
+        assert_eq!(report.rows, 100);
+    }
+}
@@ -0,0 +1,516 @@
+//! Fan-in Dispatcher
+//!
+//! Read many sources, union them into one `Fabrix`, and write it to one destination:
+//! - Readers: each reader comes with its own read options
+//! - Writer: write the union to one destination
+//!
+//! Unlike `Fabrix::vconcat`, sources do not need identical schemas. Columns are aligned by
+//! name, a column missing in a source is filled with nulls, and columns of different types are
+//! promoted to a common type (e.g. `I32` & `I64` to `I64`, `I64` & `F32` to `F64`).
+
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use polars::prelude::{DataFrame, DataType, NamedFrom, Series as PolarsSeries};
+
+use crate::{
+    Fabrix, FabrixError, FabrixResult, FromSource, IntoSource, ReadOptions, ValueType, WriteOptions,
+};
+
+// ================================================================================================
+// FanInSource
+// ================================================================================================
+
+/// a reader bound to its read options
+#[async_trait]
+pub trait FanInSource<'a>: Send {
+    fn source_type(&self) -> &str;
+
+    fn sync_read(&mut self) -> FabrixResult<Fabrix>;
+
+    async fn async_read(&mut self) -> FabrixResult<Fabrix>;
+}
+
+struct Source<'a, R, RO> {
+    reader: R,
+    options: &'a RO,
+}
+
+#[async_trait]
+impl<'a, R, RO> FanInSource<'a> for Source<'a, R, RO>
+where
+    R: FromSource<'a, RO> + Send,
+    RO: ReadOptions + Sync,
+{
+    fn source_type(&self) -> &str {
+        self.reader.source_type()
+    }
+
+    fn sync_read(&mut self) -> FabrixResult<Fabrix> {
+        self.reader.sync_read(self.options)
+    }
+
+    async fn async_read(&mut self) -> FabrixResult<Fabrix> {
+        self.reader.async_read(self.options).await
+    }
+}
+
+// ================================================================================================
+// Union
+// ================================================================================================
+
+/// union named `Fabrix` by column names, the result has no index.
+///
+/// Columns are ordered by their first appearance. If `source_tag` is set, a column of that name
+/// holds the name of the source of each row. A column without any value, e.g. inferred as strings
+/// from empty csv cells, takes the type of the same column of the other sources.
+pub fn union_fabrix(
+    frames: Vec<(String, Fabrix)>,
+    source_tag: Option<&str>,
+) -> FabrixResult<Fabrix> {
+    if frames.is_empty() {
+        return Err(FabrixError::EmptyContent("fan-in sources"));
+    }
+
+    // aligned columns, their promoted types & the first type read, kept if all values are null
+    let mut columns = Vec::<(String, ValueType, ValueType)>::new();
+    for (_, fx) in frames.iter() {
+        for fi in fx.fields()? {
+            let dtype = match fx.get_column(fi.name())?.0.null_count() == fx.height() {
+                true => ValueType::Null,
+                false => fi.dtype().clone(),
+            };
+            match columns.iter_mut().find(|(n, _, _)| n == fi.name()) {
+                Some((name, promoted, _)) => *promoted = promote_dtype(name, promoted, &dtype)?,
+                None => columns.push((fi.name().to_owned(), dtype, fi.dtype().clone())),
+            }
+        }
+    }
+    let columns = columns
+        .into_iter()
+        .map(|(name, promoted, first)| match promoted {
+            ValueType::Null => (name, first),
+            t => (name, t),
+        })
+        .collect::<Vec<_>>();
+    if let Some(tag) = source_tag.filter(|t| columns.iter().any(|(n, _)| n == t)) {
+        return Err(FabrixError::InvalidArgument(format!(
+            "source tag `{tag}` conflicts with an existing column"
+        )));
+    }
+
+    let mut res = None::<DataFrame>;
+    for (source, fx) in frames.iter() {
+        let height = fx.height();
+        let mut series = Vec::with_capacity(columns.len() + 1);
+        for (name, dtype) in columns.iter() {
+            let stack_dtype = stack_dtype(dtype);
+            let s = match fx.get_column(name) {
+                Ok(s) if s.dtype()? == &stack_dtype => s.0.clone(),
+                Ok(s) => s.cast(&stack_dtype)?.0,
+                Err(_) => PolarsSeries::full_null(name, height, &DataType::from(&stack_dtype)),
+            };
+            series.push(s);
+        }
+        if let Some(tag) = source_tag {
+            series.push(PolarsSeries::new(tag, vec![source.as_str(); height]));
+        }
+
+        let df = DataFrame::new(series)?;
+        match res.as_mut() {
+            Some(r) => {
+                r.vstack_mut(&df)?;
+            }
+            None => res = Some(df),
+        }
+    }
+
+    let mut res = Fabrix::new_no_index(res.unwrap());
+    for (name, dtype) in columns.iter().filter(|(_, t)| &stack_dtype(t) != t) {
+        res.cast(name, dtype)?;
+    }
+    res.rechunk();
+
+    Ok(res)
+}
+
+/// categories of different sources can not be stacked, they are stacked as strings instead
+fn stack_dtype(dtype: &ValueType) -> ValueType {
+    match dtype {
+        ValueType::Categorical(_) => ValueType::String,
+        t => t.clone(),
+    }
+}
+
+/// the smallest type that both types can be cast into
+fn promote_dtype(name: &str, l: &ValueType, r: &ValueType) -> FabrixResult<ValueType> {
+    use ValueType::*;
+
+    fn int_width(t: &ValueType) -> Option<(bool, u8)> {
+        match t {
+            U8 => Some((false, 8)),
+            U16 => Some((false, 16)),
+            U32 => Some((false, 32)),
+            U64 => Some((false, 64)),
+            I8 => Some((true, 8)),
+            I16 => Some((true, 16)),
+            I32 => Some((true, 32)),
+            I64 => Some((true, 64)),
+            _ => None,
+        }
+    }
+
+    fn int_type(signed: bool, width: u8) -> ValueType {
+        match (signed, width) {
+            (false, 8) => U8,
+            (false, 16) => U16,
+            (false, 32) => U32,
+            (false, _) => U64,
+            (true, 8) => I8,
+            (true, 16) => I16,
+            (true, 32) => I32,
+            (true, 64) => I64,
+            // no signed integer is wide enough for `U64`
+            (true, _) => F64,
+        }
+    }
+
+    let res = match (l, r) {
+        (l, r) if l == r => l.clone(),
+        (Null, t) | (t, Null) => t.clone(),
+        (Categorical(_), Categorical(_)) => Categorical(None),
+        (Categorical(_) | String, Categorical(_) | String) => String,
+        (Bool, t) | (t, Bool) if int_width(t).is_some() || matches!(t, F32 | F64) => t.clone(),
+        (F32, F32) => F32,
+        (F32 | F64, t) | (t, F32 | F64) if int_width(t).is_some() || matches!(t, F32 | F64) => F64,
+        (Date, DateTime) | (DateTime, Date) => DateTime,
+        (l, r) => match (int_width(l), int_width(r)) {
+            (Some((ls, lw)), Some((rs, rw))) if ls == rs => int_type(ls, lw.max(rw)),
+            (Some((true, sw)), Some((false, uw))) | (Some((false, uw)), Some((true, sw))) => {
+                if sw > uw {
+                    int_type(true, sw)
+                } else {
+                    int_type(true, uw * 2)
+                }
+            }
+            _ => {
+                return Err(FabrixError::InvalidArgument(format!(
+                    "column `{name}` has incompatible types {l} & {r}"
+                )))
+            }
+        },
+    };
+
+    Ok(res)
+}
+
+// ================================================================================================
+// FanInDispatcher
+// ================================================================================================
+
+pub struct FanInDispatcher<'a, Writer, WO>
+where
+    Writer: IntoSource<'a, WO>,
+    WO: WriteOptions,
+{
+    sources: Vec<(String, Box<dyn FanInSource<'a> + 'a>)>,
+    writer: Writer,
+    source_tag: Option<String>,
+    write_options: PhantomData<WO>,
+    fabrix: Option<Fabrix>,
+}
+
+impl<'a, W, WO> FanInDispatcher<'a, W, WO>
+where
+    W: IntoSource<'a, WO>,
+    WO: WriteOptions,
+{
+    pub fn new(writer: W) -> Self {
+        Self {
+            sources: Vec::new(),
+            writer,
+            source_tag: None,
+            write_options: PhantomData,
+            fabrix: None,
+        }
+    }
+
+    /// add a reader, `name` is the value of the source tag column
+    pub fn with_reader<R, RO>(&mut self, name: &str, reader: R, options: &'a RO) -> &mut Self
+    where
+        R: FromSource<'a, RO> + Send + 'a,
+        RO: ReadOptions + Sync,
+    {
+        self.with_source(name, Source { reader, options })
+    }
+
+    /// add a custom source
+    pub fn with_source<S>(&mut self, name: &str, source: S) -> &mut Self
+    where
+        S: FanInSource<'a> + 'a,
+    {
+        self.sources.push((name.to_owned(), Box::new(source)));
+        self
+    }
+
+    /// add a column holding the source name of each row
+    pub fn with_source_tag(&mut self, column: &str) -> &mut Self {
+        self.source_tag = Some(column.to_owned());
+        self
+    }
+
+    /// source types of the readers, in the order they were added
+    pub fn reader_types(&self) -> Vec<&str> {
+        self.sources.iter().map(|(_, s)| s.source_type()).collect()
+    }
+
+    pub fn writer_type(&self) -> &str {
+        self.writer.source_type()
+    }
+
+    /// expose writer as reference to the outside
+    pub fn writer(&self) -> &W {
+        &self.writer
+    }
+
+    /// expose writer as mutable to the outside
+    pub fn writer_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    pub fn fabrix_ref(&self) -> Option<&Fabrix> {
+        self.fabrix.as_ref()
+    }
+
+    pub fn fabrix_mut(&mut self) -> Option<&mut Fabrix> {
+        self.fabrix.as_mut()
+    }
+
+    pub fn fabrix_take(&mut self) -> Option<Fabrix> {
+        self.fabrix.take()
+    }
+
+    pub fn has_data(&self) -> bool {
+        self.fabrix.is_some()
+    }
+
+    /// read all the sources one by one, and union them
+    pub fn sync_read(&mut self) -> FabrixResult<()> {
+        let frames = self
+            .sources
+            .iter_mut()
+            .map(|(name, s)| Ok((name.clone(), s.sync_read()?)))
+            .collect::<FabrixResult<Vec<_>>>()?;
+
+        self.fabrix = Some(union_fabrix(frames, self.source_tag.as_deref())?);
+        Ok(())
+    }
+
+    /// read all the sources concurrently, and union them
+    pub async fn async_read(&mut self) -> FabrixResult<()> {
+        let names = self
+            .sources
+            .iter()
+            .map(|(n, _)| n.clone())
+            .collect::<Vec<_>>();
+        let frames = join_all(self.sources.iter_mut().map(|(_, s)| s.async_read()))
+            .await
+            .into_iter()
+            .zip(names)
+            .map(|(fx, name)| Ok((name, fx?)))
+            .collect::<FabrixResult<Vec<_>>>()?;
+
+        self.fabrix = Some(union_fabrix(frames, self.source_tag.as_deref())?);
+        Ok(())
+    }
+
+    pub fn sync_write(&mut self, options: &'a WO) -> FabrixResult<()> {
+        match self.fabrix.take() {
+            Some(fx) => self.writer.sync_write(fx, options),
+            None => Err(FabrixError::EmptyContent("fabrix data")),
+        }
+    }
+
+    pub async fn async_write(&mut self, options: &'a WO) -> FabrixResult<()> {
+        match self.fabrix.take() {
+            Some(fx) => self.writer.async_write(fx, options).await,
+            None => Err(FabrixError::EmptyContent("fabrix data")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod fan_in_dispatcher_tests {
+    use std::fs::File;
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{fx, value, CsvReadOptions, CsvReader, CsvWriteOptions, CsvWriter};
+
+    const CSV_FILE_PATH: &str = "../mock/test.csv";
+
+    struct MockSource(Option<Fabrix>);
+
+    #[async_trait]
+    impl<'a> FanInSource<'a> for MockSource {
+        fn source_type(&self) -> &str {
+            "mock"
+        }
+
+        fn sync_read(&mut self) -> FabrixResult<Fabrix> {
+            self.0.take().ok_or(FabrixError::EmptyContent("mock"))
+        }
+
+        async fn async_read(&mut self) -> FabrixResult<Fabrix> {
+            self.sync_read()
+        }
+    }
+
+    #[test]
+    fn test_union() {
+        let jan = fx![
+            "id" => [1i32, 2],
+            "amount" => [1.5f32, 2.5],
+        ]
+        .unwrap();
+        let feb = fx![
+            "id" => [3i64],
+            "region" => ["north"],
+        ]
+        .unwrap();
+
+        let res = union_fabrix(
+            vec![("jan".to_owned(), jan), ("feb".to_owned(), feb)],
+            Some("source"),
+        )
+        .unwrap();
+
+        assert_eq!(res.shape(), (3, 4));
+        let dtypes = res.dtypes().unwrap();
+        assert_eq!(dtypes[0], &ValueType::I64);
+        assert_eq!(dtypes[1], &ValueType::F32);
+        assert_eq!(dtypes[2], &ValueType::String);
+        assert_eq!(dtypes[3], &ValueType::String);
+        assert_eq!(res.get_column("id").unwrap().get(2).unwrap(), value!(3i64));
+        assert_eq!(
+            res.get_column("amount").unwrap().get(2).unwrap(),
+            value!(None::<f32>)
+        );
+        assert_eq!(
+            res.get_column("source").unwrap().get(0).unwrap(),
+            value!("jan")
+        );
+        assert_eq!(
+            res.get_column("source").unwrap().get(2).unwrap(),
+            value!("feb")
+        );
+    }
+
+    #[test]
+    fn test_union_all_null() {
+        let jan = fx!["id" => [1i64, 2], "amount" => [1.5f64, 2.5]].unwrap();
+        let feb = fx!["id" => [3i64], "amount" => [None::<&str>]].unwrap();
+        let mar = fx!["id" => [4i64], "note" => [None::<&str>]].unwrap();
+
+        let res = union_fabrix(
+            vec![
+                ("jan".to_owned(), jan),
+                ("feb".to_owned(), feb),
+                ("mar".to_owned(), mar),
+            ],
+            None,
+        )
+        .unwrap();
+
+        let dtypes = res.dtypes().unwrap();
+        assert_eq!(
+            dtypes,
+            vec![&ValueType::I64, &ValueType::F64, &ValueType::String]
+        );
+        assert_eq!(
+            res.get_column("amount").unwrap().get(2).unwrap(),
+            value!(None::<f64>)
+        );
+    }
+
+    #[test]
+    fn test_promote_dtype() {
+        use ValueType::*;
+
+        let cases = [
+            (U8, I8, I16),
+            (U32, I64, I64),
+            (U64, I32, F64),
+            (I16, F32, F64),
+            (Bool, U8, U8),
+            (Date, DateTime, DateTime),
+            (Categorical(None), String, String),
+            (Null, Date, Date),
+        ];
+        for (l, r, expected) in cases {
+            assert_eq!(promote_dtype("c", &l, &r).unwrap(), expected);
+            assert_eq!(promote_dtype("c", &r, &l).unwrap(), expected);
+        }
+
+        assert!(promote_dtype("c", &Bool, &Date).is_err());
+    }
+
+    #[test]
+    fn test_union_errors() {
+        assert!(union_fabrix(vec![], None).is_err());
+
+        let fx = fx!["source" => [1]].unwrap();
+        assert!(union_fabrix(vec![("a".to_owned(), fx)], Some("source")).is_err());
+    }
+
+    #[test]
+    fn test_fan_in_sync() {
+        let ro = CsvReadOptions::default();
+        let wo = CsvWriteOptions::default();
+        let mut buff = Cursor::new(Vec::<u8>::new());
+
+        let extra = fx!["id" => [101i64], "score" => [9.5]].unwrap();
+
+        let mut dispatcher = FanInDispatcher::new(CsvWriter::new(&mut buff));
+        dispatcher
+            .with_reader("a", CsvReader::new(File::open(CSV_FILE_PATH).unwrap()), &ro)
+            .with_reader("b", CsvReader::new(File::open(CSV_FILE_PATH).unwrap()), &ro)
+            .with_source("extra", MockSource(Some(extra)))
+            .with_source_tag("src");
+        assert_eq!(dispatcher.reader_types(), vec!["csv", "csv", "mock"]);
+        assert_eq!(dispatcher.writer_type(), "csv");
+
+        dispatcher.sync_read().unwrap();
+        let fx = dispatcher.fabrix_ref().unwrap();
+        assert_eq!(fx.height(), 201);
+        assert_eq!(fx.width(), 12);
+        assert_eq!(fx.get_column("src").unwrap().get(100).unwrap(), value!("b"));
+        assert_eq!(
+            fx.get_column("src").unwrap().get(200).unwrap(),
+            value!("extra")
+        );
+
+        dispatcher.sync_write(&wo).unwrap();
+        assert!(!dispatcher.has_data());
+        drop(dispatcher);
+
+        let res = String::from_utf8(buff.into_inner()).unwrap();
+        assert!(res.starts_with("id,first_name"));
+    }
+
+    #[tokio::test]
+    async fn test_fan_in_async() {
+        let ro = CsvReadOptions::default();
+        let wo = CsvWriteOptions::default();
+        let mut buff = Cursor::new(Vec::<u8>::new());
+
+        let mut dispatcher = FanInDispatcher::new(CsvWriter::new(&mut buff));
+        dispatcher
+            .with_reader("a", CsvReader::new(File::open(CSV_FILE_PATH).unwrap()), &ro)
+            .with_source("empty", MockSource(None));
+
+        assert!(dispatcher.async_read().await.is_err());
+        assert!(dispatcher.async_write(&wo).await.is_err());
+    }
+}
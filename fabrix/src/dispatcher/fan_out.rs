@@ -0,0 +1,579 @@
+//! Fan-out Dispatcher
+//!
+//! Read once, write the same `Fabrix` to many destinations:
+//! - Reader: read from one source
+//! - Writers: each writer comes with its own write options, and optionally a transform
+//!
+//! How a failed writer affects the others is decided by `ErrorPolicy`, and the outcome of every
+//! writer is reported by `FanOutReport`. With an `Audit`, a record of each writer is appended to
+//! an audit store; a writer not written, since the read or a writer under `FailAll` has failed,
+//! is recorded as failed.
+
+use std::marker::PhantomData;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use futures::future::join_all;
+
+use crate::{
+    Audit, AuditRecord, AuditRun, Endpoint, Fabrix, FabrixError, FabrixResult, FromSource,
+    IntoSource, ReadOptions, Transform, WriteOptions,
+};
+
+// ================================================================================================
+// FanOutTarget
+// ================================================================================================
+
+/// a writer bound to its write options
+#[async_trait]
+pub trait FanOutTarget<'a>: Send {
+    fn source_type(&self) -> &str;
+
+    /// where the data is written to, for audit records; by default only the source type
+    fn endpoint(&self) -> Endpoint {
+        Endpoint::new(self.source_type())
+    }
+
+    fn sync_write(&mut self, fabrix: Fabrix) -> FabrixResult<()>;
+
+    async fn async_write(&mut self, fabrix: Fabrix) -> FabrixResult<()>;
+}
+
+struct Target<'a, W, WO> {
+    writer: W,
+    options: &'a WO,
+    transform: Option<Box<dyn Transform>>,
+}
+
+#[async_trait]
+impl<'a, W, WO> FanOutTarget<'a> for Target<'a, W, WO>
+where
+    W: IntoSource<'a, WO> + Send,
+    WO: WriteOptions + Sync,
+{
+    fn source_type(&self) -> &str {
+        self.writer.source_type()
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        self.writer.endpoint(self.options)
+    }
+
+    fn sync_write(&mut self, fabrix: Fabrix) -> FabrixResult<()> {
+        let fabrix = match &self.transform {
+            Some(t) => t.sync_transform(fabrix)?,
+            None => fabrix,
+        };
+        self.writer.sync_write(fabrix, self.options)
+    }
+
+    async fn async_write(&mut self, fabrix: Fabrix) -> FabrixResult<()> {
+        let fabrix = match &self.transform {
+            Some(t) => t.async_transform(fabrix).await?,
+            None => fabrix,
+        };
+        self.writer.async_write(fabrix, self.options).await
+    }
+}
+
+// ================================================================================================
+// ErrorPolicy & FanOutReport
+// ================================================================================================
+
+/// how a failed writer affects the others
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// stop at the first failed writer, the rest are not written
+    #[default]
+    FailAll,
+    /// write to all the writers, failures are only reported
+    BestEffort,
+    /// write to all the writers, then fail with all the errors if any
+    CollectErrors,
+}
+
+/// outcome of one writer
+#[derive(Debug)]
+pub struct WriterReport {
+    pub name: String,
+    pub source_type: String,
+    pub result: FabrixResult<()>,
+}
+
+/// outcome of all the writers, in the order they were added
+#[derive(Debug, Default)]
+pub struct FanOutReport {
+    pub rows: usize,
+    pub writers: Vec<WriterReport>,
+}
+
+impl FanOutReport {
+    pub fn is_success(&self) -> bool {
+        self.writers.iter().all(|w| w.result.is_ok())
+    }
+
+    pub fn succeeded(&self) -> impl Iterator<Item = &WriterReport> {
+        self.writers.iter().filter(|w| w.result.is_ok())
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &WriterReport> {
+        self.writers.iter().filter(|w| w.result.is_err())
+    }
+
+    /// turn failures into `FabrixError::Writers`, `Ok` if all succeeded
+    pub fn into_result(self) -> FabrixResult<Self> {
+        if self.is_success() {
+            return Ok(self);
+        }
+
+        let errors = self
+            .writers
+            .into_iter()
+            .filter_map(|w| w.result.err().map(|e| (w.name, e)))
+            .collect();
+        Err(FabrixError::Writers(errors))
+    }
+}
+
+// ================================================================================================
+// FanOutDispatcher
+// ================================================================================================
+
+pub struct FanOutDispatcher<'a, Reader, RO>
+where
+    Reader: FromSource<'a, RO>,
+    RO: ReadOptions,
+{
+    reader: Reader,
+    targets: Vec<(String, Box<dyn FanOutTarget<'a> + 'a>)>,
+    error_policy: ErrorPolicy,
+    audit: Option<Audit>,
+    /// an audited run per target
+    runs: Vec<AuditRun>,
+    read_options: PhantomData<RO>,
+}
+
+impl<'a, R, RO> FanOutDispatcher<'a, R, RO>
+where
+    R: FromSource<'a, RO>,
+    RO: ReadOptions,
+{
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            targets: Vec::new(),
+            error_policy: ErrorPolicy::default(),
+            audit: None,
+            runs: Vec::new(),
+            read_options: PhantomData,
+        }
+    }
+
+    pub fn with_error_policy(&mut self, error_policy: ErrorPolicy) -> &mut Self {
+        self.error_policy = error_policy;
+        self
+    }
+
+    /// append an audit record of each writer to a store
+    pub fn with_audit(&mut self, audit: Audit) -> &mut Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// the records of the last audited dispatch, in the order the writers were added
+    pub fn audit_records(&self) -> Vec<&AuditRecord> {
+        self.runs.iter().filter_map(AuditRun::finished).collect()
+    }
+
+    /// add a writer, `name` identifies it in the report
+    pub fn with_writer<W, WO>(&mut self, name: &str, writer: W, options: &'a WO) -> &mut Self
+    where
+        W: IntoSource<'a, WO> + Send + 'a,
+        WO: WriteOptions + Sync,
+    {
+        self.push_target(name, writer, options, None)
+    }
+
+    /// add a writer which receives the transformed data, e.g. a summary
+    pub fn with_transformed_writer<W, WO, T>(
+        &mut self,
+        name: &str,
+        writer: W,
+        options: &'a WO,
+        transform: T,
+    ) -> &mut Self
+    where
+        W: IntoSource<'a, WO> + Send + 'a,
+        WO: WriteOptions + Sync,
+        T: Transform + 'static,
+    {
+        self.push_target(name, writer, options, Some(Box::new(transform)))
+    }
+
+    /// add a custom target
+    pub fn with_target<T>(&mut self, name: &str, target: T) -> &mut Self
+    where
+        T: FanOutTarget<'a> + 'a,
+    {
+        self.targets.push((name.to_owned(), Box::new(target)));
+        self
+    }
+
+    fn push_target<W, WO>(
+        &mut self,
+        name: &str,
+        writer: W,
+        options: &'a WO,
+        transform: Option<Box<dyn Transform>>,
+    ) -> &mut Self
+    where
+        W: IntoSource<'a, WO> + Send + 'a,
+        WO: WriteOptions + Sync,
+    {
+        let target = Target {
+            writer,
+            options,
+            transform,
+        };
+        self.with_target(name, target)
+    }
+
+    pub fn reader_type(&self) -> &str {
+        self.reader.source_type()
+    }
+
+    /// source types of the writers, in the order they were added
+    pub fn writer_types(&self) -> Vec<&str> {
+        self.targets.iter().map(|(_, t)| t.source_type()).collect()
+    }
+
+    /// expose reader as reference to the outside
+    pub fn reader(&self) -> &R {
+        &self.reader
+    }
+
+    /// expose reader as mutable to the outside
+    pub fn reader_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    pub fn sync_dispatch(&mut self, read_options: &'a RO) -> FabrixResult<FanOutReport> {
+        self.check_targets()?;
+        self.start_runs(read_options);
+        let started = Instant::now();
+        let fabrix = match self.reader.sync_read(read_options) {
+            Ok(fx) => fx,
+            Err(e) => {
+                let res = Err(e);
+                for run in self.runs.iter_mut() {
+                    run.sync_finish(&res)?;
+                }
+                return res;
+            }
+        };
+        self.read_runs(&fabrix, started)?;
+        let mut report = FanOutReport {
+            rows: fabrix.height(),
+            writers: Vec::with_capacity(self.targets.len()),
+        };
+
+        let mut failed = None;
+        let targets = self.targets.iter_mut().zip(self.runs.iter_mut());
+        for (i, ((name, target), run)) in targets.enumerate() {
+            let started = Instant::now();
+            let result = target.sync_write(fabrix.clone());
+            record_written(run, &result, report.rows, started);
+            let appended = run.sync_finish(&result);
+            let pushed = push_result(
+                &mut report,
+                self.error_policy,
+                name,
+                &**target,
+                result.and(appended),
+            );
+            if let Err(e) = pushed {
+                failed = Some((i, name.clone(), e));
+                break;
+            }
+        }
+        if let Some((i, name, e)) = failed {
+            let skipped = skipped_result(&name);
+            for run in self.runs[i + 1..].iter_mut() {
+                run.sync_finish(&skipped)?;
+            }
+            return Err(e);
+        }
+
+        finish_report(report, self.error_policy)
+    }
+
+    pub async fn async_dispatch(&mut self, read_options: &'a RO) -> FabrixResult<FanOutReport> {
+        self.check_targets()?;
+        self.start_runs(read_options);
+        let started = Instant::now();
+        let fabrix = match self.reader.async_read(read_options).await {
+            Ok(fx) => fx,
+            Err(e) => {
+                let res = Err(e);
+                for run in self.runs.iter_mut() {
+                    run.async_finish(&res).await?;
+                }
+                return res;
+            }
+        };
+        self.read_runs(&fabrix, started)?;
+        let mut report = FanOutReport {
+            rows: fabrix.height(),
+            writers: Vec::with_capacity(self.targets.len()),
+        };
+
+        if self.error_policy == ErrorPolicy::FailAll {
+            let mut failed = None;
+            let targets = self.targets.iter_mut().zip(self.runs.iter_mut());
+            for (i, ((name, target), run)) in targets.enumerate() {
+                let started = Instant::now();
+                let result = target.async_write(fabrix.clone()).await;
+                record_written(run, &result, report.rows, started);
+                let appended = run.async_finish(&result).await;
+                let pushed = push_result(
+                    &mut report,
+                    self.error_policy,
+                    name,
+                    &**target,
+                    result.and(appended),
+                );
+                if let Err(e) = pushed {
+                    failed = Some((i, name.clone(), e));
+                    break;
+                }
+            }
+            if let Some((i, name, e)) = failed {
+                let skipped = skipped_result(&name);
+                for run in self.runs[i + 1..].iter_mut() {
+                    run.async_finish(&skipped).await?;
+                }
+                return Err(e);
+            }
+        } else {
+            let started = Instant::now();
+            let results = join_all(
+                self.targets
+                    .iter_mut()
+                    .map(|(_, t)| t.async_write(fabrix.clone())),
+            )
+            .await;
+            let targets = self.targets.iter().zip(self.runs.iter_mut());
+            for (((name, target), run), result) in targets.zip(results) {
+                record_written(run, &result, report.rows, started);
+                let appended = run.async_finish(&result).await;
+                push_result(
+                    &mut report,
+                    self.error_policy,
+                    name,
+                    &**target,
+                    result.and(appended),
+                )?;
+            }
+        }
+
+        finish_report(report, self.error_policy)
+    }
+
+    /// a run from the reader to each target
+    fn start_runs(&mut self, read_options: &RO) {
+        let source = self.reader.endpoint(read_options);
+        self.runs = self
+            .targets
+            .iter()
+            .map(|(_, t)| AuditRun::start(self.audit.as_ref(), source.clone(), t.endpoint()))
+            .collect();
+    }
+
+    fn read_runs(&mut self, fabrix: &Fabrix, started: Instant) -> FabrixResult<()> {
+        for run in self.runs.iter_mut() {
+            run.read(fabrix, started)?;
+        }
+        Ok(())
+    }
+
+    fn check_targets(&self) -> FabrixResult<()> {
+        if self.targets.is_empty() {
+            return Err(FabrixError::NotSet("writers"));
+        }
+        Ok(())
+    }
+}
+
+/// the rows handed to a target, if written
+fn record_written(run: &mut AuditRun, result: &FabrixResult<()>, rows: usize, started: Instant) {
+    if result.is_ok() {
+        run.written(rows, started);
+    }
+}
+
+/// the result of the writers after a failed one, which are not written under `FailAll`
+fn skipped_result(failed: &str) -> FabrixResult<()> {
+    Err(FabrixError::new_uncategorized(format!(
+        "not written, since writer `{failed}` has failed"
+    )))
+}
+
+fn push_result(
+    report: &mut FanOutReport,
+    error_policy: ErrorPolicy,
+    name: &str,
+    target: &dyn FanOutTarget<'_>,
+    result: FabrixResult<()>,
+) -> FabrixResult<()> {
+    match (error_policy, result) {
+        (ErrorPolicy::FailAll, Err(e)) => Err(FabrixError::Writers(vec![(name.to_owned(), e)])),
+        (_, result) => {
+            report.writers.push(WriterReport {
+                name: name.to_owned(),
+                source_type: target.source_type().to_owned(),
+                result,
+            });
+            Ok(())
+        }
+    }
+}
+
+fn finish_report(report: FanOutReport, error_policy: ErrorPolicy) -> FabrixResult<FanOutReport> {
+    match error_policy {
+        ErrorPolicy::CollectErrors => report.into_result(),
+        _ => Ok(report),
+    }
+}
+
+#[cfg(test)]
+mod fan_out_dispatcher_tests {
+    use std::fs::File;
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        CsvReadOptions, CsvReader, CsvWriteOptions, CsvWriter, ParquetWriteOptions, ParquetWriter,
+        Select,
+    };
+
+    const CSV_FILE_PATH: &str = "../mock/test.csv";
+
+    /// a csv writer which has been used, so that writing fails
+    fn used_writer(buff: &mut Cursor<Vec<u8>>) -> CsvWriter<&mut Cursor<Vec<u8>>> {
+        let mut writer = CsvWriter::new(buff);
+        writer.finish(Fabrix::empty()).unwrap();
+        writer
+    }
+
+    #[test]
+    fn test_fan_out_sync() {
+        let ro = CsvReadOptions::default();
+        let cwo = CsvWriteOptions::default();
+        let pwo = ParquetWriteOptions::default();
+        let mut csv_buff = Cursor::new(Vec::<u8>::new());
+        let mut summary_buff = Cursor::new(Vec::<u8>::new());
+        let mut parquet_buff = Cursor::new(Vec::<u8>::new());
+
+        let reader = CsvReader::new(File::open(CSV_FILE_PATH).unwrap());
+        let mut dispatcher = FanOutDispatcher::new(reader);
+        dispatcher
+            .with_writer("csv", CsvWriter::new(&mut csv_buff), &cwo)
+            .with_writer("parquet", ParquetWriter::new(&mut parquet_buff), &pwo)
+            .with_transformed_writer(
+                "summary",
+                CsvWriter::new(&mut summary_buff),
+                &cwo,
+                Select::new(&["id"]),
+            );
+        assert_eq!(dispatcher.writer_types(), vec!["csv", "parquet", "csv"]);
+
+        let report = dispatcher.sync_dispatch(&ro).unwrap();
+        assert!(report.is_success());
+        assert_eq!(report.rows, 100);
+        assert_eq!(report.writers.len(), 3);
+        drop(dispatcher);
+
+        assert!(!csv_buff.get_ref().is_empty());
+        assert!(!parquet_buff.get_ref().is_empty());
+        let summary = String::from_utf8(summary_buff.into_inner()).unwrap();
+        assert!(summary.starts_with("id\n1\n2\n"));
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_error_policy() {
+        let ro = CsvReadOptions::default();
+        let cwo = CsvWriteOptions::default();
+        let mut b1 = Cursor::new(Vec::<u8>::new());
+        let mut b2 = Cursor::new(Vec::<u8>::new());
+        let mut b3 = Cursor::new(Vec::<u8>::new());
+
+        // best effort: the failure is reported, others are written
+        let reader = CsvReader::new(File::open(CSV_FILE_PATH).unwrap());
+        let mut dispatcher = FanOutDispatcher::new(reader);
+        dispatcher
+            .with_error_policy(ErrorPolicy::BestEffort)
+            .with_writer("bad", used_writer(&mut b1), &cwo)
+            .with_writer("good", CsvWriter::new(&mut b2), &cwo);
+
+        let store = crate::FileAuditStore::new("../cache/audit/test_fan_out.jsonl").unwrap();
+        dispatcher.with_audit(crate::Audit::new(std::sync::Arc::new(store)));
+
+        let report = dispatcher.async_dispatch(&ro).await.unwrap();
+        assert!(!report.is_success());
+        assert_eq!(report.failed().next().unwrap().name, "bad");
+        assert_eq!(report.succeeded().next().unwrap().name, "good");
+        let outcomes = dispatcher
+            .audit_records()
+            .iter()
+            .map(|r| (r.outcome, r.rows_written))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            outcomes,
+            vec![
+                (crate::RunOutcome::Failure, 0),
+                (crate::RunOutcome::Success, 100)
+            ]
+        );
+        drop(dispatcher);
+
+        // collect errors: all writers run, then fail
+        let reader = CsvReader::new(File::open(CSV_FILE_PATH).unwrap());
+        let mut dispatcher = FanOutDispatcher::new(reader);
+        dispatcher
+            .with_error_policy(ErrorPolicy::CollectErrors)
+            .with_writer("bad", used_writer(&mut b3), &cwo);
+
+        let res = dispatcher.async_dispatch(&ro).await;
+        assert!(matches!(res, Err(FabrixError::Writers(e)) if e.len() == 1 && e[0].0 == "bad"));
+        drop(dispatcher);
+
+        // fail all: stop at the first failure
+        let mut b4 = Cursor::new(Vec::<u8>::new());
+        let mut b5 = Cursor::new(Vec::<u8>::new());
+        let reader = CsvReader::new(File::open(CSV_FILE_PATH).unwrap());
+        let mut dispatcher = FanOutDispatcher::new(reader);
+        dispatcher
+            .with_writer("bad", used_writer(&mut b4), &cwo)
+            .with_writer("skipped", CsvWriter::new(&mut b5), &cwo);
+        let store = crate::FileAuditStore::new("../cache/audit/test_fan_out.jsonl").unwrap();
+        dispatcher.with_audit(crate::Audit::new(std::sync::Arc::new(store)));
+
+        let res = dispatcher.sync_dispatch(&ro);
+        assert!(matches!(res, Err(FabrixError::Writers(_))));
+        // the skipped writer is not left running
+        let outcomes = dispatcher
+            .audit_records()
+            .iter()
+            .map(|r| (r.outcome, r.rows_written))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            outcomes,
+            vec![
+                (crate::RunOutcome::Failure, 0),
+                (crate::RunOutcome::Failure, 0)
+            ]
+        );
+        drop(dispatcher);
+        assert!(b5.get_ref().is_empty());
+    }
+}
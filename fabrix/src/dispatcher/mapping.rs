@@ -0,0 +1,246 @@
+//! Column Mapping
+//!
+//! A `ColumnMapping` maps the columns of a source schema onto a destination schema by name,
+//! e.g. business headers of an Excel template onto snake_case columns of a table:
+//! - column: a source column renamed to a target, optionally cast to a `ValueType`
+//! - default: the value filling a target whose source column is missing
+//! - constant: a target column holding the same value in every row
+//!
+//! Target columns come in the order they are added. Unmapped source columns are dropped, unless
+//! `with_keep_unmapped` is set, in which case they follow the targets.
+//!
+//! A mapping is a `Transform`, so it plugs into a `Pipeline`, a `FanOutDispatcher` target or
+//! `Dispatcher::sync_transform`; `XlDbConvertor::with_mapping` applies it to each converted batch.
+
+use std::collections::HashSet;
+
+use crate::{Fabrix, FabrixError, FabrixResult, Series, Transform, Value, ValueType};
+
+/// where the values of a target column come from
+#[derive(Debug, Clone, PartialEq)]
+pub enum MappingSource {
+    Column(String),
+    Constant(Value),
+}
+
+/// a target column of a `ColumnMapping`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnRule {
+    pub target: String,
+    pub source: MappingSource,
+    pub dtype: Option<ValueType>,
+    /// used when the source column is missing, otherwise the mapping fails
+    pub default: Option<Value>,
+}
+
+impl ColumnRule {
+    pub fn column(source: &str, target: &str) -> Self {
+        Self {
+            target: target.to_owned(),
+            source: MappingSource::Column(source.to_owned()),
+            dtype: None,
+            default: None,
+        }
+    }
+
+    pub fn constant(target: &str, value: Value) -> Self {
+        Self {
+            target: target.to_owned(),
+            source: MappingSource::Constant(value),
+            dtype: None,
+            default: None,
+        }
+    }
+}
+
+/// source columns -> target columns
+#[derive(Debug, Clone, Default)]
+pub struct ColumnMapping {
+    rules: Vec<ColumnRule>,
+    keep_unmapped: bool,
+}
+
+impl ColumnMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_rule(&mut self, rule: ColumnRule) -> &mut Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn with_column(&mut self, source: &str, target: &str) -> &mut Self {
+        self.with_rule(ColumnRule::column(source, target))
+    }
+
+    pub fn with_cast(&mut self, source: &str, target: &str, dtype: ValueType) -> &mut Self {
+        let mut rule = ColumnRule::column(source, target);
+        rule.dtype = Some(dtype);
+        self.with_rule(rule)
+    }
+
+    /// map a column which may be missing in the source, `value` fills it then
+    pub fn with_default(&mut self, source: &str, target: &str, value: Value) -> &mut Self {
+        let mut rule = ColumnRule::column(source, target);
+        rule.default = Some(value);
+        self.with_rule(rule)
+    }
+
+    pub fn with_constant(&mut self, target: &str, value: Value) -> &mut Self {
+        self.with_rule(ColumnRule::constant(target, value))
+    }
+
+    /// keep the unmapped source columns after the targets, instead of dropping them
+    pub fn with_keep_unmapped(&mut self, keep_unmapped: bool) -> &mut Self {
+        self.keep_unmapped = keep_unmapped;
+        self
+    }
+
+    pub fn rules(&self) -> &[ColumnRule] {
+        &self.rules
+    }
+
+    pub fn targets(&self) -> Vec<&str> {
+        self.rules.iter().map(|r| r.target.as_str()).collect()
+    }
+
+    fn map_column(&self, rule: &ColumnRule, fabrix: &Fabrix) -> FabrixResult<Series> {
+        let mut series = match (&rule.source, &rule.default) {
+            (MappingSource::Column(name), default) => match fabrix.get_column(name) {
+                Ok(s) => s.clone(),
+                Err(_) => match default {
+                    Some(v) => repeat_value(v, fabrix.height())?,
+                    None => {
+                        return Err(FabrixError::NotFound(format!(
+                            "source column: {name} of target: {} not found",
+                            rule.target
+                        )))
+                    }
+                },
+            },
+            (MappingSource::Constant(v), _) => repeat_value(v, fabrix.height())?,
+        };
+        series.rename(&rule.target);
+
+        match &rule.dtype {
+            Some(dtype) => Ok(series.cast(dtype)?),
+            None => Ok(series),
+        }
+    }
+}
+
+/// a series of `height` times the value
+fn repeat_value(value: &Value, height: usize) -> FabrixResult<Series> {
+    let series = Series::from_values(vec![value.clone(); height.max(1)], "", true)?;
+    match height {
+        0 => Ok(series.slice(0, 0)),
+        _ => Ok(series),
+    }
+}
+
+impl Transform for ColumnMapping {
+    fn name(&self) -> &str {
+        "column mapping"
+    }
+
+    fn sync_transform(&self, fabrix: Fabrix) -> FabrixResult<Fabrix> {
+        let mut mapped = HashSet::new();
+        let mut targets = HashSet::new();
+        let mut series = Vec::with_capacity(self.rules.len());
+        for rule in self.rules.iter() {
+            if !targets.insert(rule.target.as_str()) {
+                return Err(FabrixError::InvalidArgument(format!(
+                    "duplicated target column: {}",
+                    rule.target
+                )));
+            }
+            if let MappingSource::Column(name) = &rule.source {
+                mapped.insert(name.as_str());
+            }
+            series.push(self.map_column(rule, &fabrix)?);
+        }
+
+        if self.keep_unmapped {
+            for s in fabrix.iter_column() {
+                if !mapped.contains(s.name()) && !targets.contains(s.name()) {
+                    series.push(s.clone());
+                }
+            }
+        }
+
+        // the index follows its column, and is dropped along with it
+        let index = fabrix.index_tag().and_then(|it| {
+            self.rules
+                .iter()
+                .find(|r| r.source == MappingSource::Column(it.name().to_owned()))
+                .map(|r| r.target.as_str())
+                .or_else(|| {
+                    (self.keep_unmapped && !targets.contains(it.name())).then_some(it.name())
+                })
+        });
+
+        match index {
+            Some(name) => Ok(Fabrix::from_series(series, name)?),
+            None => Ok(Fabrix::from_series_no_index(series)?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod column_mapping_tests {
+    use super::*;
+    use crate::{fx, value};
+
+    #[test]
+    fn test_column_mapping() {
+        let fx = fx![
+            "编号";
+            "编号" => [1, 2, 3],
+            "客户名称" => ["a", "b", "c"],
+            "金额" => [1, 2, 3],
+            "备注" => ["x", "y", "z"],
+        ]
+        .unwrap();
+
+        let mut mapping = ColumnMapping::new();
+        mapping
+            .with_column("编号", "id")
+            .with_column("客户名称", "customer_name")
+            .with_cast("金额", "amount", ValueType::F64)
+            .with_default("地区", "region", value!("unknown"))
+            .with_constant("source", value!("xl"));
+
+        let res = mapping.sync_transform(fx.clone()).unwrap();
+        let expected = fx![
+            "id";
+            "id" => [1, 2, 3],
+            "customer_name" => ["a", "b", "c"],
+            "amount" => [1.0, 2.0, 3.0],
+            "region" => ["unknown", "unknown", "unknown"],
+            "source" => ["xl", "xl", "xl"],
+        ]
+        .unwrap();
+        crate::assert_fabrix_eq!(res, expected);
+
+        mapping.with_keep_unmapped(true);
+        let res = mapping.sync_transform(fx.clone()).unwrap();
+        assert_eq!(
+            res.get_column_names(),
+            vec!["id", "customer_name", "amount", "region", "source", "备注"]
+        );
+        assert_eq!(res.index_tag().unwrap().name(), "id");
+
+        // a missing source column without default
+        let mut mapping = ColumnMapping::new();
+        mapping.with_column("地区", "region");
+        assert!(mapping.sync_transform(fx.clone()).is_err());
+
+        // the index is dropped along with its column
+        let mut mapping = ColumnMapping::new();
+        mapping.with_column("客户名称", "customer_name");
+        let res = mapping.sync_transform(fx).unwrap();
+        assert!(res.index_tag().is_none());
+        assert_eq!(res.shape(), (3, 1));
+    }
+}
@@ -0,0 +1,659 @@
+//! Mask
+//!
+//! PII masking & column-level encryption transforms:
+//! - Hash: HMAC-SHA256 keyed by a secret, irreversible
+//! - Redact: replaced by a fixed string
+//! - Tokenize: a token encrypted by a secret, the same value always gets the same token;
+//!   reversible by `Unmask` with the same secret, no token is stored
+//! - Encrypt: deterministic encryption (AES-SIV), the same value always gets the same cipher text,
+//!   so that joins still work; reversible by `Unmask` with the same secret
+//! - Phone, Email, IdNumber & Partial: format-preserving masks, e.g. `138****5678`
+//!
+//! Secrets are read from an environment variable or a file, and never printed. Fixed-length keys
+//! are derived from a secret by HKDF, one for each method.
+//!
+//! Masked columns become strings, nulls are kept, dates & times are masked in their text form.
+//! A `Mask` is a `Transform`, it applies during any dispatch, e.g. exporting a production table to
+//! a csv file for vendors.
+
+use std::fmt;
+
+use aes_siv::aead::generic_array::GenericArray;
+use aes_siv::aead::KeyInit;
+use aes_siv::siv::Aes256Siv;
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+
+use crate::{
+    Fabrix, FabrixError, FabrixResult, Series, Transform, Value, Value2ChronoHelper, ValueType,
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// salt of the key derivation
+const KDF_SALT: &[u8] = b"fabrix-mask";
+/// prefix of a token
+const TOKEN_PREFIX: &str = "tok_";
+/// shown instead of a secret
+const REDACTED: &str = "***";
+/// a phone number keeps its last digits only if it has at least this many digits
+const PHONE_MIN_DIGITS: usize = 8;
+/// digits kept by a phone number mask
+const PHONE_KEPT_DIGITS: usize = 4;
+
+// ================================================================================================
+// Secret & MaskKey
+// ================================================================================================
+
+/// where a secret is read from, e.g. `{ env: PII_KEY }` or `{ file: /run/secrets/pii_key }`.
+///
+/// A plain value can only be set in code, so that secrets are never written in a spec.
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Secret {
+    /// an environment variable
+    Env(String),
+    /// a file, e.g. a mounted secret; surrounding whitespace is trimmed
+    File(String),
+    #[serde(skip_deserializing)]
+    Value(String),
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Secret::Env(name) => f.debug_tuple("Env").field(name).finish(),
+            Secret::File(path) => f.debug_tuple("File").field(path).finish(),
+            Secret::Value(_) => f.debug_tuple("Value").field(&REDACTED).finish(),
+        }
+    }
+}
+
+impl Secret {
+    pub fn env(name: &str) -> Self {
+        Secret::Env(name.to_owned())
+    }
+
+    pub fn file(path: &str) -> Self {
+        Secret::File(path.to_owned())
+    }
+
+    pub fn value(value: &str) -> Self {
+        Secret::Value(value.to_owned())
+    }
+
+    /// read the secret, an empty secret is an error
+    pub fn resolve(&self) -> FabrixResult<String> {
+        let secret = match self {
+            Secret::Env(name) => std::env::var(name)
+                .map_err(|_| FabrixError::NotFound(format!("secret env: {name}")))?,
+            Secret::File(path) => std::fs::read_to_string(path)?.trim().to_owned(),
+            Secret::Value(value) => value.clone(),
+        };
+        if secret.is_empty() {
+            return Err(FabrixError::EmptyContent("secret"));
+        }
+
+        Ok(secret)
+    }
+}
+
+/// keys derived from a secret by HKDF-SHA256, each method has its own key
+#[derive(Clone)]
+pub struct MaskKey(Hkdf<Sha256>);
+
+impl fmt::Debug for MaskKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MaskKey(***)")
+    }
+}
+
+impl MaskKey {
+    pub fn new(secret: &str) -> Self {
+        MaskKey(Hkdf::new(Some(KDF_SALT), secret.as_bytes()))
+    }
+
+    pub fn from_secret(secret: &Secret) -> FabrixResult<Self> {
+        Ok(Self::new(&secret.resolve()?))
+    }
+
+    /// a fixed-length key for one purpose
+    fn derive<const N: usize>(&self, purpose: &str) -> [u8; N] {
+        let mut key = [0u8; N];
+        // at most 255 * 32 bytes can be derived, which is far more than any key here
+        self.0
+            .expand(purpose.as_bytes(), &mut key)
+            .expect("valid HKDF output length");
+        key
+    }
+
+    /// AES-SIV with a 512-bit key for one purpose
+    fn siv(&self, purpose: &str) -> Aes256Siv {
+        Aes256Siv::new(GenericArray::from_slice(&self.derive::<64>(purpose)))
+    }
+}
+
+// ================================================================================================
+// MaskMethod
+// ================================================================================================
+
+/// how a value is masked
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum MaskMethod {
+    Hash {
+        key: Secret,
+    },
+    Redact {
+        #[serde(default = "default_redaction")]
+        replacement: String,
+    },
+    Tokenize {
+        key: Secret,
+    },
+    Encrypt {
+        key: Secret,
+    },
+    /// keep the last 4 digits, other digits are masked, separators are kept
+    Phone,
+    /// keep the first char of the local part and the domain
+    Email,
+    /// keep the first 6 and the last 4 chars, e.g. region code & check digits of an id card
+    IdNumber,
+    /// keep the first `keep_start` and the last `keep_end` chars
+    Partial {
+        keep_start: usize,
+        keep_end: usize,
+    },
+}
+
+/// keys are never printed, not even where they are read from
+impl fmt::Debug for MaskMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MaskMethod::Hash { .. } => f.debug_struct("Hash").field("key", &REDACTED).finish(),
+            MaskMethod::Redact { replacement } => f
+                .debug_struct("Redact")
+                .field("replacement", replacement)
+                .finish(),
+            MaskMethod::Tokenize { .. } => {
+                f.debug_struct("Tokenize").field("key", &REDACTED).finish()
+            }
+            MaskMethod::Encrypt { .. } => {
+                f.debug_struct("Encrypt").field("key", &REDACTED).finish()
+            }
+            MaskMethod::Phone => f.write_str("Phone"),
+            MaskMethod::Email => f.write_str("Email"),
+            MaskMethod::IdNumber => f.write_str("IdNumber"),
+            MaskMethod::Partial {
+                keep_start,
+                keep_end,
+            } => f
+                .debug_struct("Partial")
+                .field("keep_start", keep_start)
+                .field("keep_end", keep_end)
+                .finish(),
+        }
+    }
+}
+
+fn default_redaction() -> String {
+    "***".to_owned()
+}
+
+impl MaskMethod {
+    pub fn hash(key: Secret) -> Self {
+        MaskMethod::Hash { key }
+    }
+
+    pub fn redact() -> Self {
+        MaskMethod::Redact {
+            replacement: default_redaction(),
+        }
+    }
+
+    pub fn tokenize(key: Secret) -> Self {
+        MaskMethod::Tokenize { key }
+    }
+
+    pub fn encrypt(key: Secret) -> Self {
+        MaskMethod::Encrypt { key }
+    }
+
+    /// the secret of a keyed method
+    pub fn secret(&self) -> Option<&Secret> {
+        match self {
+            MaskMethod::Hash { key }
+            | MaskMethod::Tokenize { key }
+            | MaskMethod::Encrypt { key } => Some(key),
+            _ => None,
+        }
+    }
+
+    /// read the secret & derive the keys of a keyed method
+    pub fn key(&self) -> FabrixResult<Option<MaskKey>> {
+        self.secret().map(MaskKey::from_secret).transpose()
+    }
+
+    /// mask a non-null value, the secret is read on every call; see `mask_with` for many values
+    pub fn apply(&self, value: &str) -> FabrixResult<String> {
+        self.mask_with(self.key()?.as_ref(), value)
+    }
+
+    /// mask a non-null value with the keys of `MaskMethod::key`
+    pub fn mask_with(&self, key: Option<&MaskKey>, value: &str) -> FabrixResult<String> {
+        let key = || key.ok_or(FabrixError::NotSet("mask key"));
+        let masked = match self {
+            MaskMethod::Hash { .. } => hash_value(key()?, value),
+            MaskMethod::Redact { replacement } => replacement.clone(),
+            MaskMethod::Tokenize { .. } => tokenize(key()?, value)?,
+            MaskMethod::Encrypt { .. } => encrypt(key()?, value)?,
+            MaskMethod::Phone => mask_phone(value),
+            MaskMethod::Email => mask_email(value),
+            MaskMethod::IdNumber => mask_partial(value, 6, 4),
+            MaskMethod::Partial {
+                keep_start,
+                keep_end,
+            } => mask_partial(value, *keep_start, *keep_end),
+        };
+
+        Ok(masked)
+    }
+}
+
+/// hex of HMAC-SHA256
+pub fn hash_value(key: &MaskKey, value: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(&key.derive::<32>("hash")).expect("32-byte key");
+    mac.update(value.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn siv_encrypt(key: &MaskKey, purpose: &str, value: &str) -> FabrixResult<Vec<u8>> {
+    key.siv(purpose)
+        .encrypt([purpose.as_bytes()], value.as_bytes())
+        .map_err(|_| FabrixError::new_uncategorized("AES-SIV encryption failed"))
+}
+
+fn siv_decrypt(key: &MaskKey, purpose: &str, data: &[u8]) -> Option<String> {
+    let plain = key.siv(purpose).decrypt([purpose.as_bytes()], data).ok()?;
+    String::from_utf8(plain).ok()
+}
+
+/// deterministic encryption of the value, hex with a prefix, e.g. `tok_9f86...`
+pub fn tokenize(key: &MaskKey, value: &str) -> FabrixResult<String> {
+    let cipher = siv_encrypt(key, "token", value)?;
+    Ok(format!("{TOKEN_PREFIX}{}", hex::encode(cipher)))
+}
+
+/// the reverse of `tokenize`, fails with a wrong key or a tampered token
+pub fn detokenize(key: &MaskKey, token: &str) -> FabrixResult<String> {
+    token
+        .strip_prefix(TOKEN_PREFIX)
+        .and_then(|t| hex::decode(t).ok())
+        .and_then(|data| siv_decrypt(key, "token", &data))
+        .ok_or_else(|| FabrixError::InvalidArgument(format!("cannot detokenize: {token}")))
+}
+
+/// deterministic authenticated encryption (AES-SIV), base64 of `siv || cipher text`
+pub fn encrypt(key: &MaskKey, value: &str) -> FabrixResult<String> {
+    Ok(base64::encode(siv_encrypt(key, "encrypt", value)?))
+}
+
+/// the reverse of `encrypt`, fails with a wrong key or a tampered value
+pub fn decrypt(key: &MaskKey, value: &str) -> FabrixResult<String> {
+    base64::decode(value)
+        .ok()
+        .and_then(|data| siv_decrypt(key, "encrypt", &data))
+        .ok_or_else(|| FabrixError::InvalidArgument(format!("cannot decrypt: {value}")))
+}
+
+/// keep the last 4 digits of a phone number, shorter numbers (e.g. extensions) are fully masked
+pub fn mask_phone(value: &str) -> String {
+    let digits = value.chars().filter(|c| c.is_ascii_digit()).count();
+    let kept = match digits >= PHONE_MIN_DIGITS {
+        true => PHONE_KEPT_DIGITS,
+        false => 0,
+    };
+    let mut seen = 0;
+    value
+        .chars()
+        .map(|c| match c.is_ascii_digit() {
+            true => {
+                seen += 1;
+                if seen + kept > digits {
+                    c
+                } else {
+                    '*'
+                }
+            }
+            false => c,
+        })
+        .collect()
+}
+
+pub fn mask_email(value: &str) -> String {
+    match value.split_once('@') {
+        Some((local, domain)) => format!("{}@{domain}", mask_partial(local, 1, 0)),
+        None => mask_partial(value, 1, 0),
+    }
+}
+
+/// a value not longer than `keep_start + keep_end` is masked entirely
+pub fn mask_partial(value: &str, keep_start: usize, keep_end: usize) -> String {
+    let len = value.chars().count();
+    if len <= keep_start + keep_end {
+        return "*".repeat(len);
+    }
+
+    value
+        .chars()
+        .enumerate()
+        .map(|(i, c)| match i < keep_start || i >= len - keep_end {
+            true => c,
+            false => '*',
+        })
+        .collect()
+}
+
+// ================================================================================================
+// Mask & Unmask
+// ================================================================================================
+
+/// text of a value to mask, temporal values are formatted (e.g. `2016-01-08`) rather than shown as
+/// their epoch numbers, so that an unmasked value can be cast back
+fn mask_input(value: Value) -> FabrixResult<String> {
+    let res = match value {
+        Value::Date(_) => Value2ChronoHelper::convert_value_to_naive_date(value)?.to_string(),
+        Value::Time(_) => Value2ChronoHelper::convert_value_to_naive_time(value)?.to_string(),
+        Value::DateTime(_) => {
+            Value2ChronoHelper::convert_value_to_naive_datetime(value)?.to_string()
+        }
+        v => v.to_string(),
+    };
+    Ok(res)
+}
+
+/// replace the non-null values of a column, which becomes a string column. Columns whose dtype
+/// has no `ValueType` (e.g. `List`) are rejected rather than passed through unmasked
+fn map_column<F>(mut fabrix: Fabrix, column: &str, f: F) -> FabrixResult<Fabrix>
+where
+    F: Fn(&str) -> FabrixResult<String>,
+{
+    let values = fabrix
+        .get_column(column)?
+        .try_iter()?
+        .map(|v| match v {
+            Value::Null => Ok(Value::Null),
+            v => f(&mask_input(v)?).map(Value::String),
+        })
+        .collect::<FabrixResult<Vec<_>>>()?;
+
+    // an all-null column becomes a string column as well
+    let series = Series::from_values(values, column, true)?.cast(&ValueType::String)?;
+    fabrix.data.replace(column, series.0)?;
+    // the index type may have changed
+    if fabrix.index_tag().map_or(false, |it| it.name() == column) {
+        fabrix.set_index_tag(column)?;
+    }
+
+    Ok(fabrix)
+}
+
+/// mask columns, the secrets are read once per transform
+#[derive(Debug, Clone, Default)]
+pub struct Mask {
+    columns: Vec<(String, MaskMethod)>,
+}
+
+impl Mask {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_column(&mut self, column: &str, method: MaskMethod) -> &mut Self {
+        self.columns.push((column.to_owned(), method));
+        self
+    }
+
+    pub fn columns(&self) -> &[(String, MaskMethod)] {
+        &self.columns
+    }
+}
+
+impl Transform for Mask {
+    fn name(&self) -> &str {
+        "mask"
+    }
+
+    fn sync_transform(&self, fabrix: Fabrix) -> FabrixResult<Fabrix> {
+        self.columns
+            .iter()
+            .try_fold(fabrix, |fx, (column, method)| {
+                let key = method.key()?;
+                map_column(fx, column, |v| method.mask_with(key.as_ref(), v))
+            })
+    }
+}
+
+/// how a masked value is reversed, by the secret of the mask
+#[derive(Debug, Clone)]
+pub enum Reverse {
+    Decrypt(Secret),
+    Detokenize(Secret),
+}
+
+/// reverse the encrypted or tokenized columns, the values stay strings
+#[derive(Debug, Clone, Default)]
+pub struct Unmask {
+    columns: Vec<(String, Reverse)>,
+}
+
+impl Unmask {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// decrypt a column masked by `MaskMethod::Encrypt` with the same secret
+    pub fn with_decrypt(&mut self, column: &str, key: Secret) -> &mut Self {
+        self.columns
+            .push((column.to_owned(), Reverse::Decrypt(key)));
+        self
+    }
+
+    /// reverse a column masked by `MaskMethod::Tokenize` with the same secret
+    pub fn with_detokenize(&mut self, column: &str, key: Secret) -> &mut Self {
+        self.columns
+            .push((column.to_owned(), Reverse::Detokenize(key)));
+        self
+    }
+}
+
+impl Transform for Unmask {
+    fn name(&self) -> &str {
+        "unmask"
+    }
+
+    fn sync_transform(&self, fabrix: Fabrix) -> FabrixResult<Fabrix> {
+        self.columns
+            .iter()
+            .try_fold(fabrix, |fx, (column, reverse)| match reverse {
+                Reverse::Decrypt(secret) => {
+                    let key = MaskKey::from_secret(secret)?;
+                    map_column(fx, column, |v| decrypt(&key, v))
+                }
+                Reverse::Detokenize(secret) => {
+                    let key = MaskKey::from_secret(secret)?;
+                    map_column(fx, column, |v| detokenize(&key, v))
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod mask_tests {
+    use polars::prelude::{NamedFrom, Series as PolarsSeries};
+
+    use super::*;
+    use crate::{date, datetime, fx};
+
+    #[test]
+    fn test_format_preserving() {
+        assert_eq!(mask_phone("13812345678"), "*******5678");
+        assert_eq!(mask_phone("+86 138-1234-5678"), "+** ***-****-5678");
+        assert_eq!(mask_email("john.doe@example.com"), "j*******@example.com");
+        assert_eq!(mask_email("a@b.com"), "*@b.com");
+        assert_eq!(
+            MaskMethod::IdNumber.apply("110101199003071234").unwrap(),
+            "110101********1234"
+        );
+        assert_eq!(mask_partial("abc", 2, 2), "***");
+        // short numbers keep no digit
+        assert_eq!(mask_phone("1234"), "****");
+        assert_eq!(mask_phone("ext. 12345"), "ext. *****");
+        assert_eq!(mask_phone("12345678"), "****5678");
+    }
+
+    #[test]
+    fn test_encrypt() {
+        let (k1, k2) = (MaskKey::new("k1"), MaskKey::new("k2"));
+        let cipher = encrypt(&k1, "张三").unwrap();
+        assert_eq!(cipher, encrypt(&k1, "张三").unwrap());
+        assert_ne!(cipher, encrypt(&k2, "张三").unwrap());
+        assert_ne!(cipher, encrypt(&k1, "李四").unwrap());
+        assert_eq!(decrypt(&k1, &cipher).unwrap(), "张三");
+        assert!(decrypt(&k2, &cipher).is_err());
+        assert!(decrypt(&k1, "not base64!").is_err());
+
+        let token = tokenize(&k1, "a").unwrap();
+        assert!(token.starts_with(TOKEN_PREFIX));
+        assert_eq!(detokenize(&k1, &token).unwrap(), "a");
+        assert!(detokenize(&k2, &token).is_err());
+        // tokens & cipher texts of the same secret are derived from different keys
+        assert!(decrypt(&k1, &base64::encode(hex::decode(&token[4..]).unwrap())).is_err());
+
+        assert_eq!(hash_value(&k1, "a"), hash_value(&k1, "a"));
+        assert_ne!(hash_value(&k1, "a"), hash_value(&k2, "a"));
+    }
+
+    #[test]
+    fn test_secret() {
+        std::env::set_var("FABRIX_TEST_MASK_KEY", "k");
+        let key = MaskKey::from_secret(&Secret::env("FABRIX_TEST_MASK_KEY")).unwrap();
+        assert_eq!(hash_value(&key, "a"), hash_value(&MaskKey::new("k"), "a"));
+        assert!(Secret::env("FABRIX_TEST_MASK_KEY_UNSET").resolve().is_err());
+
+        // secrets are never printed
+        let method = MaskMethod::encrypt(Secret::value("plain-secret"));
+        assert!(!format!("{method:?}").contains("plain-secret"));
+        assert!(!format!("{:?}", Secret::value("plain-secret")).contains("plain-secret"));
+
+        // specs only refer to secrets
+        let spec: MaskMethod =
+            serde_json::from_str(r#"{"method": "hash", "key": {"env": "PII_KEY"}}"#).unwrap();
+        assert_eq!(spec, MaskMethod::hash(Secret::env("PII_KEY")));
+        let plain = r#"{"method": "hash", "key": {"value": "k"}}"#;
+        assert!(serde_json::from_str::<MaskMethod>(plain).is_err());
+    }
+
+    #[test]
+    fn test_mask_transform() {
+        let fx = fx![
+            "id";
+            "id" => [1, 2, 3],
+            "name" => [Some("Alice"), Some("Bob"), None],
+            "phone" => ["13812345678", "13987654321", "13700000000"],
+            "email" => ["alice@a.com", "bob@b.com", "carol@c.com"],
+            "salary" => [1000, 2000, 3000],
+        ]
+        .unwrap();
+
+        let key = Secret::value("k");
+        let mut mask = Mask::new();
+        mask.with_column("id", MaskMethod::tokenize(key.clone()))
+            .with_column("name", MaskMethod::encrypt(key.clone()))
+            .with_column("phone", MaskMethod::Phone)
+            .with_column("email", MaskMethod::hash(key.clone()))
+            .with_column("salary", MaskMethod::redact());
+
+        let masked = mask.sync_transform(fx).unwrap();
+        assert_eq!(masked.index_tag().unwrap().name(), "id");
+        assert_eq!(
+            masked.get_column("phone").unwrap().get(0).unwrap(),
+            Value::String("*******5678".to_owned())
+        );
+        assert_eq!(
+            masked.get_column("salary").unwrap().get(1).unwrap(),
+            Value::String("***".to_owned())
+        );
+        assert!(masked.get_column("name").unwrap().get(2).unwrap().is_null());
+
+        let mut unmask = Unmask::new();
+        unmask
+            .with_detokenize("id", key.clone())
+            .with_decrypt("name", key);
+        let res = unmask.sync_transform(masked).unwrap();
+        let expected = fx![
+            "id";
+            "id" => ["1", "2", "3"],
+            "name" => [Some("Alice"), Some("Bob"), None],
+        ]
+        .unwrap();
+        crate::assert_fabrix_eq!(res.take_cols(["id", "name"]).unwrap(), expected);
+
+        let spec: MaskMethod = serde_json::from_str(r#"{"method": "redact"}"#).unwrap();
+        assert_eq!(spec, MaskMethod::redact());
+    }
+
+    #[test]
+    fn test_mask_temporal_and_unsupported() {
+        let fx = fx![
+            "birth" => [date!(2016, 1, 8), date!(2017, 1, 7)],
+            "login" => [datetime!(2016, 1, 8, 9, 10, 11), datetime!(2017, 1, 7, 9, 10, 11)],
+            "empty" => [None::<&str>, None],
+        ]
+        .unwrap();
+
+        let key = Secret::value("k");
+        let mut mask = Mask::new();
+        mask.with_column("birth", MaskMethod::encrypt(key.clone()))
+            .with_column(
+                "login",
+                MaskMethod::Partial {
+                    keep_start: 4,
+                    keep_end: 0,
+                },
+            )
+            .with_column("empty", MaskMethod::redact());
+        let masked = mask.sync_transform(fx).unwrap();
+        assert_eq!(
+            masked.get_column("login").unwrap().get(0).unwrap(),
+            Value::String("2016***************".to_owned())
+        );
+        assert_eq!(
+            masked.get_column("empty").unwrap().dtype().unwrap(),
+            &ValueType::String
+        );
+
+        let mut unmask = Unmask::new();
+        unmask.with_decrypt("birth", key);
+        let res = unmask.sync_transform(masked).unwrap();
+        let birth = res
+            .get_column("birth")
+            .unwrap()
+            .cast(&ValueType::Date)
+            .unwrap();
+        assert_eq!(birth.get(1).unwrap(), date!(2017, 1, 7));
+
+        // a list column cannot be masked, and is never passed through
+        let list = PolarsSeries::new(
+            "tags",
+            &[PolarsSeries::new("", &["a"]), PolarsSeries::new("", &["b"])],
+        );
+        let fx = Fabrix::from_series_no_index(vec![Series::from(list)]).unwrap();
+        let mut mask = Mask::new();
+        mask.with_column("tags", MaskMethod::redact());
+        assert!(mask.sync_transform(fx).is_err());
+    }
+}
@@ -1,12 +1,44 @@
 //! Fabrix Pipes
 
+pub mod audit;
+pub mod checkpoint;
 pub mod ds;
+pub mod dynamic;
+pub mod fan_in;
+pub mod fan_out;
+pub mod mapping;
+#[cfg(feature = "mask")]
+pub mod mask;
+pub mod quality;
+pub mod quarantine;
+pub mod runner;
+pub mod sink;
+pub mod spec;
+pub mod stream;
+pub mod transform;
+pub mod watermark;
 #[cfg(all(feature = "sql", feature = "xl"))]
 pub mod xl_db;
 #[cfg(all(feature = "xl", feature = "json"))]
 pub mod xl_json;
 
+pub use audit::*;
+pub use checkpoint::*;
 pub use ds::*;
+pub use dynamic::*;
+pub use fan_in::*;
+pub use fan_out::*;
+pub use mapping::*;
+#[cfg(feature = "mask")]
+pub use mask::*;
+pub use quality::*;
+pub use quarantine::*;
+pub use runner::*;
+pub use sink::*;
+pub use spec::*;
+pub use stream::*;
+pub use transform::*;
+pub use watermark::*;
 #[cfg(all(feature = "sql", feature = "xl"))]
 pub use xl_db::{XlDbConvertor, XlDbExecutor, XlDbHelper, XlIndexSelection, XlToDbConsumer};
 #[cfg(all(feature = "xl", feature = "json"))]
@@ -0,0 +1,563 @@
+//! Data Quality
+//!
+//! Declarative checks run on a `Fabrix` before it is written:
+//! - Check: not null, unique, value range, row count bounds, reference to a lookup `Fabrix` and
+//!   freshness of the max date
+//! - Severity: a failed `Warn` check is only reported, a failed `Fail` check stops the load
+//! - QualityReport: a `RecordSink` of check results, which can be written into any `IntoSource`
+//! - QualityGate: a `Transform` running checks, it passes the data through unless a `Fail` check
+//!   fails
+//!
+//! A gate placed as the last transform of a `Pipeline`, or set by
+//! `XlToDbConsumer::with_quality_gate`, stops a bad load before anything is saved.
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use polars::prelude::{DataFrame, NamedFrom, Series as PolarsSeries};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Duration, Fabrix, FabrixError, FabrixResult, NaiveDateTime, RecordSink, SinkRecord, Transform,
+    Utc, Value, Value2ChronoHelper,
+};
+
+// ================================================================================================
+// Check & Severity
+// ================================================================================================
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// a failed check is reported, the data passes
+    Warn,
+    /// a failed check stops the data
+    #[default]
+    Fail,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Warn => "warn",
+            Severity::Fail => "fail",
+        }
+    }
+}
+
+/// a declarative check, nulls are ignored by all checks except `NotNull`
+#[derive(Debug, Clone)]
+pub enum Check {
+    NotNull {
+        column: String,
+    },
+    /// the combination of the columns is unique, every row sharing its combination with another
+    /// row fails
+    Unique {
+        columns: Vec<String>,
+    },
+    /// `min <= value <= max`, numbers, dates & strings are compared
+    Range {
+        column: String,
+        min: Option<Value>,
+        max: Option<Value>,
+    },
+    RowCount {
+        min: Option<usize>,
+        max: Option<usize>,
+    },
+    /// values of `column` exist in `lookup_column` of `lookup`
+    Reference {
+        column: String,
+        lookup: Fabrix,
+        lookup_column: String,
+    },
+    /// the max date of `column` is at most `max_age` old
+    Freshness {
+        column: String,
+        max_age: Duration,
+    },
+}
+
+impl Check {
+    pub fn not_null(column: &str) -> Self {
+        Check::NotNull {
+            column: column.to_owned(),
+        }
+    }
+
+    pub fn unique<S: AsRef<str>>(columns: &[S]) -> Self {
+        Check::Unique {
+            columns: columns.iter().map(|c| c.as_ref().to_owned()).collect(),
+        }
+    }
+
+    pub fn range(column: &str, min: Option<Value>, max: Option<Value>) -> Self {
+        Check::Range {
+            column: column.to_owned(),
+            min,
+            max,
+        }
+    }
+
+    pub fn row_count(min: Option<usize>, max: Option<usize>) -> Self {
+        Check::RowCount { min, max }
+    }
+
+    pub fn reference(column: &str, lookup: Fabrix, lookup_column: &str) -> Self {
+        Check::Reference {
+            column: column.to_owned(),
+            lookup,
+            lookup_column: lookup_column.to_owned(),
+        }
+    }
+
+    pub fn freshness(column: &str, max_age: Duration) -> Self {
+        Check::Freshness {
+            column: column.to_owned(),
+            max_age,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Check::NotNull { .. } => "not_null",
+            Check::Unique { .. } => "unique",
+            Check::Range { .. } => "range",
+            Check::RowCount { .. } => "row_count",
+            Check::Reference { .. } => "reference",
+            Check::Freshness { .. } => "freshness",
+        }
+    }
+
+    /// the checked column(s), joined by comma
+    pub fn column(&self) -> Option<String> {
+        match self {
+            Check::NotNull { column }
+            | Check::Range { column, .. }
+            | Check::Reference { column, .. }
+            | Check::Freshness { column, .. } => Some(column.clone()),
+            Check::Unique { columns } => Some(columns.join(",")),
+            Check::RowCount { .. } => None,
+        }
+    }
+
+    /// returns the number of failed rows and a message, `None` if passed
+    fn run(&self, fabrix: &Fabrix) -> FabrixResult<Option<(usize, String)>> {
+        let res = match self {
+            Check::NotNull { column } => {
+                let nulls = fabrix.get_column(column)?.data().null_count();
+                (nulls > 0).then(|| (nulls, format!("{nulls} null value(s)")))
+            }
+            Check::Unique { columns } => {
+                // rows with a null key are ignored, values are compared by their types
+                let duplicated = fabrix
+                    .data()
+                    .select(columns)?
+                    .drop_nulls(None)?
+                    .is_duplicated()?
+                    .into_iter()
+                    .filter(|d| *d == Some(true))
+                    .count();
+                (duplicated > 0).then(|| (duplicated, format!("{duplicated} duplicated row(s)")))
+            }
+            Check::Range { column, min, max } => {
+                let mut out = 0;
+                for v in fabrix
+                    .get_column(column)?
+                    .try_iter()?
+                    .filter(|v| !v.is_null())
+                {
+                    let below = min.as_ref().map(|m| compare(&v, m)).transpose()?;
+                    let above = max.as_ref().map(|m| compare(&v, m)).transpose()?;
+                    if below == Some(Ordering::Less) || above == Some(Ordering::Greater) {
+                        out += 1;
+                    }
+                }
+                (out > 0).then(|| (out, format!("{out} value(s) out of range")))
+            }
+            Check::RowCount { min, max } => {
+                let rows = fabrix.height();
+                let out = min.filter(|m| rows < *m).or(max.filter(|m| rows > *m));
+                out.map(|_| (rows, format!("{rows} row(s), expected {min:?}..={max:?}")))
+            }
+            Check::Reference {
+                column,
+                lookup,
+                lookup_column,
+            } => {
+                let keys = lookup
+                    .get_column(lookup_column)?
+                    .try_iter()?
+                    .filter(|v| !v.is_null())
+                    .map(|v| v.to_string())
+                    .collect::<HashSet<_>>();
+                let missing = fabrix
+                    .get_column(column)?
+                    .try_iter()?
+                    .filter(|v| !v.is_null() && !keys.contains(&v.to_string()))
+                    .count();
+                (missing > 0).then(|| (missing, format!("{missing} value(s) not in lookup")))
+            }
+            Check::Freshness { column, max_age } => {
+                let latest = fabrix
+                    .get_column(column)?
+                    .try_iter()?
+                    .filter_map(|v| to_datetime(v).transpose())
+                    .collect::<FabrixResult<Vec<_>>>()?
+                    .into_iter()
+                    .max();
+                let oldest = Utc::now().naive_utc() - *max_age;
+                match latest {
+                    Some(dt) if dt >= oldest => None,
+                    Some(dt) => Some((0, format!("latest {dt} is older than {oldest}"))),
+                    None => Some((0, "no date".to_owned())),
+                }
+            }
+        };
+
+        Ok(res)
+    }
+}
+
+/// compare a value with a bound, `Err` if they are not comparable
+fn compare(value: &Value, bound: &Value) -> FabrixResult<Ordering> {
+    let res = match (value, bound) {
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Date(a), Value::Date(b)) => Some(a.cmp(b)),
+        (Value::Time(a), Value::Time(b)) => Some(a.cmp(b)),
+        (Value::DateTime(a), Value::DateTime(b)) => Some(a.cmp(b)),
+        (a, b) => match (to_f64(a), to_f64(b)) {
+            (Some(a), Some(b)) => a.partial_cmp(&b),
+            _ => None,
+        },
+    };
+
+    res.ok_or_else(|| {
+        FabrixError::InvalidArgument(format!("{value:?} is not comparable with {bound:?}"))
+    })
+}
+
+fn to_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::U8(v) => Some(*v as f64),
+        Value::U16(v) => Some(*v as f64),
+        Value::U32(v) => Some(*v as f64),
+        Value::U64(v) => Some(*v as f64),
+        Value::I8(v) => Some(*v as f64),
+        Value::I16(v) => Some(*v as f64),
+        Value::I32(v) => Some(*v as f64),
+        Value::I64(v) => Some(*v as f64),
+        Value::F32(v) => Some(*v as f64),
+        Value::F64(v) => Some(*v),
+        _ => None,
+    }
+}
+
+/// a date is taken at midnight, `None` for a null
+fn to_datetime(value: Value) -> FabrixResult<Option<NaiveDateTime>> {
+    let res = match value {
+        Value::Null => None,
+        Value::Date(_) => {
+            Value2ChronoHelper::convert_value_to_naive_date(value)?.and_hms_opt(0, 0, 0)
+        }
+        Value::DateTime(_) => Some(Value2ChronoHelper::convert_value_to_naive_datetime(value)?),
+        v => {
+            return Err(FabrixError::InvalidArgument(format!(
+                "{v:?} is neither a date nor a datetime"
+            )))
+        }
+    };
+
+    Ok(res)
+}
+
+// ================================================================================================
+// QualityReport
+// ================================================================================================
+
+/// column names of a quality report fabrix
+pub const QUALITY_REPORT_COLUMNS: [&str; 7] = [
+    "name",
+    "check",
+    "column",
+    "severity",
+    "passed",
+    "failed_rows",
+    "message",
+];
+
+/// the result of a check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    pub name: String,
+    pub check: String,
+    pub column: Option<String>,
+    pub severity: Severity,
+    pub passed: bool,
+    pub failed_rows: usize,
+    pub message: Option<String>,
+}
+
+impl CheckResult {
+    /// failed with `Severity::Fail`
+    pub fn is_blocking(&self) -> bool {
+        !self.passed && self.severity == Severity::Fail
+    }
+}
+
+/// results of checks, shared by gates & consumers
+pub type QualityReport = RecordSink<CheckResult>;
+
+/// one row per result, see `QUALITY_REPORT_COLUMNS`
+impl SinkRecord for CheckResult {
+    fn to_fabrix(records: &[Self]) -> FabrixResult<Fabrix> {
+        results_to_fabrix(records)
+    }
+}
+
+impl RecordSink<CheckResult> {
+    /// no check has failed with `Severity::Fail`
+    pub fn is_success(&self) -> bool {
+        self.failures().is_empty()
+    }
+
+    /// failed checks with `Severity::Warn`
+    pub fn warnings(&self) -> Vec<CheckResult> {
+        self.filter(|r| !r.passed && r.severity == Severity::Warn)
+    }
+
+    /// failed checks with `Severity::Fail`
+    pub fn failures(&self) -> Vec<CheckResult> {
+        self.filter(|r| r.is_blocking())
+    }
+}
+
+fn results_to_fabrix(results: &[CheckResult]) -> FabrixResult<Fabrix> {
+    let strings =
+        |f: &dyn Fn(&CheckResult) -> Option<String>| results.iter().map(f).collect::<Vec<_>>();
+
+    let [name, check, column, severity, passed, failed_rows, message] = QUALITY_REPORT_COLUMNS;
+    let df = DataFrame::new(vec![
+        PolarsSeries::new(name, strings(&|r| Some(r.name.clone()))),
+        PolarsSeries::new(check, strings(&|r| Some(r.check.clone()))),
+        PolarsSeries::new(column, strings(&|r| r.column.clone())),
+        PolarsSeries::new(severity, strings(&|r| Some(r.severity.as_str().to_owned()))),
+        PolarsSeries::new(passed, results.iter().map(|r| r.passed).collect::<Vec<_>>()),
+        PolarsSeries::new(
+            failed_rows,
+            results
+                .iter()
+                .map(|r| r.failed_rows as u64)
+                .collect::<Vec<_>>(),
+        ),
+        PolarsSeries::new(message, strings(&|r| r.message.clone())),
+    ])?;
+
+    Ok(Fabrix::new_no_index(df))
+}
+
+// ================================================================================================
+// QualityGate
+// ================================================================================================
+
+/// a named check with its severity
+#[derive(Debug, Clone)]
+pub struct QualityCheck {
+    pub name: String,
+    pub check: Check,
+    pub severity: Severity,
+}
+
+/// runs checks on each fabrix passing through
+#[derive(Debug, Clone, Default)]
+pub struct QualityGate {
+    checks: Vec<QualityCheck>,
+    report: Option<Arc<QualityReport>>,
+}
+
+impl QualityGate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// a check named after its kind & column, e.g. `not_null(id)`
+    pub fn with_check(&mut self, check: Check, severity: Severity) -> &mut Self {
+        let name = match check.column() {
+            Some(c) => format!("{}({c})", check.kind()),
+            None => check.kind().to_owned(),
+        };
+        self.with_named_check(&name, check, severity)
+    }
+
+    pub fn with_named_check(&mut self, name: &str, check: Check, severity: Severity) -> &mut Self {
+        self.checks.push(QualityCheck {
+            name: name.to_owned(),
+            check,
+            severity,
+        });
+        self
+    }
+
+    /// collect the results of each run into `report`
+    pub fn with_report(&mut self, report: Arc<QualityReport>) -> &mut Self {
+        self.report = Some(report);
+        self
+    }
+
+    pub fn checks(&self) -> &[QualityCheck] {
+        &self.checks
+    }
+
+    pub fn report(&self) -> Option<&Arc<QualityReport>> {
+        self.report.as_ref()
+    }
+
+    /// run all the checks, a check which cannot run, e.g. on a missing column, fails
+    pub fn validate(&self, fabrix: &Fabrix) -> Vec<CheckResult> {
+        self.checks
+            .iter()
+            .map(|qc| {
+                let (passed, failed_rows, message) = match qc.check.run(fabrix) {
+                    Ok(None) => (true, 0, None),
+                    Ok(Some((rows, msg))) => (false, rows, Some(msg)),
+                    Err(e) => (false, 0, Some(e.to_string())),
+                };
+                CheckResult {
+                    name: qc.name.clone(),
+                    check: qc.check.kind().to_owned(),
+                    column: qc.check.column(),
+                    severity: qc.severity,
+                    passed,
+                    failed_rows,
+                    message,
+                }
+            })
+            .collect()
+    }
+
+    /// validate and report, `Err` if a `Fail` check has failed
+    pub fn check(&self, fabrix: &Fabrix) -> FabrixResult<()> {
+        let results = self.validate(fabrix);
+        let failures = results
+            .iter()
+            .filter(|r| r.is_blocking())
+            .map(|r| (r.name.clone(), r.message.clone().unwrap_or_default()))
+            .collect::<Vec<_>>();
+        if let Some(report) = self.report.as_ref() {
+            report.extend(results);
+        }
+
+        match failures.is_empty() {
+            true => Ok(()),
+            false => Err(FabrixError::QualityChecks(failures)),
+        }
+    }
+}
+
+impl Transform for QualityGate {
+    fn name(&self) -> &str {
+        "quality gate"
+    }
+
+    fn sync_transform(&self, fabrix: Fabrix) -> FabrixResult<Fabrix> {
+        self.check(&fabrix)?;
+        Ok(fabrix)
+    }
+}
+
+#[cfg(test)]
+mod quality_tests {
+    use super::*;
+    use crate::{fx, value, NaiveDate};
+
+    fn orders() -> Fabrix {
+        let today = Utc::now().naive_utc().date();
+        let yesterday = today.pred_opt().unwrap();
+        fx![
+            "id" => [1, 2, 3, 3],
+            "customer" => [Some("a"), Some("b"), None, Some("c")],
+            "amount" => [10.0, 20.0, -1.0, 30.0],
+            "date" => [yesterday, yesterday, today, today],
+        ]
+        .unwrap()
+    }
+
+    #[test]
+    fn test_checks() {
+        let customers = fx!["code" => ["a", "b"]].unwrap();
+        let report = Arc::new(QualityReport::new());
+
+        let mut gate = QualityGate::new();
+        gate.with_check(Check::not_null("customer"), Severity::Warn)
+            .with_check(Check::unique(&["id"]), Severity::Fail)
+            .with_check(
+                Check::range("amount", Some(value!(0)), None),
+                Severity::Warn,
+            )
+            .with_check(Check::row_count(Some(1), Some(10)), Severity::Fail)
+            .with_check(
+                Check::reference("customer", customers, "code"),
+                Severity::Warn,
+            )
+            .with_check(Check::freshness("date", Duration::days(2)), Severity::Fail)
+            .with_check(Check::not_null("missing"), Severity::Warn)
+            .with_report(report.clone());
+
+        let results = gate.validate(&orders());
+        let passed = results.iter().map(|r| r.passed).collect::<Vec<_>>();
+        assert_eq!(passed, vec![false, false, false, true, false, true, false]);
+        assert_eq!(results[0].name, "not_null(customer)");
+        assert_eq!(results[1].failed_rows, 2);
+        assert_eq!(results[2].failed_rows, 1);
+        assert_eq!(results[4].failed_rows, 1);
+
+        // the duplicated id stops the data
+        let res = gate.sync_transform(orders());
+        assert!(matches!(res, Err(FabrixError::QualityChecks(f)) if f[0].0 == "unique(id)"));
+        assert!(!report.is_success());
+        assert_eq!(report.failures().len(), 1);
+        assert_eq!(report.warnings().len(), 4);
+
+        let fx = report.to_fabrix().unwrap();
+        assert_eq!(fx.shape(), (7, QUALITY_REPORT_COLUMNS.len()));
+
+        // stale data
+        let mut gate = QualityGate::new();
+        gate.with_check(Check::freshness("date", Duration::days(2)), Severity::Fail);
+        let stale = fx!["date" => [NaiveDate::from_ymd_opt(2020, 1, 1).unwrap()]].unwrap();
+        assert!(gate.sync_transform(stale).is_err());
+        assert!(gate.sync_transform(orders().slice(0, 3)).is_ok());
+
+        // nulls are neither duplicates of each other nor of the string "null"
+        let mut gate = QualityGate::new();
+        gate.with_check(Check::unique(&["k"]), Severity::Fail);
+        let keys = fx!["k" => [Some("null"), None, None, Some("a")]].unwrap();
+        assert!(gate.sync_transform(keys).is_ok());
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn test_report_flush() {
+        use crate::{CsvWriteOptions, CsvWriter};
+
+        let report = Arc::new(QualityReport::new());
+        let mut gate = QualityGate::new();
+        gate.with_check(Check::not_null("customer"), Severity::Warn)
+            .with_report(report.clone());
+        assert!(gate.sync_transform(orders()).is_ok());
+        assert!(report.is_success());
+
+        let mut buff = std::io::Cursor::new(Vec::<u8>::new());
+        let mut writer = CsvWriter::new(&mut buff);
+        let flushed = report
+            .sync_flush(&mut writer, &CsvWriteOptions::default())
+            .unwrap();
+        assert_eq!(flushed, 1);
+        assert!(report.is_empty());
+
+        let written = String::from_utf8(buff.into_inner()).unwrap();
+        assert!(written.starts_with("name,check,column,severity,passed,failed_rows,message\n"));
+    }
+}
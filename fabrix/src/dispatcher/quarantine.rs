@@ -0,0 +1,564 @@
+//! Quarantine
+//!
+//! Bad records handling of readers, transforms & writers:
+//! - BadRecordPolicy: fail (default), skip or quarantine a bad record
+//! - RecordLocation & BadRecord: where a record comes from, its data and its error message
+//! - DeadLetter: a `RecordSink` of quarantined records, which can be written into any `IntoSource`
+//! - ErrorHandler: a policy with a dead letter, which isolates the bad rows of a failed batch
+//!
+//! A batch is processed as a whole first; only if it fails, and the policy is not `Fail`, it is
+//! bisected and its halves are processed again until the bad rows are isolated, so that good rows
+//! are kept and bad rows are skipped or quarantined. This assumes a row-local process, i.e. a row
+//! passes or fails regardless of the other rows of its batch.
+
+use std::fmt::Display;
+use std::sync::Arc;
+
+use polars::prelude::{DataFrame, NamedFrom, Series as PolarsSeries};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    D2Value, Fabrix, FabrixError, FabrixResult, NamedRow, RecordSink, SinkRecord, Stage, ValueType,
+};
+
+// ================================================================================================
+// BadRecordPolicy, RecordLocation & BadRecord
+// ================================================================================================
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BadRecordPolicy {
+    /// the whole batch fails
+    #[default]
+    Fail,
+    /// bad records are dropped
+    Skip,
+    /// bad records are sent to a dead letter
+    Quarantine,
+}
+
+/// where a record comes from, fields are optional since sources differ
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordLocation {
+    pub file: Option<String>,
+    pub sheet: Option<String>,
+    pub table: Option<String>,
+    /// line of a file, or row number (1-based) of a batch
+    pub line: Option<usize>,
+    /// cell reference of a sheet, or column name
+    pub cell: Option<String>,
+}
+
+impl RecordLocation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_file(&mut self, file: &str) -> &mut Self {
+        self.file = Some(file.to_owned());
+        self
+    }
+
+    pub fn with_sheet(&mut self, sheet: &str) -> &mut Self {
+        self.sheet = Some(sheet.to_owned());
+        self
+    }
+
+    pub fn with_table(&mut self, table: &str) -> &mut Self {
+        self.table = Some(table.to_owned());
+        self
+    }
+
+    pub fn with_line(&mut self, line: usize) -> &mut Self {
+        self.line = Some(line);
+        self
+    }
+
+    pub fn with_cell(&mut self, cell: &str) -> &mut Self {
+        self.cell = Some(cell.to_owned());
+        self
+    }
+}
+
+/// a rejected record
+#[derive(Debug, Clone)]
+pub struct BadRecord {
+    pub stage: Stage,
+    /// source type of the reader or writer, or name of the transform
+    pub source: String,
+    pub location: RecordLocation,
+    pub row: Option<NamedRow>,
+    pub error: String,
+}
+
+// ================================================================================================
+// DeadLetter
+// ================================================================================================
+
+/// column names of a dead letter fabrix
+pub const DEAD_LETTER_COLUMNS: [&str; 9] = [
+    "stage", "source", "file", "sheet", "table", "line", "cell", "row", "error",
+];
+
+/// quarantined records, shared by readers, pipelines & consumers
+pub type DeadLetter = RecordSink<BadRecord>;
+
+/// one row per record, see `DEAD_LETTER_COLUMNS`; the record's row is a json string
+impl SinkRecord for BadRecord {
+    fn to_fabrix(records: &[Self]) -> FabrixResult<Fabrix> {
+        records_to_fabrix(records)
+    }
+}
+
+fn records_to_fabrix(records: &[BadRecord]) -> FabrixResult<Fabrix> {
+    let strings =
+        |f: &dyn Fn(&BadRecord) -> Option<String>| records.iter().map(f).collect::<Vec<_>>();
+    let rows = records
+        .iter()
+        .map(|r| r.row.as_ref().map(serde_json::to_string).transpose())
+        .collect::<Result<Vec<_>, _>>()?;
+    let lines = records
+        .iter()
+        .map(|r| r.location.line.map(|l| l as u64))
+        .collect::<Vec<_>>();
+
+    let [stage, source, file, sheet, table, line, cell, row, error] = DEAD_LETTER_COLUMNS;
+    let df = DataFrame::new(vec![
+        PolarsSeries::new(stage, strings(&|r| Some(r.stage.to_string()))),
+        PolarsSeries::new(source, strings(&|r| Some(r.source.clone()))),
+        PolarsSeries::new(file, strings(&|r| r.location.file.clone())),
+        PolarsSeries::new(sheet, strings(&|r| r.location.sheet.clone())),
+        PolarsSeries::new(table, strings(&|r| r.location.table.clone())),
+        PolarsSeries::new(line, lines),
+        PolarsSeries::new(cell, strings(&|r| r.location.cell.clone())),
+        PolarsSeries::new(row, rows),
+        PolarsSeries::new(error, strings(&|r| Some(r.error.clone()))),
+    ])?;
+
+    Ok(Fabrix::new_no_index(df))
+}
+
+// ================================================================================================
+// ErrorHandler
+// ================================================================================================
+
+/// an error policy, with a dead letter for `BadRecordPolicy::Quarantine` and the base location of
+/// the records, e.g. the file being read
+#[derive(Debug, Clone, Default)]
+pub struct ErrorHandler {
+    policy: BadRecordPolicy,
+    dead_letter: Option<Arc<DeadLetter>>,
+    location: RecordLocation,
+}
+
+impl ErrorHandler {
+    pub fn new(policy: BadRecordPolicy) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    /// quarantine bad records into `dead_letter`
+    pub fn quarantine(dead_letter: Arc<DeadLetter>) -> Self {
+        Self {
+            policy: BadRecordPolicy::Quarantine,
+            dead_letter: Some(dead_letter),
+            location: RecordLocation::default(),
+        }
+    }
+
+    pub fn with_location(&mut self, location: RecordLocation) -> &mut Self {
+        self.location = location;
+        self
+    }
+
+    pub fn policy(&self) -> BadRecordPolicy {
+        self.policy
+    }
+
+    pub fn dead_letter(&self) -> Option<&Arc<DeadLetter>> {
+        self.dead_letter.as_ref()
+    }
+
+    pub fn location(&self) -> &RecordLocation {
+        &self.location
+    }
+
+    /// base location with the row number of a batch, counted from the base line if any
+    pub fn row_location(&self, row: usize) -> RecordLocation {
+        let mut location = self.location.clone();
+        location.line = Some(self.location.line.map_or(row + 1, |l| l + row));
+        location
+    }
+
+    /// reject a record by the policy, `Fail` returns the error
+    pub fn reject<E: Display>(
+        &self,
+        stage: Stage,
+        source: &str,
+        location: RecordLocation,
+        row: Option<NamedRow>,
+        error: E,
+    ) -> FabrixResult<()> {
+        match self.policy {
+            BadRecordPolicy::Fail => Err(FabrixError::new_uncategorized(error.to_string())),
+            BadRecordPolicy::Skip => Ok(()),
+            BadRecordPolicy::Quarantine => {
+                self.dead_letter
+                    .as_ref()
+                    .ok_or(FabrixError::NotSet("dead letter"))?
+                    .push(BadRecord {
+                        stage,
+                        source: source.to_owned(),
+                        location,
+                        row,
+                        error: error.to_string(),
+                    });
+                Ok(())
+            }
+        }
+    }
+
+    /// apply `f` to a batch; if it fails and the policy is not `Fail`, apply `f` to both halves of
+    /// the batch, recursively, and reject the single rows which still fail. Results of the good
+    /// parts are returned in the order of their rows.
+    ///
+    /// `f` must be row-local: a row which passes or fails alone does the same in any batch,
+    /// otherwise e.g. an aggregation would be applied to parts of the batch
+    pub fn sync_apply<T, F>(
+        &self,
+        stage: Stage,
+        source: &str,
+        fabrix: Fabrix,
+        mut f: F,
+    ) -> FabrixResult<Vec<T>>
+    where
+        F: FnMut(Fabrix) -> FabrixResult<T>,
+    {
+        if self.policy == BadRecordPolicy::Fail {
+            return Ok(vec![f(fabrix)?]);
+        }
+
+        let mut res = Vec::new();
+        // (first row, part) to process, the top one is the next in order
+        let mut pending = vec![(0, fabrix)];
+        while let Some((offset, part)) = pending.pop() {
+            let error = match f(part.clone()) {
+                Ok(r) => {
+                    res.push(r);
+                    continue;
+                }
+                Err(e) => e,
+            };
+            match bisect(&part) {
+                Some((left, right)) => {
+                    pending.push((offset + left.height(), right));
+                    pending.push((offset, left));
+                }
+                None => {
+                    let named_row = first_named_row(&part);
+                    self.reject(stage, source, self.row_location(offset), named_row, error)?;
+                }
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// apply `f` to a batch as a whole; if it fails and the policy is not `Fail`, all rows of the
+    /// batch are rejected with its error, e.g. for a write which may have been partly committed.
+    /// Returns `None` if the batch is rejected
+    pub fn sync_apply_batch<T, F>(
+        &self,
+        stage: Stage,
+        source: &str,
+        fabrix: Fabrix,
+        f: F,
+    ) -> FabrixResult<Option<T>>
+    where
+        F: FnOnce(Fabrix) -> FabrixResult<T>,
+    {
+        if self.policy == BadRecordPolicy::Fail {
+            return f(fabrix).map(Some);
+        }
+        match f(fabrix.clone()) {
+            Ok(res) => Ok(Some(res)),
+            Err(e) => {
+                self.reject_rows(stage, source, &fabrix, e)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// reject all rows of a batch with the same error
+    pub fn reject_rows<E: Display>(
+        &self,
+        stage: Stage,
+        source: &str,
+        fabrix: &Fabrix,
+        error: E,
+    ) -> FabrixResult<()> {
+        let error = error.to_string();
+        let mut rows = fabrix.iter_named_rows().ok();
+        for i in 0..fabrix.height() {
+            let row = rows.as_mut().and_then(|r| r.next());
+            self.reject(stage, source, self.row_location(i), row, &error)?;
+        }
+
+        Ok(())
+    }
+
+    /// `sync_apply` for a transform-like `f`, the good rows are stacked together.
+    /// Returns `None` if all rows are rejected
+    pub fn sync_apply_fabrix<F>(
+        &self,
+        stage: Stage,
+        source: &str,
+        fabrix: Fabrix,
+        f: F,
+    ) -> FabrixResult<Option<Fabrix>>
+    where
+        F: FnMut(Fabrix) -> FabrixResult<Fabrix>,
+    {
+        vconcat_all(self.sync_apply(stage, source, fabrix, f)?)
+    }
+
+    /// reject the row-wise values which cannot be turned into columns named by `names`: a row of
+    /// another width, or with a value of another type than the most common type of its column.
+    /// `first_line` is the line of the first row. Nothing is rejected by `Fail`, so that the
+    /// conversion fails as a whole
+    pub fn reject_row_values(
+        &self,
+        stage: Stage,
+        source: &str,
+        names: &[String],
+        rows: D2Value,
+        first_line: usize,
+    ) -> FabrixResult<D2Value> {
+        if self.policy == BadRecordPolicy::Fail {
+            return Ok(rows);
+        }
+
+        let width = names.len();
+        // (type, count) of the non-null values of each column
+        let mut counts = vec![Vec::<(ValueType, usize)>::new(); width];
+        for row in rows.iter().filter(|r| r.len() == width) {
+            for (c, v) in counts.iter_mut().zip(row).filter(|(_, v)| !v.is_null()) {
+                let dtype = ValueType::from(v);
+                match c.iter_mut().find(|(t, _)| t == &dtype) {
+                    Some((_, n)) => *n += 1,
+                    None => c.push((dtype, 1)),
+                }
+            }
+        }
+        let dtypes = counts
+            .into_iter()
+            .map(|c| c.into_iter().rev().max_by_key(|(_, n)| *n).map(|(t, _)| t))
+            .collect::<Vec<_>>();
+
+        let mut good = Vec::with_capacity(rows.len());
+        for (i, row) in rows.into_iter().enumerate() {
+            let bad = if row.len() != width {
+                Some((
+                    None,
+                    format!("expected {width} values, found {}", row.len()),
+                ))
+            } else {
+                row.iter().zip(dtypes.iter().zip(names)).find_map(
+                    |(v, (dtype, name))| match dtype {
+                        Some(t) if !v.is_null() && &ValueType::from(v) != t => {
+                            Some((Some(name), format!("`{v}` is not of type {t:?}")))
+                        }
+                        _ => None,
+                    },
+                )
+            };
+
+            match bad {
+                None => good.push(row),
+                Some((name, error)) => {
+                    let mut location = self.location.clone();
+                    location.with_line(first_line + i);
+                    if let Some(name) = name {
+                        location.with_cell(name);
+                    }
+                    let named_row = NamedRow::from_values(names.iter().cloned().zip(row).collect());
+                    self.reject(stage, source, location, Some(named_row), error)?;
+                }
+            }
+        }
+
+        Ok(good)
+    }
+}
+
+/// the two halves of a fabrix, `None` if it has less than two rows
+pub fn bisect(fabrix: &Fabrix) -> Option<(Fabrix, Fabrix)> {
+    let height = fabrix.height();
+    if height < 2 {
+        return None;
+    }
+    let mid = height / 2;
+    Some((fabrix.slice(0, mid), fabrix.slice(mid as i64, height - mid)))
+}
+
+/// the first row of a fabrix, kept for a bad record
+pub fn first_named_row(fabrix: &Fabrix) -> Option<NamedRow> {
+    fabrix.iter_named_rows().ok()?.next()
+}
+
+/// stack fabrics vertically, `None` if empty
+pub fn vconcat_all(fabrics: Vec<Fabrix>) -> FabrixResult<Option<Fabrix>> {
+    let mut iter = fabrics.into_iter();
+    let mut res = match iter.next() {
+        Some(fx) => fx,
+        None => return Ok(None),
+    };
+    for fx in iter {
+        res.vconcat_mut(&fx)?;
+    }
+
+    Ok(Some(res))
+}
+
+#[cfg(test)]
+#[cfg(feature = "csv")]
+mod quarantine_tests {
+    use super::*;
+    use crate::{fx, CsvWriteOptions, CsvWriter, Value};
+
+    fn fail_on_odd(fx: Fabrix) -> FabrixResult<Fabrix> {
+        let odd = fx
+            .get_column("v")?
+            .try_iter()?
+            .any(|v| matches!(v, Value::I32(v) if v % 2 == 1));
+        if odd {
+            return Err(FabrixError::new_uncategorized("odd value"));
+        }
+        Ok(fx)
+    }
+
+    #[test]
+    fn test_error_policy() {
+        let data = fx!["k" => ["a", "b", "c", "d"], "v" => [2, 3, 4, 5]].unwrap();
+
+        let fail = ErrorHandler::default();
+        assert!(fail
+            .sync_apply_fabrix(Stage::Transform, "odd", data.clone(), fail_on_odd)
+            .is_err());
+
+        let skip = ErrorHandler::new(BadRecordPolicy::Skip);
+        let res = skip
+            .sync_apply_fabrix(Stage::Transform, "odd", data.clone(), fail_on_odd)
+            .unwrap()
+            .unwrap();
+        assert_eq!(res.height(), 2);
+
+        // quarantine without a dead letter
+        let res = ErrorHandler::new(BadRecordPolicy::Quarantine).sync_apply_fabrix(
+            Stage::Transform,
+            "odd",
+            data.clone(),
+            fail_on_odd,
+        );
+        assert!(matches!(res, Err(FabrixError::NotSet(_))));
+
+        let dead_letter = Arc::new(DeadLetter::new());
+        let mut quarantine = ErrorHandler::quarantine(dead_letter.clone());
+        quarantine.with_location(
+            RecordLocation::new()
+                .with_file("a.csv")
+                .with_line(2)
+                .clone(),
+        );
+        let res = quarantine
+            .sync_apply_fabrix(Stage::Transform, "odd", data, fail_on_odd)
+            .unwrap()
+            .unwrap();
+        assert_eq!(res.height(), 2);
+
+        let records = dead_letter.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].location.file.as_deref(), Some("a.csv"));
+        assert_eq!(records[0].location.line, Some(3));
+        assert_eq!(records[1].location.line, Some(5));
+        assert!(records[1].error.contains("odd value"));
+
+        let mut buff = std::io::Cursor::new(Vec::<u8>::new());
+        let mut writer = CsvWriter::new(&mut buff);
+        let flushed = dead_letter
+            .sync_flush(&mut writer, &CsvWriteOptions::default())
+            .unwrap();
+        assert_eq!(flushed, 2);
+        assert!(dead_letter.is_empty());
+
+        let written = String::from_utf8(buff.into_inner()).unwrap();
+        assert!(written.starts_with(&DEAD_LETTER_COLUMNS.join(",")));
+        assert!(written.contains(r#"transform,odd,a.csv,,,3,,"{""k"":""b"",""v"":3}","#));
+    }
+
+    #[test]
+    fn test_bisect_failed_batch() {
+        let data = fx!["v" => [0, 2, 4, 6, 8, 10, 12, 13]].unwrap();
+
+        let dead_letter = Arc::new(DeadLetter::new());
+        let quarantine = ErrorHandler::quarantine(dead_letter.clone());
+        let mut calls = 0;
+        let res = quarantine
+            .sync_apply_fabrix(Stage::Transform, "odd", data, |fx| {
+                calls += 1;
+                fail_on_odd(fx)
+            })
+            .unwrap()
+            .unwrap();
+
+        // the batch, then both halves of each failed part: 8 -> 4 -> 2 -> 1
+        assert_eq!(calls, 7);
+        assert_eq!(
+            res.get_column("v")
+                .unwrap()
+                .try_iter()
+                .unwrap()
+                .collect::<Vec<_>>(),
+            [0, 2, 4, 6, 8, 10, 12].map(Value::I32).to_vec()
+        );
+
+        let records = dead_letter.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].location.line, Some(8));
+    }
+
+    #[test]
+    fn test_reject_row_values() {
+        let names = vec!["id".to_owned(), "score".to_owned()];
+        let rows = vec![
+            vec![Value::F64(1.0), Value::F64(1.5)],
+            vec![Value::F64(2.0), Value::String("n/a".to_owned())],
+            vec![Value::F64(3.0)],
+            vec![Value::F64(4.0), Value::Null],
+        ];
+
+        let fail = ErrorHandler::default();
+        let res = fail
+            .reject_row_values(Stage::Read, "xl", &names, rows.clone(), 2)
+            .unwrap();
+        assert_eq!(res.len(), 4);
+
+        let dead_letter = Arc::new(DeadLetter::new());
+        let mut quarantine = ErrorHandler::quarantine(dead_letter.clone());
+        quarantine.with_location(RecordLocation::new().with_sheet("data").clone());
+        let res = quarantine
+            .reject_row_values(Stage::Read, "xl", &names, rows, 2)
+            .unwrap();
+        assert_eq!(res.len(), 2);
+
+        let records = dead_letter.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].location.sheet.as_deref(), Some("data"));
+        assert_eq!(records[0].location.line, Some(3));
+        assert_eq!(records[0].location.cell.as_deref(), Some("score"));
+        assert_eq!(records[1].location.line, Some(4));
+        assert_eq!(records[1].location.cell, None);
+    }
+}
@@ -0,0 +1,471 @@
+//! Job Runner
+//!
+//! Runs many jobs concurrently, e.g. nightly table exports:
+//! - Job: a named async task, re-created on each attempt, with a timeout, a retry policy & a
+//!   cancellation token
+//! - JobRunner: runs jobs under a global and a per-source concurrency limit
+//! - JobOutcome: status, attempts & elapsed time of each job
+//!
+//! Permits are acquired per attempt, so a job waiting for its retry does not hold its slots.
+//! Cancelling the runner's token cancels all the jobs of its next run.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::future::{join_all, BoxFuture};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+pub use tokio_util::sync::CancellationToken;
+
+use crate::{FabrixError, FabrixResult, PipelineSpec, SourceRegistry};
+
+// ================================================================================================
+// RetryPolicy & Job
+// ================================================================================================
+
+/// exponential backoff: the n-th retry waits `backoff * multiplier^(n-1)`, up to `max_backoff`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// attempts in total, 1 means no retry
+    pub max_attempts: usize,
+    pub backoff: Duration,
+    pub multiplier: f64,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            backoff: Duration::from_secs(1),
+            multiplier: 2.0,
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: usize, backoff: Duration) -> Self {
+        Self {
+            max_attempts,
+            backoff,
+            ..Default::default()
+        }
+    }
+
+    /// `multiplier` must be finite and at least 1, so that the backoff never shrinks
+    pub fn with_multiplier(&mut self, multiplier: f64) -> FabrixResult<&mut Self> {
+        if !multiplier.is_finite() || multiplier < 1.0 {
+            return Err(FabrixError::InvalidArgument(format!(
+                "retry multiplier {multiplier} is not a finite number of at least 1"
+            )));
+        }
+        self.multiplier = multiplier;
+        Ok(self)
+    }
+
+    pub fn with_max_backoff(&mut self, max_backoff: Duration) -> &mut Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// delay before the next attempt, after `attempt` (1-based) has failed. A delay overflowing,
+    /// or not a valid duration by an invalid `multiplier`, is `max_backoff`
+    pub fn delay(&self, attempt: usize) -> Duration {
+        let exp = attempt.saturating_sub(1).min(i32::MAX as usize) as i32;
+        let secs = self.backoff.as_secs_f64() * self.multiplier.powi(exp);
+        Duration::try_from_secs_f64(secs).map_or(self.max_backoff, |d| d.min(self.max_backoff))
+    }
+}
+
+type JobTask = Box<dyn Fn() -> BoxFuture<'static, FabrixResult<()>> + Send + Sync>;
+
+/// a named async task, `source` is the key of its concurrency limit, e.g. a database
+pub struct Job {
+    name: String,
+    source: String,
+    task: JobTask,
+    timeout: Option<Duration>,
+    retry: RetryPolicy,
+    cancellation: Option<CancellationToken>,
+}
+
+impl Job {
+    /// `f` creates the task of each attempt
+    pub fn new<F, Fut>(name: &str, source: &str, f: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = FabrixResult<()>> + Send + 'static,
+    {
+        Self {
+            name: name.to_owned(),
+            source: source.to_owned(),
+            task: Box::new(move || Box::pin(f())),
+            timeout: None,
+            retry: RetryPolicy::default(),
+            cancellation: None,
+        }
+    }
+
+    /// a job running a pipeline spec, whose source is the type of the spec's source
+    pub fn from_spec(spec: PipelineSpec, registry: Arc<SourceRegistry>) -> Self {
+        let name = spec.name.clone().unwrap_or_else(|| "pipeline".to_owned());
+        let source = spec.source.source_type.clone();
+        let spec = Arc::new(spec);
+
+        Self::new(&name, &source, move || {
+            let (spec, registry) = (spec.clone(), registry.clone());
+            async move { spec.async_run(&registry).await }
+        })
+    }
+
+    /// timeout of each attempt
+    pub fn with_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_retry(&mut self, retry: RetryPolicy) -> &mut Self {
+        self.retry = retry;
+        self
+    }
+
+    /// cancel this job only, see `JobRunner::cancellation_token` for all the jobs
+    pub fn with_cancellation(&mut self, token: CancellationToken) -> &mut Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+// ================================================================================================
+// JobOutcome
+// ================================================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobStatus {
+    Succeeded,
+    /// error message of the last attempt
+    Failed(String),
+    /// the last attempt has timed out
+    TimedOut,
+    Cancelled,
+}
+
+#[derive(Debug, Clone)]
+pub struct JobOutcome {
+    pub name: String,
+    pub source: String,
+    pub status: JobStatus,
+    /// attempts started, 0 if cancelled before the first one
+    pub attempts: usize,
+    /// from the first attempt to the end, waiting for permits excluded
+    pub elapsed: Duration,
+}
+
+impl JobOutcome {
+    pub fn is_success(&self) -> bool {
+        self.status == JobStatus::Succeeded
+    }
+}
+
+// ================================================================================================
+// JobRunner
+// ================================================================================================
+
+/// runs jobs concurrently, at most `max_concurrency` at a time, and at most the limit of a source
+/// for the jobs of the same source
+pub struct JobRunner {
+    max_concurrency: usize,
+    source_limits: HashMap<String, usize>,
+    jobs: Vec<Job>,
+    cancellation: CancellationToken,
+}
+
+impl JobRunner {
+    pub fn new(max_concurrency: usize) -> Self {
+        Self {
+            max_concurrency: max_concurrency.max(1),
+            source_limits: HashMap::new(),
+            jobs: Vec::new(),
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    /// at most `limit` jobs of `source` at a time, sources without a limit share the global one
+    pub fn with_source_limit(&mut self, source: &str, limit: usize) -> &mut Self {
+        self.source_limits.insert(source.to_owned(), limit.max(1));
+        self
+    }
+
+    pub fn with_job(&mut self, job: Job) -> &mut Self {
+        self.jobs.push(job);
+        self
+    }
+
+    pub fn jobs(&self) -> &[Job] {
+        &self.jobs
+    }
+
+    /// a token cancelling all the jobs of the next `run`. Each run takes the token and leaves a new
+    /// one, so that a cancelled run doesn't cancel the following ones
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation.clone()
+    }
+
+    /// run all the added jobs, outcomes are in the order the jobs were added
+    pub async fn run(&mut self) -> Vec<JobOutcome> {
+        let cancellation = std::mem::replace(&mut self.cancellation, CancellationToken::new());
+        let global = Arc::new(Semaphore::new(self.max_concurrency));
+        let sources = self
+            .source_limits
+            .iter()
+            .map(|(s, l)| (s.clone(), Arc::new(Semaphore::new(*l))))
+            .collect::<HashMap<_, _>>();
+
+        let handles = self
+            .jobs
+            .drain(..)
+            .map(|job| {
+                let limits = Limits {
+                    global: global.clone(),
+                    source: sources.get(&job.source).cloned(),
+                };
+                let (name, source) = (job.name.clone(), job.source.clone());
+                let handle = tokio::spawn(run_job(job, limits, cancellation.clone()));
+                (name, source, handle)
+            })
+            .collect::<Vec<_>>();
+
+        join_all(
+            handles
+                .into_iter()
+                .map(|(name, source, handle)| async move {
+                    handle.await.unwrap_or_else(|e| JobOutcome {
+                        name,
+                        source,
+                        status: JobStatus::Failed(format!("job panicked: {e}")),
+                        attempts: 1,
+                        elapsed: Duration::ZERO,
+                    })
+                }),
+        )
+        .await
+    }
+}
+
+struct Limits {
+    global: Arc<Semaphore>,
+    source: Option<Arc<Semaphore>>,
+}
+
+impl Limits {
+    /// the source permit first, so that a job waiting for its source doesn't hold a global one
+    async fn acquire(&self) -> (Option<OwnedSemaphorePermit>, OwnedSemaphorePermit) {
+        let source = match self.source.as_ref() {
+            Some(s) => Some(s.clone().acquire_owned().await.expect("semaphore closed")),
+            None => None,
+        };
+        let global = self
+            .global
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("semaphore closed");
+        (source, global)
+    }
+}
+
+/// resolves when either the runner or the job is cancelled
+async fn cancelled(runner: &CancellationToken, job: Option<&CancellationToken>) {
+    match job {
+        Some(job) => tokio::select! {
+            _ = runner.cancelled() => {},
+            _ = job.cancelled() => {},
+        },
+        None => runner.cancelled().await,
+    }
+}
+
+async fn run_job(job: Job, limits: Limits, runner: CancellationToken) -> JobOutcome {
+    let cancellation = job.cancellation.as_ref();
+    let mut start = None;
+    let mut attempts = 0;
+
+    let status = loop {
+        let permits = tokio::select! {
+            biased;
+            _ = cancelled(&runner, cancellation) => break JobStatus::Cancelled,
+            p = limits.acquire() => p,
+        };
+        start.get_or_insert_with(Instant::now);
+        attempts += 1;
+
+        let task = (job.task)();
+        let res = tokio::select! {
+            biased;
+            _ = cancelled(&runner, cancellation) => break JobStatus::Cancelled,
+            r = async {
+                match job.timeout {
+                    Some(t) => tokio::time::timeout(t, task).await.ok(),
+                    None => Some(task.await),
+                }
+            } => r,
+        };
+        drop(permits);
+
+        let status = match res {
+            Some(Ok(_)) => break JobStatus::Succeeded,
+            Some(Err(e)) => JobStatus::Failed(e.to_string()),
+            None => JobStatus::TimedOut,
+        };
+        if attempts >= job.retry.max_attempts {
+            break status;
+        }
+
+        tokio::select! {
+            biased;
+            _ = cancelled(&runner, cancellation) => break JobStatus::Cancelled,
+            _ = tokio::time::sleep(job.retry.delay(attempts)) => {},
+        }
+    };
+
+    JobOutcome {
+        name: job.name,
+        source: job.source,
+        status,
+        attempts,
+        elapsed: start.map_or(Duration::ZERO, |s| s.elapsed()),
+    }
+}
+
+#[cfg(test)]
+mod runner_tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::FabrixError;
+
+    #[test]
+    fn test_retry_delay() {
+        let mut retry = RetryPolicy::new(5, Duration::from_millis(100));
+        retry.with_max_backoff(Duration::from_millis(300));
+        assert_eq!(retry.delay(1), Duration::from_millis(100));
+        assert_eq!(retry.delay(2), Duration::from_millis(200));
+        assert_eq!(retry.delay(3), Duration::from_millis(300));
+        assert_eq!(retry.delay(usize::MAX), Duration::from_millis(300));
+
+        assert!(retry.with_multiplier(f64::NAN).is_err());
+        assert!(retry.with_multiplier(-2.0).is_err());
+        retry.with_multiplier(1e300).unwrap();
+        assert_eq!(retry.delay(2), Duration::from_millis(300));
+        assert_eq!(retry.delay(3), Duration::from_millis(300));
+
+        // an invalid multiplier set directly
+        retry.multiplier = -1.0;
+        assert_eq!(retry.delay(2), Duration::from_millis(300));
+    }
+
+    #[tokio::test]
+    async fn test_runner_limits() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+
+        let mut runner = JobRunner::new(4);
+        runner.with_source_limit("mysql", 2);
+        for i in 0..6 {
+            let (running, max_running) = (running.clone(), max_running.clone());
+            runner.with_job(Job::new(&format!("export {i}"), "mysql", move || {
+                let (running, max_running) = (running.clone(), max_running.clone());
+                async move {
+                    let n = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(n, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            }));
+        }
+
+        let outcomes = runner.run().await;
+        assert_eq!(outcomes.len(), 6);
+        assert!(outcomes.iter().all(|o| o.is_success() && o.attempts == 1));
+        assert_eq!(outcomes[5].name, "export 5");
+        assert_eq!(max_running.load(Ordering::SeqCst), 2);
+        assert!(runner.jobs().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_runner_retry_timeout_cancel() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let calls_cloned = calls.clone();
+        let mut flaky = Job::new("flaky", "pg", move || {
+            let n = calls_cloned.fetch_add(1, Ordering::SeqCst);
+            async move {
+                match n {
+                    0 | 1 => Err(FabrixError::new_uncategorized("connection reset")),
+                    _ => Ok(()),
+                }
+            }
+        });
+        flaky.with_retry(RetryPolicy::new(3, Duration::from_millis(1)));
+
+        let mut failing = Job::new("failing", "pg", || async {
+            Err(FabrixError::new_uncategorized("no such table"))
+        });
+        failing.with_retry(RetryPolicy::new(2, Duration::from_millis(1)));
+
+        let mut slow = Job::new("slow", "mongo", || async {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(())
+        });
+        slow.with_timeout(Duration::from_millis(10));
+
+        let token = CancellationToken::new();
+        let mut cancelled = Job::new("cancelled", "mongo", || async {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(())
+        });
+        cancelled.with_cancellation(token.clone());
+
+        let mut runner = JobRunner::new(8);
+        runner
+            .with_job(flaky)
+            .with_job(failing)
+            .with_job(slow)
+            .with_job(cancelled);
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            token.cancel();
+        });
+        let outcomes = runner.run().await;
+
+        assert_eq!(outcomes[0].status, JobStatus::Succeeded);
+        assert_eq!(outcomes[0].attempts, 3);
+        assert!(matches!(&outcomes[1].status, JobStatus::Failed(e) if e.contains("no such table")));
+        assert_eq!(outcomes[1].attempts, 2);
+        assert_eq!(outcomes[2].status, JobStatus::TimedOut);
+        assert_eq!(outcomes[3].status, JobStatus::Cancelled);
+
+        // cancel the runner before running
+        let mut runner = JobRunner::new(1);
+        runner.with_job(Job::new("never", "csv", || async { Ok(()) }));
+        runner.cancellation_token().cancel();
+        let outcomes = runner.run().await;
+        assert_eq!(outcomes[0].status, JobStatus::Cancelled);
+        assert_eq!(outcomes[0].attempts, 0);
+
+        // the next run isn't cancelled
+        runner.with_job(Job::new("again", "csv", || async { Ok(()) }));
+        let outcomes = runner.run().await;
+        assert_eq!(outcomes[0].status, JobStatus::Succeeded);
+    }
+}
@@ -0,0 +1,188 @@
+//! Record Sink
+//!
+//! Records collected along a run and written at its end:
+//! - SinkRecord: a record which can be turned into rows, e.g. `BadRecord` or `CheckResult`
+//! - RecordSink: keeps records in memory, shared by readers, pipelines & consumers, and writes
+//!   them into any `IntoSource` as one fabrix, e.g. `DeadLetter` or `QualityReport`
+
+use std::sync::Mutex;
+
+use crate::{Fabrix, FabrixResult, IntoSource, WriteOptions};
+
+/// a record of a `RecordSink`
+pub trait SinkRecord: Clone + Send {
+    /// one row per record
+    fn to_fabrix(records: &[Self]) -> FabrixResult<Fabrix>;
+}
+
+/// records shared by the stages of a run
+#[derive(Debug)]
+pub struct RecordSink<R> {
+    records: Mutex<Vec<R>>,
+}
+
+impl<R> Default for RecordSink<R> {
+    fn default() -> Self {
+        Self {
+            records: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl<R: SinkRecord> RecordSink<R> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&self, record: R) {
+        self.records.lock().unwrap().push(record);
+    }
+
+    pub fn extend<I: IntoIterator<Item = R>>(&self, records: I) {
+        self.records.lock().unwrap().extend(records);
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn records(&self) -> Vec<R> {
+        self.records.lock().unwrap().clone()
+    }
+
+    pub fn take(&self) -> Vec<R> {
+        std::mem::take(&mut *self.records.lock().unwrap())
+    }
+
+    /// put back the records taken by a failed flush, ahead of the records pushed meanwhile
+    fn restore(&self, mut records: Vec<R>) {
+        let mut current = self.records.lock().unwrap();
+        records.append(&mut current);
+        *current = records;
+    }
+
+    /// records matching `f`
+    pub fn filter(&self, f: impl Fn(&R) -> bool) -> Vec<R> {
+        let records = self.records.lock().unwrap();
+        records.iter().filter(|r| f(r)).cloned().collect()
+    }
+
+    /// one row per record, see `SinkRecord::to_fabrix`
+    pub fn to_fabrix(&self) -> FabrixResult<Fabrix> {
+        R::to_fabrix(&self.records.lock().unwrap())
+    }
+
+    /// write and remove the records, nothing is written if empty. The records are kept if the
+    /// write fails
+    pub fn sync_flush<'a, W, WO>(&self, writer: &mut W, options: &'a WO) -> FabrixResult<usize>
+    where
+        W: IntoSource<'a, WO>,
+        WO: WriteOptions,
+    {
+        let records = self.take();
+        if records.is_empty() {
+            return Ok(0);
+        }
+
+        match R::to_fabrix(&records).and_then(|fx| writer.sync_write(fx, options)) {
+            Ok(_) => Ok(records.len()),
+            Err(e) => {
+                self.restore(records);
+                Err(e)
+            }
+        }
+    }
+
+    /// write and remove the records asynchronously, nothing is written if empty. The records are
+    /// kept if the write fails
+    pub async fn async_flush<'a, W, WO>(
+        &self,
+        writer: &mut W,
+        options: &'a WO,
+    ) -> FabrixResult<usize>
+    where
+        W: IntoSource<'a, WO>,
+        WO: WriteOptions,
+    {
+        let records = self.take();
+        if records.is_empty() {
+            return Ok(0);
+        }
+
+        let res = match R::to_fabrix(&records) {
+            Ok(fx) => writer.async_write(fx, options).await,
+            Err(e) => Err(e),
+        };
+        match res {
+            Ok(_) => Ok(records.len()),
+            Err(e) => {
+                self.restore(records);
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "csv")]
+mod sink_tests {
+    use std::io::{self, Write};
+
+    use super::*;
+    use crate::{CsvWriteOptions, CsvWriter, Series, Value};
+
+    #[derive(Debug, Clone)]
+    struct Num(i32);
+
+    impl SinkRecord for Num {
+        fn to_fabrix(records: &[Self]) -> FabrixResult<Fabrix> {
+            let values = records.iter().map(|r| Value::I32(r.0)).collect();
+            let series = Series::from_values(values, "n", false)?;
+            Ok(Fabrix::from_series_no_index(vec![series])?)
+        }
+    }
+
+    /// a sink whose every write fails
+    struct Broken;
+
+    impl Write for Broken {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::Other, "disk full"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_flush_keeps_records() {
+        let sink = RecordSink::new();
+        sink.extend([Num(1), Num(2)]);
+
+        let options = CsvWriteOptions::default();
+        assert!(sink
+            .sync_flush(&mut CsvWriter::new(Broken), &options)
+            .is_err());
+        sink.push(Num(3));
+        assert!(sink
+            .async_flush(&mut CsvWriter::new(Broken), &options)
+            .await
+            .is_err());
+        assert_eq!(
+            sink.records().iter().map(|r| r.0).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+
+        let mut buff = io::Cursor::new(Vec::<u8>::new());
+        let flushed = sink
+            .sync_flush(&mut CsvWriter::new(&mut buff), &options)
+            .unwrap();
+        assert_eq!(flushed, 3);
+        assert!(sink.is_empty());
+    }
+}
@@ -0,0 +1,486 @@
+//! Pipeline Spec
+//!
+//! A declarative pipeline, loaded from a YAML, JSON or TOML file:
+//! - source: `type` of a registered reader, along with its options
+//! - transforms: built-in transforms, applied one by one
+//! - sink: `type` of a registered writer, along with its options
+//!
+//! ```yaml
+//! name: monthly_import
+//! source:
+//!   type: csv
+//!   path: data/2022-01.csv
+//!   delimiter: ";"
+//! transforms:
+//!   - type: rename
+//!     columns: { amt: amount }
+//!   - type: cast
+//!     columns: { amount: F64 }
+//!   - type: fill_null
+//!     column: amount
+//!     value: 0
+//!   - type: select
+//!     columns: [id, amount]
+//!   - type: mapping
+//!     columns:
+//!       - { source: id, target: order_id }
+//!       - { source: amount, target: amount, dtype: F64 }
+//!       - { target: channel, constant: import }
+//!   - type: quality
+//!     checks:
+//!       - { check: not_null, column: order_id }
+//!       - { check: range, column: amount, min: 0, severity: warn }
+//! sink:
+//!   type: sql
+//!   conn: sqlite://dev.sqlite
+//!   table_name: monthly
+//!   save_strategy: Replace
+//! ```
+//!
+//! Source & sink options are those of the reader & writer specs, e.g. `CsvReaderSpec`.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use crate::{
+    Cast, Check, ColumnMapping, ColumnRule, DynReadOptions, DynReader, DynWriteOptions, DynWriter,
+    FabrixError, FabrixResult, FieldInfo, FillNull, Pipeline, QualityGate, Rename, Select,
+    Severity, SourceRegistry, Transform, Value, ValueType,
+};
+#[cfg(feature = "mask")]
+use crate::{Mask, MaskMethod};
+
+// ================================================================================================
+// Spec
+// ================================================================================================
+
+/// a pipeline of dynamic readers & writers
+pub type DynPipeline<'a> =
+    Pipeline<'a, Box<dyn DynReader>, Box<dyn DynWriter>, DynReadOptions, DynWriteOptions>;
+
+/// a source or a sink, `options` are passed to the `SourceRegistry`
+#[derive(Debug, Clone, Deserialize)]
+pub struct SourceSpec {
+    #[serde(rename = "type")]
+    pub source_type: String,
+    #[serde(flatten)]
+    pub options: JsonMap<String, JsonValue>,
+}
+
+/// built-in transforms
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TransformSpec {
+    /// origin name -> new name
+    Rename {
+        columns: BTreeMap<String, String>,
+    },
+    /// column name -> value type, e.g. `I64`, `String`
+    Cast {
+        columns: BTreeMap<String, ValueType>,
+    },
+    Select {
+        columns: Vec<String>,
+    },
+    /// `value` is a scalar: bool, number or string
+    FillNull {
+        column: String,
+        value: JsonValue,
+    },
+    /// column name -> mask method, e.g. `{ method: hash, key: { env: PII_KEY } }` or
+    /// `{ method: phone }`
+    #[cfg(feature = "mask")]
+    Mask {
+        columns: BTreeMap<String, MaskMethod>,
+    },
+    /// target columns in order, unmapped columns are dropped unless `keep_unmapped`
+    Mapping {
+        columns: Vec<ColumnRuleSpec>,
+        #[serde(default)]
+        keep_unmapped: bool,
+    },
+    /// a `QualityGate`, the load stops if a `fail` check fails
+    Quality {
+        checks: Vec<CheckSpec>,
+    },
+}
+
+/// a target column of `TransformSpec::Mapping`, either from a `source` column or a `constant`.
+/// `default` fills a missing source column, both it and `constant` are scalars
+#[derive(Debug, Clone, Deserialize)]
+pub struct ColumnRuleSpec {
+    pub target: String,
+    pub source: Option<String>,
+    pub dtype: Option<ValueType>,
+    pub default: Option<JsonValue>,
+    pub constant: Option<JsonValue>,
+}
+
+impl ColumnRuleSpec {
+    pub fn build(&self) -> FabrixResult<ColumnRule> {
+        let mut rule = match (&self.source, &self.constant) {
+            (Some(source), None) => ColumnRule::column(source, &self.target),
+            (None, Some(constant)) => ColumnRule::constant(&self.target, scalar_value(constant)?),
+            _ => {
+                return Err(FabrixError::InvalidArgument(format!(
+                    "target column: {} needs either a source or a constant",
+                    self.target
+                )))
+            }
+        };
+        rule.dtype = self.dtype.clone();
+        rule.default = self.default.as_ref().map(scalar_value).transpose()?;
+
+        Ok(rule)
+    }
+}
+
+/// a check of `TransformSpec::Quality`, named after its kind & column unless `name` is set.
+/// `severity` defaults to `fail`
+#[derive(Debug, Clone, Deserialize)]
+pub struct CheckSpec {
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub check: CheckKindSpec,
+    #[serde(default)]
+    pub severity: Severity,
+}
+
+/// the declarative checks, a `Check::Reference` needs a lookup fabrix and is built in code
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "check", rename_all = "snake_case")]
+pub enum CheckKindSpec {
+    NotNull {
+        column: String,
+    },
+    Unique {
+        columns: Vec<String>,
+    },
+    /// `min` & `max` are scalars
+    Range {
+        column: String,
+        min: Option<JsonValue>,
+        max: Option<JsonValue>,
+    },
+    RowCount {
+        min: Option<usize>,
+        max: Option<usize>,
+    },
+    /// `max_age` in seconds
+    Freshness {
+        column: String,
+        max_age: i64,
+    },
+}
+
+impl CheckKindSpec {
+    pub fn build(&self) -> FabrixResult<Check> {
+        let res = match self {
+            CheckKindSpec::NotNull { column } => Check::not_null(column),
+            CheckKindSpec::Unique { columns } => Check::unique(columns),
+            CheckKindSpec::Range { column, min, max } => Check::range(
+                column,
+                min.as_ref().map(scalar_value).transpose()?,
+                max.as_ref().map(scalar_value).transpose()?,
+            ),
+            CheckKindSpec::RowCount { min, max } => Check::row_count(*min, *max),
+            CheckKindSpec::Freshness { column, max_age } => {
+                Check::freshness(column, chrono::Duration::seconds(*max_age))
+            }
+        };
+
+        Ok(res)
+    }
+}
+
+impl TransformSpec {
+    pub fn build(&self) -> FabrixResult<Box<dyn Transform>> {
+        let res: Box<dyn Transform> = match self {
+            TransformSpec::Rename { columns } => {
+                let pairs = columns.iter().collect::<Vec<_>>();
+                Box::new(Rename::new(&pairs))
+            }
+            TransformSpec::Cast { columns } => Box::new(Cast::new(
+                columns
+                    .iter()
+                    .map(|(n, t)| FieldInfo::new(n.as_str(), t.clone()))
+                    .collect(),
+            )),
+            TransformSpec::Select { columns } => Box::new(Select::new(columns)),
+            TransformSpec::FillNull { column, value } => {
+                Box::new(FillNull::new(column, scalar_value(value)?))
+            }
+            #[cfg(feature = "mask")]
+            TransformSpec::Mask { columns } => {
+                let mut mask = Mask::new();
+                for (column, method) in columns.iter() {
+                    mask.with_column(column, method.clone());
+                }
+                Box::new(mask)
+            }
+            TransformSpec::Mapping {
+                columns,
+                keep_unmapped,
+            } => {
+                let mut mapping = ColumnMapping::new();
+                for c in columns.iter() {
+                    mapping.with_rule(c.build()?);
+                }
+                mapping.with_keep_unmapped(*keep_unmapped);
+                Box::new(mapping)
+            }
+            TransformSpec::Quality { checks } => {
+                let mut gate = QualityGate::new();
+                for c in checks.iter() {
+                    match &c.name {
+                        Some(name) => gate.with_named_check(name, c.check.build()?, c.severity),
+                        None => gate.with_check(c.check.build()?, c.severity),
+                    };
+                }
+                Box::new(gate)
+            }
+        };
+
+        Ok(res)
+    }
+}
+
+fn scalar_value(value: &JsonValue) -> FabrixResult<Value> {
+    let res = match value {
+        JsonValue::Null => Value::Null,
+        JsonValue::Bool(v) => Value::Bool(*v),
+        JsonValue::Number(v) => match (v.as_i64(), v.as_u64(), v.as_f64()) {
+            (Some(v), _, _) => Value::I64(v),
+            (_, Some(v), _) => Value::U64(v),
+            (_, _, Some(v)) => Value::F64(v),
+            _ => return Err(FabrixError::InvalidArgument(format!("invalid number {v}"))),
+        },
+        JsonValue::String(v) => Value::String(v.clone()),
+        v => {
+            return Err(FabrixError::InvalidArgument(format!(
+                "{v} is not a scalar value"
+            )))
+        }
+    };
+
+    Ok(res)
+}
+
+/// source -> transforms -> sink
+#[derive(Debug, Clone, Deserialize)]
+pub struct PipelineSpec {
+    pub name: Option<String>,
+    pub source: SourceSpec,
+    #[serde(default)]
+    pub transforms: Vec<TransformSpec>,
+    pub sink: SourceSpec,
+}
+
+impl PipelineSpec {
+    pub fn from_json(s: &str) -> FabrixResult<Self> {
+        serde_json::from_str(s).map_err(|e| invalid_spec("json", e))
+    }
+
+    pub fn from_yaml(s: &str) -> FabrixResult<Self> {
+        serde_yaml::from_str(s).map_err(|e| invalid_spec("yaml", e))
+    }
+
+    pub fn from_toml(s: &str) -> FabrixResult<Self> {
+        toml::from_str(s).map_err(|e| invalid_spec("toml", e))
+    }
+
+    /// load a spec file, the format is chosen by its extension: `yaml`/`yml`, `json` or `toml`
+    pub fn from_path<P: AsRef<Path>>(path: P) -> FabrixResult<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)?;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml" | "yml") => Self::from_yaml(&content),
+            Some("json") => Self::from_json(&content),
+            Some("toml") => Self::from_toml(&content),
+            _ => Err(FabrixError::InvalidArgument(format!(
+                "unknown pipeline spec format: {}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// build and run the pipeline
+    pub fn sync_run(&self, registry: &SourceRegistry) -> FabrixResult<()> {
+        Pipeline::from_spec(self, registry)?.sync_run(&DynReadOptions, &DynWriteOptions)
+    }
+
+    /// build and run the pipeline, asynchronously
+    pub async fn async_run(&self, registry: &SourceRegistry) -> FabrixResult<()> {
+        Pipeline::from_spec(self, registry)?
+            .async_run(&DynReadOptions, &DynWriteOptions)
+            .await
+    }
+}
+
+fn invalid_spec<E: std::fmt::Display>(format: &str, e: E) -> FabrixError {
+    FabrixError::InvalidArgument(format!("invalid {format} pipeline spec: {e}"))
+}
+
+impl<'a> DynPipeline<'a> {
+    /// build a pipeline from a spec, readers & writers are built by the registry
+    pub fn from_spec(spec: &PipelineSpec, registry: &SourceRegistry) -> FabrixResult<Self> {
+        let reader = registry.reader(
+            &spec.source.source_type,
+            JsonValue::Object(spec.source.options.clone()),
+        )?;
+        let writer = registry.writer(
+            &spec.sink.source_type,
+            JsonValue::Object(spec.sink.options.clone()),
+        )?;
+
+        let mut pipeline = Pipeline::new(reader, writer);
+        for t in spec.transforms.iter() {
+            pipeline.with_boxed_transform(t.build()?);
+        }
+
+        Ok(pipeline)
+    }
+}
+
+#[cfg(test)]
+mod pipeline_spec_tests {
+    use super::*;
+    use crate::{CsvReadOptions, CsvReader, FromSource};
+
+    const YAML_SPEC: &str = r#"
+name: csv_to_csv
+source:
+  type: csv
+  path: ../mock/test.csv
+  num_rows: 5
+transforms:
+  - type: select
+    columns: [id, first_name, ip_address]
+  - type: rename
+    columns: { first_name: name }
+  - type: fill_null
+    column: ip_address
+    value: 0.0.0.0
+  - type: mapping
+    columns:
+      - { source: id, target: id }
+      - { source: name, target: name }
+      - { source: ip_address, target: ip }
+      - { target: batch, constant: 1 }
+sink:
+  type: csv
+  path: ../cache/write_spec.csv
+  delimiter: ";"
+"#;
+
+    const TOML_SPEC: &str = r#"
+[source]
+type = "csv"
+path = "../mock/test.csv"
+
+[[transforms]]
+type = "cast"
+columns = { id = "F64" }
+
+[sink]
+type = "parquet"
+path = "../cache/write_spec.parquet"
+"#;
+
+    #[test]
+    fn test_spec_formats() {
+        let spec = PipelineSpec::from_yaml(YAML_SPEC).unwrap();
+        assert_eq!(spec.name.as_deref(), Some("csv_to_csv"));
+        assert_eq!(spec.source.source_type, "csv");
+        assert_eq!(spec.transforms.len(), 4);
+        assert_eq!(spec.sink.options["delimiter"], ";");
+
+        let spec = PipelineSpec::from_toml(TOML_SPEC).unwrap();
+        assert_eq!(spec.sink.source_type, "parquet");
+
+        let spec = PipelineSpec::from_json(
+            r#"{"source": {"type": "json", "path": "a.json"}, "sink": {"type": "csv"}}"#,
+        )
+        .unwrap();
+        assert!(spec.transforms.is_empty());
+
+        assert!(PipelineSpec::from_json(r#"{"source": {"type": "json"}}"#).is_err());
+        assert!(PipelineSpec::from_path("pipeline.ini").is_err());
+    }
+
+    #[test]
+    fn test_spec_run() {
+        let registry = SourceRegistry::builtin();
+
+        let spec = PipelineSpec::from_yaml(YAML_SPEC).unwrap();
+        let pipeline = Pipeline::from_spec(&spec, &registry).unwrap();
+        assert_eq!(pipeline.reader_type(), "csv");
+        assert_eq!(pipeline.transforms().len(), 4);
+        spec.sync_run(&registry).unwrap();
+
+        let options = CsvReadOptions {
+            delimiter: Some(b';'),
+            ..Default::default()
+        };
+        let mut reader = CsvReader::new(std::fs::File::open("../cache/write_spec.csv").unwrap());
+        let fx = reader.sync_read(&options).unwrap();
+        assert_eq!(fx.shape(), (5, 4));
+        assert_eq!(fx.get_column_names(), vec!["id", "name", "ip", "batch"]);
+
+        // unknown sink
+        let mut spec = PipelineSpec::from_toml(TOML_SPEC).unwrap();
+        spec.sink.source_type = "unknown".to_owned();
+        assert!(spec.sync_run(&registry).is_err());
+    }
+
+    #[test]
+    fn test_spec_quality() {
+        let spec: TransformSpec = serde_yaml::from_str(
+            r#"
+type: quality
+checks:
+  - { check: not_null, column: id }
+  - { check: unique, columns: [id] }
+  - { name: positive, check: range, column: v, min: 0, severity: warn }
+  - { check: row_count, min: 1 }
+"#,
+        )
+        .unwrap();
+
+        let gate = spec.build().unwrap();
+        let fx = crate::fx![
+            "id" => [1, 2, 3],
+            "v" => [1, -1, 2],
+        ]
+        .unwrap();
+        // a failed `warn` check lets the data pass
+        assert_eq!(gate.sync_transform(fx).unwrap().height(), 3);
+
+        let fx = crate::fx![
+            "id" => [1, 1],
+            "v" => [1, 2],
+        ]
+        .unwrap();
+        assert!(matches!(
+            gate.sync_transform(fx),
+            Err(FabrixError::QualityChecks(f)) if f[0].0 == "unique(id)"
+        ));
+
+        // a reference check is not declarative
+        let res = serde_yaml::from_str::<TransformSpec>(
+            "{ type: quality, checks: [{ check: reference, column: id }] }",
+        );
+        assert!(res.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_spec_async_run() {
+        let registry = SourceRegistry::builtin();
+        let spec = PipelineSpec::from_toml(TOML_SPEC).unwrap();
+        spec.async_run(&registry).await.unwrap();
+    }
+}
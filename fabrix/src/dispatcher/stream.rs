@@ -0,0 +1,380 @@
+//! Stream Dispatcher
+//!
+//! Unlike `Dispatcher`, which holds the whole source in memory, a stream dispatcher moves data
+//! in batches:
+//! - Reader: read from one source as a stream of `Fabrix` batches
+//! - Writer: write each batch to one destination as soon as it arrives
+//!
+//! Batches are passed through a bounded buffer, so that a fast reader waits for a slow writer
+//! and memory use stays bounded by `batch_size * (buffer_size + 2)` rows.
+//!
+//! With an observer, rows & estimated bytes of each batch are reported as soon as it is read, and
+//! again as soon as it is written. With an `Audit`, a record of each dispatch is appended to an
+//! audit store, its rows & timings are added up batch by batch.
+
+use std::marker::PhantomData;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use tokio::sync::mpsc;
+
+use crate::{
+    Audit, AuditRecord, AuditRun, Endpoint, Fabrix, FabrixError, FabrixResult, ReadOptions,
+    SharedObserver, Stage, StageSpan, WriteOptions,
+};
+
+/// default number of rows in a batch
+pub const DEFAULT_BATCH_SIZE: usize = 10_000;
+/// default number of batches buffered between reader and writer
+pub const DEFAULT_BUFFER_SIZE: usize = 4;
+
+/// a stream of `Fabrix` batches
+pub type FabrixStream<'a> = BoxStream<'a, FabrixResult<Fabrix>>;
+
+// ================================================================================================
+// FromSourceStream & IntoSourceStream
+// traits that should be implemented by stream reader and stream writer respectively
+// ================================================================================================
+
+pub trait FromSourceStream<R>
+where
+    R: ReadOptions,
+{
+    fn source_type(&self) -> &str {
+        R::source_type()
+    }
+
+    /// where the batches are read from, for audit records; by default only the source type
+    fn endpoint(&self, _options: &R) -> Endpoint {
+        Endpoint::new(self.source_type())
+    }
+
+    /// read the source as a stream of batches, each batch has at most `batch_size` rows
+    fn read_stream<'s>(
+        &'s mut self,
+        options: &'s R,
+        batch_size: usize,
+    ) -> FabrixResult<FabrixStream<'s>>;
+}
+
+#[async_trait]
+pub trait IntoSourceStream<W>
+where
+    W: WriteOptions + Sync,
+{
+    fn source_type(&self) -> &str {
+        W::source_type()
+    }
+
+    /// where the batches are written to, for audit records; by default only the source type
+    fn endpoint(&self, _options: &W) -> Endpoint {
+        Endpoint::new(self.source_type())
+    }
+
+    /// write a batch, batches arrive in the order they are read
+    async fn write_batch(&mut self, fabrix: Fabrix, options: &W) -> FabrixResult<()>;
+
+    /// called once after the last batch, e.g. writing a file footer. A stream without any batch
+    /// fails with `EmptyContent`, since not even a schema is known to write
+    async fn finish_stream(&mut self, options: &W) -> FabrixResult<()>;
+}
+
+// ================================================================================================
+// StreamDispatcher
+// ================================================================================================
+
+pub struct StreamDispatcher<Reader, Writer, RO, WO>
+where
+    Reader: FromSourceStream<RO>,
+    Writer: IntoSourceStream<WO>,
+    RO: ReadOptions,
+    WO: WriteOptions + Sync,
+{
+    reader: Reader,
+    writer: Writer,
+    batch_size: usize,
+    buffer_size: usize,
+    observer: Option<SharedObserver>,
+    audit: Option<Audit>,
+    run: AuditRun,
+    read_options: PhantomData<RO>,
+    write_options: PhantomData<WO>,
+}
+
+impl<R, W, RO, WO> StreamDispatcher<R, W, RO, WO>
+where
+    R: FromSourceStream<RO>,
+    W: IntoSourceStream<WO>,
+    RO: ReadOptions,
+    WO: WriteOptions + Sync,
+{
+    pub fn new(reader: R, writer: W) -> Self {
+        Self {
+            reader,
+            writer,
+            batch_size: DEFAULT_BATCH_SIZE,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            observer: None,
+            audit: None,
+            run: AuditRun::default(),
+            read_options: PhantomData,
+            write_options: PhantomData,
+        }
+    }
+
+    pub fn with_batch_size(&mut self, batch_size: usize) -> &mut Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    pub fn with_buffer_size(&mut self, buffer_size: usize) -> &mut Self {
+        self.buffer_size = buffer_size;
+        self
+    }
+
+    /// report progress of reading & writing to an observer, batch by batch
+    pub fn with_observer(&mut self, observer: SharedObserver) -> &mut Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// append an audit record of each dispatch to a store
+    pub fn with_audit(&mut self, audit: Audit) -> &mut Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// the record of the last audited dispatch
+    pub fn audit_record(&self) -> Option<&AuditRecord> {
+        self.run.finished()
+    }
+
+    pub fn reader_type(&self) -> &str {
+        self.reader.source_type()
+    }
+
+    pub fn writer_type(&self) -> &str {
+        self.writer.source_type()
+    }
+
+    /// expose reader as reference to the outside
+    pub fn reader(&self) -> &R {
+        &self.reader
+    }
+
+    /// expose reader as mutable to the outside
+    pub fn reader_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// expose writer as reference to the outside
+    pub fn writer(&self) -> &W {
+        &self.writer
+    }
+
+    /// expose writer as mutable to the outside
+    pub fn writer_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// move all the batches from reader to writer, returns the number of rows moved.
+    ///
+    /// Reading & writing run concurrently. On the first error both sides stop, and the writer's
+    /// `finish_stream` is not called.
+    pub async fn dispatch(&mut self, read_options: &RO, write_options: &WO) -> FabrixResult<usize> {
+        if self.batch_size == 0 || self.buffer_size == 0 {
+            return Err(FabrixError::InvalidArgument(
+                "batch size & buffer size must be greater than 0".to_owned(),
+            ));
+        }
+
+        let run = Mutex::new(AuditRun::start(
+            self.audit.as_ref(),
+            self.reader.endpoint(read_options),
+            self.writer.endpoint(write_options),
+        ));
+        let res = self
+            .dispatch_audited(read_options, write_options, &run)
+            .await;
+        let mut run = run.into_inner().unwrap();
+        let appended = run.async_finish(&res).await;
+        self.run = run;
+
+        res.and_then(|rows| appended.map(|_| rows))
+    }
+
+    async fn dispatch_audited(
+        &mut self,
+        read_options: &RO,
+        write_options: &WO,
+        run: &Mutex<AuditRun>,
+    ) -> FabrixResult<usize> {
+        let read_span = StageSpan::start(self.observer.as_ref(), Stage::Read, self.reader_type());
+        let write_span = StageSpan::start(self.observer.as_ref(), Stage::Write, self.writer_type());
+
+        let (tx, mut rx) = mpsc::channel::<Fabrix>(self.buffer_size);
+        let mut stream = self.reader.read_stream(read_options, self.batch_size)?;
+        let writer = &mut self.writer;
+
+        let producer = async move {
+            let res = async {
+                let mut started = Instant::now();
+                while let Some(fx) = stream.next().await {
+                    let fx = fx?;
+                    read_span.batch(fx.height(), fx.estimated_size());
+                    run.lock().unwrap().read(&fx, started)?;
+                    // the receiver is only dropped when the writer stops
+                    tx.send(fx)
+                        .await
+                        .map_err(|_| FabrixError::new_uncategorized("stream buffer closed"))?;
+                    started = Instant::now();
+                }
+                Ok::<_, FabrixError>(())
+            }
+            .await;
+            read_span.finish(res)
+        };
+
+        let consumer = async move {
+            let res = async {
+                let mut rows = 0;
+                while let Some(fx) = rx.recv().await {
+                    let (height, bytes) = (fx.height(), fx.estimated_size());
+                    let started = Instant::now();
+                    writer.write_batch(fx, write_options).await?;
+                    write_span.batch(height, bytes);
+                    run.lock().unwrap().written(height, started);
+                    rows += height;
+                }
+                writer.finish_stream(write_options).await?;
+                Ok::<_, FabrixError>(rows)
+            }
+            .await;
+            write_span.finish(res)
+        };
+
+        let (_, rows) = tokio::try_join!(producer, consumer)?;
+
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod stream_dispatcher_tests {
+    use std::sync::Arc;
+
+    use futures::stream;
+
+    use super::*;
+    use crate::{fx, Metrics};
+
+    #[derive(Default)]
+    struct EmptyOption;
+
+    impl ReadOptions for EmptyOption {
+        fn source_type() -> &'static str {
+            "empty"
+        }
+    }
+
+    impl WriteOptions for EmptyOption {
+        fn source_type() -> &'static str {
+            "empty"
+        }
+    }
+
+    /// yields `n` batches of one row, or an error after `n` batches
+    struct MockRead {
+        n: usize,
+        fail: bool,
+    }
+
+    #[derive(Default)]
+    struct MockWrite {
+        batches: usize,
+        finished: bool,
+    }
+
+    impl FromSourceStream<EmptyOption> for MockRead {
+        fn read_stream<'s>(
+            &'s mut self,
+            _options: &'s EmptyOption,
+            _batch_size: usize,
+        ) -> FabrixResult<FabrixStream<'s>> {
+            let mut batches = (0..self.n)
+                .map(|i| Ok(fx!["ord" => [i as i32]].unwrap()))
+                .collect::<Vec<_>>();
+            if self.fail {
+                batches.push(Err(FabrixError::EmptyContent("mock")));
+            }
+            Ok(stream::iter(batches).boxed())
+        }
+    }
+
+    #[async_trait]
+    impl IntoSourceStream<EmptyOption> for MockWrite {
+        async fn write_batch(
+            &mut self,
+            _fabrix: Fabrix,
+            _options: &EmptyOption,
+        ) -> FabrixResult<()> {
+            self.batches += 1;
+            Ok(())
+        }
+
+        async fn finish_stream(&mut self, _options: &EmptyOption) -> FabrixResult<()> {
+            self.finished = true;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_dispatcher() {
+        let reader = MockRead { n: 10, fail: false };
+        let mut dispatcher = StreamDispatcher::new(reader, MockWrite::default());
+        dispatcher.with_buffer_size(2);
+
+        let res = dispatcher.dispatch(&EmptyOption, &EmptyOption).await;
+        assert_eq!(res.unwrap(), 10);
+        assert_eq!(dispatcher.writer().batches, 10);
+        assert!(dispatcher.writer().finished);
+    }
+
+    #[tokio::test]
+    async fn test_stream_dispatcher_with_observer() {
+        let metrics = Arc::new(Metrics::new());
+        let reader = MockRead { n: 5, fail: false };
+        let mut dispatcher = StreamDispatcher::new(reader, MockWrite::default());
+        dispatcher.with_observer(metrics.clone());
+
+        dispatcher
+            .dispatch(&EmptyOption, &EmptyOption)
+            .await
+            .unwrap();
+
+        let read = metrics.get(Stage::Read, "empty");
+        assert_eq!((read.rows, read.batches, read.runs), (5, 5, 1));
+        let write = metrics.get(Stage::Write, "empty");
+        assert_eq!((write.rows, write.batches, write.runs), (5, 5, 1));
+    }
+
+    #[tokio::test]
+    async fn test_stream_dispatcher_fail() {
+        let reader = MockRead { n: 3, fail: true };
+        let mut dispatcher = StreamDispatcher::new(reader, MockWrite::default());
+        let store = crate::FileAuditStore::new("../cache/audit/test_stream_fail.jsonl").unwrap();
+        dispatcher.with_audit(crate::Audit::new(Arc::new(store)));
+
+        let res = dispatcher.dispatch(&EmptyOption, &EmptyOption).await;
+        assert!(matches!(res, Err(FabrixError::EmptyContent("mock"))));
+        assert!(!dispatcher.writer().finished);
+
+        // the batches before the error are recorded
+        let record = dispatcher.audit_record().unwrap();
+        assert_eq!(record.outcome, crate::RunOutcome::Failure);
+        assert_eq!(record.rows_read, 3);
+    }
+}
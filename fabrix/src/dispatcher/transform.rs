@@ -0,0 +1,859 @@
+//! Transform & Pipeline
+//!
+//! A `Transform` turns one `Fabrix` into another, e.g. renaming or casting columns. Transforms
+//! are chained by a `Pipeline`:
+//! - Reader: read from one source
+//! - Transforms: applied one by one, in the order they are added
+//! - Writer: write the transformed data to one destination
+//!
+//! Built-in transforms: `Rename`, `Cast`, `Select`, `Filter`, `DeriveColumn`, `FillNull` and
+//! `FnTransform` (wraps a closure).
+//!
+//! With an `ErrorHandler`, rows failing a transform or the writer are skipped or quarantined
+//! instead of failing the whole run. With an `Audit`, a record of each run is appended to an audit
+//! store.
+
+use std::marker::PhantomData;
+use std::time::Instant;
+
+use async_trait::async_trait;
+
+use crate::dispatcher::{bisect, first_named_row, vconcat_all};
+use crate::{
+    Audit, AuditRecord, AuditRun, BadRecordPolicy, ErrorHandler, Fabrix, FabrixResult, FieldInfo,
+    FromSource, IntoSource, NamedRow, ReadOptions, Series, SharedObserver, Stage, StageSpan, Value,
+    ValueType, WriteOptions,
+};
+
+// ================================================================================================
+// Transform
+// ================================================================================================
+
+#[async_trait]
+pub trait Transform: Send + Sync {
+    /// name of the transform
+    fn name(&self) -> &str;
+
+    /// defaults to `sync_transform`, override it for transforms that need to await
+    async fn async_transform(&self, fabrix: Fabrix) -> FabrixResult<Fabrix> {
+        self.sync_transform(fabrix)
+    }
+
+    fn sync_transform(&self, fabrix: Fabrix) -> FabrixResult<Fabrix>;
+}
+
+/// apply transforms one by one
+pub fn sync_transform_all(
+    transforms: &[Box<dyn Transform>],
+    fabrix: Fabrix,
+) -> FabrixResult<Fabrix> {
+    transforms
+        .iter()
+        .try_fold(fabrix, |fx, t| t.sync_transform(fx))
+}
+
+/// apply transforms one by one, asynchronously
+pub async fn async_transform_all(
+    transforms: &[Box<dyn Transform>],
+    fabrix: Fabrix,
+) -> FabrixResult<Fabrix> {
+    let mut fx = fabrix;
+    for t in transforms {
+        fx = t.async_transform(fx).await?;
+    }
+    Ok(fx)
+}
+
+// ================================================================================================
+// Built-in transforms
+// ================================================================================================
+
+/// rename columns, the index tag follows the renamed column
+#[derive(Debug, Clone, Default)]
+pub struct Rename {
+    pairs: Vec<(String, String)>,
+}
+
+impl Rename {
+    pub fn new<S: AsRef<str>>(pairs: &[(S, S)]) -> Self {
+        let pairs = pairs
+            .iter()
+            .map(|(o, n)| (o.as_ref().to_owned(), n.as_ref().to_owned()))
+            .collect();
+        Self { pairs }
+    }
+
+    pub fn with_rename(&mut self, origin: &str, new: &str) -> &mut Self {
+        self.pairs.push((origin.to_owned(), new.to_owned()));
+        self
+    }
+}
+
+impl Transform for Rename {
+    fn name(&self) -> &str {
+        "rename"
+    }
+
+    fn sync_transform(&self, mut fabrix: Fabrix) -> FabrixResult<Fabrix> {
+        for (origin, new) in self.pairs.iter() {
+            fabrix.rename(origin, new)?;
+            if let Some(it) = fabrix.index_tag.as_mut().filter(|it| &it.name == origin) {
+                it.name = new.to_owned();
+            }
+        }
+        Ok(fabrix)
+    }
+}
+
+/// cast columns into the given types
+#[derive(Debug, Clone, Default)]
+pub struct Cast {
+    fields: Vec<FieldInfo>,
+}
+
+impl Cast {
+    pub fn new(fields: Vec<FieldInfo>) -> Self {
+        Self { fields }
+    }
+
+    pub fn with_cast(&mut self, name: &str, dtype: ValueType) -> &mut Self {
+        self.fields.push(FieldInfo::new(name, dtype));
+        self
+    }
+}
+
+impl Transform for Cast {
+    fn name(&self) -> &str {
+        "cast"
+    }
+
+    fn sync_transform(&self, mut fabrix: Fabrix) -> FabrixResult<Fabrix> {
+        for fi in self.fields.iter() {
+            fabrix.cast(fi.name(), fi.dtype())?;
+        }
+        Ok(fabrix)
+    }
+}
+
+/// keep only the given columns, in the given order. The index is dropped if not selected
+#[derive(Debug, Clone, Default)]
+pub struct Select {
+    columns: Vec<String>,
+}
+
+impl Select {
+    pub fn new<S: AsRef<str>>(columns: &[S]) -> Self {
+        let columns = columns.iter().map(|c| c.as_ref().to_owned()).collect();
+        Self { columns }
+    }
+}
+
+impl Transform for Select {
+    fn name(&self) -> &str {
+        "select"
+    }
+
+    fn sync_transform(&self, fabrix: Fabrix) -> FabrixResult<Fabrix> {
+        let mut res = fabrix.take_cols(&self.columns)?;
+        // the location of the index may have changed
+        res.index_tag = match fabrix.index_tag() {
+            Some(it) if self.columns.contains(&it.name) => {
+                Some(res.set_index_tag(it.name())?.clone())
+            }
+            _ => None,
+        };
+        Ok(res)
+    }
+}
+
+type ValuePredicate = Box<dyn Fn(&Value) -> bool + Send + Sync>;
+
+/// keep the rows whose value of `column` satisfies the predicate
+pub struct Filter {
+    column: String,
+    predicate: ValuePredicate,
+}
+
+impl Filter {
+    pub fn new<F>(column: &str, predicate: F) -> Self
+    where
+        F: Fn(&Value) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            column: column.to_owned(),
+            predicate: Box::new(predicate),
+        }
+    }
+}
+
+impl Transform for Filter {
+    fn name(&self) -> &str {
+        "filter"
+    }
+
+    fn sync_transform(&self, fabrix: Fabrix) -> FabrixResult<Fabrix> {
+        let indices = fabrix
+            .get_column(&self.column)?
+            .try_iter()?
+            .enumerate()
+            .filter_map(|(i, v)| (self.predicate)(&v).then_some(i))
+            .collect::<Vec<_>>();
+
+        Ok(fabrix.take_rows_by_idx(&indices)?)
+    }
+}
+
+type RowDeriver = Box<dyn Fn(&NamedRow) -> Value + Send + Sync>;
+
+/// append a new column computed from each row
+pub struct DeriveColumn {
+    column: String,
+    dtype: Option<ValueType>,
+    deriver: RowDeriver,
+}
+
+impl DeriveColumn {
+    pub fn new<F>(column: &str, deriver: F) -> Self
+    where
+        F: Fn(&NamedRow) -> Value + Send + Sync + 'static,
+    {
+        Self {
+            column: column.to_owned(),
+            dtype: None,
+            deriver: Box::new(deriver),
+        }
+    }
+
+    /// cast the derived column, otherwise its type is inferred from the derived values
+    pub fn with_dtype(&mut self, dtype: ValueType) -> &mut Self {
+        self.dtype = Some(dtype);
+        self
+    }
+}
+
+impl Transform for DeriveColumn {
+    fn name(&self) -> &str {
+        "derive column"
+    }
+
+    fn sync_transform(&self, mut fabrix: Fabrix) -> FabrixResult<Fabrix> {
+        let values = fabrix
+            .iter_named_rows()?
+            .map(|r| (self.deriver)(&r))
+            .collect::<Vec<_>>();
+        let mut series = Series::from_values(values, &self.column, true)?;
+        if let Some(dtype) = &self.dtype {
+            series = series.cast(dtype)?;
+        }
+
+        fabrix.hconcat_mut(vec![series])?;
+        Ok(fabrix)
+    }
+}
+
+/// replace nulls of a column with a value, the column keeps its type
+#[derive(Debug, Clone)]
+pub struct FillNull {
+    column: String,
+    value: Value,
+}
+
+impl FillNull {
+    pub fn new(column: &str, value: Value) -> Self {
+        Self {
+            column: column.to_owned(),
+            value,
+        }
+    }
+}
+
+impl Transform for FillNull {
+    fn name(&self) -> &str {
+        "fill null"
+    }
+
+    fn sync_transform(&self, mut fabrix: Fabrix) -> FabrixResult<Fabrix> {
+        let origin = fabrix.get_column(&self.column)?;
+        let dtype = origin.dtype()?.clone();
+        let values = origin
+            .iter()
+            .map(|v| match v {
+                Value::Null => self.value.clone(),
+                v => v,
+            })
+            .collect::<Vec<_>>();
+        let series = Series::from_values(values, &self.column, true)?.cast(&dtype)?;
+
+        fabrix.data.replace(&self.column, series.0)?;
+        Ok(fabrix)
+    }
+}
+
+type FabrixFn = Box<dyn Fn(Fabrix) -> FabrixResult<Fabrix> + Send + Sync>;
+
+/// a transform made of a closure
+pub struct FnTransform {
+    name: String,
+    f: FabrixFn,
+}
+
+impl FnTransform {
+    pub fn new<F>(name: &str, f: F) -> Self
+    where
+        F: Fn(Fabrix) -> FabrixResult<Fabrix> + Send + Sync + 'static,
+    {
+        Self {
+            name: name.to_owned(),
+            f: Box::new(f),
+        }
+    }
+}
+
+impl Transform for FnTransform {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn sync_transform(&self, fabrix: Fabrix) -> FabrixResult<Fabrix> {
+        (self.f)(fabrix)
+    }
+}
+
+// ================================================================================================
+// Pipeline
+// ================================================================================================
+
+/// reader -> transforms -> writer
+pub struct Pipeline<'a, Reader, Writer, RO, WO>
+where
+    Reader: FromSource<'a, RO>,
+    Writer: IntoSource<'a, WO>,
+    RO: ReadOptions,
+    WO: WriteOptions,
+{
+    reader: Reader,
+    writer: Writer,
+    transforms: Vec<Box<dyn Transform>>,
+    observer: Option<SharedObserver>,
+    error_handler: ErrorHandler,
+    audit: Option<Audit>,
+    run: AuditRun,
+    read_options: PhantomData<RO>,
+    write_options: PhantomData<WO>,
+    lifetime: PhantomData<&'a ()>,
+}
+
+impl<'a, R, W, RO, WO> Pipeline<'a, R, W, RO, WO>
+where
+    R: FromSource<'a, RO>,
+    W: IntoSource<'a, WO>,
+    RO: ReadOptions,
+    WO: WriteOptions,
+{
+    pub fn new(reader: R, writer: W) -> Self {
+        Self {
+            reader,
+            writer,
+            transforms: Vec::new(),
+            observer: None,
+            error_handler: ErrorHandler::default(),
+            audit: None,
+            run: AuditRun::default(),
+            read_options: PhantomData,
+            write_options: PhantomData,
+            lifetime: PhantomData,
+        }
+    }
+
+    pub fn with_transform<T: Transform + 'static>(&mut self, transform: T) -> &mut Self {
+        self.transforms.push(Box::new(transform));
+        self
+    }
+
+    pub fn with_boxed_transform(&mut self, transform: Box<dyn Transform>) -> &mut Self {
+        self.transforms.push(transform);
+        self
+    }
+
+    /// report each stage of `sync_run` & `async_run` to an observer, transforms by their names
+    pub fn with_observer(&mut self, observer: SharedObserver) -> &mut Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// skip or quarantine the rows failing a transform or the writer of `sync_run` & `async_run`.
+    /// A failed batch is bisected to isolate its bad rows (see `ErrorHandler::sync_apply`), which
+    /// assumes row-local transforms. A failed write is bisected only if the writer appends its rows
+    /// atomically (see `IntoSource::atomic_appends`), otherwise all rows of the batch are rejected
+    pub fn with_error_handler(&mut self, error_handler: ErrorHandler) -> &mut Self {
+        self.error_handler = error_handler;
+        self
+    }
+
+    /// append an audit record of each `sync_run` & `async_run` to a store
+    pub fn with_audit(&mut self, audit: Audit) -> &mut Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// the record of the last audited run
+    pub fn audit_record(&self) -> Option<&AuditRecord> {
+        self.run.finished()
+    }
+
+    pub fn transforms(&self) -> &[Box<dyn Transform>] {
+        &self.transforms
+    }
+
+    pub fn reader_type(&self) -> &str {
+        self.reader.source_type()
+    }
+
+    pub fn writer_type(&self) -> &str {
+        self.writer.source_type()
+    }
+
+    /// expose reader as reference to the outside
+    pub fn reader(&self) -> &R {
+        &self.reader
+    }
+
+    /// expose reader as mutable to the outside
+    pub fn reader_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// expose writer as reference to the outside
+    pub fn writer(&self) -> &W {
+        &self.writer
+    }
+
+    /// expose writer as mutable to the outside
+    pub fn writer_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// apply the transforms without reading or writing
+    pub fn sync_transform(&self, fabrix: Fabrix) -> FabrixResult<Fabrix> {
+        sync_transform_all(&self.transforms, fabrix)
+    }
+
+    /// apply the transforms without reading or writing, asynchronously
+    pub async fn async_transform(&self, fabrix: Fabrix) -> FabrixResult<Fabrix> {
+        async_transform_all(&self.transforms, fabrix).await
+    }
+
+    fn span(&self, stage: Stage, source: &str) -> StageSpan {
+        StageSpan::start(self.observer.as_ref(), stage, source)
+    }
+
+    fn start_run(&self, read_options: &RO, write_options: &WO) -> AuditRun {
+        AuditRun::start(
+            self.audit.as_ref(),
+            self.reader.endpoint(read_options),
+            self.writer.endpoint(write_options),
+        )
+    }
+
+    pub fn sync_run(&mut self, read_options: &'a RO, write_options: &'a WO) -> FabrixResult<()> {
+        let mut run = self.start_run(read_options, write_options);
+        let res = self.sync_run_audited(read_options, write_options, &mut run);
+        let appended = run.sync_finish(&res);
+        self.run = run;
+        res.and(appended)
+    }
+
+    fn sync_run_audited(
+        &mut self,
+        read_options: &'a RO,
+        write_options: &'a WO,
+        run: &mut AuditRun,
+    ) -> FabrixResult<()> {
+        let started = Instant::now();
+        let span = self.span(Stage::Read, self.reader_type());
+        let mut fx = span.finish_fabrix(self.reader.sync_read(read_options))?;
+        run.read(&fx, started)?;
+
+        for t in self.transforms.iter() {
+            let span = self.span(Stage::Transform, t.name());
+            let res = self
+                .error_handler
+                .sync_apply_fabrix(Stage::Transform, t.name(), fx, |fx| t.sync_transform(fx));
+            fx = match finish_optional(span, res)? {
+                Some(fx) => fx,
+                // all rows are rejected
+                None => return Ok(()),
+            };
+        }
+
+        let started = Instant::now();
+        let span = self.span(Stage::Write, self.writer_type());
+        let source = self.writer_type().to_owned();
+        let bytes = fx.estimated_size();
+        let writer = &mut self.writer;
+        let res = match writer.atomic_appends(write_options) {
+            true => self
+                .error_handler
+                .sync_apply(Stage::Write, &source, fx, |fx| {
+                    let rows = fx.height();
+                    writer.sync_write(fx, write_options).map(|_| rows)
+                })
+                .map(|written| written.into_iter().sum::<usize>()),
+            false => self
+                .error_handler
+                .sync_apply_batch(Stage::Write, &source, fx, |fx| {
+                    let rows = fx.height();
+                    writer.sync_write(fx, write_options).map(|_| rows)
+                })
+                .map(|written| written.unwrap_or(0)),
+        };
+        run.written(finish_written(span, res, bytes)?, started);
+        Ok(())
+    }
+
+    pub async fn async_run(
+        &mut self,
+        read_options: &'a RO,
+        write_options: &'a WO,
+    ) -> FabrixResult<()> {
+        let mut run = self.start_run(read_options, write_options);
+        let res = self
+            .async_run_audited(read_options, write_options, &mut run)
+            .await;
+        let appended = run.async_finish(&res).await;
+        self.run = run;
+        res.and(appended)
+    }
+
+    async fn async_run_audited(
+        &mut self,
+        read_options: &'a RO,
+        write_options: &'a WO,
+        run: &mut AuditRun,
+    ) -> FabrixResult<()> {
+        let started = Instant::now();
+        let span = self.span(Stage::Read, self.reader_type());
+        let mut fx = span.finish_fabrix(self.reader.async_read(read_options).await)?;
+        run.read(&fx, started)?;
+
+        for t in self.transforms.iter() {
+            let span = self.span(Stage::Transform, t.name());
+            let res = async_apply_transform(&self.error_handler, t.as_ref(), fx).await;
+            fx = match finish_optional(span, res)? {
+                Some(fx) => fx,
+                None => return Ok(()),
+            };
+        }
+
+        let started = Instant::now();
+        let span = self.span(Stage::Write, self.writer_type());
+        let bytes = fx.estimated_size();
+        let res = async_write_rows(&self.error_handler, &mut self.writer, fx, write_options).await;
+        run.written(finish_written(span, res, bytes)?, started);
+        Ok(())
+    }
+}
+
+/// finish a span of a stage which may reject all rows
+fn finish_optional(
+    span: StageSpan,
+    res: FabrixResult<Option<Fabrix>>,
+) -> FabrixResult<Option<Fabrix>> {
+    let (rows, bytes) = match &res {
+        Ok(Some(fx)) => (fx.height(), fx.estimated_size()),
+        _ => (0, 0),
+    };
+    span.finish_rows(res, rows, bytes)
+}
+
+/// finish a span of writing, `bytes` is the estimated size of the data before rejecting any row.
+/// Returns the written rows
+fn finish_written(span: StageSpan, res: FabrixResult<usize>, bytes: usize) -> FabrixResult<usize> {
+    let rows = *res.as_ref().unwrap_or(&0);
+    span.finish_rows(res, rows, bytes)
+}
+
+/// the async counterpart of `ErrorHandler::sync_apply_fabrix` for a transform
+async fn async_apply_transform(
+    handler: &ErrorHandler,
+    transform: &dyn Transform,
+    fabrix: Fabrix,
+) -> FabrixResult<Option<Fabrix>> {
+    if handler.policy() == BadRecordPolicy::Fail {
+        return Ok(Some(transform.async_transform(fabrix).await?));
+    }
+
+    let mut res = Vec::new();
+    let mut pending = vec![(0, fabrix)];
+    while let Some((offset, part)) = pending.pop() {
+        let error = match transform.async_transform(part.clone()).await {
+            Ok(fx) => {
+                res.push(fx);
+                continue;
+            }
+            Err(e) => e,
+        };
+        match bisect(&part) {
+            Some((left, right)) => {
+                pending.push((offset + left.height(), right));
+                pending.push((offset, left));
+            }
+            None => handler.reject(
+                Stage::Transform,
+                transform.name(),
+                handler.row_location(offset),
+                first_named_row(&part),
+                error,
+            )?,
+        }
+    }
+
+    vconcat_all(res)
+}
+
+/// the async counterpart of `ErrorHandler::sync_apply` for a writer appending its rows atomically,
+/// or else of `ErrorHandler::sync_apply_batch`. Returns the written rows
+async fn async_write_rows<'a, W, WO>(
+    handler: &ErrorHandler,
+    writer: &mut W,
+    fabrix: Fabrix,
+    options: &'a WO,
+) -> FabrixResult<usize>
+where
+    W: IntoSource<'a, WO>,
+    WO: WriteOptions,
+{
+    let rows = fabrix.height();
+    if handler.policy() == BadRecordPolicy::Fail {
+        return writer.async_write(fabrix, options).await.map(|_| rows);
+    }
+
+    let source = writer.source_type().to_owned();
+    if !writer.atomic_appends(options) {
+        return match writer.async_write(fabrix.clone(), options).await {
+            Ok(_) => Ok(rows),
+            Err(e) => handler
+                .reject_rows(Stage::Write, &source, &fabrix, e)
+                .map(|_| 0),
+        };
+    }
+
+    let mut written = 0;
+    let mut pending = vec![(0, fabrix)];
+    while let Some((offset, part)) = pending.pop() {
+        let error = match writer.async_write(part.clone(), options).await {
+            Ok(_) => {
+                written += part.height();
+                continue;
+            }
+            Err(e) => e,
+        };
+        match bisect(&part) {
+            Some((left, right)) => {
+                pending.push((offset + left.height(), right));
+                pending.push((offset, left));
+            }
+            None => handler.reject(
+                Stage::Write,
+                &source,
+                handler.row_location(offset),
+                first_named_row(&part),
+                error,
+            )?,
+        }
+    }
+
+    Ok(written)
+}
+
+#[cfg(test)]
+mod transform_tests {
+    use super::*;
+    use crate::{fx, value, CsvReadOptions, CsvReader, CsvWriteOptions, CsvWriter};
+
+    #[test]
+    fn test_transforms() {
+        let fx = fx![
+            "ord";
+            "ord" => [1, 2, 3, 4],
+            "name" => [Some("a"), None, Some("c"), Some("d")],
+            "val" => [1.5, 2.5, 3.5, 4.5],
+        ]
+        .unwrap();
+
+        let transforms: Vec<Box<dyn Transform>> = vec![
+            Box::new(Rename::new(&[("ord", "id")])),
+            Box::new(FillNull::new("name", value!("none"))),
+            Box::new(Filter::new(
+                "val",
+                |v| matches!(v, Value::F64(v) if *v > 2.0),
+            )),
+            Box::new(DeriveColumn::new("double", |r| match &r.data()[2].1 {
+                Value::F64(v) => value!(v * 2.0),
+                _ => Value::Null,
+            })),
+            Box::new(Select::new(&["id", "name", "double"])),
+        ];
+
+        let res = sync_transform_all(&transforms, fx).unwrap();
+        let expected = fx![
+            "id";
+            "id" => [2, 3, 4],
+            "name" => ["none", "c", "d"],
+            "double" => [5.0, 7.0, 9.0],
+        ]
+        .unwrap();
+        crate::assert_fabrix_eq!(res, expected);
+
+        let mut cast = Cast::default();
+        cast.with_cast("id", ValueType::I64);
+        let res = cast.sync_transform(res).unwrap();
+        assert_eq!(res.index_tag().unwrap().data_type(), &ValueType::I64);
+
+        let res = Select::new(&["name"]).sync_transform(res).unwrap();
+        assert!(res.index_tag().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_pipeline() {
+        let reader = CsvReader::new(std::fs::File::open("../mock/test.csv").unwrap());
+        let mut buff = std::io::Cursor::new(Vec::<u8>::new());
+        let writer = CsvWriter::new(&mut buff);
+
+        let mut pipeline = Pipeline::new(reader, writer);
+        pipeline
+            .with_transform(Select::new(&["id", "gender"]))
+            .with_transform(Filter::new("id", |v| matches!(v, Value::I64(v) if *v <= 3)))
+            .with_transform(FnTransform::new("upper", |mut fx| {
+                fx.rename("gender", "GENDER")?;
+                Ok(fx)
+            }));
+        assert_eq!(pipeline.transforms().len(), 3);
+
+        let metrics = std::sync::Arc::new(crate::Metrics::new());
+        pipeline.with_observer(metrics.clone());
+        let store = crate::FileAuditStore::new("../cache/audit/test_pipeline.jsonl").unwrap();
+        pipeline.with_audit(crate::Audit::new(std::sync::Arc::new(store)));
+
+        let res = pipeline
+            .async_run(&CsvReadOptions::default(), &CsvWriteOptions::default())
+            .await;
+        assert!(res.is_ok());
+
+        let record = pipeline.audit_record().unwrap();
+        assert_eq!(record.outcome, crate::RunOutcome::Success);
+        assert_eq!((record.rows_read, record.rows_written), (100, 3));
+        assert_eq!(record.source.source_type, "csv");
+
+        assert_eq!(metrics.get(Stage::Read, "csv").rows, 100);
+        assert_eq!(metrics.get(Stage::Transform, "filter").rows, 3);
+        assert_eq!(metrics.get(Stage::Transform, "upper").runs, 1);
+        assert_eq!(metrics.get(Stage::Write, "csv").rows, 3);
+
+        let written = String::from_utf8(buff.into_inner()).unwrap();
+        assert_eq!(
+            written,
+            "id,GENDER\n1,Polygender\n2,Agender\n3,Polygender\n"
+        );
+    }
+
+    #[test]
+    fn test_pipeline_quarantine() {
+        let reader = CsvReader::new(std::fs::File::open("../mock/test.csv").unwrap());
+        let mut buff = std::io::Cursor::new(Vec::<u8>::new());
+        let writer = CsvWriter::new(&mut buff);
+
+        let dead_letter = std::sync::Arc::new(crate::DeadLetter::new());
+        let mut pipeline = Pipeline::new(reader, writer);
+        pipeline
+            .with_transform(Select::new(&["id", "gender"]))
+            .with_transform(Filter::new("id", |v| matches!(v, Value::I64(v) if *v <= 3)))
+            .with_transform(FnTransform::new("reject 2", |fx| {
+                match fx.get_column("id")?.try_iter()?.any(|v| v == value!(2i64)) {
+                    true => Err(crate::FabrixError::new_uncategorized("id 2 is rejected")),
+                    false => Ok(fx),
+                }
+            }))
+            .with_error_handler(crate::ErrorHandler::quarantine(dead_letter.clone()));
+
+        let res = pipeline.sync_run(&CsvReadOptions::default(), &CsvWriteOptions::default());
+        assert!(res.is_ok());
+
+        let records = dead_letter.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].stage, Stage::Transform);
+        assert_eq!(records[0].source, "reject 2");
+        assert_eq!(records[0].location.line, Some(2));
+        assert!(records[0].error.contains("id 2 is rejected"));
+
+        let written = String::from_utf8(buff.into_inner()).unwrap();
+        assert_eq!(written, "id,gender\n1,Polygender\n3,Polygender\n");
+    }
+
+    /// fails on batches of more than one row
+    #[derive(Default)]
+    struct OneRowWrite {
+        appends: bool,
+        writes: usize,
+    }
+
+    #[async_trait]
+    impl<'a> IntoSource<'a, CsvWriteOptions> for OneRowWrite {
+        async fn async_write<'o>(
+            &mut self,
+            fabrix: Fabrix,
+            options: &'o CsvWriteOptions,
+        ) -> FabrixResult<()>
+        where
+            'o: 'a,
+        {
+            self.sync_write(fabrix, options)
+        }
+
+        fn sync_write<'o>(
+            &mut self,
+            fabrix: Fabrix,
+            _options: &'o CsvWriteOptions,
+        ) -> FabrixResult<()>
+        where
+            'o: 'a,
+        {
+            self.writes += 1;
+            match fabrix.height() {
+                0 | 1 => Ok(()),
+                _ => Err(crate::FabrixError::new_uncategorized("one row at a time")),
+            }
+        }
+
+        fn atomic_appends(&self, _options: &CsvWriteOptions) -> bool {
+            self.appends
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_write_fallback() {
+        // a writer which may have partly written a failed batch rejects the whole batch
+        let dead_letter = std::sync::Arc::new(crate::DeadLetter::new());
+        let reader = CsvReader::new(std::fs::File::open("../mock/test.csv").unwrap());
+        let mut pipeline = Pipeline::new(reader, OneRowWrite::default());
+        pipeline.with_error_handler(crate::ErrorHandler::quarantine(dead_letter.clone()));
+        let res = pipeline
+            .async_run(&CsvReadOptions::default(), &CsvWriteOptions::default())
+            .await;
+        assert!(res.is_ok());
+        assert_eq!(pipeline.writer().writes, 1);
+        assert_eq!(dead_letter.len(), 100);
+
+        // a writer appending its rows atomically bisects the failed batch down to single rows
+        let reader = CsvReader::new(std::fs::File::open("../mock/test.csv").unwrap());
+        let writer = OneRowWrite {
+            appends: true,
+            writes: 0,
+        };
+        let mut pipeline = Pipeline::new(reader, writer);
+        pipeline.with_error_handler(crate::ErrorHandler::new(crate::BadRecordPolicy::Skip));
+        let res = pipeline.sync_run(&CsvReadOptions::default(), &CsvWriteOptions::default());
+        assert!(res.is_ok());
+        assert_eq!(pipeline.writer().writes, 199);
+    }
+}
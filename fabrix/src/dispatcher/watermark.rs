@@ -0,0 +1,229 @@
+//! Watermark
+//!
+//! High-water mark of incremental loads:
+//! - Watermark: the greatest value of a watermark column loaded so far, e.g. `updated_at` or a
+//!   monotonically increasing id
+//! - WatermarkStore: where watermarks are kept between runs, e.g. `FileWatermarkStore`
+//!
+//! A run loads the watermark, reads the rows after it (see `sql::Reader::with_watermark`), writes
+//! them, e.g. with `SaveStrategy::Upsert`, and saves the advanced watermark at last, so that a
+//! failed write is read again by the next run.
+
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use super::checkpoint::{file_stem, read_json, remove_file, write_json};
+use crate::{Fabrix, FabrixResult, Series, Value};
+
+// ================================================================================================
+// Watermark & WatermarkStore
+// ================================================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Watermark {
+    /// name of the incremental load, the key of its store
+    pub name: String,
+    pub column: String,
+    /// the greatest value loaded so far, `None` before the first load. Saved with its type
+    #[serde(with = "typed_value")]
+    pub value: Option<Value>,
+}
+
+impl Watermark {
+    pub fn new(name: &str, column: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            column: column.to_owned(),
+            value: None,
+        }
+    }
+
+    /// move to the greatest non-null value of the watermark column; returns whether it has moved
+    pub fn advance(&mut self, fabrix: &Fabrix) -> FabrixResult<bool> {
+        let sorted = fabrix
+            .get_column(&self.column)?
+            .data()
+            .drop_nulls()
+            .sort(true);
+        if sorted.is_empty() {
+            return Ok(false);
+        }
+
+        let max = Series::from(sorted).get(0)?;
+        let moved = self.value.as_ref() != Some(&max);
+        self.value = Some(max);
+
+        Ok(moved)
+    }
+
+    /// `column > value`, `None` before the first load. Strict, so that a row committed later with
+    /// the same value as the watermark is skipped
+    #[cfg(feature = "sql")]
+    pub fn filter(&self) -> Option<fabrix_sql::sql_adt::Expressions> {
+        use fabrix_sql::sql_adt::{Condition, Equation};
+
+        self.value.as_ref().map(|v| {
+            fabrix_sql::xpr!([Condition {
+                column: self.column.as_str().into(),
+                equation: Equation::Greater(v.clone()),
+            }])
+        })
+    }
+
+    /// `filter` and `column > value`
+    #[cfg(feature = "sql")]
+    pub fn filter_with(
+        &self,
+        filter: Option<&fabrix_sql::sql_adt::Expressions>,
+    ) -> Option<fabrix_sql::sql_adt::Expressions> {
+        match (filter, self.filter()) {
+            (Some(f), Some(w)) => Some(fabrix_sql::xpr!([f.clone(), fabrix_sql::xpr_and!(), w])),
+            (f, w) => w.or_else(|| f.cloned()),
+        }
+    }
+}
+
+/// `Value` is untagged, e.g. a `DateTime` would be reloaded as an `U64`, so that a watermark value
+/// is saved along with its `ValueType`
+mod typed_value {
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+    use serde_json::{from_value, Value as JsonValue};
+
+    use crate::{Value, ValueType};
+
+    #[derive(Serialize)]
+    struct TypedRef<'a> {
+        dtype: ValueType,
+        value: &'a Value,
+    }
+
+    #[derive(Deserialize)]
+    struct Typed {
+        dtype: ValueType,
+        value: JsonValue,
+    }
+
+    pub fn serialize<S: Serializer>(value: &Option<Value>, s: S) -> Result<S::Ok, S::Error> {
+        value
+            .as_ref()
+            .map(|v| TypedRef {
+                dtype: ValueType::from(v),
+                value: v,
+            })
+            .serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Value>, D::Error> {
+        Option::<Typed>::deserialize(d)?
+            .map(|t| typed(&t.dtype, t.value).map_err(de::Error::custom))
+            .transpose()
+    }
+
+    fn typed(dtype: &ValueType, v: JsonValue) -> serde_json::Result<Value> {
+        let res = match dtype {
+            ValueType::Bool => Value::Bool(from_value(v)?),
+            ValueType::U8 => Value::U8(from_value(v)?),
+            ValueType::U16 => Value::U16(from_value(v)?),
+            ValueType::U32 => Value::U32(from_value(v)?),
+            ValueType::U64 => Value::U64(from_value(v)?),
+            ValueType::I8 => Value::I8(from_value(v)?),
+            ValueType::I16 => Value::I16(from_value(v)?),
+            ValueType::I32 => Value::I32(from_value(v)?),
+            ValueType::I64 => Value::I64(from_value(v)?),
+            ValueType::F32 => Value::F32(from_value(v)?),
+            ValueType::F64 => Value::F64(from_value(v)?),
+            ValueType::Date => Value::Date(from_value(v)?),
+            ValueType::Time => Value::Time(from_value(v)?),
+            ValueType::DateTime => Value::DateTime(from_value(v)?),
+            ValueType::String => Value::String(from_value(v)?),
+            dtype => from_value::<Value>(v)?.force_cast(dtype),
+        };
+        Ok(res)
+    }
+}
+
+/// persistence of watermarks, keyed by name
+#[async_trait]
+pub trait WatermarkStore: Send + Sync {
+    async fn load(&self, name: &str) -> FabrixResult<Option<Watermark>>;
+
+    async fn save(&self, watermark: &Watermark) -> FabrixResult<()>;
+
+    /// remove a watermark, the next load is a full one
+    async fn clear(&self, name: &str) -> FabrixResult<()>;
+}
+
+// ================================================================================================
+// FileWatermarkStore
+// ================================================================================================
+
+/// watermarks saved as `{dir}/{name}.watermark.json`, the name escaped like a checkpoint's run id
+#[derive(Debug, Clone)]
+pub struct FileWatermarkStore {
+    dir: PathBuf,
+}
+
+impl FileWatermarkStore {
+    /// the directory is created if not exists
+    pub fn new<P: Into<PathBuf>>(dir: P) -> FabrixResult<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.watermark.json", file_stem(name)))
+    }
+}
+
+#[async_trait]
+impl WatermarkStore for FileWatermarkStore {
+    async fn load(&self, name: &str) -> FabrixResult<Option<Watermark>> {
+        read_json(&self.path(name))
+    }
+
+    async fn save(&self, watermark: &Watermark) -> FabrixResult<()> {
+        write_json(&self.path(&watermark.name), watermark)
+    }
+
+    async fn clear(&self, name: &str) -> FabrixResult<()> {
+        remove_file(&self.path(name))
+    }
+}
+
+#[cfg(test)]
+mod watermark_tests {
+    use super::*;
+    use crate::{datetime, fx, value};
+
+    #[tokio::test]
+    async fn test_watermark() {
+        let mut wm = Watermark::new("orders", "id");
+        let batch = fx!["id" => [Some(3), None, Some(7), Some(5)]].unwrap();
+        assert!(wm.advance(&batch).unwrap());
+        assert_eq!(wm.value, Some(value!(7)));
+        assert!(!wm.advance(&batch).unwrap());
+
+        let store = FileWatermarkStore::new("../cache/watermark").unwrap();
+        store.clear("orders").await.unwrap();
+        assert!(store.load("orders").await.unwrap().is_none());
+        store.save(&wm).await.unwrap();
+
+        let loaded = store.load("orders").await.unwrap().unwrap();
+        assert_eq!(loaded, wm);
+
+        // temporal values keep their type through a reload
+        let mut wm = Watermark::new("orders", "updated_at");
+        let batch =
+            fx!["updated_at" => [datetime!(2022, 1, 1, 9, 0, 0), datetime!(2022, 1, 2, 9, 0, 0)]]
+                .unwrap();
+        wm.advance(&batch).unwrap();
+        assert!(matches!(wm.value, Some(Value::DateTime(_))));
+        store.save(&wm).await.unwrap();
+        assert_eq!(store.load("orders").await.unwrap().unwrap(), wm);
+
+        store.clear("orders").await.unwrap();
+    }
+}
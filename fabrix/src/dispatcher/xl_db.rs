@@ -8,12 +8,16 @@ use std::str::FromStr;
 use std::sync::Arc;
 
 use fabrix_core::{value, D2Value, Fabrix, Series, Value};
-use fabrix_sql::{sql_adt, DatabaseType, SqlEngine, SqlExecutor, SqlMeta};
-use fabrix_xl::{ExcelValue, XlCell, XlConsumer, XlExecutor};
+use fabrix_sql::{sql_adt, xpr, DatabaseType, SqlEngine, SqlExecutor, SqlHelper, SqlMeta};
+use fabrix_xl::{ExcelValue, XlCell, XlConsumer, XlExecutor, XL_SOURCE_TYPE};
 use itertools::Itertools;
 use tokio::sync::Mutex;
 
-use crate::{FabrixError, FabrixResult};
+use crate::dispatcher::{bisect, first_named_row};
+use crate::{
+    batch_key, BadRecordPolicy, Checkpoint, CheckpointStore, ColumnMapping, ErrorHandler,
+    FabrixError, FabrixResult, QualityGate, SharedObserver, Stage, StageSpan, Transform,
+};
 
 pub type XlDbExecutor<R, T> = XlExecutor<SqlExecutor<T>, XlDbConvertor, R>;
 
@@ -24,9 +28,15 @@ pub type XlDbExecutor<R, T> = XlExecutor<SqlExecutor<T>, XlDbConvertor, R>;
 /// A convertor's method may be called several times, but only row-wise data's field
 /// will be cached. this is because column-wise data should be treated as a whole
 /// chunk of data (DataFrame) to be consumed.
+/// With a `ColumnMapping`, each converted DataFrame is mapped onto the table's columns by name.
+/// With an `ErrorHandler`, row-wise rows which cannot be converted are skipped or quarantined.
 #[derive(Debug, Default)]
 pub struct XlDbConvertor {
     pub fields: Option<Vec<String>>,
+    mapping: Option<ColumnMapping>,
+    error_handler: ErrorHandler,
+    /// rows of the sheet converted so far, the header row included
+    lines: usize,
 }
 
 impl XlDbConvertor {
@@ -35,9 +45,51 @@ impl XlDbConvertor {
         Self::default()
     }
 
+    /// map the converted columns, e.g. business headers -> snake_case column names
+    pub fn with_mapping(&mut self, mapping: ColumnMapping) -> &mut Self {
+        self.mapping = Some(mapping);
+        self
+    }
+
+    pub fn mapping(&self) -> Option<&ColumnMapping> {
+        self.mapping.as_ref()
+    }
+
+    /// skip or quarantine the row-wise rows of `sheet_name` which cannot be converted
+    pub fn with_error_handler(
+        &mut self,
+        mut error_handler: ErrorHandler,
+        sheet_name: &str,
+    ) -> &mut Self {
+        let mut location = error_handler.location().clone();
+        location.with_sheet(sheet_name);
+        error_handler.with_location(location);
+        self.error_handler = error_handler;
+        self
+    }
+
+    /// apply the mapping if any
+    fn map(&self, fabrix: Fabrix) -> FabrixResult<Fabrix> {
+        match &self.mapping {
+            Some(m) => m.sync_transform(fabrix),
+            None => Ok(fabrix),
+        }
+    }
+
     /// clean fields
     pub fn clean_stats(&mut self) {
         self.fields = None;
+        self.lines = 0;
+    }
+
+    /// reject the rows which cannot be converted, lines are counted over the batches
+    fn reject_bad_rows(&mut self, data: D2Value) -> FabrixResult<D2Value> {
+        let first_line = self.lines + 1;
+        self.lines += data.len();
+        let fields = self.fields.as_deref().unwrap_or_default();
+
+        self.error_handler
+            .reject_row_values(Stage::Read, XL_SOURCE_TYPE, fields, data, first_line)
     }
 
     /// set fields, only works for row-wise data
@@ -52,6 +104,7 @@ impl XlDbConvertor {
                 return Ok(());
             }
             // the first row is the fields, remove it
+            self.lines += 1;
             let fld = data
                 .remove(0)
                 .iter_mut()
@@ -98,9 +151,10 @@ impl XlDbConvertor {
         match index_col.into() {
             XlIndexSelection::Num(num) => {
                 self.set_row_wise_fields(&mut data, Some(num))?;
+                let data = self.reject_bad_rows(data)?;
                 let mut df = Fabrix::from_row_values(data, Some(num), false)?;
                 df.set_column_names(self.fields.as_ref().unwrap())?;
-                Ok(df)
+                self.map(df)
             }
             XlIndexSelection::Name(name) => {
                 let idx = self
@@ -113,15 +167,17 @@ impl XlDbConvertor {
                         FabrixError::NotFound(format!("index name: {name} not found"))
                     })?;
                 self.set_row_wise_fields(&mut data, Some(idx))?;
+                let data = self.reject_bad_rows(data)?;
                 let mut df = Fabrix::from_row_values(data, Some(idx), false)?;
                 df.set_column_names(self.fields.as_ref().unwrap())?;
-                Ok(df)
+                self.map(df)
             }
             XlIndexSelection::None => {
                 self.set_row_wise_fields(&mut data, None)?;
+                let data = self.reject_bad_rows(data)?;
                 let mut df = Fabrix::from_row_values(data, None, false)?;
                 df.set_column_names(self.fields.as_ref().unwrap())?;
-                Ok(df)
+                self.map(df)
             }
         }
     }
@@ -144,7 +200,7 @@ impl XlDbConvertor {
                     )));
                 }
 
-                Ok(Fabrix::from_series(collection, num)?)
+                self.map(Fabrix::from_series(collection, num)?)
             }
             XlIndexSelection::Name(name) => {
                 let idx = collection
@@ -154,9 +210,9 @@ impl XlDbConvertor {
                         FabrixError::NotFound(format!("index name: {name} not found"))
                     })?;
 
-                Ok(Fabrix::from_series(collection, idx)?)
+                self.map(Fabrix::from_series(collection, idx)?)
             }
-            XlIndexSelection::None => Ok(Fabrix::from_series_no_index(collection)?),
+            XlIndexSelection::None => self.map(Fabrix::from_series_no_index(collection)?),
         }
     }
 }
@@ -189,12 +245,33 @@ impl<'a> From<()> for XlIndexSelection<'a> {
 /// XlToDbConsumer
 ///
 /// Used for consuming DataFrame and interacts with database, for instance, inserting or updating data.
+///
+/// With a checkpoint store, each committed batch is recorded, and `resume` skips the batches which
+/// have been committed by a previous run of the same run id. With an idempotency column, appended
+/// rows are tagged by their batch key, and rows of the same key are deleted before appending, so that
+/// a batch committed into the database but not yet into the checkpoint is never duplicated. Batch
+/// keys are made of the run id, so that an idempotency column requires a checkpoint.
+///
+/// With an error handler, a batch failing to be saved is bisected and its halves are saved again,
+/// and the single rows which still fail, e.g. by a constraint, are skipped or quarantined.
+///
+/// With a quality gate, each batch is checked before it is saved, and a failed `Fail` check stops
+/// the load.
+///
+/// With an observer, each saved batch is reported as a write of its saved rows & estimated bytes.
 pub struct XlToDbConsumer<T>
 where
     T: DatabaseType,
 {
     pub executor: SqlExecutor<T>,
     pub consume_count: usize,
+    /// offset of the next incoming batch
+    pub batch_offset: usize,
+    checkpoint: Option<(Checkpoint, Arc<dyn CheckpointStore>)>,
+    idempotency_column: Option<String>,
+    error_handler: ErrorHandler,
+    quality_gate: Option<QualityGate>,
+    observer: Option<SharedObserver>,
 }
 
 impl<T> XlToDbConsumer<T>
@@ -207,50 +284,215 @@ where
         Ok(Self {
             executor,
             consume_count: 0,
+            batch_offset: 0,
+            checkpoint: None,
+            idempotency_column: None,
+            error_handler: ErrorHandler::default(),
+            quality_gate: None,
+            observer: None,
         })
     }
 
+    /// record committed batches of the run `run_id` in a checkpoint store
+    pub fn with_checkpoint<S>(&mut self, run_id: &str, store: S) -> &mut Self
+    where
+        S: CheckpointStore + 'static,
+    {
+        self.checkpoint = Some((Checkpoint::new(run_id), Arc::new(store)));
+        self
+    }
+
+    /// tag appended rows by their batch key in `column`. Requires `with_checkpoint`, whose run id
+    /// keeps the keys of different runs apart, or saving fails
+    pub fn with_idempotency_column(&mut self, column: &str) -> &mut Self {
+        self.idempotency_column = Some(column.to_owned());
+        self
+    }
+
+    /// skip or quarantine the rows failing to be saved
+    pub fn with_error_handler(&mut self, error_handler: ErrorHandler) -> &mut Self {
+        self.error_handler = error_handler;
+        self
+    }
+
+    /// check each batch before saving it
+    pub fn with_quality_gate(&mut self, quality_gate: QualityGate) -> &mut Self {
+        self.quality_gate = Some(quality_gate);
+        self
+    }
+
+    /// report progress of saving to an observer, batch by batch
+    pub fn with_observer(&mut self, observer: SharedObserver) -> &mut Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    pub fn checkpoint(&self) -> Option<&Checkpoint> {
+        self.checkpoint.as_ref().map(|(cp, _)| cp)
+    }
+
+    /// load the checkpoint of the run, batches before its offset are skipped; returns the offset
+    pub async fn resume(&mut self) -> FabrixResult<usize> {
+        let (cp, store) = self
+            .checkpoint
+            .as_mut()
+            .ok_or(FabrixError::NotSet("checkpoint"))?;
+        if let Some(loaded) = store.load(&cp.run_id).await? {
+            *cp = loaded;
+        }
+        // committed batches count as consumed, so that the following ones are appended
+        self.consume_count = cp.offset;
+        self.batch_offset = 0;
+
+        Ok(cp.offset)
+    }
+
+    /// remove the checkpoint of a finished run
+    pub async fn clear_checkpoint(&mut self) -> FabrixResult<()> {
+        if let Some((cp, store)) = self.checkpoint.as_mut() {
+            store.clear(&cp.run_id).await?;
+            *cp = Checkpoint::new(&cp.run_id);
+        }
+        Ok(())
+    }
+
     pub fn clean_stats(&mut self) {
         self.consume_count = 0;
+        self.batch_offset = 0;
     }
 
-    /// create a table if not exists
-    pub async fn create_new_table(&mut self, table_name: &str, data: Fabrix) -> FabrixResult<()> {
-        let exc = match self.consume_count {
-            0 => {
-                self.executor
-                    .save(table_name, data, &sql_adt::SaveStrategy::FailIfExists)
-                    .await
-            }
-            _ => {
-                self.executor
-                    .save(table_name, data, &sql_adt::SaveStrategy::Append)
-                    .await
+    /// save a batch, unless it has been committed; records the checkpoint afterwards
+    async fn consume(
+        &mut self,
+        table_name: &str,
+        mut data: Fabrix,
+        strategy: sql_adt::SaveStrategy,
+    ) -> FabrixResult<()> {
+        let offset = self.batch_offset;
+        self.batch_offset += 1;
+
+        if let Some((cp, _)) = self.checkpoint.as_ref() {
+            if cp.is_committed(offset) {
+                return Ok(());
             }
-        };
+        }
+
+        if let Some(gate) = self.quality_gate.as_ref() {
+            gate.check(&data)?;
+        }
+
+        if let Some(column) = self.idempotency_column.as_deref() {
+            // without a run id, batches of another run with the same offset would be deleted
+            let run_id = self
+                .checkpoint()
+                .map(|cp| cp.run_id.as_str())
+                .ok_or(FabrixError::NotSet("checkpoint of an idempotency column"))?;
+            let key = batch_key(run_id, offset);
 
-        match exc {
-            Ok(_) => {
-                self.consume_count += 1;
-                Ok(())
+            if strategy == sql_adt::SaveStrategy::Append
+                && self.executor.get_table_exists(table_name).await
+            {
+                let mut delete = sql_adt::Delete::new(table_name.to_owned());
+                delete.filter(&xpr!([xpr!(column, "=", key.as_str())]));
+                self.executor.delete(&delete).await?;
             }
-            Err(e) => Err(e.into()),
+
+            let keys = vec![Value::String(key); data.height()];
+            data.hconcat_mut(vec![Series::from_values(keys, column, false)?])?;
+        }
+
+        let source = self.executor.get_driver().to_string();
+        let span = StageSpan::start(self.observer.as_ref(), Stage::Write, &source);
+        let bytes = data.estimated_size();
+        let res = self.save(table_name, data, strategy).await;
+        if let Ok(rows) = &res {
+            span.batch(*rows, bytes);
+        }
+        let rows = span.finish(res)?;
+        self.consume_count += 1;
+
+        if let Some((cp, store)) = self.checkpoint.as_mut() {
+            cp.commit(offset, rows);
+            store.save(cp).await?;
         }
+
+        Ok(())
     }
 
-    pub async fn append_table(&mut self, table_name: &str, data: Fabrix) -> FabrixResult<()> {
-        let exc = self
+    /// save a batch, or its good rows if the error policy is not `Fail`; returns the saved rows
+    async fn save(
+        &self,
+        table_name: &str,
+        data: Fabrix,
+        strategy: sql_adt::SaveStrategy,
+    ) -> FabrixResult<usize> {
+        let rows = data.height();
+        if self.error_handler.policy() == BadRecordPolicy::Fail {
+            self.executor.save(table_name, data, &strategy).await?;
+            return Ok(rows);
+        }
+        if self
             .executor
-            .save(table_name, data, &sql_adt::SaveStrategy::Append)
-            .await;
+            .save(table_name, data.clone(), &strategy)
+            .await
+            .is_ok()
+        {
+            return Ok(rows);
+        }
 
-        match exc {
-            Ok(_) => {
-                self.consume_count += 1;
-                Ok(())
+        // bisect the batch until its bad rows are isolated, see `ErrorHandler::sync_apply`
+        let source = self.executor.get_driver().to_string();
+        let mut strategy = strategy;
+        let mut saved = 0;
+        let mut pending = vec![(0, data)];
+        while let Some((offset, part)) = pending.pop() {
+            let rows = part.height();
+            let error = match self
+                .executor
+                .save(table_name, part.clone(), &strategy)
+                .await
+            {
+                Ok(_) => {
+                    saved += rows;
+                    // the table has been created or replaced by the first saved part
+                    if strategy != sql_adt::SaveStrategy::Upsert {
+                        strategy = sql_adt::SaveStrategy::Append;
+                    }
+                    continue;
+                }
+                Err(e) => e,
+            };
+            match bisect(&part) {
+                Some((left, right)) => {
+                    pending.push((offset + left.height(), right));
+                    pending.push((offset, left));
+                }
+                None => {
+                    let mut location = self.error_handler.row_location(offset);
+                    location.with_table(table_name);
+                    let named_row = first_named_row(&part);
+                    self.error_handler
+                        .reject(Stage::Write, &source, location, named_row, error)?;
+                }
             }
-            Err(e) => Err(e.into()),
         }
+
+        Ok(saved)
+    }
+
+    /// create a table if not exists
+    pub async fn create_new_table(&mut self, table_name: &str, data: Fabrix) -> FabrixResult<()> {
+        let strategy = match self.consume_count {
+            0 => sql_adt::SaveStrategy::FailIfExists,
+            _ => sql_adt::SaveStrategy::Append,
+        };
+
+        self.consume(table_name, data, strategy).await
+    }
+
+    pub async fn append_table(&mut self, table_name: &str, data: Fabrix) -> FabrixResult<()> {
+        self.consume(table_name, data, sql_adt::SaveStrategy::Append)
+            .await
     }
 
     /// replace a table
@@ -260,32 +502,13 @@ where
         data: Fabrix,
         ignore_index: bool,
     ) -> FabrixResult<()> {
-        let exc = match self.consume_count {
-            0 => {
-                self.executor
-                    .save(table_name, data, &sql_adt::SaveStrategy::Replace)
-                    .await
-            }
-            _ => {
-                if ignore_index {
-                    self.executor
-                        .save(table_name, data, &sql_adt::SaveStrategy::Append)
-                        .await
-                } else {
-                    self.executor
-                        .save(table_name, data, &sql_adt::SaveStrategy::Upsert)
-                        .await
-                }
-            }
+        let strategy = match (self.consume_count, ignore_index) {
+            (0, _) => sql_adt::SaveStrategy::Replace,
+            (_, true) => sql_adt::SaveStrategy::Append,
+            (_, false) => sql_adt::SaveStrategy::Upsert,
         };
 
-        match exc {
-            Ok(_) => {
-                self.consume_count += 1;
-                Ok(())
-            }
-            Err(e) => Err(e.into()),
-        }
+        self.consume(table_name, data, strategy).await
     }
 
     /// upsert a table
@@ -294,11 +517,8 @@ where
         table_name: &str,
         data: Fabrix,
     ) -> FabrixResult<()> {
-        self.executor
-            .save(table_name, data, &sql_adt::SaveStrategy::Upsert)
+        self.consume(table_name, data, sql_adt::SaveStrategy::Upsert)
             .await
-            .map(|_| ())
-            .map_err(|e| e.into())
     }
 }
 
@@ -306,12 +526,16 @@ where
 ///
 /// A XlDb is a combinator of convertor and consumer, whereas the consumer is wrapped in `Arc<Mutex<T>>`.
 /// This is to ensure the consumer is thread-safe, and can be called by an `async fn`.
+///
+/// An observer set by `with_observer` reports the saved batches; pass `observer()` to the
+/// `XlDbExecutor` as well, to report the batches read from the sheet.
 pub struct XlDbHelper<T>
 where
     T: DatabaseType,
 {
     pub convertor: XlDbConvertor,
     pub consumer: Arc<Mutex<XlToDbConsumer<T>>>,
+    observer: Option<SharedObserver>,
 }
 
 impl<T> XlDbHelper<T>
@@ -324,13 +548,30 @@ where
         Ok(Self {
             convertor,
             consumer,
+            observer: None,
         })
     }
 
+    /// report progress of saving to an observer, batch by batch
+    pub async fn with_observer(&mut self, observer: SharedObserver) -> &mut Self {
+        self.consumer.lock().await.with_observer(observer.clone());
+        self.observer = Some(observer);
+        self
+    }
+
+    pub fn observer(&self) -> Option<&SharedObserver> {
+        self.observer.as_ref()
+    }
+
     pub async fn clean_stats(&mut self) {
         self.convertor.clean_stats();
         self.consumer.lock().await.clean_stats();
     }
+
+    /// see `XlToDbConsumer::resume`
+    pub async fn resume(&self) -> FabrixResult<usize> {
+        self.consumer.lock().await.resume().await
+    }
 }
 
 impl<T> XlConsumer<XlDbConvertor> for SqlExecutor<T>
@@ -404,6 +645,26 @@ mod test_xl_reader {
         }
     }
 
+    #[test]
+    fn test_xl_db_convertor_mapping() {
+        let source: XlWorkbook<File> = XlSource::Path(XL_SOURCE.to_owned()).try_into().unwrap();
+
+        let mut mapping = ColumnMapping::new();
+        mapping
+            .with_column("id", "user_id")
+            .with_column("first_name", "name")
+            .with_constant("sheet", value!(XL_SHEET_NAME));
+        let mut convertor = XlDbConvertor::new();
+        convertor.with_mapping(mapping);
+
+        let mut xle = XlDbExecutor::<File, DatabaseSqlite>::new_with_source(source);
+        let mut iter = xle.iter_sheet(Some(20), XL_SHEET_NAME).unwrap();
+
+        let df = convertor.convert_row_wise(iter.next().unwrap(), 0).unwrap();
+        assert_eq!(df.get_column_names(), vec!["user_id", "name", "sheet"]);
+        assert_eq!(df.index_tag().unwrap().name(), "user_id");
+    }
+
     #[tokio::test]
     async fn test_xl2db_sync() {
         // Xl read from a path
@@ -454,6 +715,38 @@ mod test_xl_reader {
         println!("{:?}", res);
     }
 
+    #[tokio::test]
+    async fn test_xl2db_with_observer() {
+        let source: XlWorkbook<File> = XlSource::Path(XL_SOURCE.to_owned()).try_into().unwrap();
+        let metrics = Arc::new(crate::Metrics::new());
+
+        let mut convertor = XlDbConvertor::new();
+        let mut consumer = XlToDbConsumer::<DatabaseSqlite>::new(CONN3).await.unwrap();
+        consumer.with_observer(metrics.clone());
+
+        let mut xle = XlDbExecutor::<File, DatabaseSqlite>::new_with_source(source);
+        let iter = xle.iter_sheet(Some(40), XL_SHEET_NAME).unwrap();
+
+        let mut batches = 0;
+        for row in iter {
+            let df = convertor
+                .convert_row_wise(row, XlIndexSelection::None)
+                .unwrap();
+            consumer
+                .replace_existing_table("test_table_observed", df, true)
+                .await
+                .unwrap();
+            batches += 1;
+        }
+
+        let write = metrics.get(Stage::Write, "sqlite");
+        assert_eq!(
+            (write.batches, write.runs, write.errors),
+            (batches, batches, 0)
+        );
+        assert!(write.rows > 0);
+    }
+
     #[tokio::test]
     async fn test_xl2db_async() {
         let source: XlWorkbook<File> = XlSource::Path(XL_SOURCE.to_owned()).try_into().unwrap();
@@ -538,4 +831,108 @@ mod test_xl_reader {
         assert!(foo.is_ok());
         println!("{:?}", foo);
     }
+
+    #[tokio::test]
+    async fn test_xl2db_resume() {
+        use crate::FileCheckpointStore;
+
+        const RUN_ID: &str = "xl2db_resume";
+        const TABLE: &str = "test_resume";
+
+        let store = FileCheckpointStore::new("../cache/checkpoint").unwrap();
+        store.clear(RUN_ID).await.unwrap();
+
+        // sheet -> batches of 20 rows
+        let source: XlWorkbook<File> = XlSource::Path(XL_SOURCE.to_owned()).try_into().unwrap();
+        let mut xle = XlDbExecutor::<File, DatabaseSqlite>::new_with_source(source);
+        let mut convertor = XlDbConvertor::new();
+        let batches = xle
+            .iter_sheet(Some(20), XL_SHEET_NAME)
+            .unwrap()
+            .map(|row| convertor.convert_row_wise(row, ()).unwrap())
+            .collect::<Vec<_>>();
+        let total = batches.iter().map(|b| b.height()).sum::<usize>();
+        assert!(batches.len() > 3);
+
+        // an idempotency column without a checkpoint has no run id to key its batches
+        let mut consumer = XlToDbConsumer::<DatabaseSqlite>::new(CONN3).await.unwrap();
+        consumer.with_idempotency_column("_batch");
+        let res = consumer
+            .replace_existing_table(TABLE, batches[0].clone(), true)
+            .await;
+        assert!(matches!(res, Err(FabrixError::NotSet(_))));
+
+        // the first run dies after 3 batches
+        let mut consumer = XlToDbConsumer::<DatabaseSqlite>::new(CONN3).await.unwrap();
+        consumer
+            .with_checkpoint(RUN_ID, store.clone())
+            .with_idempotency_column("_batch");
+        for df in batches.iter().take(3) {
+            consumer
+                .replace_existing_table(TABLE, df.clone(), true)
+                .await
+                .unwrap();
+        }
+        assert_eq!(consumer.checkpoint().unwrap().offset, 3);
+
+        // the 3rd batch is in the table, but its checkpoint is lost
+        let mut lost = Checkpoint::new(RUN_ID);
+        lost.commit(0, batches[0].height())
+            .commit(1, batches[1].height());
+        store.save(&lost).await.unwrap();
+
+        // the second run skips the first 2 batches, and rewrites the 3rd one
+        let mut consumer = XlToDbConsumer::<DatabaseSqlite>::new(CONN3).await.unwrap();
+        consumer
+            .with_checkpoint(RUN_ID, store.clone())
+            .with_idempotency_column("_batch");
+        assert_eq!(consumer.resume().await.unwrap(), 2);
+        for df in batches.into_iter() {
+            consumer
+                .replace_existing_table(TABLE, df, true)
+                .await
+                .unwrap();
+        }
+        assert_eq!(consumer.checkpoint().unwrap().rows, total);
+
+        let select = sql_adt::Select::new(TABLE).columns(&["_batch"]);
+        let res = consumer.executor.select(&select).await.unwrap();
+        assert_eq!(res.height(), total);
+
+        consumer.clear_checkpoint().await.unwrap();
+        assert!(store.load(RUN_ID).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_xl2db_quarantine() {
+        use fabrix_core::fx;
+
+        use crate::DeadLetter;
+
+        const TABLE: &str = "test_quarantine";
+
+        let dead_letter = Arc::new(DeadLetter::new());
+        let mut consumer = XlToDbConsumer::<DatabaseSqlite>::new(CONN3).await.unwrap();
+        consumer.with_error_handler(ErrorHandler::quarantine(dead_letter.clone()));
+
+        let first = fx!["id"; "id" => [1, 2], "name" => ["a", "b"]].unwrap();
+        consumer
+            .replace_existing_table(TABLE, first, false)
+            .await
+            .unwrap();
+
+        // id 2 violates the primary key, the other rows are appended
+        let second = fx!["id"; "id" => [3, 2, 4], "name" => ["c", "x", "d"]].unwrap();
+        consumer.append_table(TABLE, second).await.unwrap();
+
+        let records = dead_letter.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].stage, Stage::Write);
+        assert_eq!(records[0].location.table.as_deref(), Some(TABLE));
+        assert_eq!(records[0].location.line, Some(2));
+
+        let select = sql_adt::Select::new(TABLE).columns(&["name"]);
+        let res = consumer.executor.select(&select).await.unwrap();
+        assert_eq!(res.height(), 4);
+    }
 }
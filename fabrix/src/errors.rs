@@ -76,10 +76,20 @@ pub enum FabrixError {
     #[error("{0}")]
     InvalidArgument(String),
 
+    #[error("{} writer(s) failed: {}", .0.len(), fmt_named_errors(.0))]
+    Writers(Vec<(String, FabrixError)>),
+
+    #[error("{} quality check(s) failed: {}", .0.len(), fmt_named_messages(.0))]
+    QualityChecks(Vec<(String, String)>),
+
     // IO errors
     #[error(transparent)]
     IO(#[from] std::io::Error),
 
+    // Json errors
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
     // Core errors
     #[error(transparent)]
     Core(#[from] fabrix_core::CoreError),
@@ -112,6 +122,22 @@ pub enum FabrixError {
     Unknown,
 }
 
+fn fmt_named_errors(errors: &[(String, FabrixError)]) -> String {
+    errors
+        .iter()
+        .map(|(name, e)| format!("[{name}] {e}"))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn fmt_named_messages(messages: &[(String, String)]) -> String {
+    messages
+        .iter()
+        .map(|(name, msg)| format!("[{name}] {msg}"))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 impl FabrixError {
     pub fn new_uncategorized<T>(msg: T) -> Self
     where
@@ -42,6 +42,15 @@ pub use crate::sources::json::*;
 #[cfg(feature = "mongo")]
 pub use crate::sources::mongo::*;
 
+// sources: compression
+pub use crate::sources::compression::Compression;
+
+// sources: files
+pub use crate::sources::files::{FileOrder, FilesReader, SOURCE_FILE_COLUMN};
+
+// sources: uri
+pub use crate::sources::uri::{FileStore, HttpStore, ObjectStore, UriResolver};
+
 // dispatcher
 #[cfg(all(feature = "xl", feature = "json"))]
 pub use crate::dispatcher::XlJson;
@@ -50,4 +59,53 @@ pub use crate::dispatcher::{
     XlDbConvertor, XlDbExecutor, XlDbHelper, XlIndexSelection, XlToDbConsumer,
 };
 
-pub use crate::dispatcher::{Dispatcher, FromSource, IntoSource, ReadOptions, WriteOptions};
+// dispatcher: checkpoints & watermarks
+pub use crate::dispatcher::{batch_key, Checkpoint, CheckpointStore, FileCheckpointStore};
+pub use crate::dispatcher::{FileWatermarkStore, Watermark, WatermarkStore};
+
+pub use crate::dispatcher::{
+    async_transform_all, sync_transform_all, Cast, DeriveColumn, FillNull, Filter, FnTransform,
+    Pipeline, Rename, Select, Transform,
+};
+#[cfg(feature = "mask")]
+pub use crate::dispatcher::{
+    decrypt, detokenize, encrypt, hash_value, mask_email, mask_partial, mask_phone, tokenize, Mask,
+    MaskKey, MaskMethod, Reverse, Secret, Unmask,
+};
+pub use crate::dispatcher::{
+    mask_conn, schema_hash, Audit, AuditQuery, AuditRecord, AuditRun, AuditStore, Endpoint,
+    FileAuditStore, RunOutcome,
+};
+pub use crate::dispatcher::{union_fabrix, FanInDispatcher, FanInSource};
+pub use crate::dispatcher::{
+    BadRecord, BadRecordPolicy, DeadLetter, ErrorHandler, RecordLocation, DEAD_LETTER_COLUMNS,
+};
+pub use crate::dispatcher::{
+    CancellationToken, Job, JobOutcome, JobRunner, JobStatus, RetryPolicy,
+};
+pub use crate::dispatcher::{
+    Check, CheckResult, QualityCheck, QualityGate, QualityReport, Severity, QUALITY_REPORT_COLUMNS,
+};
+pub use crate::dispatcher::{
+    CheckKindSpec, CheckSpec, ColumnRuleSpec, DynPipeline, PipelineSpec, SourceSpec, TransformSpec,
+};
+pub use crate::dispatcher::{ColumnMapping, ColumnRule, MappingSource};
+pub use crate::dispatcher::{
+    Dispatcher, FromSource, IntoSource, PlanSource, ReadOptions, WriteOptions,
+};
+pub use crate::dispatcher::{
+    DynReadOptions, DynReader, DynReaderBuilder, DynWriteOptions, DynWriter, DynWriterBuilder,
+    SourceRegistry,
+};
+pub use crate::dispatcher::{
+    ErrorPolicy, FanOutDispatcher, FanOutReport, FanOutTarget, WriterReport,
+};
+pub use crate::dispatcher::{
+    FabrixStream, FromSourceStream, IntoSourceStream, StreamDispatcher, DEFAULT_BATCH_SIZE,
+    DEFAULT_BUFFER_SIZE,
+};
+pub use crate::dispatcher::{RecordSink, SinkRecord};
+#[cfg(feature = "sql")]
+pub use crate::dispatcher::{SqlAuditStore, DEFAULT_AUDIT_TABLE};
+#[cfg(feature = "sql")]
+pub use crate::dispatcher::{SqlCheckpointStore, DEFAULT_CHECKPOINT_TABLE};
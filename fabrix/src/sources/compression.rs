@@ -0,0 +1,365 @@
+//! Compression
+//!
+//! Transparent compression of csv & json sources and sinks, by one of gzip, zstd, bzip2 & xz:
+//! - reading: the codec is set explicitly, or detected by the magic bytes of the content. A
+//!   compressed source is streamed through a decoder, either into an anonymous temp file which
+//!   polars parses like any file, or batch by batch by a stream reader
+//! - writing: the codec is set explicitly, or detected by the extension of the path, e.g.
+//!   `reports.csv.gz`. A stream writer compresses batch by batch, and writes the trailer of the
+//!   codec once the stream is finished
+//!
+//! Parquet files compress their pages by themselves, see `ParquetCodec`.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Cursor, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::FabrixResult;
+
+/// number of bytes read for detecting a codec
+const MAGIC_LEN: u64 = 6;
+
+/// codec of a compressed file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Bzip2,
+    Xz,
+}
+
+impl Compression {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+            Compression::Bzip2 => "bz2",
+            Compression::Xz => "xz",
+        }
+    }
+
+    /// codec of the last extension of a path, e.g. `gz` of `reports.csv.gz`
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "gz" | "gzip" => Some(Compression::Gzip),
+            "zst" | "zstd" => Some(Compression::Zstd),
+            "bz2" => Some(Compression::Bzip2),
+            "xz" => Some(Compression::Xz),
+            _ => None,
+        }
+    }
+
+    /// codec of the first bytes of a content
+    pub fn from_magic(head: &[u8]) -> Option<Self> {
+        match head {
+            [0x1f, 0x8b, ..] => Some(Compression::Gzip),
+            [0x28, 0xb5, 0x2f, 0xfd, ..] => Some(Compression::Zstd),
+            [b'B', b'Z', b'h', b'1'..=b'9', ..] => Some(Compression::Bzip2),
+            [0xfd, b'7', b'z', b'X', b'Z', 0x00, ..] => Some(Compression::Xz),
+            _ => None,
+        }
+    }
+
+    /// codec of the content from the current position, which is kept
+    pub fn detect<R: Read + Seek>(reader: &mut R) -> FabrixResult<Option<Self>> {
+        let position = reader.stream_position()?;
+        let mut head = Vec::with_capacity(MAGIC_LEN as usize);
+        reader.by_ref().take(MAGIC_LEN).read_to_end(&mut head)?;
+        reader.seek(SeekFrom::Start(position))?;
+
+        Ok(Self::from_magic(&head))
+    }
+}
+
+// ================================================================================================
+// Decompression
+// ================================================================================================
+
+/// a reader decompressing `reader`
+pub fn decoder<'r, R: Read + Send + 'r>(
+    reader: R,
+    compression: Compression,
+) -> FabrixResult<Box<dyn Read + Send + 'r>> {
+    Ok(match compression {
+        Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+        Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+        Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+        Compression::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(reader)),
+    })
+}
+
+/// content of a source, as is or decompressed into an anonymous temp file
+pub enum Decoded<R> {
+    Plain(R),
+    Decompressed(File),
+}
+
+/// decompress the content if `compression` is set or detected. The content is streamed through
+/// the decoder into an anonymous temp file, removed once closed, so that it is never held in
+/// memory as a whole
+pub fn decode<R: Read + Seek + Send>(
+    mut reader: R,
+    compression: Option<Compression>,
+) -> FabrixResult<Decoded<R>> {
+    let compression = match compression {
+        Some(c) => Some(c),
+        None => Compression::detect(&mut reader)?,
+    };
+
+    match compression {
+        Some(c) => {
+            let mut file = tempfile::tempfile()?;
+            std::io::copy(&mut decoder(reader, c)?, &mut file)?;
+            file.seek(SeekFrom::Start(0))?;
+            Ok(Decoded::Decompressed(file))
+        }
+        None => Ok(Decoded::Plain(reader)),
+    }
+}
+
+/// a buffered reader of the content, decompressed on the fly if `compression` is set or detected.
+/// The magic bytes are read ahead, until all of them or the end of the content even if `reader`
+/// returns fewer bytes per read, then chained back, so that `reader` needs no `Seek`
+pub fn decode_stream<'r, R: Read + Send + 'r>(
+    mut reader: R,
+    compression: Option<Compression>,
+) -> FabrixResult<Box<dyn BufRead + Send + 'r>> {
+    let mut head = Vec::with_capacity(MAGIC_LEN as usize);
+    let compression = match compression {
+        Some(c) => Some(c),
+        None => {
+            reader.by_ref().take(MAGIC_LEN).read_to_end(&mut head)?;
+            Compression::from_magic(&head)
+        }
+    };
+    let reader = BufReader::new(Cursor::new(head).chain(reader));
+
+    Ok(match compression {
+        Some(c) => Box::new(BufReader::new(decoder(reader, c)?)),
+        None => Box::new(reader),
+    })
+}
+
+// ================================================================================================
+// Compression
+// ================================================================================================
+
+/// a writer compressing into `W`, `finish` writes the trailer of the codec
+pub enum Encoder<W: Write> {
+    Gzip(flate2::write::GzEncoder<W>),
+    Zstd(zstd::stream::write::Encoder<'static, W>),
+    Bzip2(bzip2::write::BzEncoder<W>),
+    Xz(xz2::write::XzEncoder<W>),
+}
+
+impl<W: Write> Encoder<W> {
+    /// the default level of each codec
+    pub fn new(writer: W, compression: Compression) -> FabrixResult<Self> {
+        Ok(match compression {
+            Compression::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(
+                writer,
+                flate2::Compression::default(),
+            )),
+            Compression::Zstd => Encoder::Zstd(zstd::stream::write::Encoder::new(writer, 0)?),
+            Compression::Bzip2 => Encoder::Bzip2(bzip2::write::BzEncoder::new(
+                writer,
+                bzip2::Compression::default(),
+            )),
+            Compression::Xz => Encoder::Xz(xz2::write::XzEncoder::new(writer, 6)),
+        })
+    }
+
+    pub fn finish(self) -> FabrixResult<W> {
+        Ok(match self {
+            Encoder::Gzip(e) => e.finish()?,
+            Encoder::Zstd(e) => e.finish()?,
+            Encoder::Bzip2(e) => e.finish()?,
+            Encoder::Xz(e) => e.finish()?,
+        })
+    }
+}
+
+/// a sink, as is or compressing, `finish` writes the trailer of the codec if any
+pub enum Encoded<W: Write> {
+    Plain(W),
+    Compressed(Encoder<W>),
+}
+
+impl<W: Write> Encoded<W> {
+    pub fn new(writer: W, compression: Option<Compression>) -> FabrixResult<Self> {
+        Ok(match compression {
+            Some(c) => Encoded::Compressed(Encoder::new(writer, c)?),
+            None => Encoded::Plain(writer),
+        })
+    }
+
+    pub fn finish(self) -> FabrixResult<W> {
+        match self {
+            Encoded::Plain(w) => Ok(w),
+            Encoded::Compressed(e) => e.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for Encoded<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Encoded::Plain(w) => w.write(buf),
+            Encoded::Compressed(e) => e.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Encoded::Plain(w) => w.flush(),
+            Encoded::Compressed(e) => e.flush(),
+        }
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Encoder::Gzip(e) => e.write(buf),
+            Encoder::Zstd(e) => e.write(buf),
+            Encoder::Bzip2(e) => e.write(buf),
+            Encoder::Xz(e) => e.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Encoder::Gzip(e) => e.flush(),
+            Encoder::Zstd(e) => e.flush(),
+            Encoder::Bzip2(e) => e.flush(),
+            Encoder::Xz(e) => e.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod compression_tests {
+    use super::*;
+
+    const CONTENT: &[u8] = b"id,name\n1,a\n2,b\n";
+
+    #[test]
+    fn test_round_trip() {
+        for c in [
+            Compression::Gzip,
+            Compression::Zstd,
+            Compression::Bzip2,
+            Compression::Xz,
+        ] {
+            let mut encoder = Encoder::new(Vec::new(), c).unwrap();
+            encoder.write_all(CONTENT).unwrap();
+            let compressed = encoder.finish().unwrap();
+            assert_eq!(Compression::from_magic(&compressed), Some(c));
+
+            let mut reader = Cursor::new(compressed);
+            assert_eq!(Compression::detect(&mut reader).unwrap(), Some(c));
+            assert_eq!(reader.position(), 0);
+
+            match decode(reader.clone(), None).unwrap() {
+                Decoded::Decompressed(mut d) => {
+                    let mut buff = Vec::new();
+                    d.read_to_end(&mut buff).unwrap();
+                    assert_eq!(buff, CONTENT);
+                }
+                Decoded::Plain(_) => panic!("{c:?} is not detected"),
+            }
+
+            let mut buff = Vec::new();
+            decode_stream(reader, None)
+                .unwrap()
+                .read_to_end(&mut buff)
+                .unwrap();
+            assert_eq!(buff, CONTENT);
+        }
+
+        assert!(matches!(
+            decode(Cursor::new(CONTENT), None).unwrap(),
+            Decoded::Plain(_)
+        ));
+        let mut buff = Vec::new();
+        decode_stream(CONTENT, None)
+            .unwrap()
+            .read_to_end(&mut buff)
+            .unwrap();
+        assert_eq!(buff, CONTENT);
+    }
+
+    /// a reader returning one byte per read
+    struct Trickle<'a>(&'a [u8]);
+
+    impl<'a> Read for Trickle<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match (self.0.split_first(), buf.first_mut()) {
+                (Some((b, rest)), Some(first)) => {
+                    *first = *b;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_stream_short_reads() {
+        for c in [Compression::Zstd, Compression::Xz] {
+            let mut encoder = Encoder::new(Vec::new(), c).unwrap();
+            encoder.write_all(CONTENT).unwrap();
+            let compressed = encoder.finish().unwrap();
+
+            let mut buff = Vec::new();
+            decode_stream(Trickle(&compressed), None)
+                .unwrap()
+                .read_to_end(&mut buff)
+                .unwrap();
+            assert_eq!(buff, CONTENT);
+        }
+
+        // shorter than the magic bytes
+        let mut buff = Vec::new();
+        decode_stream(Trickle(b"id"), None)
+            .unwrap()
+            .read_to_end(&mut buff)
+            .unwrap();
+        assert_eq!(buff, b"id");
+    }
+
+    #[test]
+    fn test_from_magic() {
+        assert_eq!(
+            Compression::from_magic(b"BZh91AY"),
+            Some(Compression::Bzip2)
+        );
+        // a plain csv starting with `BZh`
+        assert_eq!(Compression::from_magic(b"BZhang,1\n"), None);
+        assert_eq!(Compression::from_magic(b"BZh"), None);
+        assert_eq!(Compression::from_magic(CONTENT), None);
+    }
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(
+            Compression::from_path("reports/2026-01.csv.gz"),
+            Some(Compression::Gzip)
+        );
+        assert_eq!(
+            Compression::from_path("a.json.ZST"),
+            Some(Compression::Zstd)
+        );
+        assert_eq!(
+            Compression::from_path("a.csv.bz2"),
+            Some(Compression::Bzip2)
+        );
+        assert_eq!(Compression::from_path("a.csv.xz"), Some(Compression::Xz));
+        assert_eq!(Compression::from_path("a.csv"), None);
+    }
+}
@@ -8,8 +8,14 @@ use std::io::Cursor;
 pub mod reader;
 pub mod writer;
 
-pub use reader::{CsvReadOptions, Reader as CsvReader};
-pub use writer::{CsvWriteOptions, Writer as CsvWriter};
+pub use reader::{
+    CsvReadOptions, Reader as CsvReader, ReaderSpec as CsvReaderSpec,
+    StreamReader as CsvStreamReader,
+};
+pub use writer::{
+    CsvWriteOptions, StreamWriter as CsvStreamWriter, Writer as CsvWriter,
+    WriterSpec as CsvWriterSpec,
+};
 
 pub(crate) const UNSUPPORTED_TYPE: &str = "Unsupported CSVSource type";
 
@@ -18,6 +24,10 @@ pub enum CsvSource<'a> {
     File(File),
     Path(&'a str),
     Uri(&'a str),
+    /// files matching a glob pattern, read by `FilesReader`
+    Glob(&'a str),
+    /// files of a directory with the extension of the format, read by `FilesReader`
+    Dir(&'a str),
     BuffRead(Cursor<Vec<u8>>),
     BuffWrite(&'a mut Cursor<Vec<u8>>),
 }
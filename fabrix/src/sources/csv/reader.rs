@@ -1,17 +1,34 @@
 //! CSV Reader
 //!
 //! Reading CSV files.
+//!
+//! With an `ErrorHandler`, rows with values which cannot be parsed into their column types are
+//! skipped or quarantined, instead of failing the whole read.
 
+use std::collections::BTreeMap;
 use std::fs::File;
-use std::io::Cursor;
+use std::io::{BufRead, Cursor, Read};
 
 use async_trait::async_trait;
+use futures::{stream, StreamExt};
 use polars::io::mmap::MmapBytesReader;
 use polars::io::RowCount;
-use polars::prelude::{CsvReader, SerReader};
+use polars::prelude::{
+    CsvReader, CsvWriter, DataFrame, DataType, Schema as PolarsSchema, SerReader, SerWriter,
+};
+use serde::Deserialize;
 
 use super::{CsvSource, UNSUPPORTED_TYPE};
-use crate::{Fabrix, FabrixError, FabrixResult, FromSource, ReadOptions, Schema, ValueTypes};
+use crate::dispatcher::ascii_byte;
+use crate::sources::compression::{decode, decode_stream, Compression, Decoded};
+use crate::sources::uri::UriResolver;
+use crate::{
+    BadRecordPolicy, DynReader, Endpoint, ErrorHandler, Fabrix, FabrixError, FabrixResult,
+    FabrixStream, FromSource, FromSourceStream, ReadOptions, Schema, Stage, ValueType, ValueTypes,
+};
+
+/// number of values used for inferring a column type, the same as polars
+const INFER_SCHEMA_LENGTH: usize = 100;
 
 // ================================================================================================
 // CSV Reader
@@ -21,144 +38,275 @@ use crate::{Fabrix, FabrixError, FabrixResult, FromSource, ReadOptions, Schema,
 ///
 /// Read csv files from `std::fs::File` or `std::io::Cursor<T>`.
 ///
-/// Since all the methods provided by `CsvReader` are `mut self`, there is also an
-/// alternative way to build a `Reader` by using unsafe code:
-/// ```rust,ignore
-/// pub struct Reader<'a, R: MmapBytesReader + 'a> {
-///     csv_reader: CsvReader<'a, R>,
-/// }
-///
-/// impl<'a, R: MmapBytesReader> Reader<'a, R> {
-///     pub fn has_header(&mut self) -> &mut Self {
-///         let mut inner = unsafe { std::ptr::read(&self.csv_reader) };
-///         inner = inner.has_header(true);
-///         unsafe { std::ptr::write(&mut self.csv_reader, inner) };
-///         self
-///     }
-/// }
-/// ```
+/// Settings are kept until `finish`, where a polars `CsvReader` is built over the source, or
+/// over its decompressed content if it is compressed (see `Compression`).
 pub struct Reader<'a, R: MmapBytesReader + 'a> {
-    csv_reader: Option<CsvReader<'a, R>>,
+    reader: Option<R>,
+    compression: Option<Compression>,
+    has_header: Option<bool>,
+    skip_rows_after_header: Option<usize>,
+    num_rows: Option<usize>,
+    row_count: Option<(String, usize)>,
+    ignore_parser_errors: Option<bool>,
+    skip_rows: Option<usize>,
+    rechunk: Option<bool>,
+    delimiter: Option<u8>,
+    comment_char: Option<u8>,
+    dtypes: Option<&'a Schema>,
+    dtypes_slice: Option<&'a ValueTypes>,
+    projection: Option<Vec<usize>>,
+    error_handler: ErrorHandler,
 }
 
 impl<'a, R: MmapBytesReader> Reader<'a, R> {
     pub fn new(reader: R) -> Self {
         Self {
-            csv_reader: Some(CsvReader::new(reader)),
+            reader: Some(reader),
+            compression: None,
+            has_header: None,
+            skip_rows_after_header: None,
+            num_rows: None,
+            row_count: None,
+            ignore_parser_errors: None,
+            skip_rows: None,
+            rechunk: None,
+            delimiter: None,
+            comment_char: None,
+            dtypes: None,
+            dtypes_slice: None,
+            projection: None,
+            error_handler: ErrorHandler::default(),
         }
     }
 
+    /// skip or quarantine rows with bad values. Columns are then read as strings, and cast into
+    /// the types of `dtypes` or `dtypes_slice`, or the types inferred from their first values.
+    /// A non-null value which cannot be cast makes its row bad
+    pub fn with_error_handler(&mut self, error_handler: ErrorHandler) -> &mut Self {
+        self.error_handler = error_handler;
+        self
+    }
+
     pub fn has_reader(&self) -> bool {
-        self.csv_reader.is_some()
+        self.reader.is_some()
     }
 
     pub fn new_reader(&mut self, reader: R) -> &mut Self {
-        self.csv_reader = Some(CsvReader::new(reader));
+        self.reader = Some(reader);
+        self
+    }
+
+    /// codec of the source, detected by its magic bytes if not set
+    pub fn with_compression(&mut self, compression: Compression) -> &mut Self {
+        self.compression = Some(compression);
         self
     }
 
     pub fn with_header(&mut self, has_header: bool) -> &mut Self {
-        self.csv_reader = self.csv_reader.take().map(|r| r.has_header(has_header));
+        self.has_header = Some(has_header);
         self
     }
 
     pub fn with_skip_rows_after_header(&mut self, offset: usize) -> &mut Self {
-        self.csv_reader = self
-            .csv_reader
-            .take()
-            .map(|r| r.with_skip_rows_after_header(offset));
+        self.skip_rows_after_header = Some(offset);
         self
     }
 
     pub fn with_n_rows(&mut self, num_rows: usize) -> &mut Self {
-        self.csv_reader = self
-            .csv_reader
-            .take()
-            .map(|r| r.with_n_rows(Some(num_rows)));
+        self.num_rows = Some(num_rows);
         self
     }
 
     pub fn with_row_count(&mut self, name: &str, offset: usize) -> &mut Self {
-        let name = name.to_string();
-        let offset = offset as u64;
-        self.csv_reader = self
-            .csv_reader
-            .take()
-            .map(|r| r.with_row_count(Some(RowCount { name, offset })));
+        self.row_count = Some((name.to_string(), offset));
         self
     }
 
     pub fn with_ignore_parser_errors(&mut self, ignore: bool) -> &mut Self {
-        self.csv_reader = self
-            .csv_reader
-            .take()
-            .map(|r| r.with_ignore_parser_errors(ignore));
+        self.ignore_parser_errors = Some(ignore);
         self
     }
 
     pub fn with_skip_rows(&mut self, skip_rows: usize) -> &mut Self {
-        self.csv_reader = self.csv_reader.take().map(|r| r.with_skip_rows(skip_rows));
+        self.skip_rows = Some(skip_rows);
         self
     }
 
     pub fn with_rechunk(&mut self, rechunk: bool) -> &mut Self {
-        self.csv_reader = self.csv_reader.take().map(|r| r.with_rechunk(rechunk));
+        self.rechunk = Some(rechunk);
         self
     }
 
     pub fn with_delimiter(&mut self, delimiter: u8) -> &mut Self {
-        self.csv_reader = self.csv_reader.take().map(|r| r.with_delimiter(delimiter));
+        self.delimiter = Some(delimiter);
         self
     }
 
     pub fn with_comment_char(&mut self, comment_char: u8) -> &mut Self {
-        self.csv_reader
-            .take()
-            .map(|r| r.with_comment_char(Some(comment_char)));
+        self.comment_char = Some(comment_char);
         self
     }
 
     // schema must be a subset of the total schema
     pub fn with_dtypes(&mut self, schema: &'a Schema) -> &mut Self {
-        self.csv_reader = self
-            .csv_reader
-            .take()
-            .map(|r| r.with_dtypes(Some(schema.as_ref())));
+        self.dtypes = Some(schema);
         self
     }
 
     pub fn with_dtypes_slice(&mut self, dtypes: &'a ValueTypes) -> &mut Self {
-        self.csv_reader = self
-            .csv_reader
-            .take()
-            .map(|r| r.with_dtypes_slice(Some(dtypes.as_ref())));
+        self.dtypes_slice = Some(dtypes);
         self
     }
 
     pub fn with_projection(&mut self, projection: Vec<usize>) -> &mut Self {
-        self.csv_reader = self
-            .csv_reader
-            .take()
-            .map(|r| r.with_projection(Some(projection)));
+        self.projection = Some(projection);
         self
     }
 
-    pub fn finish(&mut self, index: Option<usize>) -> FabrixResult<Fabrix> {
+    /// a polars `CsvReader` with the settings
+    fn csv_reader<S: MmapBytesReader + 'a>(&self, source: S) -> CsvReader<'a, S> {
+        let mut reader = CsvReader::new(source);
+        if let Some(has_header) = self.has_header {
+            reader = reader.has_header(has_header);
+        }
+        if let Some(offset) = self.skip_rows_after_header {
+            reader = reader.with_skip_rows_after_header(offset);
+        }
+        if let Some(num_rows) = self.num_rows {
+            reader = reader.with_n_rows(Some(num_rows));
+        }
+        if let Some((name, offset)) = &self.row_count {
+            reader = reader.with_row_count(Some(RowCount {
+                name: name.clone(),
+                offset: *offset as u64,
+            }));
+        }
+        if let Some(ignore) = self.ignore_parser_errors {
+            reader = reader.with_ignore_parser_errors(ignore);
+        }
+        if let Some(skip_rows) = self.skip_rows {
+            reader = reader.with_skip_rows(skip_rows);
+        }
+        if let Some(rechunk) = self.rechunk {
+            reader = reader.with_rechunk(rechunk);
+        }
+        if let Some(delimiter) = self.delimiter {
+            reader = reader.with_delimiter(delimiter);
+        }
+        if let Some(comment_char) = self.comment_char {
+            reader = reader.with_comment_char(Some(comment_char));
+        }
+        if let Some(schema) = self.dtypes {
+            reader = reader.with_dtypes(Some(schema.as_ref()));
+        }
+        if let Some(dtypes) = self.dtypes_slice {
+            reader = reader.with_dtypes_slice(Some(dtypes.as_ref()));
+        }
+        if let Some(projection) = &self.projection {
+            reader = reader.with_projection(Some(projection.clone()));
+        }
+        reader
+    }
+
+    /// the source, decompressed if needed
+    fn take_source(&mut self) -> FabrixResult<Decoded<R>> {
         let reader = self
-            .csv_reader
+            .reader
             .take()
             .ok_or(FabrixError::NotInitialized("CsvReader"))?;
 
-        let df = reader.finish()?;
+        decode(reader, self.compression)
+    }
+
+    pub fn finish(&mut self, index: Option<usize>) -> FabrixResult<Fabrix> {
+        let df = match self.take_source()? {
+            Decoded::Plain(r) => self.csv_reader(r).finish()?,
+            Decoded::Decompressed(r) => self.csv_reader(r).finish()?,
+        };
+
+        Ok(Fabrix::new(df, index)?)
+    }
+
+    /// read all the columns as strings, then cast them and reject the rows failing the cast.
+    /// `first_line` is the line of the first row in the file
+    fn finish_with_handler(
+        &mut self,
+        index: Option<usize>,
+        dtypes: Option<&Schema>,
+        dtypes_slice: Option<&ValueTypes>,
+        first_line: usize,
+    ) -> FabrixResult<Fabrix> {
+        let raw = match self.take_source()? {
+            Decoded::Plain(r) => self.csv_reader(r).infer_schema(Some(0)).finish()?,
+            Decoded::Decompressed(r) => self.csv_reader(r).infer_schema(Some(0)).finish()?,
+        };
+        let inferred = infer_dtypes(&raw)?;
+        let mut df = raw.clone();
+        // row -> (column, value, dtype) of its first bad value
+        let mut bad = BTreeMap::new();
+        for (i, origin) in raw.get_columns().iter().enumerate() {
+            if origin.dtype() != &DataType::Utf8 {
+                continue;
+            }
+            let dtype = dtypes
+                .and_then(|s| s.as_ref().get(origin.name()).cloned())
+                .or_else(|| dtypes_slice.and_then(|d| d.as_ref().get(i).cloned()))
+                .or_else(|| inferred.get(origin.name()).cloned())
+                .unwrap_or(DataType::Utf8);
+            if dtype == DataType::Utf8 {
+                continue;
+            }
+
+            let cast = origin.cast(&dtype)?;
+            let failed = origin.is_not_null() & cast.is_null();
+            let values = origin.utf8()?.into_iter();
+            for (row, (f, v)) in failed.into_iter().zip(values).enumerate() {
+                if let (Some(true), Some(v)) = (f, v) {
+                    bad.entry(row)
+                        .or_insert_with(|| (origin.name().to_owned(), v.to_owned(), dtype.clone()));
+                }
+            }
+            df.replace(origin.name(), cast)?;
+        }
 
-        if let Some(index) = index {
-            Ok(Fabrix::new(df, index)?)
-        } else {
-            Ok(Fabrix::new_no_index(df))
+        let raw = Fabrix::new_no_index(raw);
+        for (row, (column, value, dtype)) in bad.iter() {
+            let mut location = self.error_handler.location().clone();
+            location.with_line(first_line + row).with_cell(column);
+            self.error_handler.reject(
+                Stage::Read,
+                CsvReadOptions::source_type(),
+                location,
+                raw.get_named_row_by_idx(*row).ok(),
+                format!("cannot parse `{value}` as {dtype}"),
+            )?;
         }
+
+        let good = (0..df.height())
+            .filter(|r| !bad.contains_key(r))
+            .collect::<Vec<_>>();
+        let fx = Fabrix::new(df, index)?;
+
+        Ok(match bad.is_empty() {
+            true => fx,
+            false => fx.take_rows_by_idx(&good)?,
+        })
     }
 }
 
+/// the types polars infers from the first rows of string columns, the same as a read without an
+/// error handler: the rows are written back into csv and read with the default schema inference
+fn infer_dtypes(raw: &DataFrame) -> FabrixResult<PolarsSchema> {
+    let mut head = raw.head(Some(INFER_SCHEMA_LENGTH));
+    let mut buff = Vec::new();
+    CsvWriter::new(&mut buff).finish(&mut head)?;
+    let inferred = CsvReader::new(Cursor::new(buff))
+        .infer_schema(Some(INFER_SCHEMA_LENGTH))
+        .finish()?;
+
+    Ok(inferred.schema())
+}
+
 // ================================================================================================
 // CsvReader TryFrom CsvSource
 // ================================================================================================
@@ -170,6 +318,7 @@ impl<'a> TryFrom<CsvSource<'a>> for Reader<'a, File> {
         match source {
             CsvSource::File(file) => Ok(Self::new(file)),
             CsvSource::Path(path) => Ok(Self::new(File::open(path)?)),
+            CsvSource::Uri(uri) => Ok(Self::new(UriResolver::default().to_file(uri)?)),
             _ => Err(FabrixError::UnsupportedType(UNSUPPORTED_TYPE)),
         }
     }
@@ -181,6 +330,7 @@ impl<'a> TryFrom<CsvSource<'a>> for Reader<'a, Cursor<Vec<u8>>> {
     fn try_from(source: CsvSource) -> FabrixResult<Self> {
         match source {
             CsvSource::BuffRead(bytes) => Ok(Reader::new(bytes)),
+            CsvSource::Uri(uri) => Ok(Reader::new(UriResolver::default().to_buffer(uri)?)),
             _ => Err(FabrixError::UnsupportedType(UNSUPPORTED_TYPE)),
         }
     }
@@ -190,7 +340,7 @@ impl<'a> TryFrom<CsvSource<'a>> for Reader<'a, Cursor<Vec<u8>>> {
 // Csv read options & FromSource impl
 // ================================================================================================
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct CsvReadOptions {
     pub has_header: Option<bool>,
     pub skip_rows_after_header: Option<usize>,
@@ -205,6 +355,8 @@ pub struct CsvReadOptions {
     pub dtypes_slice: Option<ValueTypes>,
     pub projection: Option<Vec<usize>>,
     pub index: Option<usize>,
+    /// detected by the magic bytes of the source if not set
+    pub compression: Option<Compression>,
 }
 
 impl ReadOptions for CsvReadOptions {
@@ -243,8 +395,13 @@ where
             dtypes_slice,
             projection,
             index,
+            compression,
         } = options;
 
+        if let Some(compression) = compression {
+            self.with_compression(*compression);
+        }
+
         if let Some(has_header) = has_header {
             self.with_header(*has_header);
         }
@@ -272,20 +429,364 @@ where
         if let Some(comment_char) = comment_char {
             self.with_comment_char(*comment_char);
         }
+        if let Some(projection) = projection {
+            self.with_projection(projection.clone());
+        }
+
+        if self.error_handler.policy() != BadRecordPolicy::Fail {
+            let first_line = 1
+                + usize::from(has_header.unwrap_or(true))
+                + skip_rows.unwrap_or(0)
+                + skip_rows_after_header.unwrap_or(0);
+            return self.finish_with_handler(
+                *index,
+                dtypes.as_ref(),
+                dtypes_slice.as_ref(),
+                first_line,
+            );
+        }
+
         if let Some(dtypes) = dtypes {
             self.with_dtypes(dtypes);
         }
         if let Some(dtypes_slice) = dtypes_slice {
             self.with_dtypes_slice(dtypes_slice);
         }
-        if let Some(projection) = projection {
-            self.with_projection(projection.clone());
-        }
 
         self.finish(*index)
     }
 }
 
+// ================================================================================================
+// Csv reader spec & DynReader impl
+// ================================================================================================
+
+/// a csv file reader, which can be deserialized from an options map (see `SourceRegistry`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReaderSpec {
+    pub path: String,
+    pub has_header: Option<bool>,
+    pub skip_rows_after_header: Option<usize>,
+    pub num_rows: Option<usize>,
+    pub row_count: Option<(String, usize)>,
+    pub ignore_parser_errors: Option<bool>,
+    pub skip_rows: Option<usize>,
+    pub delimiter: Option<char>,
+    pub comment_char: Option<char>,
+    pub dtypes: Option<Vec<ValueType>>,
+    pub projection: Option<Vec<usize>>,
+    pub index: Option<usize>,
+    pub compression: Option<Compression>,
+}
+
+impl ReaderSpec {
+    pub fn read_options(&self) -> FabrixResult<CsvReadOptions> {
+        Ok(CsvReadOptions {
+            has_header: self.has_header,
+            skip_rows_after_header: self.skip_rows_after_header,
+            num_rows: self.num_rows,
+            row_count: self.row_count.clone(),
+            ignore_parser_errors: self.ignore_parser_errors,
+            skip_rows: self.skip_rows,
+            delimiter: self
+                .delimiter
+                .map(|c| ascii_byte("delimiter", c))
+                .transpose()?,
+            comment_char: self
+                .comment_char
+                .map(|c| ascii_byte("comment char", c))
+                .transpose()?,
+            dtypes_slice: self.dtypes.clone().map(ValueTypes::from),
+            projection: self.projection.clone(),
+            index: self.index,
+            compression: self.compression,
+            ..Default::default()
+        })
+    }
+}
+
+#[async_trait]
+impl DynReader for ReaderSpec {
+    fn source_type(&self) -> &str {
+        CsvReadOptions::source_type()
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        Endpoint::file(self.source_type(), &self.path)
+    }
+
+    fn sync_read(&mut self) -> FabrixResult<Fabrix> {
+        let options = self.read_options()?;
+        Reader::new(File::open(&self.path)?).sync_read(&options)
+    }
+
+    async fn async_read(&mut self) -> FabrixResult<Fabrix> {
+        self.sync_read()
+    }
+}
+
+// ================================================================================================
+// CSV Stream Reader
+// ================================================================================================
+
+/// CSV Stream Reader
+///
+/// Read csv files in batches. Records are split at line breaks outside of quotes, and every
+/// `batch_size` records are parsed into a `Fabrix`. Each batch is cast into the dtypes of the
+/// previous ones; when a later batch needs a wider dtype (e.g. floats after integers, or text
+/// after numbers), the column is widened from that batch on.
+///
+/// A compressed source is decompressed on the fly (see `Compression`), the codec is set by
+/// `with_compression` or the read options, or detected by the magic bytes.
+pub struct StreamReader<R: Read> {
+    reader: Option<R>,
+    compression: Option<Compression>,
+}
+
+impl<R: Read> StreamReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: Some(reader),
+            compression: None,
+        }
+    }
+
+    pub fn with_compression(&mut self, compression: Compression) -> &mut Self {
+        self.compression = Some(compression);
+        self
+    }
+}
+
+impl<'a> TryFrom<CsvSource<'a>> for StreamReader<File> {
+    type Error = FabrixError;
+
+    fn try_from(source: CsvSource<'a>) -> FabrixResult<Self> {
+        match source {
+            CsvSource::File(file) => Ok(Self::new(file)),
+            CsvSource::Path(path) => Ok(Self::new(File::open(path)?)),
+            CsvSource::Uri(uri) => Ok(Self::new(UriResolver::default().to_file(uri)?)),
+            _ => Err(FabrixError::UnsupportedType(UNSUPPORTED_TYPE)),
+        }
+    }
+}
+
+impl<'a> TryFrom<CsvSource<'a>> for StreamReader<Cursor<Vec<u8>>> {
+    type Error = FabrixError;
+
+    fn try_from(source: CsvSource<'a>) -> FabrixResult<Self> {
+        match source {
+            CsvSource::BuffRead(bytes) => Ok(Self::new(bytes)),
+            CsvSource::Uri(uri) => Ok(Self::new(UriResolver::default().to_buffer(uri)?)),
+            _ => Err(FabrixError::UnsupportedType(UNSUPPORTED_TYPE)),
+        }
+    }
+}
+
+impl<R> FromSourceStream<CsvReadOptions> for StreamReader<R>
+where
+    R: Read + Send,
+{
+    fn read_stream<'s>(
+        &'s mut self,
+        options: &'s CsvReadOptions,
+        batch_size: usize,
+    ) -> FabrixResult<FabrixStream<'s>> {
+        let reader = self
+            .reader
+            .take()
+            .ok_or(FabrixError::NotInitialized("CsvStreamReader"))?;
+        let compression = options.compression.or(self.compression);
+
+        let batches = CsvBatches {
+            reader: decode_stream(reader, compression)?,
+            options,
+            batch_size,
+            header: None,
+            schema: None,
+            rows_read: 0,
+            started: false,
+            done: false,
+        };
+
+        Ok(stream::iter(batches).boxed())
+    }
+}
+
+/// iterator of batches, created by `StreamReader::read_stream`
+struct CsvBatches<'s> {
+    reader: Box<dyn BufRead + Send + 's>,
+    options: &'s CsvReadOptions,
+    batch_size: usize,
+    header: Option<Vec<u8>>,
+    // dtypes of the batches read so far
+    schema: Option<PolarsSchema>,
+    rows_read: usize,
+    started: bool,
+    done: bool,
+}
+
+impl<'s> CsvBatches<'s> {
+    /// skip leading rows & read the header, called before the first batch
+    fn start(&mut self) -> FabrixResult<()> {
+        let mut buf = Vec::new();
+        for _ in 0..self.options.skip_rows.unwrap_or(0) {
+            self.reader.read_until(b'\n', &mut buf)?;
+        }
+
+        if self.options.has_header.unwrap_or(true) {
+            buf.clear();
+            if read_record(&mut self.reader, &mut buf)? {
+                self.header = Some(buf);
+            }
+        }
+
+        for _ in 0..self.options.skip_rows_after_header.unwrap_or(0) {
+            if !read_record(&mut self.reader, &mut Vec::new())? {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn next_batch(&mut self) -> FabrixResult<Option<Fabrix>> {
+        if !self.started {
+            self.started = true;
+            self.start()?;
+        }
+
+        let limit = match self.options.num_rows {
+            Some(n) => self.batch_size.min(n - self.rows_read),
+            None => self.batch_size,
+        };
+
+        let mut buf = self.header.clone().unwrap_or_default();
+        let mut rows = 0;
+        while rows < limit && read_record(&mut self.reader, &mut buf)? {
+            rows += 1;
+        }
+        if rows == 0 {
+            return Ok(None);
+        }
+
+        let has_header = self.header.is_some();
+        let mut reader = CsvReader::new(Cursor::new(buf)).has_header(has_header);
+        if let Some(delimiter) = self.options.delimiter {
+            reader = reader.with_delimiter(delimiter);
+        }
+        if let Some(comment_char) = self.options.comment_char {
+            reader = reader.with_comment_char(Some(comment_char));
+        }
+        if let Some(ignore) = self.options.ignore_parser_errors {
+            reader = reader.with_ignore_parser_errors(ignore);
+        }
+        if let Some(projection) = &self.options.projection {
+            reader = reader.with_projection(Some(projection.clone()));
+        }
+        if let Some((name, offset)) = &self.options.row_count {
+            let name = name.to_owned();
+            let offset = (offset + self.rows_read) as u64;
+            reader = reader.with_row_count(Some(RowCount { name, offset }));
+        }
+        reader = match (&self.options.dtypes, &self.options.dtypes_slice) {
+            (Some(dtypes), _) => reader.with_dtypes(Some(dtypes.as_ref())),
+            (None, Some(dtypes)) => reader.with_dtypes_slice(Some(dtypes.as_ref())),
+            _ => reader,
+        };
+
+        let mut df = reader.finish()?;
+        self.align(&mut df)?;
+
+        self.rows_read += rows;
+        if self.options.num_rows == Some(self.rows_read) {
+            self.done = true;
+        }
+
+        Ok(Some(Fabrix::new(df, self.options.index)?))
+    }
+
+    /// cast the columns of a batch into the dtypes of the previous batches, a column whose
+    /// inferred dtype is wider than before is kept, and its dtype is widened for the next batches
+    fn align(&mut self, df: &mut DataFrame) -> FabrixResult<()> {
+        let schema = self.schema.get_or_insert_with(PolarsSchema::new);
+        let names = df
+            .get_column_names()
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        for name in names {
+            let column = df.column(&name)?;
+            let dtype = match schema.get(&name) {
+                // a column without any value is inferred as string
+                Some(prev) if column.null_count() == column.len() => prev.clone(),
+                Some(prev) => widen_dtype(prev, column.dtype()),
+                None => column.dtype().clone(),
+            };
+            if column.dtype() != &dtype {
+                let casted = column.cast(&dtype)?;
+                df.replace(&name, casted)?;
+            }
+            schema.with_column(name, dtype);
+        }
+
+        Ok(())
+    }
+}
+
+/// the dtype which values of both inferred dtypes can be parsed into
+fn widen_dtype(l: &DataType, r: &DataType) -> DataType {
+    match (l, r) {
+        (l, r) if l == r => l.clone(),
+        (DataType::Int64 | DataType::Float64, DataType::Int64 | DataType::Float64) => {
+            DataType::Float64
+        }
+        (DataType::Date, t @ DataType::Datetime(_, _))
+        | (t @ DataType::Datetime(_, _), DataType::Date) => t.clone(),
+        _ => DataType::Utf8,
+    }
+}
+
+impl<'s> Iterator for CsvBatches<'s> {
+    type Item = FabrixResult<Fabrix>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let res = self.next_batch().transpose();
+        if !matches!(res, Some(Ok(_))) {
+            self.done = true;
+        }
+        res
+    }
+}
+
+/// append a record to `buf`, returns `false` if there is nothing left to read
+fn read_record<R: BufRead>(reader: &mut R, buf: &mut Vec<u8>) -> std::io::Result<bool> {
+    let start = buf.len();
+    let mut quotes = 0;
+    loop {
+        let offset = buf.len();
+        if reader.read_until(b'\n', buf)? == 0 {
+            break;
+        }
+        quotes += buf[offset..].iter().filter(|b| **b == b'"').count();
+        // a line break inside quotes belongs to the record
+        if quotes % 2 == 0 {
+            break;
+        }
+    }
+
+    if buf.len() == start {
+        return Ok(false);
+    }
+    if buf.last() != Some(&b'\n') {
+        buf.push(b'\n');
+    }
+    Ok(true)
+}
+
 #[cfg(test)]
 mod test_csv_reader {
     use std::io::Cursor;
@@ -316,6 +817,19 @@ mod test_csv_reader {
         assert!(!reader.has_reader());
     }
 
+    #[test]
+    fn categorical_read() {
+        let fi = vec![FieldInfo::new("gender", ValueType::Categorical(None))];
+        let foo = Schema::from_field_infos(fi);
+
+        let mut reader: Reader<File> = CsvSource::Path(CSV_FILE_PATH).try_into().unwrap();
+
+        let foo = reader.with_dtypes(&foo).finish(None).unwrap();
+
+        let gender = foo.get_column("gender").unwrap();
+        assert!(matches!(gender.dtype(), Ok(ValueType::Categorical(_))));
+    }
+
     #[test]
     fn buff_read() {
         let mock_data = r#"
@@ -339,4 +853,132 @@ mod test_csv_reader {
 
         println!("{:?}", foo);
     }
+
+    #[test]
+    fn read_with_quarantine() {
+        let data = "id,name,score\n1,a,1.5\n2,b,oops\nx,c,2.5\n4,d,3\n";
+        let dead_letter = std::sync::Arc::new(crate::DeadLetter::new());
+        let mut location = crate::RecordLocation::new();
+        location.with_file("scores.csv");
+
+        let mut reader = Reader::new(Cursor::new(data.as_bytes()));
+        let mut handler = ErrorHandler::quarantine(dead_letter.clone());
+        handler.with_location(location);
+        reader.with_error_handler(handler);
+
+        let options = CsvReadOptions {
+            dtypes: Some(Schema::from_field_infos(vec![
+                FieldInfo::new("id", ValueType::I64),
+                FieldInfo::new("score", ValueType::F64),
+            ])),
+            ..Default::default()
+        };
+        let fx = reader.sync_read(&options).unwrap();
+        assert_eq!(fx.height(), 2);
+        assert_eq!(
+            fx.get_column("id").unwrap().dtype().unwrap(),
+            &ValueType::I64
+        );
+        assert_eq!(
+            fx.get_column("name").unwrap().dtype().unwrap(),
+            &ValueType::String
+        );
+
+        let records = dead_letter.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].location.file.as_deref(), Some("scores.csv"));
+        assert_eq!(records[0].location.line, Some(3));
+        assert_eq!(records[0].location.cell.as_deref(), Some("score"));
+        assert_eq!(records[1].location.line, Some(4));
+        assert_eq!(records[1].location.cell.as_deref(), Some("id"));
+        assert!(records[1].error.contains("cannot parse `x`"));
+        assert_eq!(
+            records[1].row.as_ref().unwrap().data()[0].1,
+            crate::value!("x")
+        );
+
+        // skipped, without dtypes the types are inferred as a read without a handler would
+        let data = "id,score,passed\n1,1.5,true\n2,2.5,false\n";
+        let mut reader = Reader::new(Cursor::new(data.as_bytes()));
+        reader.with_error_handler(ErrorHandler::new(BadRecordPolicy::Skip));
+        let fx = reader.sync_read(&CsvReadOptions::default()).unwrap();
+        let expected = Reader::new(Cursor::new(data.as_bytes()))
+            .sync_read(&CsvReadOptions::default())
+            .unwrap();
+        assert_eq!(fx.dtypes().unwrap(), expected.dtypes().unwrap());
+        assert_eq!(
+            fx.dtypes().unwrap(),
+            vec![&ValueType::I64, &ValueType::F64, &ValueType::Bool]
+        );
+    }
+
+    #[tokio::test]
+    async fn stream_read() {
+        let options = CsvReadOptions {
+            row_count: Some(("row_nr".to_owned(), 0)),
+            ..Default::default()
+        };
+        let mut reader: StreamReader<File> = CsvSource::Path(CSV_FILE_PATH).try_into().unwrap();
+
+        let batches = reader
+            .read_stream(&options, 30)
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+
+        let batches = batches.into_iter().map(|b| b.unwrap()).collect::<Vec<_>>();
+        let heights = batches.iter().map(|b| b.height()).collect::<Vec<_>>();
+        assert_eq!(heights, vec![30, 30, 30, 10]);
+        assert!(batches
+            .iter()
+            .all(|b| b.dtypes().unwrap() == batches[0].dtypes().unwrap()));
+
+        let last = batches[3].get_column("row_nr").unwrap();
+        assert_eq!(last.get(0).unwrap(), crate::value!(90u64));
+    }
+
+    #[tokio::test]
+    async fn stream_read_quoted() {
+        let data = "id,note\n1,\"a\nb\"\n2,c\n3,d";
+        let mut reader = StreamReader::new(Cursor::new(data.as_bytes().to_vec()));
+
+        let batches = reader
+            .read_stream(&CsvReadOptions::default(), 2)
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(batches.len(), 2);
+        let first = batches[0].as_ref().unwrap();
+        assert_eq!(
+            first.get_column("note").unwrap().get(0).unwrap(),
+            crate::value!("a\nb")
+        );
+    }
+
+    #[tokio::test]
+    async fn stream_read_widened() {
+        let data = "id,score\n1,2\n2,\n3,2.5\n4,x";
+        let mut reader = StreamReader::new(Cursor::new(data.as_bytes().to_vec()));
+
+        let batches = reader
+            .read_stream(&CsvReadOptions::default(), 1)
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+
+        let dtypes = batches
+            .iter()
+            .map(|b| b.as_ref().unwrap().dtypes().unwrap()[1].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            dtypes,
+            vec![
+                ValueType::I64,
+                ValueType::I64,
+                ValueType::F64,
+                ValueType::String
+            ]
+        );
+    }
 }
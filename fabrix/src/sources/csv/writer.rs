@@ -7,9 +7,16 @@ use std::io::{Cursor, Write};
 
 use async_trait::async_trait;
 use polars::prelude::{CsvWriter, SerWriter};
+use serde::Deserialize;
 
 use super::{CsvSource, UNSUPPORTED_TYPE};
-use crate::{Fabrix, FabrixError, FabrixResult, IntoSource, WriteOptions};
+use crate::dispatcher::ascii_byte;
+use crate::sources::compression::{Compression, Encoded, Encoder};
+use crate::sources::uri::write_path;
+use crate::{
+    DynWriter, Endpoint, Fabrix, FabrixError, FabrixResult, IntoSource, IntoSourceStream,
+    WriteOptions,
+};
 
 // TODO:
 // custom value types cannot be written to csv files
@@ -18,72 +25,117 @@ use crate::{Fabrix, FabrixError, FabrixResult, IntoSource, WriteOptions};
 // CSV Writer
 // ================================================================================================
 
+/// CSV Writer
+///
+/// Settings are kept until `finish`, where a polars `CsvWriter` is built over the writer, or over
+/// a compressing writer if a codec is set (see `Compression`).
 pub struct Writer<W: Write> {
-    csv_writer: Option<CsvWriter<W>>,
+    writer: Option<W>,
+    compression: Option<Compression>,
+    has_header: Option<bool>,
+    delimiter: Option<u8>,
+    date_format: Option<String>,
+    time_format: Option<String>,
+    datetime_format: Option<String>,
+    quoting_char: Option<u8>,
 }
 
 impl<W: Write> Writer<W> {
     pub fn new(writer: W) -> Self {
         Self {
-            csv_writer: Some(CsvWriter::new(writer)),
+            writer: Some(writer),
+            compression: None,
+            has_header: None,
+            delimiter: None,
+            date_format: None,
+            time_format: None,
+            datetime_format: None,
+            quoting_char: None,
         }
     }
 
     pub fn has_writer(&self) -> bool {
-        self.csv_writer.is_some()
+        self.writer.is_some()
     }
 
     pub fn new_writer(&mut self, writer: W) -> &mut Self {
-        self.csv_writer = Some(CsvWriter::new(writer));
+        self.writer = Some(writer);
+        self
+    }
+
+    pub fn with_compression(&mut self, compression: Compression) -> &mut Self {
+        self.compression = Some(compression);
         self
     }
 
     pub fn has_header(&mut self, has_header: bool) -> &mut Self {
-        self.csv_writer = self.csv_writer.take().map(|r| r.has_header(has_header));
+        self.has_header = Some(has_header);
         self
     }
 
     pub fn with_delimiter(&mut self, delimiter: u8) -> &mut Self {
-        self.csv_writer = self.csv_writer.take().map(|r| r.with_delimiter(delimiter));
+        self.delimiter = Some(delimiter);
         self
     }
 
     pub fn with_date_format(&mut self, format: &str) -> &mut Self {
-        self.csv_writer = self
-            .csv_writer
-            .take()
-            .map(|r| r.with_date_format(Some(format.to_owned())));
+        self.date_format = Some(format.to_owned());
         self
     }
 
     pub fn with_time_format(&mut self, format: &str) -> &mut Self {
-        self.csv_writer = self
-            .csv_writer
-            .take()
-            .map(|r| r.with_time_format(Some(format.to_owned())));
+        self.time_format = Some(format.to_owned());
         self
     }
 
     pub fn with_datetime_format(&mut self, format: &str) -> &mut Self {
-        self.csv_writer = self
-            .csv_writer
-            .take()
-            .map(|r| r.with_datetime_format(Some(format.to_owned())));
+        self.datetime_format = Some(format.to_owned());
         self
     }
 
     pub fn with_quoting_char(&mut self, char: u8) -> &mut Self {
-        self.csv_writer = self.csv_writer.take().map(|r| r.with_quoting_char(char));
+        self.quoting_char = Some(char);
         self
     }
 
+    /// a polars `CsvWriter` with the settings
+    fn csv_writer<S: Write>(&self, sink: S) -> CsvWriter<S> {
+        let mut writer = CsvWriter::new(sink);
+        if let Some(has_header) = self.has_header {
+            writer = writer.has_header(has_header);
+        }
+        if let Some(delimiter) = self.delimiter {
+            writer = writer.with_delimiter(delimiter);
+        }
+        if let Some(format) = &self.date_format {
+            writer = writer.with_date_format(Some(format.clone()));
+        }
+        if let Some(format) = &self.time_format {
+            writer = writer.with_time_format(Some(format.clone()));
+        }
+        if let Some(format) = &self.datetime_format {
+            writer = writer.with_datetime_format(Some(format.clone()));
+        }
+        if let Some(quoting_char) = self.quoting_char {
+            writer = writer.with_quoting_char(quoting_char);
+        }
+        writer
+    }
+
     pub fn finish(&mut self, mut fabrix: Fabrix) -> FabrixResult<()> {
-        let mut writer = self
-            .csv_writer
+        let writer = self
+            .writer
             .take()
             .ok_or(FabrixError::NotInitialized("CsvWriter"))?;
 
-        writer.finish(&mut fabrix.data)?;
+        match self.compression {
+            Some(compression) => {
+                let mut encoder = Encoder::new(writer, compression)?;
+                self.csv_writer(&mut encoder).finish(&mut fabrix.data)?;
+                encoder.finish()?;
+            }
+            None => self.csv_writer(writer).finish(&mut fabrix.data)?,
+        }
         Ok(())
     }
 }
@@ -98,7 +150,21 @@ impl<'a> TryFrom<CsvSource<'a>> for Writer<File> {
     fn try_from(source: CsvSource<'a>) -> FabrixResult<Self> {
         match source {
             CsvSource::File(file) => Ok(Self::new(file)),
-            CsvSource::Path(path) => Ok(Self::new(File::create(path)?)),
+            CsvSource::Path(path) => {
+                let mut writer = Self::new(File::create(path)?);
+                if let Some(compression) = Compression::from_path(path) {
+                    writer.with_compression(compression);
+                }
+                Ok(writer)
+            }
+            CsvSource::Uri(uri) => {
+                let path = write_path(uri)?;
+                let mut writer = Self::new(File::create(&path)?);
+                if let Some(compression) = Compression::from_path(&path) {
+                    writer.with_compression(compression);
+                }
+                Ok(writer)
+            }
             _ => Err(FabrixError::UnsupportedType(UNSUPPORTED_TYPE)),
         }
     }
@@ -127,6 +193,8 @@ pub struct CsvWriteOptions<'a> {
     pub time_format: Option<&'a str>,
     pub datetime_format: Option<&'a str>,
     pub quoting_char: Option<u8>,
+    /// detected by the extension of the path if not set, when the writer is made from a path
+    pub compression: Option<Compression>,
 }
 
 impl<'a> WriteOptions for CsvWriteOptions<'a> {
@@ -173,17 +241,209 @@ where
         if let Some(quoting_char) = options.quoting_char {
             self.with_quoting_char(quoting_char);
         }
+        if let Some(compression) = options.compression {
+            self.with_compression(compression);
+        }
 
         self.finish(fabrix)
     }
 }
 
+// ================================================================================================
+// Csv writer spec & DynWriter impl
+// ================================================================================================
+
+/// a csv file writer, which can be deserialized from an options map (see `SourceRegistry`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct WriterSpec {
+    pub path: String,
+    pub has_header: Option<bool>,
+    pub delimiter: Option<char>,
+    pub date_format: Option<String>,
+    pub time_format: Option<String>,
+    pub datetime_format: Option<String>,
+    pub quoting_char: Option<char>,
+    pub compression: Option<Compression>,
+}
+
+impl WriterSpec {
+    pub fn write_options(&self) -> FabrixResult<CsvWriteOptions<'_>> {
+        Ok(CsvWriteOptions {
+            has_header: self.has_header,
+            delimiter: self
+                .delimiter
+                .map(|c| ascii_byte("delimiter", c))
+                .transpose()?,
+            date_format: self.date_format.as_deref(),
+            time_format: self.time_format.as_deref(),
+            datetime_format: self.datetime_format.as_deref(),
+            quoting_char: self
+                .quoting_char
+                .map(|c| ascii_byte("quoting char", c))
+                .transpose()?,
+            compression: self
+                .compression
+                .or_else(|| Compression::from_path(&self.path)),
+        })
+    }
+}
+
+#[async_trait]
+impl DynWriter for WriterSpec {
+    fn source_type(&self) -> &str {
+        CsvWriteOptions::source_type()
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        Endpoint::file(self.source_type(), &self.path)
+    }
+
+    fn sync_write(&mut self, fabrix: Fabrix) -> FabrixResult<()> {
+        let options = self.write_options()?;
+        Writer::new(File::create(&self.path)?).sync_write(fabrix, &options)
+    }
+
+    async fn async_write(&mut self, fabrix: Fabrix) -> FabrixResult<()> {
+        self.sync_write(fabrix)
+    }
+}
+
+// ================================================================================================
+// CSV Stream Writer
+// ================================================================================================
+
+/// CSV Stream Writer
+///
+/// Write batches to the same csv file, the header is only written along with the first batch.
+///
+/// With a codec, set by `with_compression` or the write options, batches are compressed as they
+/// are written, and the trailer of the codec is written by `finish_stream`.
+pub struct StreamWriter<W: Write> {
+    writer: Option<W>,
+    sink: Option<Encoded<W>>,
+    compression: Option<Compression>,
+    has_written: bool,
+}
+
+impl<W: Write> StreamWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Some(writer),
+            sink: None,
+            compression: None,
+            has_written: false,
+        }
+    }
+
+    pub fn with_compression(&mut self, compression: Compression) -> &mut Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// the sink of the batches, created before the first batch
+    fn take_sink(&mut self, options: &CsvWriteOptions) -> FabrixResult<Encoded<W>> {
+        if let Some(sink) = self.sink.take() {
+            return Ok(sink);
+        }
+
+        let writer = self
+            .writer
+            .take()
+            .ok_or(FabrixError::NotInitialized("CsvStreamWriter"))?;
+        Encoded::new(writer, options.compression.or(self.compression))
+    }
+}
+
+impl<'a> TryFrom<CsvSource<'a>> for StreamWriter<File> {
+    type Error = FabrixError;
+
+    fn try_from(source: CsvSource<'a>) -> FabrixResult<Self> {
+        match source {
+            CsvSource::File(file) => Ok(Self::new(file)),
+            CsvSource::Path(path) => {
+                let mut writer = Self::new(File::create(path)?);
+                if let Some(compression) = Compression::from_path(path) {
+                    writer.with_compression(compression);
+                }
+                Ok(writer)
+            }
+            CsvSource::Uri(uri) => {
+                let path = write_path(uri)?;
+                let mut writer = Self::new(File::create(&path)?);
+                if let Some(compression) = Compression::from_path(&path) {
+                    writer.with_compression(compression);
+                }
+                Ok(writer)
+            }
+            _ => Err(FabrixError::UnsupportedType(UNSUPPORTED_TYPE)),
+        }
+    }
+}
+
+impl<'a> TryFrom<CsvSource<'a>> for StreamWriter<&'a mut Cursor<Vec<u8>>> {
+    type Error = FabrixError;
+
+    fn try_from(source: CsvSource<'a>) -> FabrixResult<Self> {
+        match source {
+            CsvSource::BuffWrite(bytes) => Ok(Self::new(bytes)),
+            _ => Err(FabrixError::UnsupportedType(UNSUPPORTED_TYPE)),
+        }
+    }
+}
+
+#[async_trait]
+impl<'a, W> IntoSourceStream<CsvWriteOptions<'a>> for StreamWriter<W>
+where
+    W: Write + Send,
+{
+    async fn write_batch(
+        &mut self,
+        mut fabrix: Fabrix,
+        options: &CsvWriteOptions<'a>,
+    ) -> FabrixResult<()> {
+        let has_header = options.has_header.unwrap_or(true) && !self.has_written;
+        let sink = self.take_sink(options)?;
+        let sink = self.sink.insert(sink);
+        let mut writer = CsvWriter::new(sink).has_header(has_header);
+        if let Some(delimiter) = options.delimiter {
+            writer = writer.with_delimiter(delimiter);
+        }
+        if let Some(date_format) = options.date_format {
+            writer = writer.with_date_format(Some(date_format.to_owned()));
+        }
+        if let Some(time_format) = options.time_format {
+            writer = writer.with_time_format(Some(time_format.to_owned()));
+        }
+        if let Some(datetime_format) = options.datetime_format {
+            writer = writer.with_datetime_format(Some(datetime_format.to_owned()));
+        }
+        if let Some(quoting_char) = options.quoting_char {
+            writer = writer.with_quoting_char(quoting_char);
+        }
+
+        writer.finish(&mut fabrix.data)?;
+        self.has_written = true;
+        Ok(())
+    }
+
+    async fn finish_stream(&mut self, options: &CsvWriteOptions<'a>) -> FabrixResult<()> {
+        if !self.has_written {
+            return Err(FabrixError::EmptyContent("CsvStreamWriter"));
+        }
+        let mut writer = self.take_sink(options)?.finish()?;
+        writer.flush()?;
+        self.writer = Some(writer);
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod test_csv_writer {
     use super::*;
     use crate::{date, datetime, fx, time};
 
     const CSV_FILE_PATH: &str = "../cache/write.csv";
+    const CSV_STREAM_FILE_PATH: &str = "../cache/write_stream.csv";
 
     #[test]
     fn file_writer() {
@@ -206,4 +466,132 @@ mod test_csv_writer {
         assert!(foo.is_ok(), "writing to csv should not fail");
         assert!(!writer.has_writer());
     }
+
+    #[test]
+    fn compressed_write() {
+        use crate::CsvReader;
+
+        let fx = fx![
+            "id";
+            "id" => [1, 2, 3],
+            "name" => ["a", "b", "c"],
+        ]
+        .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("write.csv.gz");
+        let path = path.to_str().unwrap();
+
+        // gzip by the extension
+        let mut writer: Writer<File> = CsvSource::Path(path).try_into().unwrap();
+        writer.finish(fx.clone()).unwrap();
+        let mut file = File::open(path).unwrap();
+        assert_eq!(
+            Compression::detect(&mut file).unwrap(),
+            Some(Compression::Gzip)
+        );
+
+        // gzip by the magic bytes
+        let mut reader: CsvReader<File> = CsvSource::Path(path).try_into().unwrap();
+        let res = reader.finish(Some(0)).unwrap();
+        crate::assert_fabrix_eq!(res, fx);
+
+        // explicit codec
+        let mut buff = Cursor::new(Vec::new());
+        let options = CsvWriteOptions {
+            compression: Some(Compression::Zstd),
+            ..Default::default()
+        };
+        Writer::new(&mut buff)
+            .sync_write(fx.clone(), &options)
+            .unwrap();
+        let mut reader = CsvReader::new(Cursor::new(buff.into_inner()));
+        let res = reader
+            .with_compression(Compression::Zstd)
+            .finish(Some(0))
+            .unwrap();
+        crate::assert_fabrix_eq!(res, fx);
+    }
+
+    #[tokio::test]
+    async fn stream_dispatch() {
+        use crate::{CsvReadOptions, CsvReader, CsvStreamReader, StreamDispatcher};
+
+        let reader: CsvStreamReader<File> = CsvSource::Path("../mock/test.csv").try_into().unwrap();
+        let writer: StreamWriter<File> = CsvSource::Path(CSV_STREAM_FILE_PATH).try_into().unwrap();
+
+        let mut dispatcher = StreamDispatcher::new(reader, writer);
+        dispatcher.with_batch_size(30).with_buffer_size(2);
+
+        let rows = dispatcher
+            .dispatch(&CsvReadOptions::default(), &CsvWriteOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(rows, 100);
+
+        let expected = CsvReader::new(File::open("../mock/test.csv").unwrap())
+            .finish(None)
+            .unwrap();
+        let written = CsvReader::new(File::open(CSV_STREAM_FILE_PATH).unwrap())
+            .finish(None)
+            .unwrap();
+        crate::assert_fabrix_eq!(written, expected);
+    }
+
+    #[tokio::test]
+    async fn empty_stream() {
+        let mut buff = Cursor::new(Vec::<u8>::new());
+        let mut writer = StreamWriter::new(&mut buff);
+        let res = writer.finish_stream(&CsvWriteOptions::default()).await;
+        assert!(matches!(res, Err(FabrixError::EmptyContent(_))));
+    }
+
+    #[tokio::test]
+    async fn compressed_stream_dispatch() {
+        use futures::StreamExt;
+
+        use crate::{
+            CsvReadOptions, CsvReader, CsvStreamReader, FromSourceStream, StreamDispatcher,
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("write_stream.csv.zst");
+        let path = path.to_str().unwrap();
+
+        // zstd by the extension, batch by batch
+        let reader: CsvStreamReader<File> = CsvSource::Path("../mock/test.csv").try_into().unwrap();
+        let writer: StreamWriter<File> = CsvSource::Path(path).try_into().unwrap();
+        let mut dispatcher = StreamDispatcher::new(reader, writer);
+        dispatcher.with_batch_size(30);
+        dispatcher
+            .dispatch(&CsvReadOptions::default(), &CsvWriteOptions::default())
+            .await
+            .unwrap();
+        let mut file = File::open(path).unwrap();
+        assert_eq!(
+            Compression::detect(&mut file).unwrap(),
+            Some(Compression::Zstd)
+        );
+
+        // zstd by the magic bytes, decompressed on the fly
+        let mut reader: CsvStreamReader<File> = CsvSource::Path(path).try_into().unwrap();
+        let batches = reader
+            .read_stream(&CsvReadOptions::default(), 30)
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+        let heights = batches
+            .iter()
+            .map(|b| b.as_ref().unwrap().height())
+            .collect::<Vec<_>>();
+        assert_eq!(heights, vec![30, 30, 30, 10]);
+
+        let expected = CsvReader::new(File::open("../mock/test.csv").unwrap())
+            .finish(None)
+            .unwrap();
+        let written = CsvReader::new(File::open(path).unwrap())
+            .finish(None)
+            .unwrap();
+        crate::assert_fabrix_eq!(written, expected);
+    }
 }
@@ -0,0 +1,462 @@
+//! Files
+//!
+//! Reads many files of one format into one `Fabrix`, from the `Glob` & `Dir` variants of
+//! `CsvSource`, `ParquetSource` & `JsonSource`:
+//! - Glob: files matching a pattern, e.g. `reports/2026-*.csv`
+//! - Dir: files of a directory with the extension of the format, in any case, e.g. `a.csv` &
+//!   `B.CSV` of `reports`; csv & json files may also be compressed, e.g. `c.csv.gz`
+//!
+//! Files are ordered (by name by default) and optionally limited, then read in parallel with the
+//! same read options. The frames are unioned by `union_fabrix`: columns are aligned by name, a
+//! missing column is filled with nulls, and types are promoted. With `with_source_file`, the
+//! `__source_file` column holds the path of the file of each row.
+//!
+//! The union keeps the index of the first file, if any. `async_read` reads on tokio's blocking
+//! threads, so that neither the file io nor the parallel parsing blocks the async runtime.
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+use serde::Deserialize;
+
+use super::compression::Compression;
+use crate::{union_fabrix, Fabrix, FabrixError, FabrixResult};
+
+/// name of the column holding the file of each row
+pub const SOURCE_FILE_COLUMN: &str = "__source_file";
+
+/// order in which files are read, and limited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileOrder {
+    #[default]
+    Name,
+    NameDesc,
+    Modified,
+    ModifiedDesc,
+}
+
+/// files of a glob pattern, read into one `Fabrix`
+#[derive(Debug, Clone)]
+pub struct FilesReader {
+    pattern: String,
+    // extension of the files of a dir, and whether a codec extension may follow it
+    extension: Option<(String, bool)>,
+    order: FileOrder,
+    limit: Option<usize>,
+    source_file: bool,
+}
+
+impl FilesReader {
+    pub fn glob(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.to_owned(),
+            extension: None,
+            order: FileOrder::default(),
+            limit: None,
+            source_file: false,
+        }
+    }
+
+    /// files of `dir` (not recursive) with the extension, compared case-insensitively
+    pub fn dir(dir: &str, extension: &str) -> Self {
+        let dir = glob::Pattern::escape(dir.trim_end_matches('/'));
+        let mut reader = Self::glob(&format!("{dir}/*"));
+        reader.extension = Some((extension.to_owned(), false));
+        reader
+    }
+
+    /// also match files of a dir whose extension is followed by a codec extension, e.g.
+    /// `reports.csv.gz`, for formats which are decompressed on read (see `Compression`)
+    pub fn with_compressed(&mut self, compressed: bool) -> &mut Self {
+        if let Some((_, c)) = self.extension.as_mut() {
+            *c = compressed;
+        }
+        self
+    }
+
+    pub fn with_order(&mut self, order: FileOrder) -> &mut Self {
+        self.order = order;
+        self
+    }
+
+    /// read the first `limit` files of the order only
+    pub fn with_limit(&mut self, limit: usize) -> &mut Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// add the `__source_file` column
+    pub fn with_source_file(&mut self, source_file: bool) -> &mut Self {
+        self.source_file = source_file;
+        self
+    }
+
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// matched files, ordered & limited
+    pub fn files(&self) -> FabrixResult<Vec<PathBuf>> {
+        let paths = glob::glob(&self.pattern).map_err(|e| {
+            FabrixError::InvalidArgument(format!("invalid pattern {}: {e}", self.pattern))
+        })?;
+        let mut files = Vec::new();
+        for path in paths {
+            let path = path.map_err(|e| e.into_error())?;
+            let matched = match &self.extension {
+                Some((ext, compressed)) => has_extension(&path, ext, *compressed),
+                None => true,
+            };
+            if matched && path.is_file() {
+                files.push(path);
+            }
+        }
+
+        match self.order {
+            FileOrder::Name => files.sort(),
+            FileOrder::NameDesc => files.sort_by(|a, b| b.cmp(a)),
+            FileOrder::Modified | FileOrder::ModifiedDesc => {
+                let mut modified = files
+                    .into_iter()
+                    .map(|p| Ok((p.metadata()?.modified()?, p)))
+                    .collect::<FabrixResult<Vec<_>>>()?;
+                modified.sort();
+                if self.order == FileOrder::ModifiedDesc {
+                    modified.reverse();
+                }
+                files = modified.into_iter().map(|(_, p)| p).collect();
+            }
+        }
+        if let Some(limit) = self.limit {
+            files.truncate(limit);
+        }
+
+        Ok(files)
+    }
+
+    /// read each file in parallel by `read`, then union the results
+    pub fn read_with<F>(&self, read: F) -> FabrixResult<Fabrix>
+    where
+        F: Fn(File) -> FabrixResult<Fabrix> + Send + Sync,
+    {
+        let files = self.files()?;
+        if files.is_empty() {
+            return Err(FabrixError::NotFound(format!(
+                "no file matches {}",
+                self.pattern
+            )));
+        }
+
+        let frames = files
+            .par_iter()
+            .map(|p| Ok((p.display().to_string(), read(File::open(p)?)?)))
+            .collect::<FabrixResult<Vec<_>>>()?;
+        let index = frames[0].1.index_tag().map(|it| it.name().to_owned());
+
+        let source_tag = self.source_file.then_some(SOURCE_FILE_COLUMN);
+        let mut res = union_fabrix(frames, source_tag)?;
+        if let Some(index) = index {
+            res.set_index_tag(index)?;
+        }
+
+        Ok(res)
+    }
+}
+
+/// whether the extension of `path` is `extension` in any case, optionally followed by the extension
+/// of a codec
+fn has_extension(path: &Path, extension: &str, compressed: bool) -> bool {
+    let path = match Compression::from_path(path) {
+        Some(_) if compressed => Path::new(path.file_stem().unwrap_or_default()),
+        _ => path,
+    };
+
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map_or(false, |e| e.eq_ignore_ascii_case(extension))
+}
+
+// ================================================================================================
+// FilesReader TryFrom sources & FromSource impl
+// ================================================================================================
+
+#[cfg(feature = "csv")]
+mod csv_files {
+    use async_trait::async_trait;
+
+    use super::FilesReader;
+    use crate::sources::csv::{CsvReadOptions, CsvReader, CsvSource, UNSUPPORTED_TYPE};
+    use crate::sources::spawn_blocking;
+    use crate::{Fabrix, FabrixError, FabrixResult, FromSource};
+
+    impl<'a> TryFrom<CsvSource<'a>> for FilesReader {
+        type Error = FabrixError;
+
+        fn try_from(source: CsvSource<'a>) -> FabrixResult<Self> {
+            match source {
+                CsvSource::Glob(pattern) => Ok(Self::glob(pattern)),
+                CsvSource::Dir(dir) => {
+                    let mut reader = Self::dir(dir, "csv");
+                    reader.with_compressed(true);
+                    Ok(reader)
+                }
+                _ => Err(FabrixError::UnsupportedType(UNSUPPORTED_TYPE)),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl<'a> FromSource<'a, CsvReadOptions> for FilesReader {
+        async fn async_read<'o>(&mut self, options: &'o CsvReadOptions) -> FabrixResult<Fabrix>
+        where
+            'o: 'a,
+        {
+            let (reader, options) = (self.clone(), options.clone());
+            spawn_blocking(move || {
+                reader.read_with(|file| CsvReader::new(file).sync_read(&options))
+            })
+            .await
+        }
+
+        fn sync_read<'o>(&mut self, options: &'o CsvReadOptions) -> FabrixResult<Fabrix>
+        where
+            'o: 'a,
+        {
+            self.read_with(|file| CsvReader::new(file).sync_read(options))
+        }
+    }
+}
+
+#[cfg(feature = "parquet")]
+mod parquet_files {
+    use async_trait::async_trait;
+
+    use super::FilesReader;
+    use crate::sources::parquet::{
+        ParquetReadOptions, ParquetReader, ParquetSource, UNSUPPORTED_TYPE,
+    };
+    use crate::sources::spawn_blocking;
+    use crate::{Fabrix, FabrixError, FabrixResult, FromSource};
+
+    impl<'a> TryFrom<ParquetSource<'a>> for FilesReader {
+        type Error = FabrixError;
+
+        fn try_from(source: ParquetSource<'a>) -> FabrixResult<Self> {
+            match source {
+                ParquetSource::Glob(pattern) => Ok(Self::glob(pattern)),
+                ParquetSource::Dir(dir) => Ok(Self::dir(dir, "parquet")),
+                _ => Err(FabrixError::UnsupportedType(UNSUPPORTED_TYPE)),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl<'a> FromSource<'a, ParquetReadOptions> for FilesReader {
+        async fn async_read<'o>(&mut self, options: &'o ParquetReadOptions) -> FabrixResult<Fabrix>
+        where
+            'o: 'a,
+        {
+            let (reader, options) = (self.clone(), options.clone());
+            spawn_blocking(move || {
+                reader.read_with(|file| ParquetReader::new(file).sync_read(&options))
+            })
+            .await
+        }
+
+        fn sync_read<'o>(&mut self, options: &'o ParquetReadOptions) -> FabrixResult<Fabrix>
+        where
+            'o: 'a,
+        {
+            self.read_with(|file| ParquetReader::new(file).sync_read(options))
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+mod json_files {
+    use std::io::BufReader;
+
+    use async_trait::async_trait;
+
+    use super::FilesReader;
+    use crate::sources::json::{JsonReadOptions, JsonReader, JsonSource, UNSUPPORTED_TYPE};
+    use crate::sources::spawn_blocking;
+    use crate::{Fabrix, FabrixError, FabrixResult, FromSource};
+
+    impl<'a> TryFrom<JsonSource<'a>> for FilesReader {
+        type Error = FabrixError;
+
+        fn try_from(source: JsonSource<'a>) -> FabrixResult<Self> {
+            match source {
+                JsonSource::Glob(pattern) => Ok(Self::glob(pattern)),
+                JsonSource::Dir(dir) => {
+                    let mut reader = Self::dir(dir, "json");
+                    reader.with_compressed(true);
+                    Ok(reader)
+                }
+                _ => Err(FabrixError::UnsupportedType(UNSUPPORTED_TYPE)),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl<'a> FromSource<'a, JsonReadOptions> for FilesReader {
+        async fn async_read<'o>(&mut self, options: &'o JsonReadOptions) -> FabrixResult<Fabrix>
+        where
+            'o: 'a,
+        {
+            let (reader, options) = (self.clone(), options.clone());
+            spawn_blocking(move || {
+                reader.read_with(|file| JsonReader::new(BufReader::new(file)).sync_read(&options))
+            })
+            .await
+        }
+
+        fn sync_read<'o>(&mut self, options: &'o JsonReadOptions) -> FabrixResult<Fabrix>
+        where
+            'o: 'a,
+        {
+            self.read_with(|file| JsonReader::new(BufReader::new(file)).sync_read(options))
+        }
+    }
+}
+
+#[cfg(test)]
+mod files_tests {
+    use std::fs;
+
+    use super::*;
+
+    const FILES_DIR: &str = "../cache/files";
+
+    fn mock_files() {
+        fs::create_dir_all(FILES_DIR).unwrap();
+        let files = [
+            ("2026-01.csv", "id,name\n1,a\n2,b\n"),
+            ("2026-02.csv", "id,name,amount\n3,c,1.5\n"),
+            ("2026-03.csv", "id,amount\n4,2\n"),
+            ("notes.txt", "not a csv"),
+        ];
+        for (name, content) in files {
+            fs::write(format!("{FILES_DIR}/{name}"), content).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_files() {
+        mock_files();
+
+        let mut reader = FilesReader::dir(FILES_DIR, "csv");
+        let names = reader
+            .files()
+            .unwrap()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["2026-01.csv", "2026-02.csv", "2026-03.csv"]);
+
+        reader.with_order(FileOrder::NameDesc).with_limit(2);
+        let files = reader.files().unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files[0].ends_with("2026-03.csv"));
+
+        let missing = FilesReader::glob(&format!("{FILES_DIR}/1999-*.csv"));
+        assert!(missing.files().unwrap().is_empty());
+        assert!(missing.read_with(|_| Ok(Fabrix::empty())).is_err());
+    }
+
+    #[test]
+    fn test_dir_extensions() {
+        let dir = "../cache/files_ext";
+        fs::create_dir_all(dir).unwrap();
+        for name in ["a.csv", "B.CSV", "c.csv.gz", "d.json.gz", "e.txt", "f.gz"] {
+            fs::write(format!("{dir}/{name}"), "id\n1\n").unwrap();
+        }
+        let names = |reader: &FilesReader| {
+            reader
+                .files()
+                .unwrap()
+                .iter()
+                .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let mut reader = FilesReader::dir(dir, "csv");
+        assert_eq!(names(&reader), vec!["B.CSV", "a.csv"]);
+        reader.with_compressed(true);
+        assert_eq!(names(&reader), vec!["B.CSV", "a.csv", "c.csv.gz"]);
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn test_csv_dir_compressed() {
+        use std::io::Write;
+
+        use flate2::write::GzEncoder;
+
+        use crate::sources::csv::{CsvReadOptions, CsvSource};
+        use crate::FromSource;
+
+        let dir = "../cache/files_gz";
+        fs::create_dir_all(dir).unwrap();
+        fs::write(format!("{dir}/a.csv"), "id,amount\n1,\n").unwrap();
+        let mut gz = GzEncoder::new(
+            File::create(format!("{dir}/b.CSV.gz")).unwrap(),
+            flate2::Compression::default(),
+        );
+        gz.write_all(b"id,amount\n2,2.5\n").unwrap();
+        gz.finish().unwrap();
+
+        let mut reader: FilesReader = CsvSource::Dir(dir).try_into().unwrap();
+        let fx = reader.sync_read(&CsvReadOptions::default()).unwrap();
+        assert_eq!(fx.shape(), (2, 2));
+        // the empty `amount` of `a.csv` is promoted to the floats of `b.CSV.gz`
+        assert_eq!(fx.dtypes().unwrap()[1], &crate::ValueType::F64);
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn test_csv_glob() {
+        use crate::sources::csv::{CsvReadOptions, CsvSource};
+        use crate::FromSource;
+
+        mock_files();
+
+        let pattern = format!("{FILES_DIR}/2026-*.csv");
+        let mut reader: FilesReader = CsvSource::Glob(&pattern).try_into().unwrap();
+        reader.with_source_file(true);
+
+        let fx = reader.sync_read(&CsvReadOptions::default()).unwrap();
+        assert_eq!(fx.shape(), (4, 4));
+        assert_eq!(
+            fx.get_column_names(),
+            vec!["id", "name", "amount", SOURCE_FILE_COLUMN]
+        );
+        let source = fx.get_column(SOURCE_FILE_COLUMN).unwrap();
+        assert!(source.get(3).unwrap().to_string().ends_with("2026-03.csv"));
+
+        reader.with_order(FileOrder::NameDesc).with_limit(1);
+        let fx = reader.sync_read(&CsvReadOptions::default()).unwrap();
+        assert_eq!(
+            fx.get_column_names(),
+            vec!["id", "amount", SOURCE_FILE_COLUMN]
+        );
+    }
+
+    #[cfg(feature = "csv")]
+    #[tokio::test]
+    async fn test_csv_glob_async() {
+        use crate::sources::csv::{CsvReadOptions, CsvSource};
+        use crate::FromSource;
+
+        mock_files();
+
+        let pattern = format!("{FILES_DIR}/2026-*.csv");
+        let mut reader: FilesReader = CsvSource::Glob(&pattern).try_into().unwrap();
+
+        let fx = reader.async_read(&CsvReadOptions::default()).await.unwrap();
+        assert_eq!(fx.shape(), (4, 3));
+    }
+}
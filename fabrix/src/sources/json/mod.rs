@@ -8,8 +8,8 @@ use std::io::Cursor;
 pub mod reader;
 pub mod writer;
 
-pub use reader::{JsonReadOptions, Reader as JsonReader};
-pub use writer::{JsonWriteOptions, Writer as JsonWriter};
+pub use reader::{JsonReadOptions, Reader as JsonReader, ReaderSpec as JsonReaderSpec};
+pub use writer::{JsonWriteOptions, Writer as JsonWriter, WriterSpec as JsonWriterSpec};
 
 pub(crate) const UNSUPPORTED_TYPE: &str = "Unsupported JsonSource type";
 
@@ -18,6 +18,10 @@ pub enum JsonSource<'a> {
     File(File),
     Path(&'a str),
     Uri(&'a str),
+    /// files matching a glob pattern, read by `FilesReader`
+    Glob(&'a str),
+    /// files of a directory with the extension of the format, read by `FilesReader`
+    Dir(&'a str),
     BuffRead(Cursor<Vec<u8>>),
     BuffWrite(&'a mut Cursor<Vec<u8>>),
 }
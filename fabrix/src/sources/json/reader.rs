@@ -7,65 +7,83 @@ use std::io::{BufReader, Cursor};
 
 use async_trait::async_trait;
 use polars::io::mmap::MmapBytesReader;
-use polars::prelude::{JsonFormat, JsonReader, SerReader};
+use polars::prelude::{JsonFormat, JsonReader, Schema as PolarsSchema, SerReader};
+use serde::Deserialize;
 
-use crate::{Fabrix, FabrixError, FabrixResult, FromSource, JsonSource, ReadOptions, Schema};
+use crate::{
+    DynReader, Endpoint, Fabrix, FabrixError, FabrixResult, FromSource, JsonSource, ReadOptions,
+    Schema,
+};
 
 use super::UNSUPPORTED_TYPE;
+use crate::sources::compression::{decode, Compression, Decoded};
+use crate::sources::uri::UriResolver;
 
 // ================================================================================================
 // JSON Reader
 // ================================================================================================
 
+/// JSON Reader
+///
+/// Settings are kept until `finish`, where a polars `JsonReader` is built over the source, or
+/// over its decompressed content if it is compressed (see `Compression`).
 pub struct Reader<R: MmapBytesReader> {
-    json_reader: Option<JsonReader<R>>,
+    reader: Option<R>,
+    compression: Option<Compression>,
+    schema: Option<PolarsSchema>,
+    infer_schema_len: Option<usize>,
+    batch_size: Option<usize>,
+    projection: Option<Vec<String>>,
+    json_format: Option<JsonFormat>,
+    rechunk: Option<bool>,
 }
 
 impl<R: MmapBytesReader> Reader<R> {
     pub fn new(reader: R) -> Self {
         Self {
-            json_reader: Some(JsonReader::new(reader)),
+            reader: Some(reader),
+            compression: None,
+            schema: None,
+            infer_schema_len: None,
+            batch_size: None,
+            projection: None,
+            json_format: None,
+            rechunk: None,
         }
     }
 
     pub fn has_reader(&self) -> bool {
-        self.json_reader.is_some()
+        self.reader.is_some()
     }
 
     pub fn new_reader(&mut self, reader: R) -> &mut Self {
-        self.json_reader = Some(JsonReader::new(reader));
+        self.reader = Some(reader);
+        self
+    }
+
+    /// codec of the source, detected by its magic bytes if not set
+    pub fn with_compression(&mut self, compression: Compression) -> &mut Self {
+        self.compression = Some(compression);
         self
     }
 
     pub fn with_schema(&mut self, schema: &Schema) -> &mut Self {
-        self.json_reader = self
-            .json_reader
-            .take()
-            .map(|r| r.with_schema(schema.as_ref()));
+        self.schema = Some(schema.as_ref().clone());
         self
     }
 
     pub fn with_infer_schema_len(&mut self, max_records: usize) -> &mut Self {
-        self.json_reader = self
-            .json_reader
-            .take()
-            .map(|r| r.infer_schema_len(Some(max_records)));
+        self.infer_schema_len = Some(max_records);
         self
     }
 
     pub fn with_batch_size(&mut self, batch_size: usize) -> &mut Self {
-        self.json_reader = self
-            .json_reader
-            .take()
-            .map(|r| r.with_batch_size(batch_size));
+        self.batch_size = Some(batch_size);
         self
     }
 
     pub fn with_projection(&mut self, projection: Vec<String>) -> &mut Self {
-        self.json_reader = self
-            .json_reader
-            .take()
-            .map(|r| r.with_projection(Some(projection)));
+        self.projection = Some(projection);
         self
     }
 
@@ -76,31 +94,51 @@ impl<R: MmapBytesReader> Reader<R> {
             JsonFormat::JsonLines
         };
 
-        self.json_reader = self
-            .json_reader
-            .take()
-            .map(|r| r.with_json_format(json_format));
+        self.json_format = Some(json_format);
         self
     }
 
     pub fn with_rechunk(&mut self, rechunk: bool) -> &mut Self {
-        self.json_reader = self.json_reader.take().map(|r| r.set_rechunk(rechunk));
+        self.rechunk = Some(rechunk);
         self
     }
 
+    /// a polars `JsonReader` with the settings
+    fn json_reader<S: MmapBytesReader>(&self, source: S) -> JsonReader<S> {
+        let mut reader = JsonReader::new(source);
+        if let Some(schema) = &self.schema {
+            reader = reader.with_schema(schema);
+        }
+        if let Some(max_records) = self.infer_schema_len {
+            reader = reader.infer_schema_len(Some(max_records));
+        }
+        if let Some(batch_size) = self.batch_size {
+            reader = reader.with_batch_size(batch_size);
+        }
+        if let Some(projection) = &self.projection {
+            reader = reader.with_projection(Some(projection.clone()));
+        }
+        if let Some(json_format) = &self.json_format {
+            reader = reader.with_json_format(json_format.clone());
+        }
+        if let Some(rechunk) = self.rechunk {
+            reader = reader.set_rechunk(rechunk);
+        }
+        reader
+    }
+
     pub fn finish(&mut self, index: Option<usize>) -> FabrixResult<Fabrix> {
         let reader = self
-            .json_reader
+            .reader
             .take()
             .ok_or(FabrixError::NotInitialized("JsonReader"))?;
 
-        let df = reader.finish()?;
+        let df = match decode(reader, self.compression)? {
+            Decoded::Plain(r) => self.json_reader(r).finish()?,
+            Decoded::Decompressed(r) => self.json_reader(r).finish()?,
+        };
 
-        if let Some(index) = index {
-            Ok(Fabrix::new(df, index)?)
-        } else {
-            Ok(Fabrix::new_no_index(df))
-        }
+        Ok(Fabrix::new(df, index)?)
     }
 }
 
@@ -115,6 +153,9 @@ impl<'a> TryFrom<JsonSource<'a>> for Reader<BufReader<File>> {
         match source {
             JsonSource::File(file) => Ok(Reader::new(BufReader::new(file))),
             JsonSource::Path(path) => Ok(Reader::new(BufReader::new(File::open(path)?))),
+            JsonSource::Uri(uri) => Ok(Reader::new(BufReader::new(
+                UriResolver::default().to_file(uri)?,
+            ))),
             _ => Err(FabrixError::UnsupportedType(UNSUPPORTED_TYPE)),
         }
     }
@@ -126,6 +167,7 @@ impl<'a> TryFrom<JsonSource<'a>> for Reader<Cursor<Vec<u8>>> {
     fn try_from(source: JsonSource<'a>) -> Result<Self, Self::Error> {
         match source {
             JsonSource::BuffRead(bytes) => Ok(Reader::new(bytes)),
+            JsonSource::Uri(uri) => Ok(Reader::new(UriResolver::default().to_buffer(uri)?)),
             _ => Err(FabrixError::UnsupportedType(UNSUPPORTED_TYPE)),
         }
     }
@@ -135,7 +177,7 @@ impl<'a> TryFrom<JsonSource<'a>> for Reader<Cursor<Vec<u8>>> {
 // Json read options & FromSource impl
 // ================================================================================================
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct JsonReadOptions {
     pub infer_schema_len: Option<usize>,
     pub batch_size: Option<usize>,
@@ -143,6 +185,8 @@ pub struct JsonReadOptions {
     pub format_is_json: Option<bool>,
     pub rechunk: Option<bool>,
     pub index: Option<usize>,
+    /// detected by the magic bytes of the source if not set
+    pub compression: Option<Compression>,
 }
 
 impl ReadOptions for JsonReadOptions {
@@ -174,8 +218,13 @@ where
             format_is_json,
             rechunk,
             index,
+            compression,
         } = options;
 
+        if let Some(compression) = compression {
+            self.with_compression(*compression);
+        }
+
         if let Some(infer_schema_len) = infer_schema_len {
             self.with_infer_schema_len(*infer_schema_len);
         }
@@ -196,6 +245,56 @@ where
     }
 }
 
+// ================================================================================================
+// Json reader spec & DynReader impl
+// ================================================================================================
+
+/// a json file reader, which can be deserialized from an options map (see `SourceRegistry`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReaderSpec {
+    pub path: String,
+    pub infer_schema_len: Option<usize>,
+    pub batch_size: Option<usize>,
+    pub projection: Option<Vec<String>>,
+    pub format_is_json: Option<bool>,
+    pub index: Option<usize>,
+    pub compression: Option<Compression>,
+}
+
+impl ReaderSpec {
+    pub fn read_options(&self) -> JsonReadOptions {
+        JsonReadOptions {
+            infer_schema_len: self.infer_schema_len,
+            batch_size: self.batch_size,
+            projection: self.projection.clone(),
+            format_is_json: self.format_is_json,
+            rechunk: None,
+            index: self.index,
+            compression: self.compression,
+        }
+    }
+}
+
+#[async_trait]
+impl DynReader for ReaderSpec {
+    fn source_type(&self) -> &str {
+        JsonReadOptions::source_type()
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        Endpoint::file(self.source_type(), &self.path)
+    }
+
+    fn sync_read(&mut self) -> FabrixResult<Fabrix> {
+        let options = self.read_options();
+        Reader::new(BufReader::new(File::open(&self.path)?)).sync_read(&options)
+    }
+
+    async fn async_read(&mut self) -> FabrixResult<Fabrix> {
+        self.sync_read()
+    }
+}
+
 #[cfg(test)]
 mod test_json_reader {
     use super::*;
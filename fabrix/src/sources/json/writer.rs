@@ -7,32 +7,50 @@ use std::io::{Cursor, Write};
 
 use async_trait::async_trait;
 use polars::prelude::{JsonFormat, JsonWriter, SerWriter};
+use serde::Deserialize;
 
-use crate::{Fabrix, FabrixError, FabrixResult, IntoSource, JsonSource, WriteOptions};
+use crate::{
+    DynWriter, Endpoint, Fabrix, FabrixError, FabrixResult, IntoSource, JsonSource, WriteOptions,
+};
 
 use super::UNSUPPORTED_TYPE;
+use crate::sources::compression::{Compression, Encoder};
+use crate::sources::uri::write_path;
 
 // ================================================================================================
 // JSON Writer
 // ================================================================================================
 
+/// JSON Writer
+///
+/// Settings are kept until `finish`, where a polars `JsonWriter` is built over the writer, or over
+/// a compressing writer if a codec is set (see `Compression`).
 pub struct Writer<W: Write> {
-    json_writer: Option<JsonWriter<W>>,
+    writer: Option<W>,
+    compression: Option<Compression>,
+    json_format: Option<JsonFormat>,
 }
 
 impl<W: Write> Writer<W> {
     pub fn new(writer: W) -> Self {
         Self {
-            json_writer: Some(JsonWriter::new(writer)),
+            writer: Some(writer),
+            compression: None,
+            json_format: None,
         }
     }
 
     pub fn has_writer(&self) -> bool {
-        self.json_writer.is_some()
+        self.writer.is_some()
     }
 
     pub fn new_writer(&mut self, writer: W) -> &mut Self {
-        self.json_writer = Some(JsonWriter::new(writer));
+        self.writer = Some(writer);
+        self
+    }
+
+    pub fn with_compression(&mut self, compression: Compression) -> &mut Self {
+        self.compression = Some(compression);
         self
     }
 
@@ -43,20 +61,33 @@ impl<W: Write> Writer<W> {
             JsonFormat::JsonLines
         };
 
-        self.json_writer = self
-            .json_writer
-            .take()
-            .map(|r| r.with_json_format(json_format));
+        self.json_format = Some(json_format);
         self
     }
 
+    /// a polars `JsonWriter` with the settings
+    fn json_writer<S: Write>(&self, sink: S) -> JsonWriter<S> {
+        let writer = JsonWriter::new(sink);
+        match &self.json_format {
+            Some(json_format) => writer.with_json_format(json_format.clone()),
+            None => writer,
+        }
+    }
+
     pub fn finish(&mut self, mut fabrix: Fabrix) -> FabrixResult<()> {
-        let mut writer = self
-            .json_writer
+        let writer = self
+            .writer
             .take()
             .ok_or(FabrixError::NotInitialized("JsonWriter"))?;
 
-        writer.finish(&mut fabrix.data)?;
+        match self.compression {
+            Some(compression) => {
+                let mut encoder = Encoder::new(writer, compression)?;
+                self.json_writer(&mut encoder).finish(&mut fabrix.data)?;
+                encoder.finish()?;
+            }
+            None => self.json_writer(writer).finish(&mut fabrix.data)?,
+        }
         Ok(())
     }
 }
@@ -71,7 +102,21 @@ impl<'a> TryFrom<JsonSource<'a>> for Writer<File> {
     fn try_from(source: JsonSource<'a>) -> FabrixResult<Self> {
         match source {
             JsonSource::File(file) => Ok(Writer::new(file)),
-            JsonSource::Path(path) => Ok(Writer::new(File::create(path)?)),
+            JsonSource::Path(path) => {
+                let mut writer = Writer::new(File::create(path)?);
+                if let Some(compression) = Compression::from_path(path) {
+                    writer.with_compression(compression);
+                }
+                Ok(writer)
+            }
+            JsonSource::Uri(uri) => {
+                let path = write_path(uri)?;
+                let mut writer = Writer::new(File::create(&path)?);
+                if let Some(compression) = Compression::from_path(&path) {
+                    writer.with_compression(compression);
+                }
+                Ok(writer)
+            }
             _ => Err(FabrixError::UnsupportedType(UNSUPPORTED_TYPE)),
         }
     }
@@ -95,6 +140,8 @@ impl<'a> TryFrom<JsonSource<'a>> for Writer<&'a mut Cursor<Vec<u8>>> {
 #[derive(Default)]
 pub struct JsonWriteOptions {
     pub is_json: Option<bool>,
+    /// detected by the extension of the path if not set, when the writer is made from a path
+    pub compression: Option<Compression>,
 }
 
 impl WriteOptions for JsonWriteOptions {
@@ -126,11 +173,57 @@ where
         if let Some(is_json) = options.is_json {
             self.with_json_format(is_json);
         }
+        if let Some(compression) = options.compression {
+            self.with_compression(compression);
+        }
 
         self.finish(fabrix)
     }
 }
 
+// ================================================================================================
+// Json writer spec & DynWriter impl
+// ================================================================================================
+
+/// a json file writer, which can be deserialized from an options map (see `SourceRegistry`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct WriterSpec {
+    pub path: String,
+    pub is_json: Option<bool>,
+    pub compression: Option<Compression>,
+}
+
+impl WriterSpec {
+    pub fn write_options(&self) -> JsonWriteOptions {
+        JsonWriteOptions {
+            is_json: self.is_json,
+            compression: self
+                .compression
+                .or_else(|| Compression::from_path(&self.path)),
+        }
+    }
+}
+
+#[async_trait]
+impl DynWriter for WriterSpec {
+    fn source_type(&self) -> &str {
+        JsonWriteOptions::source_type()
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        Endpoint::file(self.source_type(), &self.path)
+    }
+
+    fn sync_write(&mut self, fabrix: Fabrix) -> FabrixResult<()> {
+        let options = self.write_options();
+        Writer::new(File::create(&self.path)?).sync_write(fabrix, &options)
+    }
+
+    async fn async_write(&mut self, fabrix: Fabrix) -> FabrixResult<()> {
+        self.sync_write(fabrix)
+    }
+}
+
 #[cfg(test)]
 mod test_json_writer {
     use std::fs::File;
@@ -1,7 +1,9 @@
 //! Fabrix sources
 
+pub mod compression;
 #[cfg(feature = "csv")]
 pub mod csv;
+pub mod files;
 #[cfg(feature = "json")]
 pub mod json;
 #[cfg(feature = "mongo")]
@@ -10,5 +12,20 @@ pub mod mongo;
 pub mod parquet;
 #[cfg(feature = "sql")]
 pub mod sql;
+pub mod uri;
 #[cfg(feature = "xl")]
 pub mod xl;
+
+use crate::{FabrixError, FabrixResult};
+
+/// run blocking io, e.g. a fetch or a file read, on tokio's blocking threads, so that async
+/// readers do not block the runtime
+pub(crate) async fn spawn_blocking<T, F>(f: F) -> FabrixResult<T>
+where
+    F: FnOnce() -> FabrixResult<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| FabrixError::new_uncategorized(format!("blocking task failed: {e}")))?
+}
@@ -5,5 +5,21 @@
 pub mod reader;
 pub mod writer;
 
-pub use reader::{MongoReadOptions, Reader as MongoReader};
-pub use writer::{MongoWriteOptions, Writer as MongoWriter};
+pub use reader::{MongoReadOptions, Reader as MongoReader, ReaderSpec as MongoReaderSpec};
+pub use writer::{MongoWriteOptions, Writer as MongoWriter, WriterSpec as MongoWriterSpec};
+
+use fabrix_mg::SavingCategory;
+
+use crate::{FabrixError, FabrixResult};
+
+/// parse a saving category: `column`, `row` or `dataset`
+pub(crate) fn saving_category(s: &str) -> FabrixResult<SavingCategory> {
+    match s {
+        "column" => Ok(SavingCategory::Column),
+        "row" => Ok(SavingCategory::Row),
+        "dataset" => Ok(SavingCategory::Dataset),
+        _ => Err(FabrixError::InvalidArgument(format!(
+            "unknown saving category `{s}`"
+        ))),
+    }
+}
@@ -5,8 +5,10 @@
 use async_trait::async_trait;
 use fabrix_core::Fabrix;
 use fabrix_mg::{MongoEc, MongoExecutor, SavingCategory};
+use serde::Deserialize;
 
-use crate::{FabrixError, FabrixResult, FromSource, ReadOptions};
+use super::saving_category;
+use crate::{DynReader, Endpoint, FabrixError, FabrixResult, FromSource, ReadOptions};
 
 // ================================================================================================
 // Mongo Reader
@@ -122,3 +124,54 @@ impl<'a> FromSource<'a, MongoReadOptions<'a>> for Reader<'a> {
         unimplemented!("sync_read is not allowed in mongo reader")
     }
 }
+
+// ================================================================================================
+// Mongo reader spec & DynReader impl
+// ================================================================================================
+
+/// a mongo reader, which can be deserialized from an options map (see `SourceRegistry`).
+/// `saving_category` is one of `column` (default), `row` & `dataset`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReaderSpec {
+    pub conn: String,
+    pub database: String,
+    pub collection: String,
+    pub saving_category: Option<String>,
+    pub id: String,
+}
+
+#[async_trait]
+impl DynReader for ReaderSpec {
+    fn source_type(&self) -> &str {
+        MongoReadOptions::source_type()
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        let collection = format!("{}.{}", self.database, self.collection);
+        Endpoint::table(self.source_type(), &self.conn, &collection)
+    }
+
+    fn sync_read(&mut self) -> FabrixResult<Fabrix> {
+        Err(FabrixError::new_uncategorized(
+            "sync_read is not allowed in mongo reader",
+        ))
+    }
+
+    async fn async_read(&mut self) -> FabrixResult<Fabrix> {
+        let saving_category = self
+            .saving_category
+            .as_deref()
+            .map(saving_category)
+            .transpose()?;
+        let options = MongoReadOptions {
+            saving_category: saving_category.as_ref(),
+            id: Some(&self.id),
+            ..Default::default()
+        };
+
+        Reader::new(&self.conn, &self.database, &self.collection)
+            .await?
+            .async_read(&options)
+            .await
+    }
+}
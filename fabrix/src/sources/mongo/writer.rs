@@ -9,8 +9,10 @@
 use async_trait::async_trait;
 use fabrix_core::Fabrix;
 use fabrix_mg::{MongoEc, MongoExecutor, SavingCategory};
+use serde::Deserialize;
 
-use crate::{FabrixResult, IntoSource, WriteOptions};
+use super::saving_category;
+use crate::{DynWriter, Endpoint, FabrixError, FabrixResult, IntoSource, WriteOptions};
 
 pub struct Writer<'a> {
     mg_reader: MongoExecutor,
@@ -134,3 +136,54 @@ impl<'a> IntoSource<'a, MongoWriteOptions<'a>> for Writer<'a> {
         unimplemented!("sync_write is not allowed in mongo writer")
     }
 }
+
+// ================================================================================================
+// Mongo writer spec & DynWriter impl
+// ================================================================================================
+
+/// a mongo writer, which can be deserialized from an options map (see `SourceRegistry`).
+/// `saving_category` is one of `column` (default), `row` & `dataset`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WriterSpec {
+    pub conn: String,
+    pub database: String,
+    pub collection: String,
+    pub saving_category: Option<String>,
+    pub id: Option<String>,
+}
+
+#[async_trait]
+impl DynWriter for WriterSpec {
+    fn source_type(&self) -> &str {
+        MongoWriteOptions::source_type()
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        let collection = format!("{}.{}", self.database, self.collection);
+        Endpoint::table(self.source_type(), &self.conn, &collection)
+    }
+
+    fn sync_write(&mut self, _fabrix: Fabrix) -> FabrixResult<()> {
+        Err(FabrixError::new_uncategorized(
+            "sync_write is not allowed in mongo writer",
+        ))
+    }
+
+    async fn async_write(&mut self, fabrix: Fabrix) -> FabrixResult<()> {
+        let saving_category = self
+            .saving_category
+            .as_deref()
+            .map(saving_category)
+            .transpose()?;
+        let options = MongoWriteOptions {
+            saving_category: saving_category.as_ref(),
+            id: self.id.as_deref(),
+            ..Default::default()
+        };
+
+        Writer::new(&self.conn, &self.database, &self.collection)
+            .await?
+            .async_write(fabrix, &options)
+            .await
+    }
+}
@@ -8,8 +8,14 @@ use std::io::Cursor;
 pub mod reader;
 pub mod writer;
 
-pub use reader::{ParquetReadOptions, Reader as ParquetReader};
-pub use writer::{ParquetWriteOptions, Writer as ParquetWriter};
+pub use reader::{
+    ParquetReadOptions, Reader as ParquetReader, ReaderSpec as ParquetReaderSpec,
+    StreamReader as ParquetStreamReader,
+};
+pub use writer::{
+    ParquetCodec, ParquetWriteOptions, StreamWriter as ParquetStreamWriter,
+    Writer as ParquetWriter, WriterSpec as ParquetWriterSpec,
+};
 
 pub(crate) const UNSUPPORTED_TYPE: &str = "Unsupported ParquetSource type";
 
@@ -18,6 +24,10 @@ pub enum ParquetSource<'a> {
     File(File),
     Path(&'a str),
     Uri(&'a str),
+    /// files matching a glob pattern, read by `FilesReader`
+    Glob(&'a str),
+    /// files of a directory with the extension of the format, read by `FilesReader`
+    Dir(&'a str),
     BuffRead(Cursor<Vec<u8>>),
     BuffWrite(&'a mut Cursor<Vec<u8>>),
 }
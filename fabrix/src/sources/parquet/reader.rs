@@ -3,15 +3,22 @@
 //! Reading Parquet files.
 
 use std::fs::File;
-use std::io::Cursor;
+use std::io::{Cursor, Read, Seek};
 
 use async_trait::async_trait;
+use futures::{stream, StreamExt};
+use polars::export::arrow::io::parquet::read::{infer_schema, read_metadata, FileReader};
 use polars::io::mmap::MmapBytesReader;
 use polars::io::RowCount;
-use polars::prelude::{ParquetReader, SerReader};
+use polars::prelude::{DataFrame, IdxSize, ParquetReader, PolarsError, SerReader};
+use serde::Deserialize;
 
 use super::{ParquetSource, UNSUPPORTED_TYPE};
-use crate::{Fabrix, FabrixError, FabrixResult, FromSource, ReadOptions};
+use crate::sources::uri::UriResolver;
+use crate::{
+    DynReader, Endpoint, Fabrix, FabrixError, FabrixResult, FabrixStream, FromSource,
+    FromSourceStream, ReadOptions,
+};
 
 // ================================================================================================
 // Parquet Reader
@@ -88,11 +95,8 @@ impl<R: MmapBytesReader> Reader<R> {
 
         let df = reader.finish()?;
 
-        if let Some(index) = index {
-            Ok(Fabrix::new(df, index)?)
-        } else {
-            Ok(Fabrix::new_no_index(df))
-        }
+        // `Fabrix::new` rejects columns whose dtypes are not supported by `ValueType`
+        Ok(Fabrix::new(df, index)?)
     }
 }
 
@@ -110,6 +114,7 @@ impl<'a> TryFrom<ParquetSource<'a>> for Reader<File> {
                 let file = File::open(path)?;
                 Ok(Self::new(file))
             }
+            ParquetSource::Uri(uri) => Ok(Self::new(UriResolver::default().to_file(uri)?)),
             _ => Err(FabrixError::UnsupportedType(UNSUPPORTED_TYPE)),
         }
     }
@@ -121,6 +126,7 @@ impl<'a> TryFrom<ParquetSource<'a>> for Reader<Cursor<Vec<u8>>> {
     fn try_from(value: ParquetSource<'a>) -> Result<Self, Self::Error> {
         match value {
             ParquetSource::BuffRead(bytes) => Ok(Self::new(bytes)),
+            ParquetSource::Uri(uri) => Ok(Self::new(UriResolver::default().to_buffer(uri)?)),
             _ => Err(FabrixError::UnsupportedType(UNSUPPORTED_TYPE)),
         }
     }
@@ -130,7 +136,7 @@ impl<'a> TryFrom<ParquetSource<'a>> for Reader<Cursor<Vec<u8>>> {
 // Parquet read options & FromSource impl
 // ================================================================================================
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct ParquetReadOptions {
     // pub read_parallel: Option<bool>,
     pub num_rows: Option<usize>,
@@ -191,6 +197,158 @@ where
     }
 }
 
+// ================================================================================================
+// Parquet reader spec & DynReader impl
+// ================================================================================================
+
+/// a parquet file reader, which can be deserialized from an options map (see `SourceRegistry`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReaderSpec {
+    pub path: String,
+    pub num_rows: Option<usize>,
+    pub select_columns: Option<Vec<String>>,
+    pub projection: Option<Vec<usize>>,
+    pub row_count: Option<(String, usize)>,
+    pub index: Option<usize>,
+}
+
+impl ReaderSpec {
+    pub fn read_options(&self) -> ParquetReadOptions {
+        ParquetReadOptions {
+            num_rows: self.num_rows,
+            select_columns: self.select_columns.clone(),
+            projection: self.projection.clone(),
+            row_count: self.row_count.clone(),
+            index: self.index,
+        }
+    }
+}
+
+#[async_trait]
+impl DynReader for ReaderSpec {
+    fn source_type(&self) -> &str {
+        ParquetReadOptions::source_type()
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        Endpoint::file(self.source_type(), &self.path)
+    }
+
+    fn sync_read(&mut self) -> FabrixResult<Fabrix> {
+        let options = self.read_options();
+        Reader::new(File::open(&self.path)?).sync_read(&options)
+    }
+
+    async fn async_read(&mut self) -> FabrixResult<Fabrix> {
+        self.sync_read()
+    }
+}
+
+// ================================================================================================
+// Parquet Stream Reader
+// ================================================================================================
+
+/// Parquet Stream Reader
+///
+/// Read parquet files in batches, a batch never spans over two row groups.
+pub struct StreamReader<R: Read + Seek> {
+    reader: Option<R>,
+}
+
+impl<R: Read + Seek> StreamReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: Some(reader),
+        }
+    }
+
+    pub fn has_reader(&self) -> bool {
+        self.reader.is_some()
+    }
+
+    pub fn new_reader(&mut self, reader: R) -> &mut Self {
+        self.reader = Some(reader);
+        self
+    }
+}
+
+impl<'a> TryFrom<ParquetSource<'a>> for StreamReader<File> {
+    type Error = FabrixError;
+
+    fn try_from(value: ParquetSource<'a>) -> Result<Self, Self::Error> {
+        match value {
+            ParquetSource::File(file) => Ok(Self::new(file)),
+            ParquetSource::Path(path) => Ok(Self::new(File::open(path)?)),
+            ParquetSource::Uri(uri) => Ok(Self::new(UriResolver::default().to_file(uri)?)),
+            _ => Err(FabrixError::UnsupportedType(UNSUPPORTED_TYPE)),
+        }
+    }
+}
+
+impl<'a> TryFrom<ParquetSource<'a>> for StreamReader<Cursor<Vec<u8>>> {
+    type Error = FabrixError;
+
+    fn try_from(value: ParquetSource<'a>) -> Result<Self, Self::Error> {
+        match value {
+            ParquetSource::BuffRead(bytes) => Ok(Self::new(bytes)),
+            ParquetSource::Uri(uri) => Ok(Self::new(UriResolver::default().to_buffer(uri)?)),
+            _ => Err(FabrixError::UnsupportedType(UNSUPPORTED_TYPE)),
+        }
+    }
+}
+
+impl<R> FromSourceStream<ParquetReadOptions> for StreamReader<R>
+where
+    R: Read + Seek + Send + 'static,
+{
+    fn read_stream<'s>(
+        &'s mut self,
+        options: &'s ParquetReadOptions,
+        batch_size: usize,
+    ) -> FabrixResult<FabrixStream<'s>> {
+        let mut reader = self
+            .reader
+            .take()
+            .ok_or(FabrixError::NotInitialized("ParquetStreamReader"))?;
+
+        let metadata = read_metadata(&mut reader).map_err(PolarsError::from)?;
+        let mut schema = infer_schema(&metadata).map_err(PolarsError::from)?;
+        if let Some(columns) = &options.select_columns {
+            schema.fields.retain(|f| columns.contains(&f.name));
+        }
+        if let Some(projection) = &options.projection {
+            schema.fields = projection
+                .iter()
+                .filter_map(|i| schema.fields.get(*i).cloned())
+                .collect();
+        }
+        let fields = schema.fields.clone();
+
+        let file_reader = FileReader::new(
+            reader,
+            metadata.row_groups,
+            schema,
+            Some(batch_size),
+            options.num_rows,
+            None,
+        );
+
+        let mut offset = options.row_count.as_ref().map_or(0, |(_, o)| *o);
+        let batches = file_reader.map(move |chunk| {
+            let chunk = chunk.map_err(PolarsError::from)?;
+            let mut df = DataFrame::try_from((chunk, fields.as_slice()))?;
+            if let Some((name, _)) = &options.row_count {
+                df = df.with_row_count(name, Some(offset as IdxSize))?;
+            }
+            offset += df.height();
+
+            Ok(Fabrix::new(df, options.index)?)
+        });
+
+        Ok(stream::iter(batches).boxed())
+    }
+}
+
 #[cfg(test)]
 mod test_parquet_reader {
     use super::*;
@@ -211,4 +369,56 @@ mod test_parquet_reader {
 
         assert!(!reader.has_reader());
     }
+
+    #[test]
+    fn unsupported_dtype_read() {
+        use polars::prelude::{df, NamedFrom, ParquetWriter, Series};
+
+        let list = Series::new("l", [Series::new("", [1, 2]), Series::new("", [3])]);
+        let mut df = df!["id" => [1, 2], "l" => list].unwrap();
+        let mut buff = Cursor::new(Vec::<u8>::new());
+        ParquetWriter::new(&mut buff).finish(&mut df).unwrap();
+
+        let mut reader: Reader<Cursor<Vec<u8>>> =
+            ParquetSource::BuffRead(Cursor::new(buff.into_inner()))
+                .try_into()
+                .unwrap();
+
+        let foo = reader.finish(None);
+
+        assert!(matches!(
+            foo,
+            Err(FabrixError::Core(fabrix_core::CoreError::TypeMismatch(
+                "List"
+            )))
+        ));
+    }
+
+    #[tokio::test]
+    async fn stream_read() {
+        let mut reader: Reader<File> = ParquetSource::Path(PARQUET_FILE_PATH).try_into().unwrap();
+        let expected = reader.finish(None).unwrap();
+
+        let options = ParquetReadOptions {
+            num_rows: Some(expected.height() - 1),
+            ..Default::default()
+        };
+        let mut reader: StreamReader<File> =
+            ParquetSource::Path(PARQUET_FILE_PATH).try_into().unwrap();
+
+        let batches = reader
+            .read_stream(&options, 10)
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+
+        let batches = batches.into_iter().map(|b| b.unwrap()).collect::<Vec<_>>();
+        assert!(batches.iter().all(|b| b.height() <= 10));
+        assert!(batches
+            .iter()
+            .all(|b| b.dtypes().unwrap() == expected.dtypes().unwrap()));
+        let height = batches.iter().map(|b| b.height()).sum::<usize>();
+        assert_eq!(height, expected.height() - 1);
+        assert!(!reader.has_reader());
+    }
 }
@@ -10,11 +10,50 @@ use std::fs::File;
 use std::io::{Cursor, Write};
 
 use async_trait::async_trait;
-use polars::prelude::ParquetWriter;
+use polars::export::arrow::datatypes::{DataType as ArrowDataType, PhysicalType};
+use polars::export::arrow::io::parquet::write::{
+    transverse, CompressionOptions, Encoding, FileWriter, RowGroupIterator, Version,
+    WriteOptions as ArrowWriteOptions,
+};
+use polars::prelude::{ParquetWriter, PolarsError};
+use serde::Deserialize;
 
-use crate::{Fabrix, FabrixError, FabrixResult, IntoSource, WriteOptions};
+use crate::{
+    DynWriter, Endpoint, Fabrix, FabrixError, FabrixResult, IntoSource, IntoSourceStream,
+    WriteOptions,
+};
 
 use super::{ParquetSource, UNSUPPORTED_TYPE};
+use crate::sources::uri::write_path;
+
+/// compression codec of the pages of a parquet file, each codec with its default level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParquetCodec {
+    Uncompressed,
+    Snappy,
+    Gzip,
+    Lzo,
+    Brotli,
+    Lz4,
+    Lz4Raw,
+    Zstd,
+}
+
+impl From<ParquetCodec> for CompressionOptions {
+    fn from(codec: ParquetCodec) -> Self {
+        match codec {
+            ParquetCodec::Uncompressed => CompressionOptions::Uncompressed,
+            ParquetCodec::Snappy => CompressionOptions::Snappy,
+            ParquetCodec::Gzip => CompressionOptions::Gzip(None),
+            ParquetCodec::Lzo => CompressionOptions::Lzo,
+            ParquetCodec::Brotli => CompressionOptions::Brotli(None),
+            ParquetCodec::Lz4 => CompressionOptions::Lz4,
+            ParquetCodec::Lz4Raw => CompressionOptions::Lz4Raw,
+            ParquetCodec::Zstd => CompressionOptions::Zstd(None),
+        }
+    }
+}
 
 pub struct Writer<W: Write> {
     parquet_writer: Option<ParquetWriter<W>>,
@@ -36,8 +75,13 @@ impl<W: Write> Writer<W> {
         self
     }
 
-    // TODO:
-    // pub fn with_compression()
+    pub fn with_compression(&mut self, compression: ParquetCodec) -> &mut Self {
+        self.parquet_writer = self
+            .parquet_writer
+            .take()
+            .map(|r| r.with_compression(compression.into()));
+        self
+    }
 
     pub fn with_statistics(&mut self, statistics: bool) -> &mut Self {
         self.parquet_writer = self
@@ -69,6 +113,7 @@ impl<'a> TryFrom<ParquetSource<'a>> for Writer<File> {
         match source {
             ParquetSource::File(file) => Ok(Self::new(file)),
             ParquetSource::Path(path) => Ok(Self::new(File::create(path)?)),
+            ParquetSource::Uri(uri) => Ok(Self::new(File::create(write_path(uri)?)?)),
             _ => Err(FabrixError::UnsupportedType(UNSUPPORTED_TYPE)),
         }
     }
@@ -92,6 +137,8 @@ impl<'a> TryFrom<ParquetSource<'a>> for Writer<&'a mut Cursor<Vec<u8>>> {
 #[derive(Default)]
 pub struct ParquetWriteOptions {
     pub statistics: Option<bool>,
+    /// `Lz4Raw` if not set
+    pub compression: Option<ParquetCodec>,
 }
 
 impl WriteOptions for ParquetWriteOptions {
@@ -127,11 +174,172 @@ where
         if let Some(statistics) = options.statistics {
             self.with_statistics(statistics);
         }
+        if let Some(compression) = options.compression {
+            self.with_compression(compression);
+        }
 
         self.finish(fabrix)
     }
 }
 
+// ================================================================================================
+// Parquet writer spec & DynWriter impl
+// ================================================================================================
+
+/// a parquet file writer, which can be deserialized from an options map (see `SourceRegistry`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct WriterSpec {
+    pub path: String,
+    pub statistics: Option<bool>,
+    pub compression: Option<ParquetCodec>,
+}
+
+impl WriterSpec {
+    pub fn write_options(&self) -> ParquetWriteOptions {
+        ParquetWriteOptions {
+            statistics: self.statistics,
+            compression: self.compression,
+        }
+    }
+}
+
+#[async_trait]
+impl DynWriter for WriterSpec {
+    fn source_type(&self) -> &str {
+        ParquetWriteOptions::source_type()
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        Endpoint::file(self.source_type(), &self.path)
+    }
+
+    fn sync_write(&mut self, fabrix: Fabrix) -> FabrixResult<()> {
+        let options = self.write_options();
+        Writer::new(File::create(&self.path)?).sync_write(fabrix, &options)
+    }
+
+    async fn async_write(&mut self, fabrix: Fabrix) -> FabrixResult<()> {
+        self.sync_write(fabrix)
+    }
+}
+
+// ================================================================================================
+// Parquet Stream Writer
+// ================================================================================================
+
+/// Parquet Stream Writer
+///
+/// Write batches to the same parquet file, each batch becomes one or more row groups. The schema
+/// is taken from the first batch, and the file footer is written by `finish_stream`.
+pub struct StreamWriter<W: Write> {
+    writer: Option<W>,
+    file_writer: Option<FileWriter<W>>,
+}
+
+impl<W: Write> StreamWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Some(writer),
+            file_writer: None,
+        }
+    }
+}
+
+impl<'a> TryFrom<ParquetSource<'a>> for StreamWriter<File> {
+    type Error = FabrixError;
+
+    fn try_from(source: ParquetSource<'a>) -> FabrixResult<Self> {
+        match source {
+            ParquetSource::File(file) => Ok(Self::new(file)),
+            ParquetSource::Path(path) => Ok(Self::new(File::create(path)?)),
+            ParquetSource::Uri(uri) => Ok(Self::new(File::create(write_path(uri)?)?)),
+            _ => Err(FabrixError::UnsupportedType(UNSUPPORTED_TYPE)),
+        }
+    }
+}
+
+impl<'a> TryFrom<ParquetSource<'a>> for StreamWriter<&'a mut Cursor<Vec<u8>>> {
+    type Error = FabrixError;
+
+    fn try_from(source: ParquetSource<'a>) -> FabrixResult<Self> {
+        match source {
+            ParquetSource::BuffWrite(bytes) => Ok(Self::new(bytes)),
+            _ => Err(FabrixError::UnsupportedType(UNSUPPORTED_TYPE)),
+        }
+    }
+}
+
+#[async_trait]
+impl<W> IntoSourceStream<ParquetWriteOptions> for StreamWriter<W>
+where
+    W: Write + Send,
+{
+    async fn write_batch(
+        &mut self,
+        mut fabrix: Fabrix,
+        options: &ParquetWriteOptions,
+    ) -> FabrixResult<()> {
+        fabrix.data.rechunk();
+        let schema = fabrix.data.schema().to_arrow();
+        let write_options = ArrowWriteOptions {
+            write_statistics: options.statistics.unwrap_or(false),
+            compression: options
+                .compression
+                .map(CompressionOptions::from)
+                .unwrap_or(CompressionOptions::Lz4Raw),
+            version: Version::V2,
+        };
+
+        let file_writer = match self.file_writer.as_mut() {
+            Some(w) => w,
+            None => {
+                let writer = self
+                    .writer
+                    .take()
+                    .ok_or(FabrixError::NotInitialized("ParquetStreamWriter"))?;
+                let w = FileWriter::try_new(writer, schema.clone(), write_options)
+                    .map_err(PolarsError::from)?;
+                self.file_writer.insert(w)
+            }
+        };
+
+        let encoding_map = |data_type: &ArrowDataType| match data_type.to_physical_type() {
+            PhysicalType::Dictionary(_) => Encoding::RleDictionary,
+            _ => Encoding::Plain,
+        };
+        let encodings = schema
+            .fields
+            .iter()
+            .map(|f| transverse(&f.data_type, encoding_map))
+            .collect();
+
+        let row_groups = RowGroupIterator::try_new(
+            fabrix.data.iter_chunks().map(Ok),
+            &schema,
+            write_options,
+            encodings,
+        )
+        .map_err(PolarsError::from)?;
+        for group in row_groups {
+            file_writer
+                .write(group.map_err(PolarsError::from)?)
+                .map_err(PolarsError::from)?;
+        }
+
+        Ok(())
+    }
+
+    async fn finish_stream(&mut self, _options: &ParquetWriteOptions) -> FabrixResult<()> {
+        let file_writer = self
+            .file_writer
+            .as_mut()
+            .ok_or(FabrixError::EmptyContent("ParquetStreamWriter"))?;
+        file_writer.end(None).map_err(PolarsError::from)?;
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod test_parquet_writer {
     use super::*;
@@ -160,4 +368,52 @@ mod test_parquet_writer {
         assert!(foo.is_ok(), "writing to parquet should not fail");
         assert!(!writer.has_writer());
     }
+
+    #[test]
+    fn compressed_writer() {
+        use crate::ParquetReader;
+
+        let fx = fx!["id" => [1, 2, 3], "name" => ["a", "b", "c"]].unwrap();
+        for codec in [
+            ParquetCodec::Uncompressed,
+            ParquetCodec::Snappy,
+            ParquetCodec::Zstd,
+        ] {
+            let mut buff = Cursor::new(Vec::<u8>::new());
+            let options = ParquetWriteOptions {
+                compression: Some(codec),
+                ..Default::default()
+            };
+            Writer::new(&mut buff)
+                .sync_write(fx.clone(), &options)
+                .unwrap();
+
+            let res = ParquetReader::new(Cursor::new(buff.into_inner()))
+                .finish(None)
+                .unwrap();
+            crate::assert_fabrix_eq!(res, fx);
+        }
+    }
+
+    #[tokio::test]
+    async fn stream_writer() {
+        use crate::ParquetReader;
+
+        let mut buff = Cursor::new(Vec::<u8>::new());
+        let mut writer: StreamWriter<&mut Cursor<Vec<u8>>> =
+            ParquetSource::BuffWrite(&mut buff).try_into().unwrap();
+
+        let options = ParquetWriteOptions::default();
+        let fx1 = fx!["id" => [1, 2, 3], "name" => ["a", "b", "c"]].unwrap();
+        let fx2 = fx!["id" => [4, 5], "name" => ["d", "e"]].unwrap();
+        writer.write_batch(fx1, &options).await.unwrap();
+        writer.write_batch(fx2, &options).await.unwrap();
+        writer.finish_stream(&options).await.unwrap();
+
+        let res = ParquetReader::new(Cursor::new(buff.into_inner()))
+            .finish(None)
+            .unwrap();
+        let expected = fx!["id" => [1, 2, 3, 4, 5], "name" => ["a", "b", "c", "d", "e"]].unwrap();
+        crate::assert_fabrix_eq!(res, expected);
+    }
 }
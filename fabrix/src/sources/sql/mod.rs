@@ -4,5 +4,18 @@
 pub mod reader;
 pub mod writer;
 
-pub use reader::{Reader as SqlReader, SqlReadOptions};
-pub use writer::{SqlWriteOptions, Writer as SqlWriter};
+pub use reader::{Reader as SqlReader, ReaderSpec as SqlReaderSpec, SqlReadOptions};
+pub use writer::{SqlWriteOptions, Writer as SqlWriter, WriterSpec as SqlWriterSpec};
+
+use std::str::FromStr;
+
+use fabrix_sql::SqlBuilder;
+
+use crate::FabrixResult;
+
+/// the driver of a connection string, e.g. `sqlite` of `sqlite://dev.sqlite`
+pub(crate) fn sql_driver(conn: &str) -> FabrixResult<SqlBuilder> {
+    let driver = conn.split_once("://").map_or(conn, |(d, _)| d);
+
+    Ok(SqlBuilder::from_str(driver)?)
+}
@@ -1,13 +1,26 @@
 //! Sql Reader
 //!
 //! Reading by SQL.
+//!
+//! With a watermark, only the rows after the last loaded value of the watermark column are read,
+//! by `finish` as well as by `read_stream`.
 
 use std::str::FromStr;
 
 use async_trait::async_trait;
-use fabrix_sql::{sql_adt, DatabaseType, SqlConnInfo, SqlEngine, SqlError, SqlExecutor, SqlMeta};
-
-use crate::{Fabrix, FabrixError, FabrixResult, FromSource, ReadOptions};
+use fabrix_core::CoreError;
+use fabrix_sql::{
+    sql_adt, DatabaseMysql, DatabasePg, DatabaseSqlite, DatabaseType, SqlBuilder, SqlConnInfo,
+    SqlEngine, SqlError, SqlExecutor, SqlHelper, SqlMeta,
+};
+use futures::{stream, StreamExt};
+use serde::Deserialize;
+
+use super::sql_driver;
+use crate::{
+    DynReader, Endpoint, Fabrix, FabrixError, FabrixResult, FabrixStream, FieldInfo, FromSource,
+    FromSourceStream, ReadOptions, ValueType, Watermark,
+};
 
 // ================================================================================================
 // Sql Reader
@@ -28,6 +41,7 @@ where
     join: Option<&'a sql_adt::Join>,
     group_by: Option<&'a [sql_adt::Column]>,
     include_primary_key: Option<bool>,
+    watermark: Option<Watermark>,
 }
 
 impl<'a, T> Reader<'a, T>
@@ -53,6 +67,7 @@ where
             join: None,
             group_by: None,
             include_primary_key: None,
+            watermark: None,
         })
     }
 
@@ -71,6 +86,7 @@ where
             join: None,
             group_by: None,
             include_primary_key: None,
+            watermark: None,
         })
     }
 
@@ -94,6 +110,7 @@ where
             join: None,
             group_by: None,
             include_primary_key: None,
+            watermark: None,
         })
     }
 
@@ -113,6 +130,7 @@ where
             join: None,
             group_by: None,
             include_primary_key: None,
+            watermark: None,
         })
     }
 
@@ -120,6 +138,16 @@ where
         &self.sql_reader
     }
 
+    /// the table of the options, or else the reader's
+    fn endpoint_of(&self, options: &SqlReadOptions) -> Endpoint {
+        let mut endpoint = Endpoint::new(SqlReadOptions::source_type());
+        endpoint.with_conn(self.sql_reader.get_conn_str());
+        if let Some(table) = options.table.or(self.table) {
+            endpoint.with_location(table);
+        }
+        endpoint
+    }
+
     pub fn with_table(&mut self, table: &'a str) -> &mut Self {
         self.table = Some(table);
         self
@@ -165,14 +193,36 @@ where
         self
     }
 
+    /// read the rows after the watermark only, and advance it after each read or streamed batch.
+    /// Save the watermark once the rows are written, see `WatermarkStore`. No new rows is an empty
+    /// result, and the watermark stays.
+    ///
+    /// The watermark column is selected along with the columns, and `limit` & `offset` cannot be
+    /// set. Rows are read by `column > watermark`, so a row committed late with the same value as
+    /// the watermark (e.g. the same `updated_at`) is never read; prefer a strictly increasing
+    /// column, e.g. an auto-increment id
+    pub fn with_watermark(&mut self, watermark: Watermark) -> &mut Self {
+        self.watermark = Some(watermark);
+        self
+    }
+
+    pub fn watermark(&self) -> Option<&Watermark> {
+        self.watermark.as_ref()
+    }
+
     pub async fn finish(&mut self) -> FabrixResult<Fabrix> {
         let table = self.table.ok_or(FabrixError::NotSet("table"))?.to_owned();
-        let columns = self.columns.ok_or(FabrixError::NotSet("columns"))?.to_vec();
+        let mut columns = self.columns.ok_or(FabrixError::NotSet("columns"))?.to_vec();
+        check_incremental(self.watermark.as_ref(), self.limit, self.offset)?;
+        select_watermark(&mut columns, self.watermark.as_ref());
 
         let select = sql_adt::Select {
             table,
             columns,
-            filter: self.filter.cloned(),
+            filter: match self.watermark.as_ref() {
+                Some(wm) => wm.filter_with(self.filter),
+                None => self.filter.cloned(),
+            },
             order: self.order.map(|o| o.to_vec()),
             limit: self.limit,
             offset: self.offset,
@@ -181,12 +231,67 @@ where
             include_primary_key: self.include_primary_key,
         };
 
-        let fx = self.sql_reader.select(&select).await?;
+        let fx = match self.sql_reader.select(&select).await {
+            Ok(fx) => fx,
+            // no new rows after the watermark
+            Err(SqlError::Core(CoreError::EmptyContent(_))) if self.watermark.is_some() => {
+                return empty_select(&self.sql_reader, &select).await;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(wm) = self.watermark.as_mut() {
+            wm.advance(&fx)?;
+        }
 
         Ok(fx)
     }
 }
 
+/// `limit` & `offset` would leave rows out of an incremental read, and the rows left out are never
+/// read once the watermark has moved past them
+fn check_incremental(
+    watermark: Option<&Watermark>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> FabrixResult<()> {
+    if watermark.is_some() && (limit.is_some() || offset.is_some()) {
+        return Err(FabrixError::InvalidArgument(
+            "limit & offset cannot be set on an incremental read".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+/// the watermark is advanced by its column, which is appended to the columns if missing
+fn select_watermark(columns: &mut Vec<sql_adt::Column>, watermark: Option<&Watermark>) {
+    if let Some(wm) = watermark {
+        if !columns.iter().any(|c| c.name() == wm.column) {
+            columns.push(sql_adt::Column::col(&wm.column));
+        }
+    }
+}
+
+/// an empty result of a select, typed by the schema of its table
+async fn empty_select<T: DatabaseType>(
+    executor: &SqlExecutor<T>,
+    select: &sql_adt::Select,
+) -> FabrixResult<Fabrix> {
+    let schema = executor.get_table_schema(&select.table).await?;
+    let fields = select
+        .columns
+        .iter()
+        .map(|c| {
+            let dtype = schema
+                .iter()
+                .find(|s| s.name == c.name())
+                .map_or(ValueType::String, |s| s.dtype.clone());
+            FieldInfo::new(c.name(), dtype)
+        })
+        .collect();
+
+    Ok(Fabrix::new_empty_no_index(fields)?)
+}
+
 // ================================================================================================
 // Sql read options & FromSource impl
 // ================================================================================================
@@ -201,6 +306,8 @@ pub struct SqlReadOptions<'a> {
     pub offset: Option<usize>,
     pub join: Option<&'a sql_adt::Join>,
     pub group_by: Option<&'a [sql_adt::Column]>,
+    /// replaces the watermark of the reader, see `Reader::with_watermark`
+    pub watermark: Option<&'a Watermark>,
 }
 
 impl<'a> ReadOptions for SqlReadOptions<'a> {
@@ -220,6 +327,7 @@ impl<'a> SqlReadOptions<'a> {
             offset: select.offset,
             join: select.join.as_ref(),
             group_by: select.group_by.as_deref(),
+            watermark: None,
         }
     }
 }
@@ -229,6 +337,10 @@ impl<'a, T> FromSource<'a, SqlReadOptions<'_>> for Reader<'a, T>
 where
     T: DatabaseType,
 {
+    fn endpoint(&self, options: &SqlReadOptions) -> Endpoint {
+        self.endpoint_of(options)
+    }
+
     async fn async_read<'o>(&mut self, options: &'o SqlReadOptions) -> FabrixResult<Fabrix>
     where
         'o: 'a,
@@ -242,6 +354,7 @@ where
             offset,
             join,
             group_by,
+            watermark,
         } = options;
 
         if let Some(table) = table {
@@ -268,6 +381,9 @@ where
         if let Some(group_by) = group_by {
             self.with_group_by(group_by);
         }
+        if let Some(watermark) = watermark {
+            self.with_watermark((*watermark).clone());
+        }
 
         self.finish().await
     }
@@ -280,6 +396,217 @@ where
     }
 }
 
+// ================================================================================================
+// Sql reader spec & DynReader impl
+// ================================================================================================
+
+/// a sql reader, which can be deserialized from an options map (see `SourceRegistry`).
+/// The database type is chosen by the driver of `conn`, e.g. `sqlite://dev.sqlite`, and an
+/// empty `columns` selects all the columns of the table.
+///
+/// With a `watermark`, only the rows after it are read, and it is advanced by each read.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReaderSpec {
+    pub conn: String,
+    #[serde(flatten)]
+    pub select: sql_adt::Select,
+    pub watermark: Option<Watermark>,
+}
+
+impl ReaderSpec {
+    async fn read<T: DatabaseType>(&mut self) -> FabrixResult<Fabrix> {
+        let mut reader = Reader::<T>::new_from_str(&self.conn).await?;
+        let mut select = self.select.clone();
+        if select.columns.is_empty() {
+            select.columns = reader
+                .reader()
+                .get_table_schema(&select.table)
+                .await?
+                .into_iter()
+                .map(|s| sql_adt::Column::col(s.name))
+                .collect();
+        }
+        let mut options = SqlReadOptions::from_sql_select(&select);
+        options.watermark = self.watermark.as_ref();
+        if let Some(include_primary_key) = self.select.include_primary_key {
+            reader.with_include_primary_key(include_primary_key);
+        }
+
+        let fx = reader.async_read(&options).await?;
+        let advanced = reader.watermark().cloned();
+        if advanced.is_some() {
+            self.watermark = advanced;
+        }
+
+        Ok(fx)
+    }
+}
+
+#[async_trait]
+impl DynReader for ReaderSpec {
+    fn source_type(&self) -> &str {
+        SqlReadOptions::source_type()
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        Endpoint::table(self.source_type(), &self.conn, &self.select.table)
+    }
+
+    fn sync_read(&mut self) -> FabrixResult<Fabrix> {
+        Err(FabrixError::new_uncategorized(
+            "sync_read is not allowed in sql reader",
+        ))
+    }
+
+    async fn async_read(&mut self) -> FabrixResult<Fabrix> {
+        match sql_driver(&self.conn)? {
+            SqlBuilder::Mysql => self.read::<DatabaseMysql>().await,
+            SqlBuilder::Postgres => self.read::<DatabasePg>().await,
+            SqlBuilder::Sqlite => self.read::<DatabaseSqlite>().await,
+        }
+    }
+}
+
+// ================================================================================================
+// FromSourceStream impl
+// ================================================================================================
+
+/// read by pages of `batch_size` rows, `limit` & `offset` of the options apply to the whole
+/// stream.
+///
+/// Pages are fetched by keyset pagination over the primary key of the table: each page is ordered
+/// by the key and starts after the greatest key of the previous one, so the paging is stable and
+/// does not rescan skipped rows. A selected key column is the index of each batch, an unselected
+/// one is dropped from the batches. The table must have a primary key, and `order` & `group_by`
+/// cannot be streamed.
+///
+/// With a watermark, of the options or of the reader, only the rows after it are streamed, and
+/// the reader's watermark is advanced by each batch; `limit` & `offset` cannot be set then.
+impl<'a, T> FromSourceStream<SqlReadOptions<'_>> for Reader<'a, T>
+where
+    T: DatabaseType,
+{
+    fn endpoint(&self, options: &SqlReadOptions) -> Endpoint {
+        self.endpoint_of(options)
+    }
+
+    fn read_stream<'s>(
+        &'s mut self,
+        options: &'s SqlReadOptions,
+        batch_size: usize,
+    ) -> FabrixResult<FabrixStream<'s>> {
+        let table = options
+            .table
+            .or(self.table)
+            .ok_or(FabrixError::NotSet("table"))?;
+        let columns = options
+            .columns
+            .or(self.columns)
+            .ok_or(FabrixError::NotSet("columns"))?;
+        if options.order.or(self.order).is_some() {
+            return Err(FabrixError::InvalidArgument(
+                "a sql stream is ordered by the primary key, order cannot be set".to_owned(),
+            ));
+        }
+        if options.group_by.or(self.group_by).is_some() {
+            return Err(FabrixError::InvalidArgument(
+                "a grouped sql select cannot be streamed".to_owned(),
+            ));
+        }
+
+        if let Some(watermark) = options.watermark {
+            self.with_watermark(watermark.clone());
+        }
+        let filter = options.filter.or(self.filter);
+        let offset = options.offset.or(self.offset);
+        let remaining = options.limit.or(self.limit);
+        check_incremental(self.watermark.as_ref(), remaining, offset)?;
+        let mut columns = columns.to_vec();
+        select_watermark(&mut columns, self.watermark.as_ref());
+
+        let select = sql_adt::Select {
+            table: table.to_owned(),
+            columns,
+            filter: match self.watermark.as_ref() {
+                Some(wm) => wm.filter_with(filter),
+                None => filter.cloned(),
+            },
+            order: None,
+            limit: None,
+            offset: None,
+            join: options.join.or(self.join).cloned(),
+            group_by: None,
+            include_primary_key: None,
+        };
+        let offset = offset.unwrap_or(0);
+        let executor = &self.sql_reader;
+        let watermark = &mut self.watermark;
+
+        // the cursor of the pages, `None` until the primary key is known
+        let batches = stream::try_unfold(
+            (None::<Watermark>, offset, remaining, false),
+            move |(cursor, offset, remaining, done)| {
+                let mut select = select.clone();
+                async move {
+                    let limit = remaining.map_or(batch_size, |r| r.min(batch_size));
+                    if done || limit == 0 {
+                        return Ok(None);
+                    }
+
+                    let mut cursor = match cursor {
+                        Some(c) => c,
+                        None => {
+                            let key = executor.get_primary_key(&select.table).await?;
+                            Watermark::new(&select.table, &key)
+                        }
+                    };
+                    // the key pages the select, but is only returned if it has been selected
+                    let key_idx = select
+                        .columns
+                        .iter()
+                        .position(|c| c.name() == cursor.column);
+                    if key_idx.is_none() {
+                        select.columns.push(sql_adt::Column::col(&cursor.column));
+                    }
+                    select.filter = cursor.filter_with(select.filter.as_ref());
+                    select.order = Some(vec![sql_adt::Order::Asc(cursor.column.clone())]);
+                    select.limit = Some(limit);
+                    // the offset skips rows of the first page only
+                    select.offset = (offset > 0).then_some(offset);
+
+                    let mut fx = match executor.select(&select).await {
+                        Ok(fx) => fx,
+                        // no more rows
+                        Err(SqlError::Core(CoreError::EmptyContent(_))) => return Ok(None),
+                        Err(e) => return Err(FabrixError::from(e)),
+                    };
+                    cursor.advance(&fx)?;
+                    match key_idx {
+                        Some(idx) => {
+                            fx.set_index_tag(idx)?;
+                        }
+                        None => fx = Fabrix::new_no_index(fx.data().drop(&cursor.column)?),
+                    }
+
+                    let height = fx.height();
+                    let remaining = remaining.map(|r| r - height);
+                    Ok(Some((fx, (Some(cursor), 0, remaining, height < limit))))
+                }
+            },
+        );
+
+        let batches = batches.map(move |batch: FabrixResult<Fabrix>| -> FabrixResult<Fabrix> {
+            let fx = batch?;
+            if let Some(wm) = watermark.as_mut() {
+                wm.advance(&fx)?;
+            }
+            Ok(fx)
+        });
+
+        Ok(batches.boxed())
+    }
+}
+
 #[cfg(test)]
 mod test_sql_reader {
     use fabrix_sql::DatabaseSqlite;
@@ -315,4 +642,168 @@ mod test_sql_reader {
 
         println!("{:?}", fx);
     }
+
+    #[tokio::test]
+    async fn test_read_incremental() {
+        use crate::{fx, FileWatermarkStore, WatermarkStore};
+
+        const INCR_TABLE: &str = "ds_sql_incremental";
+
+        let mut reader = Reader::<DatabaseSqlite>::new_from_str(CONN).await.unwrap();
+        let executor = reader.reader();
+        let mut data = fx!["id" => [1, 2, 3], "name" => ["a", "b", "c"]].unwrap();
+        // the primary key pages the stream below
+        data.set_index_tag("id").unwrap();
+        executor
+            .save(INCR_TABLE, data, &sql_adt::SaveStrategy::Replace)
+            .await
+            .unwrap();
+
+        let store = FileWatermarkStore::new("../cache/watermark").unwrap();
+        store.clear(INCR_TABLE).await.unwrap();
+        let columns = vec!["id".into(), "name".into()];
+
+        // the first run reads all
+        let wm = store.load(INCR_TABLE).await.unwrap();
+        reader
+            .with_table(INCR_TABLE)
+            .with_columns(&columns)
+            .with_watermark(wm.unwrap_or_else(|| Watermark::new(INCR_TABLE, "id")));
+        assert_eq!(reader.finish().await.unwrap().height(), 3);
+        store.save(reader.watermark().unwrap()).await.unwrap();
+
+        let data = fx!["id" => [4, 5], "name" => ["d", "e"]].unwrap();
+        reader
+            .reader()
+            .save(INCR_TABLE, data, &sql_adt::SaveStrategy::Append)
+            .await
+            .unwrap();
+
+        // the next run reads the new rows only
+        let mut reader = Reader::<DatabaseSqlite>::new_from_str(CONN).await.unwrap();
+        let wm = store.load(INCR_TABLE).await.unwrap().unwrap();
+        reader
+            .with_table(INCR_TABLE)
+            .with_columns(&columns)
+            .with_watermark(wm);
+        let fx = reader.finish().await.unwrap();
+        assert_eq!(fx.height(), 2);
+        assert_eq!(reader.watermark().unwrap().value, Some(crate::value!(5)));
+
+        // no new rows is an empty read, and the watermark stays
+        let fx = reader.finish().await.unwrap();
+        assert_eq!(fx.height(), 0);
+        assert_eq!(fx.get_column_names(), vec!["id", "name"]);
+        assert_eq!(reader.watermark().unwrap().value, Some(crate::value!(5)));
+
+        // the watermark column is selected, and limit cannot be set
+        let names = vec!["name".into()];
+        let mut reader = Reader::<DatabaseSqlite>::new_from_str(CONN).await.unwrap();
+        reader
+            .with_table(INCR_TABLE)
+            .with_columns(&names)
+            .with_watermark(Watermark::new(INCR_TABLE, "id"));
+        let fx = reader.finish().await.unwrap();
+        assert_eq!(fx.get_column_names(), vec!["name", "id"]);
+        reader.with_limit(1);
+        assert!(reader.finish().await.is_err());
+
+        // a stream reads the rows after the watermark of the options, and advances the reader's
+        let data = fx!["id" => [6, 7, 8], "name" => ["f", "g", "h"]].unwrap();
+        reader
+            .reader()
+            .save(INCR_TABLE, data, &sql_adt::SaveStrategy::Append)
+            .await
+            .unwrap();
+        let wm = store.load(INCR_TABLE).await.unwrap().unwrap();
+        let options = SqlReadOptions {
+            table: Some(INCR_TABLE),
+            columns: Some(&columns),
+            watermark: Some(&wm),
+            ..Default::default()
+        };
+        let mut reader = Reader::<DatabaseSqlite>::new_from_str(CONN).await.unwrap();
+        let heights = reader
+            .read_stream(&options, 2)
+            .unwrap()
+            .map(|b| b.unwrap().height())
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(heights, vec![2, 2, 1]);
+        assert_eq!(reader.watermark().unwrap().value, Some(crate::value!(8)));
+
+        store.clear(INCR_TABLE).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_stream() {
+        use crate::fx;
+
+        const STREAM_TABLE: &str = "ds_sql_stream";
+
+        let mut reader = Reader::<DatabaseSqlite>::new_from_str(CONN).await.unwrap();
+        let mut data = fx!["id" => [5, 3, 1, 4, 2], "name" => ["e", "c", "a", "d", "b"]].unwrap();
+        data.set_index_tag("id").unwrap();
+        reader
+            .reader()
+            .save(STREAM_TABLE, data, &sql_adt::SaveStrategy::Replace)
+            .await
+            .unwrap();
+
+        let columns = vec!["name".into()];
+        let options = SqlReadOptions {
+            table: Some(STREAM_TABLE),
+            columns: Some(&columns),
+            ..Default::default()
+        };
+        let batches = reader
+            .read_stream(&options, 2)
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .map(|b| b.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            batches.iter().map(|b| b.height()).collect::<Vec<_>>(),
+            vec![2, 2, 1]
+        );
+        // pages follow the primary key
+        let names = batches
+            .iter()
+            .flat_map(|b| {
+                b.get_column("name")
+                    .unwrap()
+                    .try_iter()
+                    .unwrap()
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec!["a", "b", "c", "d", "e"]
+                .into_iter()
+                .map(crate::Value::from)
+                .collect::<Vec<_>>()
+        );
+        // the primary key is not selected
+        assert!(batches
+            .iter()
+            .all(|b| b.width() == 1 && b.index_tag().is_none()));
+
+        let options = SqlReadOptions {
+            table: Some(STREAM_TABLE),
+            columns: Some(&columns),
+            offset: Some(1),
+            limit: Some(3),
+            ..Default::default()
+        };
+        let heights = reader
+            .read_stream(&options, 2)
+            .unwrap()
+            .map(|b| b.unwrap().height())
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(heights, vec![2, 1]);
+    }
 }
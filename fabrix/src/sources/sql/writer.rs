@@ -5,9 +5,17 @@
 use std::str::FromStr;
 
 use async_trait::async_trait;
-use fabrix_sql::{sql_adt, DatabaseType, SqlConnInfo, SqlEngine, SqlError, SqlExecutor, SqlMeta};
-
-use crate::{Fabrix, FabrixError, FabrixResult, IntoSource, WriteOptions};
+use fabrix_sql::{
+    sql_adt, DatabaseMysql, DatabasePg, DatabaseSqlite, DatabaseType, SqlBuilder, SqlConnInfo,
+    SqlEngine, SqlError, SqlExecutor, SqlMeta,
+};
+use serde::Deserialize;
+
+use super::sql_driver;
+use crate::{
+    DynWriter, Endpoint, Fabrix, FabrixError, FabrixResult, IntoSource, IntoSourceStream,
+    PlanSource, WriteOptions,
+};
 
 // ================================================================================================
 // Sql Writer
@@ -18,7 +26,10 @@ where
     T: DatabaseType,
 {
     sql_writer: SqlExecutor<T>,
+    table_name: Option<String>,
     save_strategy: Option<sql_adt::SaveStrategy>,
+    // whether a stream has written its first batch
+    streaming: bool,
 }
 
 impl<T> Writer<T>
@@ -35,7 +46,9 @@ where
 
         Ok(Self {
             sql_writer,
+            table_name: None,
             save_strategy: None,
+            streaming: false,
         })
     }
 
@@ -45,7 +58,9 @@ where
 
         Ok(Self {
             sql_writer,
+            table_name: None,
             save_strategy: None,
+            streaming: false,
         })
     }
 
@@ -60,7 +75,9 @@ where
 
         Ok(Self {
             sql_writer,
+            table_name: None,
             save_strategy: None,
+            streaming: false,
         })
     }
 
@@ -71,7 +88,9 @@ where
 
         Ok(Self {
             sql_writer,
+            table_name: None,
             save_strategy: None,
+            streaming: false,
         })
     }
 
@@ -79,6 +98,31 @@ where
         &self.sql_writer
     }
 
+    /// the table written when the write options have none
+    pub fn with_table_name(&mut self, table_name: &str) -> &mut Self {
+        self.table_name = Some(table_name.to_owned());
+        self
+    }
+
+    /// the table of the options, or else the writer's
+    fn table_name(&self, options: &SqlWriteOptions) -> FabrixResult<String> {
+        options
+            .table_name
+            .map(str::to_owned)
+            .or_else(|| self.table_name.clone())
+            .ok_or(FabrixError::NotSet("table name"))
+    }
+
+    /// the table of the options, or else the writer's
+    fn endpoint_of(&self, options: &SqlWriteOptions) -> Endpoint {
+        let mut endpoint = Endpoint::new(SqlWriteOptions::source_type());
+        endpoint.with_conn(self.sql_writer.get_conn_str());
+        if let Ok(table) = self.table_name(options) {
+            endpoint.with_location(&table);
+        }
+        endpoint
+    }
+
     pub fn with_save_strategy(&mut self, save_strategy: sql_adt::SaveStrategy) -> &mut Self {
         self.save_strategy = Some(save_strategy);
         self
@@ -120,6 +164,10 @@ impl<'a, T> IntoSource<'a, SqlWriteOptions<'_>> for Writer<T>
 where
     T: DatabaseType,
 {
+    fn endpoint(&self, options: &SqlWriteOptions) -> Endpoint {
+        self.endpoint_of(options)
+    }
+
     async fn async_write<'o>(
         &mut self,
         fabrix: Fabrix,
@@ -128,18 +176,13 @@ where
     where
         'o: 'a,
     {
-        let SqlWriteOptions {
-            table_name,
-            save_strategy,
-        } = options;
-
-        let table_name = table_name.ok_or_else(|| FabrixError::NotSet("table name"))?;
+        let table_name = self.table_name(options)?;
 
-        if let Some(save_strategy) = save_strategy {
+        if let Some(save_strategy) = &options.save_strategy {
             self.save_strategy = Some(save_strategy.clone());
         }
 
-        self.finish(table_name, fabrix).await
+        self.finish(&table_name, fabrix).await
     }
 
     fn sync_write<'o>(&mut self, _fabrix: Fabrix, _options: &'o SqlWriteOptions) -> FabrixResult<()>
@@ -148,6 +191,142 @@ where
     {
         unimplemented!("sync_write is not allowed in sql writer")
     }
+
+    /// only `Append` inserts a batch by a single statement, `Upsert` inserts & updates apart
+    fn atomic_appends(&self, options: &SqlWriteOptions) -> bool {
+        matches!(
+            options
+                .save_strategy
+                .as_ref()
+                .or(self.save_strategy.as_ref()),
+            Some(sql_adt::SaveStrategy::Append)
+        )
+    }
+}
+
+/// the plan of saving with the strategy of the options, or else the writer's
+#[async_trait]
+impl<'a, T> PlanSource<'a, SqlWriteOptions<'_>> for Writer<T>
+where
+    T: DatabaseType,
+{
+    type Plan = sql_adt::SavePlan;
+
+    async fn plan<'o>(
+        &mut self,
+        fabrix: &Fabrix,
+        options: &'o SqlWriteOptions,
+    ) -> FabrixResult<sql_adt::SavePlan>
+    where
+        'o: 'a,
+    {
+        let table_name = self.table_name(options)?;
+        let save_strategy = options
+            .save_strategy
+            .as_ref()
+            .or(self.save_strategy.as_ref())
+            .unwrap_or(&sql_adt::SaveStrategy::FailIfExists);
+
+        Ok(self
+            .sql_writer
+            .plan(&table_name, fabrix, save_strategy)
+            .await?)
+    }
+}
+
+// ================================================================================================
+// Sql writer spec & DynWriter impl
+// ================================================================================================
+
+/// a sql writer, which can be deserialized from an options map (see `SourceRegistry`).
+/// The database type is chosen by the driver of `conn`, e.g. `sqlite://dev.sqlite`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WriterSpec {
+    pub conn: String,
+    pub table_name: String,
+    pub save_strategy: Option<sql_adt::SaveStrategy>,
+}
+
+impl WriterSpec {
+    async fn write<T: DatabaseType>(&self, fabrix: Fabrix) -> FabrixResult<()> {
+        let options = SqlWriteOptions {
+            table_name: Some(&self.table_name),
+            save_strategy: self.save_strategy.clone(),
+        };
+
+        Writer::<T>::new_from_str(&self.conn)
+            .await?
+            .async_write(fabrix, &options)
+            .await
+    }
+}
+
+#[async_trait]
+impl DynWriter for WriterSpec {
+    fn source_type(&self) -> &str {
+        SqlWriteOptions::source_type()
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        Endpoint::table(self.source_type(), &self.conn, &self.table_name)
+    }
+
+    fn sync_write(&mut self, _fabrix: Fabrix) -> FabrixResult<()> {
+        Err(FabrixError::new_uncategorized(
+            "sync_write is not allowed in sql writer",
+        ))
+    }
+
+    async fn async_write(&mut self, fabrix: Fabrix) -> FabrixResult<()> {
+        match sql_driver(&self.conn)? {
+            SqlBuilder::Mysql => self.write::<DatabaseMysql>(fabrix).await,
+            SqlBuilder::Postgres => self.write::<DatabasePg>(fabrix).await,
+            SqlBuilder::Sqlite => self.write::<DatabaseSqlite>(fabrix).await,
+        }
+    }
+}
+
+// ================================================================================================
+// IntoSourceStream impl
+// ================================================================================================
+
+/// the save strategy of the options only applies to the first batch, the following batches
+/// are appended (or upserted) to the same table.
+#[async_trait]
+impl<'a, T> IntoSourceStream<SqlWriteOptions<'a>> for Writer<T>
+where
+    T: DatabaseType,
+{
+    fn endpoint(&self, options: &SqlWriteOptions<'a>) -> Endpoint {
+        self.endpoint_of(options)
+    }
+
+    async fn write_batch(
+        &mut self,
+        fabrix: Fabrix,
+        options: &SqlWriteOptions<'a>,
+    ) -> FabrixResult<()> {
+        let table_name = self.table_name(options)?;
+
+        let save_strategy = match (&options.save_strategy, self.streaming) {
+            (Some(sql_adt::SaveStrategy::Upsert), true) => sql_adt::SaveStrategy::Upsert,
+            (_, true) => sql_adt::SaveStrategy::Append,
+            (Some(s), false) => s.clone(),
+            (None, false) => sql_adt::SaveStrategy::FailIfExists,
+        };
+        self.save_strategy = Some(save_strategy);
+        self.streaming = true;
+
+        self.finish(&table_name, fabrix).await
+    }
+
+    async fn finish_stream(&mut self, _options: &SqlWriteOptions<'a>) -> FabrixResult<()> {
+        if !self.streaming {
+            return Err(FabrixError::EmptyContent("SqlStreamWriter"));
+        }
+        self.streaming = false;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -175,4 +354,54 @@ mod test_sql_writer {
         let res = writer.finish(TABLE, fx).await;
         assert!(res.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_plan() {
+        let mut writer = Writer::<DatabaseSqlite>::new_from_str(CONN).await.unwrap();
+        let table = "ds_sql_plan";
+
+        let fx = fx![
+            "ord";
+            "ord" => [1, 2],
+            "name" => ["John", "Mary"],
+        ]
+        .unwrap();
+        let options = SqlWriteOptions {
+            table_name: Some(table),
+            save_strategy: Some(sql_adt::SaveStrategy::Replace),
+        };
+        writer.async_write(fx, &options).await.unwrap();
+
+        let fx = fx![
+            "ord";
+            "ord" => [2, 3, 4],
+            "name" => ["Mike", "Tom", "Jack"],
+        ]
+        .unwrap();
+        let options = SqlWriteOptions {
+            table_name: Some(table),
+            save_strategy: Some(sql_adt::SaveStrategy::Upsert),
+        };
+        let plan = writer.plan(&fx, &options).await.unwrap();
+        assert!(plan.is_valid());
+        assert_eq!((plan.rows_to_insert, plan.rows_to_update), (2, 1));
+        assert_eq!(plan.existing_rows, 2);
+
+        // the writer's table, when the options have none
+        let options = SqlWriteOptions {
+            table_name: None,
+            save_strategy: Some(sql_adt::SaveStrategy::Upsert),
+        };
+        assert!(matches!(
+            writer.plan(&fx, &options).await,
+            Err(FabrixError::NotSet(_))
+        ));
+        writer.with_table_name(table);
+        let plan = writer.plan(&fx, &options).await.unwrap();
+        assert_eq!((plan.rows_to_insert, plan.rows_to_update), (2, 1));
+
+        let endpoint = IntoSource::endpoint(&writer, &options);
+        assert_eq!(endpoint.location.as_deref(), Some(table));
+        assert!(endpoint.conn.unwrap().starts_with("sqlite"));
+    }
 }
@@ -4,6 +4,6 @@
 
 pub mod reader;
 
-pub use reader::{Reader as XlReader, XlReadOptions};
+pub use reader::{Reader as XlReader, ReaderSpec as XlReaderSpec, XlReadOptions};
 
 pub(crate) const UNSUPPORTED_TYPE: &str = "Unsupported XlSource type";
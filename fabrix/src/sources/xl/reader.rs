@@ -1,6 +1,10 @@
 //! Xl reader
 //!
 //! Reading Xl files
+//!
+//! With an `ErrorHandler`, rows of a row-wise sheet which cannot be converted, e.g. a text in a
+//! column of numbers, are skipped or quarantined with their sheet, line & column, instead of
+//! failing the whole read.
 
 use std::{
     fs::File,
@@ -8,11 +12,18 @@ use std::{
 };
 
 use async_trait::async_trait;
-use fabrix_core::{value, Fabrix, Value, D2};
+use fabrix_core::{value, Fabrix, FieldInfo, Value, D2};
 use fabrix_xl::{ExcelValue, XlCell, XlConsumer, XlExecutor, XlSource, XlWorkbook};
+use futures::{stream, StreamExt};
+use serde::Deserialize;
+use tokio::sync::mpsc;
 
 use super::UNSUPPORTED_TYPE;
-use crate::{FabrixError, FabrixResult, FromSource, ReadOptions};
+use crate::sources::uri::UriResolver;
+use crate::{
+    DynReader, Endpoint, ErrorHandler, FabrixError, FabrixResult, FabrixStream, FromSource,
+    FromSourceStream, ReadOptions, Stage, ValueType,
+};
 
 // ================================================================================================
 // Xl into Fabrix convertor implementation
@@ -47,6 +58,39 @@ impl XlFabrix {
     }
 }
 
+/// reject the bad rows of a row-wise batch, whose header row, if any, is kept.
+/// `first_line` is the sheet line of the first row after the header
+fn reject_bad_rows(
+    handler: &ErrorHandler,
+    mut data: D2<Value>,
+    has_header: bool,
+    first_line: usize,
+) -> FabrixResult<D2<Value>> {
+    let header = match has_header && !data.is_empty() {
+        true => Some(data.remove(0)),
+        false => None,
+    };
+    let names = match &header {
+        Some(h) => h.iter().map(|v| v.to_string()).collect::<Vec<_>>(),
+        None => (0..data.first().map_or(0, |r| r.len()))
+            .map(|i| format!("Column_{i}"))
+            .collect(),
+    };
+
+    let mut rows = handler.reject_row_values(
+        Stage::Read,
+        XlReadOptions::source_type(),
+        &names,
+        data,
+        first_line,
+    )?;
+    if let Some(h) = header {
+        rows.insert(0, h);
+    }
+
+    Ok(rows)
+}
+
 impl XlConsumer<()> for XlFabrix {
     type UnitOut = Value;
     type FinalOut = Fabrix;
@@ -75,6 +119,8 @@ pub struct Reader<R: Read + Seek> {
     sheet_name: Option<String>,
     has_header: Option<bool>,
     is_column_wise: Option<bool>,
+    dtypes: Option<Vec<FieldInfo>>,
+    error_handler: ErrorHandler,
 }
 
 impl<R: Read + Seek> Reader<R> {
@@ -84,9 +130,27 @@ impl<R: Read + Seek> Reader<R> {
             sheet_name: None,
             has_header: None,
             is_column_wise: None,
+            dtypes: None,
+            error_handler: ErrorHandler::default(),
         })
     }
 
+    /// skip or quarantine the rows of a row-wise sheet which cannot be converted, the sheet name
+    /// is added to the location of the rejected rows
+    pub fn with_error_handler(&mut self, error_handler: ErrorHandler) -> &mut Self {
+        self.error_handler = error_handler;
+        self
+    }
+
+    /// the error handler with the sheet in its location
+    fn sheet_error_handler(&self, sheet_name: &str) -> ErrorHandler {
+        let mut handler = self.error_handler.clone();
+        let mut location = handler.location().clone();
+        location.with_sheet(sheet_name);
+        handler.with_location(location);
+        handler
+    }
+
     pub fn has_reader(&self) -> bool {
         self.xl_reader.is_some()
     }
@@ -106,6 +170,12 @@ impl<R: Read + Seek> Reader<R> {
         self
     }
 
+    /// cast columns into the given types after reading, e.g. `ValueType::Categorical`
+    pub fn with_dtypes(&mut self, dtypes: &[FieldInfo]) -> &mut Self {
+        self.dtypes = Some(dtypes.to_vec());
+        self
+    }
+
     pub fn finish(&mut self, index: Option<usize>) -> FabrixResult<Fabrix> {
         let mut xl_reader = self
             .xl_reader
@@ -123,11 +193,19 @@ impl<R: Read + Seek> Reader<R> {
 
         let is_column_wise = self.is_column_wise.take().unwrap_or(false);
 
+        let handler = self.sheet_error_handler(&sheet_name);
+        // sheet lines are 1-based
+        let first_line = 1 + has_header as usize;
+
         xl_reader.consume_fn_mut(
             None,
             &sheet_name,
             |d| {
-                XlFabrix::transform_data(d, is_column_wise, has_header)
+                let d = match is_column_wise {
+                    true => Ok(d),
+                    false => reject_bad_rows(&handler, d, has_header, first_line),
+                };
+                d.and_then(|d| XlFabrix::transform_data(d, is_column_wise, has_header))
                     .map_err(|e| fabrix_xl::XlError::Unexpected(format!("fabrix error {:?}", e)))
             },
             |d| {
@@ -137,6 +215,9 @@ impl<R: Read + Seek> Reader<R> {
         )?;
 
         let mut res = helper.data.take().unwrap();
+        for fi in self.dtypes.take().unwrap_or_default() {
+            res.cast(fi.name(), fi.dtype())?;
+        }
         if let Some(index) = index {
             res.set_index_tag(index)?;
         }
@@ -156,6 +237,7 @@ impl TryFrom<XlSource> for Reader<File> {
         match source {
             XlSource::File(file) => Self::new(file),
             XlSource::Path(path) => Self::new(File::open(path)?),
+            XlSource::Uri(uri) => Self::new(UriResolver::default().to_file(&uri)?),
             _ => Err(FabrixError::UnsupportedType(UNSUPPORTED_TYPE)),
         }
     }
@@ -167,6 +249,7 @@ impl TryFrom<XlSource> for Reader<Cursor<Vec<u8>>> {
     fn try_from(source: XlSource) -> Result<Self, Self::Error> {
         match source {
             XlSource::Buff(bytes) => Self::new(bytes),
+            XlSource::Uri(uri) => Self::new(UriResolver::default().to_buffer(&uri)?),
             _ => Err(FabrixError::UnsupportedType(UNSUPPORTED_TYPE)),
         }
     }
@@ -181,6 +264,7 @@ pub struct XlReadOptions {
     pub sheet_name: Option<String>,
     pub has_header: Option<bool>,
     pub is_column_wise: Option<bool>,
+    pub dtypes: Option<Vec<FieldInfo>>,
     pub index: Option<usize>,
 }
 
@@ -210,6 +294,7 @@ where
             sheet_name,
             has_header,
             is_column_wise,
+            dtypes,
             index,
         } = options;
 
@@ -222,14 +307,161 @@ where
         if let Some(is_column_wise) = is_column_wise {
             self.with_column_wise(*is_column_wise);
         }
+        if let Some(dtypes) = dtypes {
+            self.with_dtypes(dtypes);
+        }
 
         self.finish(*index)
     }
 }
 
+// ================================================================================================
+// Xl reader spec & DynReader impl
+// ================================================================================================
+
+/// a xl file reader, which can be deserialized from an options map (see `SourceRegistry`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReaderSpec {
+    pub path: String,
+    pub sheet_name: Option<String>,
+    pub has_header: Option<bool>,
+    pub is_column_wise: Option<bool>,
+    pub dtypes: Option<Vec<(String, ValueType)>>,
+    pub index: Option<usize>,
+}
+
+impl ReaderSpec {
+    pub fn read_options(&self) -> XlReadOptions {
+        XlReadOptions {
+            sheet_name: self.sheet_name.clone(),
+            has_header: self.has_header,
+            is_column_wise: self.is_column_wise,
+            dtypes: self.dtypes.as_ref().map(|d| {
+                d.iter()
+                    .map(|(n, t)| FieldInfo::new(n.as_str(), t.clone()))
+                    .collect()
+            }),
+            index: self.index,
+        }
+    }
+}
+
+#[async_trait]
+impl DynReader for ReaderSpec {
+    fn source_type(&self) -> &str {
+        XlReadOptions::source_type()
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        Endpoint::file(self.source_type(), &self.path)
+    }
+
+    fn sync_read(&mut self) -> FabrixResult<Fabrix> {
+        let options = self.read_options();
+        Reader::new(File::open(&self.path)?)?.sync_read(&options)
+    }
+
+    async fn async_read(&mut self) -> FabrixResult<Fabrix> {
+        self.sync_read()
+    }
+}
+
+// ================================================================================================
+// FromSourceStream impl
+// ================================================================================================
+
+/// read a sheet by batches of rows, the header row is shared by all the batches.
+/// Column-wise sheets can not be streamed.
+///
+/// The underlying sheet reader is not `Send`, so the sheet is read on a dedicated thread which
+/// takes the reader over, and converts at most one batch ahead of the consumer.
+impl<R> FromSourceStream<XlReadOptions> for Reader<R>
+where
+    R: Seek + Read + Send + 'static,
+{
+    fn read_stream<'s>(
+        &'s mut self,
+        options: &'s XlReadOptions,
+        batch_size: usize,
+    ) -> FabrixResult<FabrixStream<'s>> {
+        if options.is_column_wise.or(self.is_column_wise) == Some(true) {
+            return Err(FabrixError::InvalidArgument(
+                "column-wise xl sheet cannot be streamed".to_owned(),
+            ));
+        }
+
+        let sheet_name = options
+            .sheet_name
+            .clone()
+            .or_else(|| self.sheet_name.take())
+            .ok_or(FabrixError::NotSet("sheet name"))?;
+        let has_header = options.has_header.or(self.has_header).unwrap_or(true);
+        let dtypes = options.dtypes.clone().or_else(|| self.dtypes.take());
+        let index = options.index;
+        let handler = self.sheet_error_handler(&sheet_name);
+
+        let mut xl_reader = self
+            .xl_reader
+            .take()
+            .ok_or(FabrixError::NotInitialized("XlReader"))?;
+
+        let (tx, rx) = mpsc::channel::<FabrixResult<Fabrix>>(1);
+        std::thread::spawn(move || {
+            let iter = match xl_reader.iter_sheet(Some(batch_size), &sheet_name) {
+                Ok(iter) => iter,
+                Err(e) => {
+                    let _ = tx.blocking_send(Err(e.into()));
+                    return;
+                }
+            };
+
+            // the header row comes along with the first batch
+            let mut header = None::<Vec<Value>>;
+            // sheet line of the first row of the next batch, after the header
+            let mut line = 1 + has_header as usize;
+            for mut rows in iter {
+                if has_header {
+                    match &header {
+                        Some(h) => rows.insert(0, h.clone()),
+                        None => header = Some(rows[0].clone()),
+                    }
+                    if rows.len() == 1 {
+                        continue;
+                    }
+                }
+                let first_line = line;
+                line += rows.len() - has_header as usize;
+
+                let res = reject_bad_rows(&handler, rows, has_header, first_line)
+                    .and_then(|rows| XlFabrix::transform_data(rows, false, has_header))
+                    .and_then(|mut fx| {
+                        for fi in dtypes.iter().flatten() {
+                            fx.cast(fi.name(), fi.dtype())?;
+                        }
+                        if let Some(index) = index {
+                            fx.set_index_tag(index)?;
+                        }
+                        Ok(fx)
+                    });
+                // the stream is dropped
+                if tx.blocking_send(res).is_err() {
+                    return;
+                }
+            }
+        });
+
+        let batches = stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|batch| (batch, rx))
+        });
+
+        Ok(batches.boxed())
+    }
+}
+
 #[cfg(test)]
 mod test_xl_reader {
     use super::*;
+    use fabrix_core::ValueType;
 
     const XL_FILE_PATH: &str = "../mock/test.xlsx";
 
@@ -269,4 +501,45 @@ mod test_xl_reader {
 
         assert!(!reader.has_reader());
     }
+
+    #[test]
+    fn categorical_file_read() {
+        let mut reader: Reader<File> = XlSource::Path(XL_FILE_PATH.to_string()).try_into().unwrap();
+
+        let foo = reader
+            .with_header(true)
+            .with_sheet_name("data")
+            .with_dtypes(&[FieldInfo::new("gender", ValueType::Categorical(None))])
+            .finish(None)
+            .unwrap();
+
+        let gender = foo.get_column("gender").unwrap();
+        assert!(matches!(gender.dtype(), Ok(ValueType::Categorical(_))));
+    }
+
+    #[tokio::test]
+    async fn stream_read() {
+        let mut reader: Reader<File> = XlSource::Path(XL_FILE_PATH.to_string()).try_into().unwrap();
+        let expected = reader.with_sheet_name("data").finish(None).unwrap();
+
+        let options = XlReadOptions {
+            sheet_name: Some("data".to_owned()),
+            ..Default::default()
+        };
+        let mut reader: Reader<File> = XlSource::Path(XL_FILE_PATH.to_string()).try_into().unwrap();
+        let batches = reader
+            .read_stream(&options, 20)
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+
+        let batches = batches.into_iter().map(|b| b.unwrap()).collect::<Vec<_>>();
+        assert!(batches.len() > 1);
+        assert!(batches.iter().all(|b| b.height() <= 20));
+        assert!(batches
+            .iter()
+            .all(|b| b.get_column_names() == expected.get_column_names()));
+        let height = batches.iter().map(|b| b.height()).sum::<usize>();
+        assert_eq!(height, expected.height());
+    }
 }
@@ -86,6 +86,7 @@ async fn read_db_write_parquet() {
         offset: None,
         join: None,
         group_by: None,
+        watermark: None,
     };
     let res = dispatcher.async_read(&ro).await;
     assert!(res.is_ok());
@@ -126,6 +127,7 @@ async fn read_db_operate_write_csv() {
         offset: None,
         join: None,
         group_by: None,
+        watermark: None,
     };
     let res = dispatcher.async_read(&ro).await;
     assert!(res.is_ok());